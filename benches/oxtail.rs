@@ -0,0 +1,87 @@
+//! Ingestion, filtering and drawing timed with criterion, so changes to storage and drawing
+//! can be compared run to run: `cargo bench`, or `cargo bench -- filter` for one group.
+//! `oxtail --benchmark <N>` gives a quicker one-off reading of the same work
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion, Throughput};
+use ratatui::{backend::TestBackend, Terminal};
+use oxtail::benchmark::{build_view, filters};
+use oxtail::log_entry::LogEntry;
+use oxtail::log_storage::{Filter, LogStorage};
+use oxtail::settings::LogSettings;
+use oxtail::synthetic::{LineGenerator, SOURCE};
+use oxtail::theme::Theme;
+use oxtail::widgets::{LogViewer, LogViewerExt};
+
+/// Lines in the storage being filtered and drawn
+const LINES: usize = 100_000;
+const LINE_SIZE: usize = 200;
+
+/// Size of the pretend terminal frames are drawn to
+const WIDTH: u16 = 200;
+const HEIGHT: u16 = 50;
+
+fn contents() -> Vec<String> {
+    LineGenerator::new(LINE_SIZE).take(LINES).collect()
+}
+
+fn storage(contents: Vec<String>) -> LogStorage {
+    let mut log_storage = LogStorage::new();
+    for (line_number, content) in contents.into_iter().enumerate() {
+        let mut entry = LogEntry::new(SOURCE, content);
+        entry.line_number = line_number as i64;
+        log_storage.add_entry(entry);
+    }
+    log_storage
+}
+
+/// Parsing lines into entries and adding them to storage
+fn ingest(c: &mut Criterion) {
+    let contents = contents();
+    let mut group = c.benchmark_group("ingest");
+    group.throughput(Throughput::Elements(LINES as u64));
+    group.sample_size(10);
+    group.bench_function("synthetic", |b| b.iter_batched(|| contents.clone(), storage, BatchSize::LargeInput));
+    group.finish();
+}
+
+/// Building views over everything, the way changing a filter does
+fn filter(c: &mut Criterion) {
+    let log_storage = storage(contents());
+    let mut group = c.benchmark_group("filter");
+    group.throughput(Throughput::Elements(LINES as u64));
+    group.sample_size(10);
+    for (name, filter) in filters().expect("benchmark filters") {
+        group.bench_function(name, |b| b.iter(|| build_view(&log_storage, &filter)));
+    }
+    group.finish();
+}
+
+/// Formatting entries' metadata and drawing a page of them, scrolled to new ones every frame
+/// and standing still, when the rows come from the line cache
+fn render(c: &mut Criterion) {
+    let log_storage = storage(contents());
+    let view = build_view(&log_storage, &Filter::new());
+    let entries = log_storage.view_entries(&view);
+    let settings = LogSettings::default();
+    let theme = Theme::neon();
+    let mut group = c.benchmark_group("render");
+
+    group.bench_function("prefix", |b| {
+        let mut index = 0;
+        b.iter(|| {
+            index = (index + 1) % entries.len();
+            entries.get(index).map(|entry| entry.prefix(&settings))
+        })
+    });
+    for (name, scroll) in [("scrolling", HEIGHT as usize), ("still", 0)] {
+        let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT)).expect("test terminal");
+        let mut viewer = LogViewer::new();
+        group.bench_function(name, |b| b.iter(|| {
+            viewer.scroll_up(scroll, entries.len());
+            terminal.draw(|f| f.render_log_viewer(&viewer, None, f.size(), &entries, &settings, &theme)).expect("draw");
+        }));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, ingest, filter, render);
+criterion_main!(benches);
@@ -0,0 +1,73 @@
+use std::collections::BTreeMap;
+use crate::commands::find_command;
+
+/// Short names for commands, or for several run in turn, as defined with `:alias` or in the
+/// config file's `[aliases]` table
+#[derive(Debug, Default)]
+pub struct Aliases {
+    aliases: BTreeMap<String, Vec<String>>,
+}
+
+impl Aliases {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Make `name` stand for the commands, replacing what it stood for. Commands can't be
+    /// redefined
+    pub fn set(&mut self, name: &str, commands: Vec<String>) -> Result<(), String> {
+        if name.is_empty() || name.contains(char::is_whitespace) {
+            return Err(format!("Invalid alias name: {}", name));
+        }
+        if find_command(name).is_some() {
+            return Err(format!("{} is already a command", name));
+        }
+        if commands.is_empty() {
+            return Err(format!("No commands for {}", name));
+        }
+        self.aliases.insert(name.to_string(), commands);
+        Ok(())
+    }
+
+    /// Forget an alias, returning whether there was one
+    pub fn remove(&mut self, name: &str) -> bool {
+        self.aliases.remove(name).is_some()
+    }
+
+    /// Add the aliases of another set, replacing those of the same name
+    pub fn extend(&mut self, other: Aliases) {
+        self.aliases.extend(other.aliases);
+    }
+
+    /// The commands a command line stands for, if it starts with an alias. Anything after
+    /// the alias is added to the last of them, so `:errs api` can pass on a source name
+    pub fn expand(&self, line: &str) -> Option<Vec<String>> {
+        let line = line.trim();
+        let (name, rest) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+        let mut commands = self.aliases.get(name)?.clone();
+        if let Some(last) = commands.last_mut().filter(|_| !rest.trim().is_empty()) {
+            last.push(' ');
+            last.push_str(rest.trim());
+        }
+        Some(commands)
+    }
+
+    /// Each alias as `name = command; command`, by name
+    pub fn lines(&self) -> Vec<String> {
+        self.aliases.iter()
+            .map(|(name, commands)| format!("{} = {}", name, commands.join("; ")))
+            .collect()
+    }
+}
+
+/// The commands in an alias's definition, separated by `;`, with any quotes around it removed
+/// (e.g. `"where level=error; hide_source stdout"`)
+pub fn split_commands(definition: &str) -> Vec<String> {
+    let definition = definition.trim();
+    let definition = definition.strip_prefix('"').and_then(|inner| inner.strip_suffix('"')).unwrap_or(definition);
+    definition.split(';')
+        .map(|command| command.trim())
+        .filter(|command| !command.is_empty())
+        .map(|command| command.to_string())
+        .collect()
+}
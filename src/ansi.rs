@@ -0,0 +1,125 @@
+use std::ops::Range;
+use ratatui::style::{Color, Modifier, Style};
+
+/// The 16 basic colors in SGR order (30-37, then the bright 90-97)
+const BASIC_COLORS: [Color; 16] = [
+    Color::Black, Color::Red, Color::Green, Color::Yellow,
+    Color::Blue, Color::Magenta, Color::Cyan, Color::Gray,
+    Color::DarkGray, Color::LightRed, Color::LightGreen, Color::LightYellow,
+    Color::LightBlue, Color::LightMagenta, Color::LightCyan, Color::White,
+];
+
+/// Split text containing ANSI escapes into styled segments, as byte ranges of the text
+/// SGR sequences (colors and text attributes) become styles; all other escapes are dropped
+pub fn parse(input: &str) -> Vec<(Style, Range<usize>)> {
+    let mut segments: Vec<(Style, Range<usize>)> = Vec::new();
+    let mut style = Style::default();
+    let mut text_start = 0;
+    let mut chars = input.char_indices().peekable();
+
+    while let Some((index, c)) = chars.next() {
+        if c != '\x1b' {
+            continue;
+        }
+        if index > text_start {
+            segments.push((style, text_start..index));
+        }
+
+        match chars.next().map(|(_, c)| c) {
+            // CSI: parameters, intermediates, then a final byte in @..~
+            Some('[') => {
+                let mut params = String::new();
+                let mut final_byte = None;
+                for (_, c) in chars.by_ref() {
+                    if ('@'..='~').contains(&c) {
+                        final_byte = Some(c);
+                        break;
+                    }
+                    params.push(c);
+                }
+                if final_byte == Some('m') {
+                    style = apply_sgr(style, &params);
+                }
+            },
+            // OSC: runs until BEL or ESC \
+            Some(']') => {
+                while let Some((_, c)) = chars.next() {
+                    if c == '\x07' {
+                        break;
+                    }
+                    if c == '\x1b' && chars.peek().is_some_and(|(_, c)| *c == '\\') {
+                        chars.next();
+                        break;
+                    }
+                }
+            },
+            // Two-character escapes carry nothing to show
+            _ => {},
+        }
+        text_start = chars.peek().map_or(input.len(), |(index, _)| *index);
+    }
+
+    if text_start < input.len() {
+        segments.push((style, text_start..input.len()));
+    }
+    segments
+}
+
+/// Apply the `;`-separated parameters of an SGR sequence to a style
+fn apply_sgr(mut style: Style, params: &str) -> Style {
+    let codes: Vec<u16> = if params.is_empty() {
+        vec![0]
+    } else {
+        params.split([';', ':']).map(|p| p.parse().unwrap_or(0)).collect()
+    };
+
+    let mut i = 0;
+    while i < codes.len() {
+        match codes[i] {
+            0 => style = Style::default(),
+            1 => style = style.add_modifier(Modifier::BOLD),
+            2 => style = style.add_modifier(Modifier::DIM),
+            3 => style = style.add_modifier(Modifier::ITALIC),
+            4 => style = style.add_modifier(Modifier::UNDERLINED),
+            5 | 6 => style = style.add_modifier(Modifier::SLOW_BLINK),
+            7 => style = style.add_modifier(Modifier::REVERSED),
+            8 => style = style.add_modifier(Modifier::HIDDEN),
+            9 => style = style.add_modifier(Modifier::CROSSED_OUT),
+            22 => style = style.remove_modifier(Modifier::BOLD | Modifier::DIM),
+            23 => style = style.remove_modifier(Modifier::ITALIC),
+            24 => style = style.remove_modifier(Modifier::UNDERLINED),
+            25 => style = style.remove_modifier(Modifier::SLOW_BLINK),
+            27 => style = style.remove_modifier(Modifier::REVERSED),
+            28 => style = style.remove_modifier(Modifier::HIDDEN),
+            29 => style = style.remove_modifier(Modifier::CROSSED_OUT),
+            code @ 30..=37 => style = style.fg(BASIC_COLORS[(code - 30) as usize]),
+            code @ 40..=47 => style = style.bg(BASIC_COLORS[(code - 40) as usize]),
+            code @ 90..=97 => style = style.fg(BASIC_COLORS[(code - 90 + 8) as usize]),
+            code @ 100..=107 => style = style.bg(BASIC_COLORS[(code - 100 + 8) as usize]),
+            // Reset to the terminal default, which lets the source color show through
+            39 => style.fg = None,
+            49 => style.bg = None,
+            code @ (38 | 48) => {
+                let (color, used) = extended_color(&codes[i + 1..]);
+                if let Some(color) = color {
+                    style = if code == 38 { style.fg(color) } else { style.bg(color) };
+                }
+                i += used;
+            },
+            _ => {},
+        }
+        i += 1;
+    }
+    style
+}
+
+/// Parse the arguments of a 38/48 sequence: `5;n` or `2;r;g;b`
+/// Returns the color and how many arguments were consumed
+fn extended_color(args: &[u16]) -> (Option<Color>, usize) {
+    match args {
+        [5, n, ..] if *n < 16 => (Some(BASIC_COLORS[*n as usize]), 2),
+        [5, n, ..] => (Some(Color::Indexed(*n as u8)), 2),
+        [2, r, g, b, ..] => (Some(Color::Rgb(*r as u8, *g as u8, *b as u8)), 4),
+        _ => (None, args.len()),
+    }
+}
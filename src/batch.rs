@@ -0,0 +1,62 @@
+use std::sync::mpsc::{self, Receiver, SendError, SyncSender};
+use crate::log_entry::{LogEntry, Origin};
+
+/// Most entries sent in one message
+const MAX_BATCH: usize = 512;
+
+/// Batches that can wait for the UI before readers block, so a flood of input slows the
+/// readers down instead of piling up in memory
+pub const MAX_PENDING_BATCHES: usize = 64;
+
+/// Channel carrying entries from the readers to the UI a batch at a time
+pub fn channel() -> (SyncSender<Vec<LogEntry>>, Receiver<Vec<LogEntry>>) {
+    mpsc::sync_channel(MAX_PENDING_BATCHES)
+}
+
+/// Collects a reader's entries and sends them a batch at a time. Readers flush before
+/// they might wait for input, so entries never sit in a half-full batch; whatever is
+/// left is sent when the batcher is dropped
+pub struct EntryBatcher {
+    tx: SyncSender<Vec<LogEntry>>,
+    batch: Vec<LogEntry>,
+    /// Where the entries come from, for those that don't say
+    origin: Option<Origin>,
+}
+
+impl EntryBatcher {
+    pub fn new(tx: SyncSender<Vec<LogEntry>>) -> Self {
+        Self { tx, batch: Vec::new(), origin: None }
+    }
+
+    /// Give the entries pushed from now on this origin, unless they have their own
+    pub fn with_origin(mut self, origin: Option<Origin>) -> Self {
+        self.origin = origin;
+        self
+    }
+
+    /// Add an entry, sending the batch once it's full
+    pub fn push(&mut self, mut entry: LogEntry) -> Result<(), SendError<Vec<LogEntry>>> {
+        if entry.origin.is_none() {
+            entry.origin = self.origin.clone();
+        }
+        self.batch.push(entry);
+        if self.batch.len() >= MAX_BATCH {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// Send the entries collected so far
+    pub fn flush(&mut self) -> Result<(), SendError<Vec<LogEntry>>> {
+        if !self.batch.is_empty() {
+            self.tx.send(std::mem::take(&mut self.batch))?;
+        }
+        Ok(())
+    }
+}
+
+impl Drop for EntryBatcher {
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
@@ -0,0 +1,88 @@
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use ratatui::{backend::TestBackend, Terminal};
+use crate::fields::FieldCondition;
+use crate::log_entry::LogEntry;
+use crate::log_storage::{Filter, FilteredView, LogStorage};
+use crate::pattern::Pattern;
+use crate::settings::{format_size, LogSettings};
+use crate::synthetic::{LineGenerator, SOURCE};
+use crate::theme::Theme;
+use crate::widgets::{LogViewer, LogViewerExt};
+
+/// Frames drawn when timing the log viewer
+const FRAMES: usize = 500;
+
+/// Size of the pretend terminal frames are drawn to
+const WIDTH: u16 = 200;
+const HEIGHT: u16 = 50;
+
+/// Time ingesting, filtering and drawing `lines` synthetic lines and print the results, so
+/// changes to storage and drawing can be compared on the same machine
+pub fn run(lines: usize, line_size: usize) -> Result<()> {
+    println!("oxtail benchmark: {} lines of {} bytes", lines, line_size);
+    let contents: Vec<String> = LineGenerator::new(line_size).take(lines).collect();
+
+    // Parsing lines into entries and adding them to storage
+    let mut log_storage = LogStorage::new();
+    let started = Instant::now();
+    for (line_number, content) in contents.into_iter().enumerate() {
+        let mut entry = LogEntry::new(SOURCE, content);
+        entry.line_number = line_number as i64;
+        log_storage.add_entry(entry);
+    }
+    let elapsed = started.elapsed();
+    report("ingest", elapsed, lines, &format_size(log_storage.memory()));
+
+    // Building views over everything, the way changing a filter does
+    for (name, filter) in filters()? {
+        let started = Instant::now();
+        let view = build_view(&log_storage, &filter);
+        report(&format!("filter: {}", name), started.elapsed(), lines, &format!("{} matched", log_storage.view_entries(&view).len()));
+    }
+
+    // Drawing a page of entries, scrolled to new ones every frame and then standing still
+    let view = build_view(&log_storage, &Filter::new());
+    let entries = log_storage.view_entries(&view);
+    let settings = LogSettings::default();
+    let theme = Theme::neon();
+    let mut terminal = Terminal::new(TestBackend::new(WIDTH, HEIGHT))?;
+    let mut viewer = LogViewer::new();
+    for (name, scroll) in [("draw: scrolling", HEIGHT as usize), ("draw: still", 0)] {
+        let started = Instant::now();
+        for _ in 0..FRAMES {
+            viewer.scroll_up(scroll, entries.len());
+            terminal.draw(|f| f.render_log_viewer(&viewer, None, f.size(), &entries, &settings, &theme))?;
+        }
+        let elapsed = started.elapsed();
+        println!("  {:<24} {:>10.3}s {:>12.0} frames/s", name, elapsed.as_secs_f64(), FRAMES as f64 / elapsed.as_secs_f64());
+    }
+    Ok(())
+}
+
+/// The filters timed, by name: none, plain text, a regex and a field condition
+pub fn filters() -> Result<Vec<(&'static str, Filter)>> {
+    let mut text = Filter::new();
+    text.filter_in = Some(Pattern::new("/api/orders")?);
+    let mut regex = Filter::new();
+    regex.filter_in = Some(Pattern::new(r"status=5\d\d")?);
+    let mut slow = Filter::new();
+    slow.field_conditions = vec![FieldCondition::parse("duration_ms>250").map_err(anyhow::Error::msg)?];
+    Ok(vec![("all", Filter::new()), ("text", text), ("regex", regex), ("where", slow)])
+}
+
+/// A view of every entry passing `filter`, waiting for any background filtering to finish
+pub fn build_view(log_storage: &LogStorage, filter: &Filter) -> FilteredView {
+    let mut view = FilteredView::new();
+    log_storage.refresh_view(&mut view, filter);
+    while view.progress().is_some() {
+        thread::sleep(Duration::from_millis(1));
+        log_storage.refresh_view(&mut view, filter);
+    }
+    view
+}
+
+fn report(name: &str, elapsed: Duration, lines: usize, detail: &str) {
+    println!("  {:<24} {:>10.3}s {:>12.0} lines/s  {}", name, elapsed.as_secs_f64(), lines as f64 / elapsed.as_secs_f64(), detail);
+}
@@ -0,0 +1,62 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use crate::xdg;
+
+/// Where a file was read up to, and which file it was
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Checkpoint {
+    dev: u64,
+    inode: u64,
+    offset: u64,
+}
+
+/// How far each file was read, saved for `--resume` to carry on from next time. Files are
+/// known by their full path, and a checkpoint only counts while the path names the same file
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Checkpoints {
+    files: HashMap<PathBuf, Checkpoint>,
+}
+
+impl Checkpoints {
+    /// Location of the checkpoint file, honouring XDG_STATE_HOME
+    pub fn default_path() -> Option<PathBuf> {
+        Some(xdg::state_dir()?.join("checkpoints.json"))
+    }
+
+    /// Load saved checkpoints. A missing or unreadable file just means starting afresh
+    pub fn load(path: &Path) -> Self {
+        fs::read(path).ok()
+            .and_then(|bytes| serde_json::from_slice(&bytes).ok())
+            .unwrap_or_default()
+    }
+
+    /// Save the checkpoints, replacing the file in one step so a crash can't leave half of it
+    pub fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        let temporary = path.with_extension("json.tmp");
+        fs::write(&temporary, serde_json::to_vec(self)?)
+            .with_context(|| format!("Failed to write {}", temporary.display()))?;
+        fs::rename(&temporary, path).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Offset a file was read up to, if it's still the file that was read
+    pub fn get(&self, path: &Path, identity: (u64, u64)) -> Option<u64> {
+        let checkpoint = self.files.get(&full_path(path))?;
+        ((checkpoint.dev, checkpoint.inode) == identity).then_some(checkpoint.offset)
+    }
+
+    pub fn set(&mut self, path: &Path, identity: (u64, u64), offset: u64) {
+        let (dev, inode) = identity;
+        self.files.insert(full_path(path), Checkpoint { dev, inode, offset });
+    }
+}
+
+/// A path that names the file the same way whatever the working directory
+fn full_path(path: &Path) -> PathBuf {
+    fs::canonicalize(path).unwrap_or_else(|_| path.to_path_buf())
+}
@@ -0,0 +1,75 @@
+use std::io::Write;
+use std::process::{Command, Stdio};
+use anyhow::Result;
+
+/// How text ended up on the clipboard
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClipboardMethod {
+    /// A native clipboard tool (wl-copy, xclip, xsel, pbcopy) accepted it
+    Native(&'static str),
+    /// Only the OSC52 escape was sent; it's up to the terminal to honor it
+    Osc52,
+}
+
+/// Native clipboard tools to try, in order
+const NATIVE_TOOLS: &[(&str, &[&str])] = &[
+    ("wl-copy", &[]),
+    ("xclip", &["-selection", "clipboard"]),
+    ("xsel", &["--clipboard", "--input"]),
+    ("pbcopy", &[]),
+];
+
+/// Copy text to the system clipboard
+/// The OSC52 escape is always written to `terminal` so copying works over SSH,
+/// and a native tool is tried as well for terminals that ignore OSC52
+pub fn copy(text: &str, terminal: &mut impl Write) -> Result<ClipboardMethod> {
+    write!(terminal, "\x1b]52;c;{}\x07", base64_encode(text.as_bytes()))?;
+    terminal.flush()?;
+
+    for (tool, args) in NATIVE_TOOLS {
+        if copy_with_tool(tool, args, text) {
+            return Ok(ClipboardMethod::Native(tool));
+        }
+    }
+    Ok(ClipboardMethod::Osc52)
+}
+
+/// Pipe text into a clipboard tool, returning whether it succeeded
+fn copy_with_tool(tool: &str, args: &[&str], text: &str) -> bool {
+    let child = Command::new(tool)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn();
+
+    let mut child = match child {
+        Ok(child) => child,
+        Err(_) => return false,
+    };
+    if let Some(mut stdin) = child.stdin.take() {
+        if stdin.write_all(text.as_bytes()).is_err() {
+            // Reap the tool rather than leave a zombie behind
+            drop(stdin);
+            let _ = child.kill();
+            let _ = child.wait();
+            return false;
+        }
+    }
+    child.wait().map(|status| status.success()).unwrap_or(false)
+}
+
+/// Standard base64 encoding, as required by OSC52
+fn base64_encode(input: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut output = String::with_capacity(input.len().div_ceil(3) * 4);
+    for chunk in input.chunks(3) {
+        let b = [chunk[0], *chunk.get(1).unwrap_or(&0), *chunk.get(2).unwrap_or(&0)];
+        let n = ((b[0] as u32) << 16) | ((b[1] as u32) << 8) | b[2] as u32;
+        output.push(ALPHABET[(n >> 18) as usize & 63] as char);
+        output.push(ALPHABET[(n >> 12) as usize & 63] as char);
+        output.push(if chunk.len() > 1 { ALPHABET[(n >> 6) as usize & 63] as char } else { '=' });
+        output.push(if chunk.len() > 2 { ALPHABET[n as usize & 63] as char } else { '=' });
+    }
+    output
+}
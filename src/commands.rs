@@ -1,18 +1,562 @@
-use crate::settings::LogSettings;
+use std::time::Duration;
+use crate::aliases::split_commands;
+use crate::settings::{parse_duration, parse_size, Highlight, LogSettings, YankFormat};
+use crate::fields::FieldCondition;
+use crate::log_entry::{Severity, SEVERITY_NAMES};
+use crate::pattern::Pattern;
+use crate::process_handler::parse_signal;
+use crate::tabs::{PaneLayout, TabCommand};
+use crate::theme::BUILTIN_THEMES;
+use crate::throttle::parse_rate;
+
+/// Description of a command, which its arguments are checked and completed against and
+/// the help is written from
+pub struct CommandSpec {
+    pub name: &'static str,
+    pub aliases: &'static [&'static str],
+    /// Arguments accepted after the name
+    pub usage: &'static str,
+    /// The words accepted after the name, in order
+    pub args: &'static [Arg],
+    pub description: &'static str,
+}
+
+impl CommandSpec {
+    /// The command as it's typed, e.g. `:close <source> [drop]`
+    pub fn usage_line(&self) -> String {
+        format!(":{} {}", self.name, self.usage).trim_end().to_string()
+    }
+
+    /// Whether the command takes this many words after its name
+    fn accepts(&self, count: usize) -> bool {
+        let required = self.args.iter().filter(|arg| !arg.optional).count();
+        let unlimited = self.args.last().is_some_and(|arg| arg.rest);
+        count >= required && (unlimited || count <= self.args.len())
+    }
+
+    /// What goes in a position after the name; the rest of the line counts as the last one
+    fn arg(&self, index: usize) -> Option<&Arg> {
+        self.args.get(index).or_else(|| self.args.last().filter(|arg| arg.rest))
+    }
+}
+
+/// One position in a command's arguments
+pub struct Arg {
+    pub values: Values,
+    pub optional: bool,
+    /// Takes the rest of the line, which can be any number of words
+    pub rest: bool,
+}
+
+const fn required(values: Values) -> Arg {
+    Arg { values, optional: false, rest: false }
+}
+
+const fn optional(values: Values) -> Arg {
+    Arg { values, optional: true, rest: false }
+}
+
+const fn required_rest(values: Values) -> Arg {
+    Arg { values, optional: false, rest: true }
+}
+
+const fn optional_rest(values: Values) -> Arg {
+    Arg { values, optional: true, rest: true }
+}
+
+/// What an argument is completed from. The values themselves are checked by the command
+pub enum Values {
+    /// Anything, such as a pattern or a path, with nothing to complete
+    Any,
+    /// Known source names
+    Sources,
+    /// Known source names and these words
+    SourcesAnd(&'static [&'static str]),
+    /// One of these words
+    Words(&'static [&'static str]),
+    /// Names of the options `set` takes
+    Options,
+    /// Command names
+    Commands,
+}
+
+impl Values {
+    fn candidates(&self, sources: &[String]) -> Vec<String> {
+        match self {
+            Values::Any => Vec::new(),
+            Values::Sources => sources.to_vec(),
+            Values::SourcesAnd(words) => words.iter().map(|s| s.to_string()).chain(sources.iter().cloned()).collect(),
+            Values::Words(words) => words.iter().map(|s| s.to_string()).collect(),
+            Values::Options => SET_OPTIONS.iter().map(|(option, _)| option.to_string()).collect(),
+            Values::Commands => COMMANDS.iter().map(|spec| spec.name.to_string()).collect(),
+        }
+    }
+}
+
+/// Every command accepted by the prompt
+pub const COMMANDS: &[CommandSpec] = &[
+    CommandSpec {
+        name: "show_source",
+        aliases: &["show"],
+        usage: "<source>|<group>|all",
+        args: &[required(Values::SourcesAnd(&["all"]))],
+        description: "Show entries from a source or --group",
+    },
+    CommandSpec {
+        name: "hide_source",
+        aliases: &["hide"],
+        usage: "<source>|<group>|all",
+        args: &[required(Values::SourcesAnd(&["all"]))],
+        description: "Hide entries from a source or --group",
+    },
+    CommandSpec {
+        name: "show_meta",
+        aliases: &[],
+        usage: "<meta>",
+        args: &[required(Values::Words(META_TYPES))],
+        description: "Show a metadata column (time, source, lines, filetype, level, delta, ansi)",
+    },
+    CommandSpec {
+        name: "hide_meta",
+        aliases: &[],
+        usage: "<meta>",
+        args: &[required(Values::Words(META_TYPES))],
+        description: "Hide a metadata column (time, source, lines, filetype, level, delta, ansi)",
+    },
+    CommandSpec {
+        name: "where",
+        aliases: &[],
+        usage: "[<field><op><value>...]",
+        args: &[optional_rest(Values::Any)],
+        description: "Filter on parsed fields (=, !=, <, <=, >, >=); no args clears",
+    },
+    CommandSpec {
+        name: "filter_in",
+        aliases: &[],
+        usage: "[<regex>]",
+        args: &[optional_rest(Values::Any)],
+        description: "Only show entries matching a pattern; no args clears",
+    },
+    CommandSpec {
+        name: "filter_out",
+        aliases: &[],
+        usage: "[<regex>]",
+        args: &[optional_rest(Values::Any)],
+        description: "Hide entries matching a pattern; no args clears",
+    },
+    CommandSpec {
+        name: "level",
+        aliases: &[],
+        usage: "[<severity>]",
+        args: &[optional(Values::Words(SEVERITY_NAMES))],
+        description: "Hide entries less severe than this (entries without a level stay); no args clears",
+    },
+    CommandSpec {
+        name: "tab",
+        aliases: &[],
+        usage: "new [name]|next|prev|rename <name>|close|<n>",
+        args: &[required(Values::Words(TAB_SUBCOMMANDS)), optional_rest(Values::Any)],
+        description: "Manage tabs",
+    },
+    CommandSpec {
+        name: "split",
+        aliases: &[],
+        usage: "[<n>]",
+        args: &[optional(Values::Any)],
+        description: "Show tab n in a pane beside the active tab; no args closes the split",
+    },
+    CommandSpec {
+        name: "layout",
+        aliases: &[],
+        usage: "grid|rows|columns",
+        args: &[required(Values::Words(&["grid", "rows", "columns"]))],
+        description: "Show each visible source in a pane of its own",
+    },
+    CommandSpec {
+        name: "sync",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Toggle scrolling the split panes together by timestamp",
+    },
+    CommandSpec {
+        name: "sources",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "List the sources with their state, entry counts, visibility, rate and newest entry; Space shows or hides one",
+    },
+    CommandSpec {
+        name: "filters",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "List the filters in effect in this tab",
+    },
+    CommandSpec {
+        name: "write",
+        aliases: &["w"],
+        usage: "<path>",
+        args: &[required_rest(Values::Any)],
+        description: "Write the selected entries to a file",
+    },
+    CommandSpec {
+        name: "pipe",
+        aliases: &[],
+        usage: "<command>",
+        args: &[required_rest(Values::Any)],
+        description: "Pipe the selected entries to a shell command",
+    },
+    CommandSpec {
+        name: "narrow",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Only show entries within the selection's time range",
+    },
+    CommandSpec {
+        name: "widen",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Clear the time range set by narrow",
+    },
+    CommandSpec {
+        name: "load_more",
+        aliases: &[],
+        usage: "[<file>]",
+        args: &[optional(Values::Sources)],
+        description: "Read earlier lines of a file (or all files) started with --tail-lines",
+    },
+    CommandSpec {
+        name: "open",
+        aliases: &[],
+        usage: "<path>",
+        args: &[required_rest(Values::Any)],
+        description: "Start following another file",
+    },
+    CommandSpec {
+        name: "close",
+        aliases: &[],
+        usage: "<source> [drop]",
+        args: &[required(Values::Sources), optional(Values::Words(&["drop"]))],
+        description: "Stop following a file or a command started with exec; drop also removes its entries",
+    },
+    CommandSpec {
+        name: "clear",
+        aliases: &[],
+        usage: "[<source>] [renumber]",
+        args: &[optional(Values::SourcesAnd(&["renumber"])), optional(Values::Words(&["renumber"]))],
+        description: "Remove every stored entry, or a source's; renumber starts line numbers over at 1",
+    },
+    CommandSpec {
+        name: "mark",
+        aliases: &[],
+        usage: "[<text>]",
+        args: &[optional_rest(Values::Any)],
+        description: "Insert a divider into the log, e.g. mark \"deploy v2.3\"; numbered if no text is given (also m)",
+    },
+    CommandSpec {
+        name: "exec",
+        aliases: &[],
+        usage: "[--name <name>] <command>",
+        args: &[required_rest(Values::Any)],
+        description: "Run a command line as another source (<name>:stdout and <name>:stderr)",
+    },
+    CommandSpec {
+        name: "restart",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Stop the command given after -- and start it again",
+    },
+    CommandSpec {
+        name: "term",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Send SIGTERM to the command given after --",
+    },
+    CommandSpec {
+        name: "int",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Send SIGINT to the command, as Ctrl-C would",
+    },
+    CommandSpec {
+        name: "kill",
+        aliases: &[],
+        usage: "",
+        args: &[],
+        description: "Send SIGKILL to the command",
+    },
+    CommandSpec {
+        name: "signal",
+        aliases: &[],
+        usage: "<signal>",
+        args: &[required(Values::Any)],
+        description: "Send any signal to the command (e.g. HUP, SIGUSR1, 10)",
+    },
+    CommandSpec {
+        name: "throttle",
+        aliases: &[],
+        usage: "<source> <lines>/s|off",
+        args: &[required(Values::Sources), required(Values::Words(&["off"]))],
+        description: "Cap the lines a second taken from a source, dropping the rest with a marker saying how many",
+    },
+    CommandSpec {
+        name: "expect",
+        aliases: &[],
+        usage: "<source> <duration>|off [alert]",
+        args: &[required(Values::Sources), required(Values::Words(&["off"])), optional(Values::Words(&["alert"]))],
+        description: "Add a marker when a source produces nothing for this long (e.g. 30s); alert also raises an alert",
+    },
+    CommandSpec {
+        name: "alias",
+        aliases: &[],
+        usage: "[<name> <command>[; <command>...]|off]",
+        args: &[optional(Values::Any), optional_rest(Values::Any)],
+        description: "Name a command, or several run in turn (e.g. alias errs \"where level=error; hide_source stdout\"); on its own, list the aliases",
+    },
+    CommandSpec {
+        name: "config",
+        aliases: &[],
+        usage: "reload",
+        args: &[required(Values::Words(&["reload"]))],
+        description: "Read the config file again and apply its settings to every tab",
+    },
+    CommandSpec {
+        name: "set",
+        aliases: &[],
+        usage: "<option> <value>",
+        args: &[required(Values::Options), optional_rest(Values::Any)],
+        description: "Change an option (yank, autoresume, delta_threshold, wrap, wrap_prefix, max_lines, max_lines_per_source, max_memory, fps)",
+    },
+    CommandSpec {
+        name: "alert",
+        aliases: &[],
+        usage: "[<regex>]",
+        args: &[optional_rest(Values::Any)],
+        description: "Show the latest entry matching a pattern in the alert bar; no args clears",
+    },
+    CommandSpec {
+        name: "highlight",
+        aliases: &[],
+        usage: "[<regex>[:<color>]]",
+        args: &[optional_rest(Values::Any)],
+        description: "Draw matches of a pattern in a color (the theme's search color by default); no args clears",
+    },
+    CommandSpec {
+        name: "theme",
+        aliases: &[],
+        usage: "<name>",
+        args: &[required(Values::Words(BUILTIN_THEMES))],
+        description: "Switch the color theme (neon, solarized-dark, light or one from the config)",
+    },
+    CommandSpec {
+        name: "help",
+        aliases: &[],
+        usage: "[<command>]",
+        args: &[optional(Values::Commands)],
+        description: "Show a command's usage and what it does; on its own, the keys and every command",
+    },
+    CommandSpec {
+        name: "quit",
+        aliases: &["q"],
+        usage: "",
+        args: &[],
+        description: "Exit the application",
+    },
+];
+
+/// Metadata types accepted by show_meta/hide_meta
+pub const META_TYPES: &[&str] = &["time", "source", "lines", "filetype", "level", "delta", "ansi"];
+
+/// Subcommands accepted by `tab`
+const TAB_SUBCOMMANDS: &[&str] = &["new", "next", "prev", "rename", "close"];
+
+/// Highest frame rate `set fps` accepts
+pub const MAX_FPS: u32 = 240;
+
+/// Options accepted by `set`, with the values completed for each
+const SET_OPTIONS: &[(&str, &[&str])] = &[
+    ("yank", &["raw", "plain", "formatted"]),
+    ("autoresume", &["off"]),
+    ("delta_threshold", &[]),
+    ("wrap", &["on", "off"]),
+    ("wrap_prefix", &[]),
+    ("max_lines", &["off"]),
+    ("max_lines_per_source", &["off"]),
+    ("max_memory", &["off"]),
+    ("fps", &[]),
+];
+
+/// The command a name or alias stands for
+pub fn find_command(name: &str) -> Option<&'static CommandSpec> {
+    COMMANDS.iter().find(|spec| spec.name == name || spec.aliases.contains(&name))
+}
+
+/// The error for a command given the wrong arguments, showing how it's used
+fn usage_error(name: &str) -> CommandResult {
+    match find_command(name) {
+        Some(spec) => CommandResult::Error(format!("Usage: {}", spec.usage_line())),
+        None => CommandResult::Error(format!("Unknown command: {}", name)),
+    }
+}
+
+/// Completion candidates for the word being typed
+/// `previous` holds the words already entered before it, `sources` the known source names
+pub fn completions(previous: &[&str], prefix: &str, sources: &[String]) -> Vec<String> {
+    let options: Vec<String> = match previous {
+        // Completing the command name itself
+        [] => COMMANDS.iter()
+            .flat_map(|spec| std::iter::once(spec.name).chain(spec.aliases.iter().copied()))
+            .map(|s| s.to_string())
+            .collect(),
+        // An option's values depend on the option
+        ["set", option] => SET_OPTIONS.iter()
+            .find(|(name, _)| name == option)
+            .map(|(_, values)| values.iter().map(|s| s.to_string()).collect())
+            .unwrap_or_default(),
+        [command, args @ ..] => find_command(command)
+            .and_then(|spec| spec.arg(args.len()))
+            .map(|arg| arg.values.candidates(sources))
+            .unwrap_or_default(),
+    };
+
+    let mut matches: Vec<String> = options.into_iter()
+        .filter(|option| option.starts_with(prefix))
+        .collect();
+    matches.sort();
+    matches.dedup();
+    matches
+}
 
 pub enum CommandResult {
     Success(()),  // Changed to unit type as we don't use the string value
     Error(String),
     Quit,
+    Tab(TabCommand),
+    Selection(SelectionCommand),
+    /// Switch to the named theme
+    Theme(String),
+    Report(Report),
+    Storage(StorageCommand),
+    /// Draw at most this many frames per second
+    Fps(u32),
+    /// Read earlier lines of the named file, or of all files
+    LoadMore(Option<String>),
+    /// Start following a file
+    Open(String),
+    /// Stop following the named file or command, and with `drop` remove its entries too
+    Close { source: String, drop: bool },
+    /// Remove the stored entries of a source, or of all of them with None, with `renumber`
+    /// starting line numbers over
+    Clear { source: Option<String>, renumber: bool },
+    /// Insert a divider with this text, or a numbered one with None
+    Mark(Option<String>),
+    /// Run a command line as another source, named `name` or after its program
+    Exec { command: String, name: Option<String> },
+    /// Start the command given after `--` again
+    Restart,
+    /// Send a signal to the command given after `--`
+    Signal(i32),
+    /// Cap the entries a second taken from a source, or lift its cap with None
+    Throttle { source: String, per_second: Option<usize> },
+    /// Mark a source going without output for `within`, raising an alert too with `alert`,
+    /// or stop watching it with None
+    Expect { source: String, within: Option<Duration>, alert: bool },
+    /// Read the config file again
+    ReloadConfig,
+    /// Define, remove or list aliases
+    Alias(AliasCommand),
+    /// Scroll to a line of the tab's view, or by a number of lines
+    Jump(LineJump),
+    /// Show a command's usage and description, or with None all the keys and commands
+    Help(Option<&'static CommandSpec>),
+}
+
+/// Where `:<n>`, `:+<n>` and `:-<n>` scroll to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineJump {
+    /// A line of the view, counting from 1
+    To(usize),
+    /// This many lines further down
+    Down(usize),
+    /// This many lines further up
+    Up(usize),
+}
+
+/// Changes to the aliases shared by all tabs
+#[derive(Debug, Clone, PartialEq)]
+pub enum AliasCommand {
+    List,
+    Define { name: String, commands: Vec<String> },
+    Remove(String),
+}
+
+/// Changes to the log storage shared by all tabs
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StorageCommand {
+    /// Cap on entries across all sources (None for no cap)
+    Lines(Option<usize>),
+    /// Cap on entries from any one source
+    LinesPerSource(Option<usize>),
+    /// Cap on the approximate bytes taken by entries in memory
+    Memory(Option<usize>),
+}
+
+/// Commands whose multi-line output is shown in the output pane
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Report {
+    Sources,
+    Filters,
+}
+
+/// Operations on the selected entries (visual selection or cursor line)
+#[derive(Debug, Clone, PartialEq)]
+pub enum SelectionCommand {
+    /// Write the entries to a file
+    Write(String),
+    /// Pipe the entries to a shell command's stdin
+    Pipe(String),
+    /// Only show entries between the first and last selected timestamps
+    Narrow,
+}
+
+/// A line jump such as `1234`, `+100` or `-100`
+fn parse_jump(value: &str) -> Option<LineJump> {
+    if let Some(lines) = value.strip_prefix('+') {
+        lines.parse().ok().map(LineJump::Down)
+    } else if let Some(lines) = value.strip_prefix('-') {
+        lines.parse().ok().map(LineJump::Up)
+    } else {
+        value.parse().ok().map(LineJump::To)
+    }
 }
 
 pub fn execute_command(cmd: &str, settings: &mut LogSettings) -> CommandResult {
-    let parts: Vec<&str> = cmd.trim().split_whitespace().collect();
+    let parts: Vec<&str> = cmd.split_whitespace().collect();
     
     if parts.is_empty() {
-        return CommandResult::Success({});
+        return CommandResult::Success(());
     }
     
+    // A bare number is a line to jump to; with a sign, a number of lines to move
+    if let [number] = parts[..] {
+        if let Some(jump) = parse_jump(number) {
+            return CommandResult::Jump(jump);
+        }
+    }
+
+    // Every command is checked against its table entry for the number of arguments first
+    match find_command(parts[0]) {
+        Some(spec) if !spec.accepts(parts.len() - 1) => return usage_error(spec.name),
+        Some(_) => {},
+        None => return CommandResult::Error(format!("Unknown command: {}", parts[0])),
+    }
+
     match parts[0] {
         // Quit command
         "q" | "quit" => {
@@ -21,14 +565,13 @@ pub fn execute_command(cmd: &str, settings: &mut LogSettings) -> CommandResult {
         
         // Source visibility commands - accept both full and shortened forms
         "show_source" | "show" => {
-            if parts.len() < 2 {
-                return CommandResult::Error("Source name required".to_string());
-            }
-            
             let source_name = parts[1];
             if source_name == "all" {
                 settings.set_all_sources_visibility(true);
                 CommandResult::Success(())
+            } else if settings.groups.contains(source_name) {
+                settings.set_group_visibility(source_name, true);
+                CommandResult::Success(())
             } else {
                 settings.get_source_config(source_name).visible = true;
                 CommandResult::Success(())
@@ -36,14 +579,13 @@ pub fn execute_command(cmd: &str, settings: &mut LogSettings) -> CommandResult {
         },
         
         "hide_source" | "hide" => {
-            if parts.len() < 2 {
-                return CommandResult::Error("Source name required".to_string());
-            }
-            
             let source_name = parts[1];
             if source_name == "all" {
                 settings.set_all_sources_visibility(false);
                 CommandResult::Success(())
+            } else if settings.groups.contains(source_name) {
+                settings.set_group_visibility(source_name, false);
+                CommandResult::Success(())
             } else {
                 settings.get_source_config(source_name).visible = false;
                 CommandResult::Success(())
@@ -51,37 +593,339 @@ pub fn execute_command(cmd: &str, settings: &mut LogSettings) -> CommandResult {
         },
         
         "show_meta" | "hide_meta" => {
-            if parts.len() < 2 {
-                return CommandResult::Error("Metadata type required".to_string());
-            }
-            
             let show = parts[0].starts_with("show");
-            
-            match parts[1] {
-                "time" => {
-                    settings.show_time = show;
-                    CommandResult::Success(())
+            if settings.set_meta(parts[1], show) {
+                CommandResult::Success(())
+            } else {
+                CommandResult::Error(format!("Unknown metadata type: {}", parts[1]))
+            }
+        },
+        
+        // Tab management: tab new [name] / next / prev / rename <name> / close / <n>
+        "tab" => {
+            match parts.get(1).copied() {
+                Some("new") => CommandResult::Tab(TabCommand::New(parts.get(2).map(|s| s.to_string()))),
+                Some("next") => CommandResult::Tab(TabCommand::Next),
+                Some("prev") => CommandResult::Tab(TabCommand::Prev),
+                Some("close") => CommandResult::Tab(TabCommand::Close),
+                Some("rename") => {
+                    if parts.len() < 3 {
+                        return CommandResult::Error("Usage: :tab rename <name>".to_string());
+                    }
+                    CommandResult::Tab(TabCommand::Rename(parts[2..].join(" ")))
                 },
-                "source" => {
-                    settings.show_source_labels = show;
-                    CommandResult::Success(())
+                Some(n) => match n.parse::<usize>() {
+                    Ok(index) => CommandResult::Tab(TabCommand::Select(index)),
+                    Err(_) => CommandResult::Error(format!("Unknown tab command: {}", n)),
+                },
+                None => usage_error("tab"),
+            }
+        },
+
+        "split" => match parts.get(1) {
+            Some(n) => match n.parse::<usize>() {
+                Ok(index) => CommandResult::Tab(TabCommand::Split(Some(index))),
+                Err(_) => CommandResult::Error(format!("Invalid tab number: {}", n)),
+            },
+            None => CommandResult::Tab(TabCommand::Split(None)),
+        },
+
+        "layout" => match PaneLayout::parse(parts[1]) {
+            Some(layout) => CommandResult::Tab(TabCommand::Layout(layout)),
+            None => CommandResult::Error(format!("Unknown layout: {} (grid/rows/columns)", parts[1])),
+        },
+
+        "sync" => CommandResult::Tab(TabCommand::Sync),
+
+        // Selection operations
+        "write" | "w" => {
+            // Paths may have runs of spaces in them
+            let path = cmd.trim_start().strip_prefix(parts[0]).unwrap_or("").trim();
+            CommandResult::Selection(SelectionCommand::Write(path.to_string()))
+        },
+
+        "pipe" => {
+            // Keep the command's own spacing intact
+            let command = cmd.trim_start().strip_prefix("pipe").unwrap_or("").trim();
+            CommandResult::Selection(SelectionCommand::Pipe(command.to_string()))
+        },
+
+        "narrow" => CommandResult::Selection(SelectionCommand::Narrow),
+
+        "load_more" => CommandResult::LoadMore(parts.get(1).map(|source| source.to_string())),
+
+        "open" => {
+            // Paths may have spaces in them
+            let path = cmd.trim_start().strip_prefix("open").unwrap_or("").trim();
+            CommandResult::Open(path.to_string())
+        },
+
+        "close" => match parts[1..] {
+            [source] => CommandResult::Close { source: source.to_string(), drop: false },
+            [source, "drop"] => CommandResult::Close { source: source.to_string(), drop: true },
+            _ => usage_error("close"),
+        },
+
+        "clear" => match parts[1..] {
+            [] => CommandResult::Clear { source: None, renumber: false },
+            ["renumber"] => CommandResult::Clear { source: None, renumber: true },
+            [source] => CommandResult::Clear { source: Some(source.to_string()), renumber: false },
+            [source, "renumber"] => CommandResult::Clear { source: Some(source.to_string()), renumber: true },
+            _ => usage_error("clear"),
+        },
+
+        "mark" => {
+            // The text is kept as typed, without any quotes around it
+            let text = cmd.trim_start().strip_prefix("mark").unwrap_or("").trim();
+            let text = text.strip_prefix('"').and_then(|inner| inner.strip_suffix('"')).unwrap_or(text);
+            CommandResult::Mark(Some(text.to_string()).filter(|text| !text.is_empty()))
+        },
+
+        "exec" => {
+            // The command line is passed to the shell as typed
+            let rest = cmd.trim_start().strip_prefix("exec").unwrap_or("").trim();
+            let (name, command) = match rest.strip_prefix("--name") {
+                Some(named) if named.starts_with(char::is_whitespace) => {
+                    let named = named.trim_start();
+                    let (name, command) = named.split_once(char::is_whitespace).unwrap_or((named, ""));
+                    (Some(name.to_string()), command.trim())
                 },
-                "lines" => {
-                    settings.show_line_numbers = show;
+                _ => (None, rest),
+            };
+            if command.is_empty() {
+                return usage_error("exec");
+            }
+            CommandResult::Exec { command: command.to_string(), name }
+        },
+
+        "alias" => match parts[1..] {
+            [] => CommandResult::Alias(AliasCommand::List),
+            [name, "off"] => CommandResult::Alias(AliasCommand::Remove(name.to_string())),
+            [name, _, ..] => {
+                // The commands are kept as typed
+                let rest = cmd.trim_start().strip_prefix("alias").unwrap_or("").trim_start();
+                let definition = rest.strip_prefix(name).unwrap_or(rest);
+                CommandResult::Alias(AliasCommand::Define { name: name.to_string(), commands: split_commands(definition) })
+            },
+            _ => usage_error("alias"),
+        },
+
+        "throttle" => match parts[1..] {
+            [source, "off"] => CommandResult::Throttle { source: source.to_string(), per_second: None },
+            [source, rate] => match parse_rate(rate) {
+                Some(per_second) => CommandResult::Throttle { source: source.to_string(), per_second: Some(per_second) },
+                None => CommandResult::Error(format!("Invalid rate: {} (e.g. 1000/s, off)", rate)),
+            },
+            _ => usage_error("throttle"),
+        },
+
+        "expect" => match parts[1..] {
+            [source, "off"] => CommandResult::Expect { source: source.to_string(), within: None, alert: false },
+            [source, duration] | [source, duration, "alert"] => match parse_duration(duration) {
+                Some(within) if !within.is_zero() => CommandResult::Expect { source: source.to_string(), within: Some(within), alert: parts.len() == 4 },
+                _ => CommandResult::Error(format!("Invalid duration: {} (e.g. 30s, 5m)", duration)),
+            },
+            _ => usage_error("expect"),
+        },
+
+        "config" => match parts[1..] {
+            ["reload"] => CommandResult::ReloadConfig,
+            _ => usage_error("config"),
+        },
+
+        "restart" => CommandResult::Restart,
+        "term" => CommandResult::Signal(libc::SIGTERM),
+        "int" => CommandResult::Signal(libc::SIGINT),
+        "kill" => CommandResult::Signal(libc::SIGKILL),
+        "signal" => match parse_signal(parts[1]) {
+            Some(signal) => CommandResult::Signal(signal),
+            None => CommandResult::Error(format!("Unknown signal: {} (e.g. HUP, USR1)", parts[1])),
+        },
+
+        "sources" => CommandResult::Report(Report::Sources),
+        "filters" => CommandResult::Report(Report::Filters),
+
+        // Alert patterns, e.g. `alert panic|HTTP/1.1" 5\d\d`
+        // With no pattern, clears all alerts
+        "alert" => {
+            // Keep the pattern's own spacing intact
+            let pattern = cmd.trim_start().strip_prefix("alert").unwrap_or("").trim();
+            if pattern.is_empty() {
+                settings.alert_patterns.clear();
+                return CommandResult::Success(());
+            }
+            match Pattern::new(pattern) {
+                Ok(pattern) => {
+                    settings.alert_patterns.push(pattern);
                     CommandResult::Success(())
                 },
-                "filetype" => {
-                    settings.show_file_type = show;
+                Err(err) => CommandResult::Error(format!("Invalid pattern: {}", err)),
+            }
+        },
+
+        "highlight" => {
+            // Keep the pattern's own spacing intact
+            let spec = cmd.trim_start().strip_prefix("highlight").unwrap_or("").trim();
+            if spec.is_empty() {
+                settings.highlights.clear();
+                return CommandResult::Success(());
+            }
+            match Highlight::parse(spec) {
+                Ok(highlight) => {
+                    settings.highlights.push(highlight);
                     CommandResult::Success(())
                 },
-                "ansi" => {
-                    settings.show_raw = show;
+                Err(err) => CommandResult::Error(err),
+            }
+        },
+
+        "theme" => CommandResult::Theme(parts[1].to_string()),
+
+        "help" => match parts.get(1) {
+            Some(name) => match find_command(name) {
+                Some(spec) => CommandResult::Help(Some(spec)),
+                None => CommandResult::Error(format!("Unknown command: {}", name)),
+            },
+            None => CommandResult::Help(None),
+        },
+
+        "widen" => {
+            settings.time_range = None;
+            CommandResult::Success(())
+        },
+
+        // Options: set <option> <value>
+        "set" => {
+            // The prefix is taken verbatim so it can end in a space, e.g. `set wrap_prefix -> `
+            if parts.get(1) == Some(&"wrap_prefix") {
+                let prefix = cmd.trim_start()
+                    .strip_prefix("set").unwrap_or("").trim_start()
+                    .strip_prefix("wrap_prefix").unwrap_or("");
+                settings.wrap_prefix = prefix.strip_prefix(' ').unwrap_or(prefix).to_string();
+                return CommandResult::Success(());
+            }
+            if parts.len() < 3 {
+                return usage_error("set");
+            }
+            match parts[1] {
+                "yank" => match YankFormat::parse(parts[2]) {
+                    Some(format) => {
+                        settings.yank_format = format;
+                        CommandResult::Success(())
+                    },
+                    None => CommandResult::Error(format!("Unknown yank format: {} (raw/plain/formatted)", parts[2])),
+                },
+                // `off` (or a zero duration) disables it
+                "autoresume" => match parts[2] {
+                    "off" => {
+                        settings.autoresume = None;
+                        CommandResult::Success(())
+                    },
+                    value => match parse_duration(value) {
+                        Some(duration) => {
+                            settings.autoresume = Some(duration).filter(|d| !d.is_zero());
+                            CommandResult::Success(())
+                        },
+                        None => CommandResult::Error(format!("Invalid duration: {} (e.g. 10s, 2m, off)", value)),
+                    },
+                },
+                "wrap" => match parts[2] {
+                    "on" => {
+                        settings.wrap = true;
+                        CommandResult::Success(())
+                    },
+                    "off" => {
+                        settings.wrap = false;
+                        CommandResult::Success(())
+                    },
+                    value => CommandResult::Error(format!("Invalid value for wrap: {} (on/off)", value)),
+                },
+                // Retention caps apply to the storage every tab shares
+                option @ ("max_lines" | "max_lines_per_source") => {
+                    let max = match parts[2] {
+                        "off" => None,
+                        value => match value.parse::<usize>() {
+                            Ok(max) if max > 0 => Some(max),
+                            _ => return CommandResult::Error(format!("Invalid line count: {} (a positive number or off)", value)),
+                        },
+                    };
+                    CommandResult::Storage(if option == "max_lines" {
+                        StorageCommand::Lines(max)
+                    } else {
+                        StorageCommand::LinesPerSource(max)
+                    })
+                },
+                // The frame rate applies to the whole screen rather than a tab
+                "fps" => match parts[2].parse::<u32>() {
+                    Ok(fps) if (1..=MAX_FPS).contains(&fps) => CommandResult::Fps(fps),
+                    _ => CommandResult::Error(format!("Invalid frame rate: {} (1-{})", parts[2], MAX_FPS)),
+                },
+                "max_memory" => match parts[2] {
+                    "off" => CommandResult::Storage(StorageCommand::Memory(None)),
+                    value => match parse_size(value) {
+                        Some(max) if max > 0 => CommandResult::Storage(StorageCommand::Memory(Some(max))),
+                        _ => CommandResult::Error(format!("Invalid size: {} (e.g. 512M, 2G, off)", value)),
+                    },
+                },
+                "delta_threshold" => match parse_duration(parts[2]) {
+                    Some(duration) => {
+                        settings.delta_threshold = duration;
+                        CommandResult::Success(())
+                    },
+                    None => CommandResult::Error(format!("Invalid duration: {} (e.g. 500ms, 5s)", parts[2])),
+                },
+                _ => CommandResult::Error(format!("Unknown option: {}", parts[1])),
+            }
+        },
+
+        // Structured field filter, e.g. `where duration_ms>250 status>=500`
+        // With no conditions, clears the field filter
+        "where" => {
+            let conditions: Result<Vec<FieldCondition>, String> = parts[1..].iter()
+                .map(|expr| FieldCondition::parse(expr))
+                .collect();
+            match conditions {
+                Ok(conditions) => {
+                    settings.field_conditions = conditions;
                     CommandResult::Success(())
                 },
-                _ => CommandResult::Error(format!("Unknown metadata type: {}", parts[1]))
+                Err(err) => CommandResult::Error(err),
             }
         },
+
+        // Text filters, e.g. `filter_out health check`; with no pattern, clears the filter
+        "filter_in" | "filter_out" => {
+            // Keep the pattern's own spacing intact
+            let pattern = cmd.trim_start().strip_prefix(parts[0]).unwrap_or("").trim();
+            let pattern = match pattern {
+                "" => None,
+                pattern => match Pattern::new(pattern) {
+                    Ok(pattern) => Some(pattern),
+                    Err(err) => return CommandResult::Error(format!("Invalid pattern: {}", err)),
+                },
+            };
+            if parts[0] == "filter_in" {
+                settings.filter_in = pattern;
+            } else {
+                settings.filter_out = pattern;
+            }
+            CommandResult::Success(())
+        },
+
+        "level" => match parts.get(1) {
+            None => {
+                settings.min_severity = None;
+                CommandResult::Success(())
+            },
+            Some(name) => match Severity::parse(name) {
+                Some(severity) => {
+                    settings.min_severity = Some(severity);
+                    CommandResult::Success(())
+                },
+                None => CommandResult::Error(format!("Unknown level: {} ({})", name, SEVERITY_NAMES.join("/"))),
+            },
+        },
         
-        _ => CommandResult::Error(format!("Unknown command: {}", parts[0]))
+        _ => CommandResult::Error(format!("Unknown command: {}", parts[0])),
     }
 }
@@ -0,0 +1,335 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use crate::aliases::{split_commands, Aliases};
+use crate::commands::{MAX_FPS, META_TYPES};
+use crate::fields::FieldCondition;
+use crate::keymap::KeyMap;
+use crate::log_entry::{Severity, SEVERITY_NAMES};
+use crate::log_storage::Retention;
+use crate::pattern::Pattern;
+use crate::settings::{parse_duration, parse_size, Highlight, LogSettings, YankFormat};
+use crate::theme::{ColorDepth, Theme, Themes};
+use crate::throttle::parse_rate;
+use crate::xdg;
+
+/// The file `--init-config` writes: every setting, commented out, with what it does
+const DEFAULT_CONFIG: &str = include_str!("default_config.toml");
+
+/// User configuration read from `~/.config/oxtail/config.toml`, or the file given with
+/// `--config`. Anything it leaves out keeps its default
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct Config {
+    /// Theme to start with
+    pub theme: Option<String>,
+    /// Color depth override (`truecolor`, `256` or `16`) when detection gets it wrong
+    pub colors: Option<String>,
+    /// User-defined themes, each a table of color overrides
+    pub themes: HashMap<String, HashMap<String, String>>,
+    /// Key bindings by action name, e.g. `page_down = ["ctrl-f", "pagedown"]`
+    pub keys: HashMap<String, StringList>,
+    /// Metadata columns to show or hide, by the names `show_meta` takes, e.g. `lines = true`
+    pub show: HashMap<String, bool>,
+    /// Wrap long entries instead of truncating them
+    pub wrap: Option<bool>,
+    pub wrap_prefix: Option<String>,
+    /// What `y` copies: `raw`, `plain` or `formatted`
+    pub yank: Option<String>,
+    /// Deltas at least this long are highlighted, e.g. `500ms`
+    pub delta_threshold: Option<String>,
+    /// Go back to following after this long without input while paused
+    pub autoresume: Option<String>,
+    /// Most frames drawn a second
+    pub fps: Option<u32>,
+    /// Limits on the entries kept; the command line's take precedence
+    pub retention: RetentionConfig,
+    /// Filters every tab starts with
+    pub filters: FilterConfig,
+    /// Settings for particular sources, by name
+    pub sources: HashMap<String, SourceDefaults>,
+    /// Aliases, each for a command, several separated by `;`, or a list of them
+    pub aliases: HashMap<String, StringList>,
+    /// Patterns to highlight, as `:highlight` takes them
+    pub highlights: Vec<String>,
+}
+
+/// One string or a list of them, such as the keys bound to an action
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum StringList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl StringList {
+    pub fn to_vec(&self) -> Vec<String> {
+        match self {
+            StringList::One(value) => vec![value.clone()],
+            StringList::Many(values) => values.clone(),
+        }
+    }
+}
+
+/// The `[retention]` table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct RetentionConfig {
+    pub max_lines: Option<usize>,
+    pub max_lines_per_source: Option<usize>,
+    /// A size such as `512M`
+    pub max_memory: Option<String>,
+}
+
+/// The `[filters]` table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FilterConfig {
+    /// Sources or groups hidden to begin with
+    pub hide: Vec<String>,
+    /// Field conditions, as `:where` takes them
+    #[serde(rename = "where")]
+    pub conditions: Vec<String>,
+    /// Patterns for the alert bar, as `:alert` takes them
+    pub alerts: Vec<String>,
+    /// Patterns entries must and mustn't match, as `:filter_in` and `:filter_out` take them
+    pub filter_in: Option<String>,
+    pub filter_out: Option<String>,
+    /// Least severe level shown, as `:level` takes it
+    pub level: Option<String>,
+}
+
+/// A `[sources."<name>"]` table
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct SourceDefaults {
+    pub visible: Option<bool>,
+    /// A cap such as `1000/s`, as `:throttle` takes it
+    pub throttle: Option<String>,
+    /// The longest the source may go quiet, as `:expect` takes it
+    pub expect: Option<String>,
+    /// Whether going quiet raises an alert too
+    pub expect_alert: bool,
+}
+
+/// A source's settings from its table that aren't kept per tab
+pub struct SourceLimits {
+    pub source: String,
+    /// Lines a second
+    pub throttle: Option<usize>,
+    /// The longest it may go without output
+    pub expect: Option<Duration>,
+    /// Whether going quiet raises an alert too
+    pub alert: bool,
+}
+
+impl Config {
+    /// Location of the config file, honouring XDG_CONFIG_HOME
+    pub fn default_path() -> Option<PathBuf> {
+        Some(xdg::config_dir()?.join("config.toml"))
+    }
+
+    /// Write a config file with every setting commented out, for `--init-config`, refusing
+    /// to replace one that's there
+    pub fn init(path: &Path) -> Result<()> {
+        if path.exists() {
+            anyhow::bail!("{} already exists", path.display());
+        }
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        }
+        fs::write(path, DEFAULT_CONFIG).with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Key binding overrides as (action, keys), sorted by action name
+    pub fn key_overrides(&self) -> Vec<(String, Vec<String>)> {
+        let mut overrides: Vec<(String, Vec<String>)> = self.keys.iter()
+            .map(|(action, keys)| (action.clone(), keys.to_vec()))
+            .collect();
+        overrides.sort();
+        overrides
+    }
+
+    /// Load the config file given with `--config`, or else the default one, falling back to
+    /// defaults when that doesn't exist. Every setting is checked, so a mistake is reported
+    /// here rather than when it's used
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path.to_path_buf(),
+            None => match Self::default_path() {
+                Some(path) if path.exists() => path,
+                _ => return Ok(Self::default()),
+            },
+        };
+        let text = fs::read_to_string(&path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        let config: Self = toml::from_str(&text)
+            .with_context(|| format!("Failed to parse {}", path.display()))?;
+        config.apply(&mut LogSettings::default())
+            .and_then(|()| config.retention(Retention::default()).map(|_| ()))
+            .and_then(|()| config.source_limits().map(|_| ()))
+            .and_then(|()| config.aliases().map(|_| ()))
+            .and_then(|()| match config.fps {
+                Some(fps) if !(1..=MAX_FPS).contains(&fps) => Err(format!("Invalid frame rate: {} (1-{})", fps, MAX_FPS)),
+                _ => Ok(()),
+            })
+            .map_err(anyhow::Error::msg)
+            .with_context(|| format!("Invalid setting in {}", path.display()))?;
+        Ok(config)
+    }
+
+    /// These settings with those of another config laid over them, such as the ones given as
+    /// flags: what it sets replaces what these do, and its filters add to these
+    pub fn with_overrides(mut self, overrides: &Config) -> Self {
+        self.theme = overrides.theme.clone().or(self.theme);
+        self.colors = overrides.colors.clone().or(self.colors);
+        self.themes.extend(overrides.themes.clone());
+        self.keys.extend(overrides.keys.clone());
+        self.show.extend(overrides.show.clone());
+        self.wrap = overrides.wrap.or(self.wrap);
+        self.wrap_prefix = overrides.wrap_prefix.clone().or(self.wrap_prefix);
+        self.yank = overrides.yank.clone().or(self.yank);
+        self.delta_threshold = overrides.delta_threshold.clone().or(self.delta_threshold);
+        self.autoresume = overrides.autoresume.clone().or(self.autoresume);
+        self.fps = overrides.fps.or(self.fps);
+        self.filters.hide.extend(overrides.filters.hide.iter().cloned());
+        self.filters.conditions.extend(overrides.filters.conditions.iter().cloned());
+        self.filters.alerts.extend(overrides.filters.alerts.iter().cloned());
+        self.filters.filter_in = overrides.filters.filter_in.clone().or(self.filters.filter_in);
+        self.filters.filter_out = overrides.filters.filter_out.clone().or(self.filters.filter_out);
+        self.filters.level = overrides.filters.level.clone().or(self.filters.level);
+        self.sources.extend(overrides.sources.clone());
+        self.aliases.extend(overrides.aliases.clone());
+        self.highlights.extend(overrides.highlights.iter().cloned());
+        self
+    }
+
+    /// The themes, built-in and from the file, and the one to start with
+    pub fn themes(&self) -> Result<(Themes, Theme)> {
+        let depth = match &self.colors {
+            Some(colors) => ColorDepth::parse(colors)
+                .with_context(|| format!("Unknown color depth: {} (truecolor/256/16)", colors))?,
+            None => ColorDepth::detect(),
+        };
+        let themes = Themes::from_config(&self.themes, depth).map_err(anyhow::Error::msg)?;
+        let theme_name = self.theme.as_deref().unwrap_or("neon");
+        let theme = themes.get(theme_name).with_context(|| format!("Unknown theme: {}", theme_name))?;
+        Ok((themes, theme))
+    }
+
+    /// The default key bindings with the file's changes
+    pub fn keymap(&self) -> Result<KeyMap> {
+        KeyMap::with_overrides(&self.key_overrides()).map_err(anyhow::Error::msg)
+    }
+
+    /// Set what the file sets in a tab's settings, leaving the rest as they are
+    pub fn apply(&self, settings: &mut LogSettings) -> Result<(), String> {
+        for (column, show) in &self.show {
+            if !settings.set_meta(column, *show) {
+                return Err(format!("Unknown column in [show]: {} ({})", column, META_TYPES.join("/")));
+            }
+        }
+        if let Some(wrap) = self.wrap {
+            settings.wrap = wrap;
+        }
+        if let Some(prefix) = &self.wrap_prefix {
+            settings.wrap_prefix = prefix.clone();
+        }
+        if let Some(yank) = &self.yank {
+            settings.yank_format = YankFormat::parse(yank)
+                .ok_or_else(|| format!("Unknown yank format: {} (raw/plain/formatted)", yank))?;
+        }
+        if let Some(threshold) = &self.delta_threshold {
+            settings.delta_threshold = duration(threshold)?;
+        }
+        if let Some(autoresume) = &self.autoresume {
+            settings.autoresume = match autoresume.as_str() {
+                "off" => None,
+                value => Some(duration(value)?).filter(|duration| !duration.is_zero()),
+            };
+        }
+        for source in &self.filters.hide {
+            settings.get_source_config(source).visible = false;
+        }
+        for (source, defaults) in &self.sources {
+            if let Some(visible) = defaults.visible {
+                settings.get_source_config(source).visible = visible;
+            }
+        }
+        if !self.filters.conditions.is_empty() {
+            settings.field_conditions = self.filters.conditions.iter()
+                .flat_map(|conditions| conditions.split_whitespace())
+                .map(FieldCondition::parse)
+                .collect::<Result<_, _>>()?;
+        }
+        if !self.filters.alerts.is_empty() {
+            settings.alert_patterns = self.filters.alerts.iter()
+                .map(|pattern| Pattern::new(pattern).map_err(|err| format!("Invalid alert pattern: {}", err)))
+                .collect::<Result<_, _>>()?;
+        }
+        if !self.highlights.is_empty() {
+            settings.highlights = self.highlights.iter()
+                .map(|highlight| Highlight::parse(highlight).map_err(|err| format!("Invalid highlight {}: {}", highlight, err)))
+                .collect::<Result<_, _>>()?;
+        }
+        if let Some(pattern) = &self.filters.filter_in {
+            settings.filter_in = Some(Pattern::new(pattern).map_err(|err| format!("Invalid filter_in pattern: {}", err))?);
+        }
+        if let Some(pattern) = &self.filters.filter_out {
+            settings.filter_out = Some(Pattern::new(pattern).map_err(|err| format!("Invalid filter_out pattern: {}", err))?);
+        }
+        if let Some(level) = &self.filters.level {
+            settings.min_severity = Some(Severity::parse(level)
+                .ok_or_else(|| format!("Unknown level: {} ({})", level, SEVERITY_NAMES.join("/")))?);
+        }
+        Ok(())
+    }
+
+    /// The aliases defined in the file
+    pub fn aliases(&self) -> Result<Aliases, String> {
+        let mut aliases = Aliases::new();
+        for (name, commands) in &self.aliases {
+            let commands = commands.to_vec().iter().flat_map(|commands| split_commands(commands)).collect();
+            aliases.set(name, commands)?;
+        }
+        Ok(aliases)
+    }
+
+    /// The limits on the entries kept: those from the command line, or else the file's
+    pub fn retention(&self, args: Retention) -> Result<Retention, String> {
+        let max_memory = match &self.retention.max_memory {
+            Some(size) => Some(parse_size(size).ok_or_else(|| format!("Invalid size: {} (e.g. 512M, 2G)", size))?),
+            None => None,
+        };
+        Ok(Retention {
+            max_lines: args.max_lines.or(self.retention.max_lines),
+            max_lines_per_source: args.max_lines_per_source.or(self.retention.max_lines_per_source),
+            max_memory: args.max_memory.or(max_memory),
+        })
+    }
+
+    /// The throttles and expected output of the sources that have them
+    pub fn source_limits(&self) -> Result<Vec<SourceLimits>, String> {
+        self.sources.iter()
+            .map(|(source, defaults)| {
+                let throttle = match &defaults.throttle {
+                    Some(rate) => Some(parse_rate(rate).ok_or_else(|| format!("Invalid rate for {}: {} (e.g. 1000/s)", source, rate))?),
+                    None => None,
+                };
+                let expect = match &defaults.expect {
+                    Some(within) => Some(parse_duration(within).filter(|within| !within.is_zero())
+                        .ok_or_else(|| format!("Invalid duration for {}: {} (e.g. 30s, 5m)", source, within))?),
+                    None => None,
+                };
+                Ok(SourceLimits { source: source.clone(), throttle, expect, alert: defaults.expect_alert })
+            })
+            .collect()
+    }
+}
+
+fn duration(value: &str) -> Result<Duration, String> {
+    parse_duration(value).ok_or_else(|| format!("Invalid duration: {} (e.g. 500ms, 30s)", value))
+}
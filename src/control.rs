@@ -0,0 +1,155 @@
+use std::fs;
+use std::io::{self, BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use anyhow::{Context, Result};
+use serde_json::{json, Value};
+
+/// JSON-RPC error codes
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// The command ran and failed, e.g. with `Unknown command`
+const COMMAND_FAILED: i64 = -32000;
+
+/// Where to take commands from other programs, as given with `--control unix:///tmp/oxtail.ctl`
+#[derive(Debug, Clone)]
+pub struct ControlAddress {
+    path: PathBuf,
+}
+
+impl ControlAddress {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        match value.split_once("://") {
+            Some(("unix", path)) if !path.is_empty() => Ok(Self { path: PathBuf::from(path) }),
+            Some(("unix", _)) => Err(format!("socket path required: {} (e.g. unix:///tmp/oxtail.ctl)", value)),
+            Some((scheme, _)) => Err(format!("unknown protocol: {} (unix)", scheme)),
+            None => Err(format!("invalid address: {} (e.g. unix:///tmp/oxtail.ctl)", value)),
+        }
+    }
+}
+
+/// What a request asks the UI to do
+#[derive(Debug, Clone)]
+pub enum ControlAction {
+    /// Run a command line, as typed after `:`
+    Command(String),
+    /// Write every entry in the active tab to a file, as --write-on-exit does
+    Export(PathBuf),
+}
+
+/// A request waiting for the UI, which answers with the message it showed, or the error
+pub struct ControlRequest {
+    pub action: ControlAction,
+    pub reply: Sender<Result<Option<String>, String>>,
+}
+
+/// The listening socket, removed once this is dropped so the next instance can bind it
+pub struct ControlSocket {
+    path: PathBuf,
+}
+
+impl Drop for ControlSocket {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+/// Start taking requests on the socket, one JSON-RPC request a line on each connection.
+/// Binding happens here, so a socket another oxtail is using is reported before the UI starts
+pub fn listen(address: &ControlAddress) -> Result<(ControlSocket, Receiver<ControlRequest>)> {
+    let path = &address.path;
+    // A socket left behind by an instance that didn't exit cleanly is taken over, but
+    // anything else at the path is left alone
+    if let Ok(metadata) = fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            anyhow::bail!("{} exists and isn't a socket", path.display());
+        }
+        if UnixStream::connect(path).is_err() {
+            let _ = fs::remove_file(path);
+        }
+    }
+    let listener = UnixListener::bind(path)
+        .with_context(|| format!("Failed to listen on {} (is another oxtail using it?)", path.display()))?;
+    let (tx, rx) = mpsc::channel();
+
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let Ok(stream) = stream else {
+                continue;
+            };
+            let tx = tx.clone();
+            thread::spawn(move || {
+                let _ = serve(stream, &tx);
+            });
+        }
+    });
+    Ok((ControlSocket { path: path.clone() }, rx))
+}
+
+/// Answer the requests on one connection until it closes. Notifications, which have no id,
+/// are carried out without an answer
+fn serve(stream: UnixStream, tx: &Sender<ControlRequest>) -> io::Result<()> {
+    let mut writer = stream.try_clone()?;
+    for line in BufReader::new(stream).lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (id, outcome) = match serde_json::from_str::<Value>(&line) {
+            Ok(request) => {
+                let id = request.get("id").cloned();
+                let outcome = parse_request(&request).and_then(|action| send(tx, action));
+                if id.is_none() {
+                    continue;
+                }
+                (id, outcome)
+            },
+            Err(err) => (None, Err((PARSE_ERROR, format!("Parse error: {}", err)))),
+        };
+        let response = match outcome {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+            Err((code, message)) => json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } }),
+        };
+        writeln!(writer, "{}", response)?;
+    }
+    Ok(())
+}
+
+/// The action a request stands for. `command` runs any command; `open`, `filter` and
+/// `export` are shorthands taking their argument by name
+fn parse_request(request: &Value) -> Result<ControlAction, (i64, String)> {
+    let method = request.get("method").and_then(Value::as_str)
+        .ok_or((INVALID_REQUEST, "Invalid request: method required".to_string()))?;
+    let params = request.get("params").unwrap_or(&Value::Null);
+    let param = |name: &str| -> Result<String, (i64, String)> {
+        // By name, or the first of a list
+        params.get(name).or_else(|| params.get(0))
+            .and_then(Value::as_str)
+            .map(|value| value.to_string())
+            .ok_or((INVALID_PARAMS, format!("Invalid params: {} required", name)))
+    };
+    match method {
+        "command" => Ok(ControlAction::Command(param("command")?)),
+        "open" => Ok(ControlAction::Command(format!("open {}", param("path")?))),
+        // No conditions clears the filter, as `:where` on its own does
+        "filter" => Ok(ControlAction::Command(format!("where {}", param("where").unwrap_or_default()))),
+        // No pattern clears the highlights, as `:highlight` on its own does
+        "highlight" => Ok(ControlAction::Command(format!("highlight {}", param("pattern").unwrap_or_default()))),
+        "export" => Ok(ControlAction::Export(PathBuf::from(param("path")?))),
+        _ => Err((METHOD_NOT_FOUND, format!("Method not found: {} (command, open, filter, highlight or export)", method))),
+    }
+}
+
+/// Hand an action to the UI and wait for how it went
+fn send(tx: &Sender<ControlRequest>, action: ControlAction) -> Result<Option<String>, (i64, String)> {
+    let closing = || (COMMAND_FAILED, "oxtail is exiting".to_string());
+    let (reply, answer) = mpsc::channel();
+    tx.send(ControlRequest { action, reply }).map_err(|_| closing())?;
+    answer.recv().map_err(|_| closing())?.map_err(|err| (COMMAND_FAILED, err))
+}
+
@@ -0,0 +1,188 @@
+use serde_json::Value;
+use crate::log_entry::Severity;
+
+/// Standard fields pulled out of a structured log envelope
+#[derive(Debug, Clone, Default)]
+pub struct DecodedMessage {
+    pub host: Option<String>,
+    pub app_name: Option<String>,
+    pub severity: Option<Severity>,
+    pub message: String,
+}
+
+/// Try every known envelope format against a line
+/// `json` is the already-parsed line when it was valid JSON, so we don't parse twice
+pub fn decode(line: &str, json: Option<&Value>) -> Option<DecodedMessage> {
+    json.and_then(decode_gelf)
+        .or_else(|| decode_rfc5424(line))
+        .or_else(|| decode_rfc3164(line))
+}
+
+/// Decode a GELF payload (https://go2docs.graylog.org/current/getting_in_log_data/gelf.html)
+/// GELF requires `version`, `host` and `short_message`; everything else is optional
+pub fn decode_gelf(json: &Value) -> Option<DecodedMessage> {
+    let obj = json.as_object()?;
+    obj.get("version")?.as_str()?;
+    let host = obj.get("host")?.as_str()?.to_string();
+    let short_message = obj.get("short_message")?.as_str()?;
+
+    // Prefer the full message when present - it's what the sender meant to log
+    let message = obj.get("full_message")
+        .and_then(|m| m.as_str())
+        .filter(|m| !m.is_empty())
+        .unwrap_or(short_message)
+        .to_string();
+
+    // GELF levels are syslog severities
+    let severity = obj.get("level")
+        .and_then(|l| l.as_u64())
+        .and_then(|l| Severity::from_code(l as u8));
+
+    // There's no standard application field, so check the common conventions
+    let app_name = ["_app_name", "_application_name", "_app", "facility"]
+        .iter()
+        .find_map(|key| obj.get(*key).and_then(|v| v.as_str()))
+        .map(|s| s.to_string());
+
+    Some(DecodedMessage {
+        host: Some(host),
+        app_name,
+        severity,
+        message,
+    })
+}
+
+/// Decode an RFC5424 syslog message
+/// <PRI>VERSION TIMESTAMP HOSTNAME APP-NAME PROCID MSGID STRUCTURED-DATA [MSG]
+pub fn decode_rfc5424(line: &str) -> Option<DecodedMessage> {
+    let (severity, rest) = parse_pri(line)?;
+
+    // Only version 1 is defined
+    let rest = rest.strip_prefix("1 ")?;
+
+    let mut header = rest.splitn(6, ' ');
+    let _timestamp = header.next()?;
+    let host = nil_value(header.next()?);
+    let app_name = nil_value(header.next()?);
+    let _proc_id = header.next()?;
+    let _msg_id = header.next()?;
+    let rest = header.next().unwrap_or("");
+
+    let message = skip_structured_data(rest)?;
+    // MSG may be prefixed with a UTF-8 BOM
+    let message = message.trim_start_matches('\u{feff}').to_string();
+
+    Some(DecodedMessage {
+        host,
+        app_name,
+        severity,
+        message,
+    })
+}
+
+/// Decode a BSD (RFC3164) syslog message, as older devices and daemons send them
+/// <PRI>Mmm dd hh:mm:ss [HOSTNAME] TAG[PID]: MSG
+pub fn decode_rfc3164(line: &str) -> Option<DecodedMessage> {
+    let (severity, rest) = parse_pri(line)?;
+    let timestamp = rest.get(..15)?;
+    if !is_bsd_timestamp(timestamp) {
+        return None;
+    }
+    let rest = rest[15..].trim_start_matches(' ');
+
+    // The hostname is often left out, in which case the tag comes first
+    let (first, after) = rest.split_once(' ').unwrap_or((rest, ""));
+    let (host, rest) = if first.ends_with(':') || first.contains('[') {
+        (None, rest)
+    } else {
+        (Some(first.to_string()), after)
+    };
+
+    // A tag is the program name, maybe with its PID, ended by a colon
+    let (app_name, message) = match rest.split_once(": ") {
+        Some((tag, message)) if !tag.is_empty() && !tag.contains(' ') => {
+            let app = tag.split('[').next().unwrap_or(tag);
+            (nil_value(app), message)
+        },
+        _ => (None, rest),
+    };
+
+    Some(DecodedMessage {
+        host,
+        app_name,
+        severity,
+        message: message.to_string(),
+    })
+}
+
+/// Whether text is a BSD syslog timestamp, e.g. `Oct  5 22:14:15`
+fn is_bsd_timestamp(text: &str) -> bool {
+    const MONTHS: [&str; 12] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+    let bytes = text.as_bytes();
+    let digits = |range: std::ops::Range<usize>| bytes[range].iter().all(u8::is_ascii_digit);
+    bytes.len() == 15
+        && text.get(..3).is_some_and(|month| MONTHS.contains(&month))
+        && bytes[3] == b' '
+        && (bytes[4] == b' ' || bytes[4].is_ascii_digit()) && bytes[5].is_ascii_digit()
+        && bytes[6] == b' '
+        && digits(7..9) && bytes[9] == b':' && digits(10..12) && bytes[12] == b':' && digits(13..15)
+}
+
+/// Parse a `<PRI>` prefix, returning the severity and the remainder of the line
+pub fn parse_pri(line: &str) -> Option<(Option<Severity>, &str)> {
+    let rest = line.strip_prefix('<')?;
+    let end = rest.find('>')?;
+    let digits = &rest[..end];
+    if digits.is_empty() || digits.len() > 3 || !digits.bytes().all(|b| b.is_ascii_digit()) {
+        return None;
+    }
+    let pri: u8 = digits.parse().ok()?;
+    if pri > 191 {
+        return None;
+    }
+    Some((Severity::from_code(pri % 8), &rest[end + 1..]))
+}
+
+/// Map the RFC5424 NILVALUE to None
+fn nil_value(field: &str) -> Option<String> {
+    if field == "-" || field.is_empty() {
+        None
+    } else {
+        Some(field.to_string())
+    }
+}
+
+/// Skip over the STRUCTURED-DATA section and return whatever message follows it
+fn skip_structured_data(rest: &str) -> Option<&str> {
+    if let Some(msg) = rest.strip_prefix('-') {
+        return Some(msg.strip_prefix(' ').unwrap_or(msg));
+    }
+    if !rest.starts_with('[') {
+        return None;
+    }
+
+    // Walk SD-ELEMENTs, honoring quoted param values and their escapes
+    let bytes = rest.as_bytes();
+    let mut i = 0;
+    while i < bytes.len() && bytes[i] == b'[' {
+        let mut in_quotes = false;
+        i += 1;
+        while i < bytes.len() {
+            match bytes[i] {
+                b'\\' if in_quotes => i += 1,
+                b'"' => in_quotes = !in_quotes,
+                b']' if !in_quotes => break,
+                _ => {}
+            }
+            i += 1;
+        }
+        if i >= bytes.len() {
+            // Unterminated element
+            return None;
+        }
+        i += 1;
+    }
+
+    let msg = &rest[i..];
+    Some(msg.strip_prefix(' ').unwrap_or(msg))
+}
@@ -0,0 +1,139 @@
+use std::io::{self, Read};
+use encoding_rs::{Encoding, UTF_16BE, UTF_16LE};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+
+/// The character encoding of one source, or of every source, as given with `--encoding`
+#[derive(Debug, Clone)]
+pub struct EncodingSetting {
+    source: Option<String>,
+    encoding: &'static Encoding,
+}
+
+impl EncodingSetting {
+    /// Parse `[SOURCE=]ENCODING`, e.g. `utf-16le` or `legacy.log=shift_jis`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (source, label) = match value.rsplit_once('=') {
+            Some((source, label)) => (Some(source.to_string()), label),
+            None => (None, value),
+        };
+        let encoding = Encoding::for_label(label.trim().as_bytes())
+            .ok_or_else(|| format!("unknown encoding: {} (e.g. utf-16le, latin1, shift_jis)", label))?;
+        Ok(Self { source, encoding })
+    }
+}
+
+/// Encodings of the sources that don't write UTF-8
+#[derive(Debug, Clone, Default)]
+pub struct Encodings {
+    settings: Vec<EncodingSetting>,
+}
+
+impl Encodings {
+    pub fn new(settings: Vec<EncodingSetting>) -> Self {
+        Self { settings }
+    }
+
+    /// The encoding given for a source, by its name or for every source
+    pub fn get(&self, source: &str) -> Option<&'static Encoding> {
+        self.get_aliased(source, source)
+    }
+
+    /// The encoding given for a source by its name, by another name it goes by (`stdout` for
+    /// `server:stdout`), or for every source
+    pub fn get_aliased(&self, source: &str, alias: &str) -> Option<&'static Encoding> {
+        let setting = |source: Option<&str>| self.settings.iter().rev().find(|setting| setting.source.as_deref() == source);
+        setting(Some(source)).or_else(|| setting(Some(alias))).or_else(|| setting(None)).map(|setting| setting.encoding)
+    }
+}
+
+/// The encoding named by a byte order mark at the start of `bytes`
+pub fn from_bom(bytes: &[u8]) -> Option<&'static Encoding> {
+    Encoding::for_bom(bytes).map(|(encoding, _)| encoding)
+}
+
+/// Turn `reader` into UTF-8 from `encoding`, unless a byte order mark names another one.
+/// Without an encoding the bytes are passed on as they are
+pub fn decode<'a>(reader: impl Read + 'a, encoding: Option<&'static Encoding>) -> Box<dyn Read + 'a> {
+    match encoding {
+        Some(encoding) => Box::new(DecodeReaderBytesBuilder::new()
+            .encoding(Some(encoding))
+            .bom_override(true)
+            .strip_bom(true)
+            .build(reader)),
+        None => Box::new(reader),
+    }
+}
+
+/// How a source's bytes are turned into text
+#[derive(Debug, Clone, Copy)]
+pub enum Text {
+    /// Text in an encoding, or UTF-8 without one
+    Encoded(Option<&'static Encoding>),
+    /// Binary data, shown as printable ASCII with every other byte escaped (e.g. `\x00`)
+    Escaped,
+}
+
+impl Text {
+    /// Turn `reader` into UTF-8 text
+    pub fn reader<'a>(self, reader: impl Read + 'a) -> Box<dyn Read + 'a> {
+        match self {
+            Self::Encoded(encoding) => decode(reader, encoding),
+            Self::Escaped => Box::new(Escaped { reader, escaped: Vec::new(), position: 0 }),
+        }
+    }
+}
+
+/// Whether `bytes` from the start of a source are binary data rather than text. Like git and
+/// grep, a NUL byte gives it away, except in UTF-16, which has one in every ASCII character
+pub fn is_binary(bytes: &[u8], encoding: Option<&'static Encoding>) -> bool {
+    let utf16 = encoding.is_some_and(|encoding| encoding == UTF_16LE || encoding == UTF_16BE);
+    !utf16 && bytes.contains(&0)
+}
+
+/// A reader passing on printable ASCII, tabs and line breaks, and escaping everything else
+struct Escaped<R> {
+    reader: R,
+    /// Escaped bytes not passed on yet, from `position`
+    escaped: Vec<u8>,
+    position: usize,
+}
+
+impl<R: Read> Read for Escaped<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if self.position == self.escaped.len() {
+            let mut raw = [0; 4096];
+            let read = self.reader.read(&mut raw)?;
+            self.escaped.clear();
+            self.position = 0;
+            for byte in &raw[..read] {
+                match byte {
+                    b'\t' | b'\n' | b' '..=b'~' => self.escaped.push(*byte),
+                    _ => self.escaped.extend_from_slice(format!("\\x{:02x}", byte).as_bytes()),
+                }
+            }
+        }
+        let count = buf.len().min(self.escaped.len() - self.position);
+        buf[..count].copy_from_slice(&self.escaped[self.position..self.position + count]);
+        self.position += count;
+        Ok(count)
+    }
+}
+
+/// How a line break is written in an encoding, for finding lines without decoding them.
+/// Every other encoding oxtail reads writes it as a single `\n` byte
+pub fn newline(encoding: Option<&'static Encoding>) -> &'static [u8] {
+    match encoding {
+        Some(encoding) if encoding == UTF_16LE => b"\n\0",
+        Some(encoding) if encoding == UTF_16BE => b"\0\n",
+        _ => b"\n",
+    }
+}
+
+/// How a `\r` is written in an encoding, like [`newline`]
+pub fn carriage_return(encoding: Option<&'static Encoding>) -> &'static [u8] {
+    match encoding {
+        Some(encoding) if encoding == UTF_16LE => b"\r\0",
+        Some(encoding) if encoding == UTF_16BE => b"\0\r",
+        _ => b"\r",
+    }
+}
@@ -0,0 +1,138 @@
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+use anyhow::{Context, Result};
+use notify::event::{EventKind, ModifyKind};
+use notify::{RecursiveMode, Watcher};
+use regex::Regex;
+use crate::file_watcher::glob_regex;
+use crate::process_handler::ProcessHandle;
+
+/// How long matching files have to stay unchanged before the command is restarted, so saving
+/// several files at once restarts it once
+const SETTLE: Duration = Duration::from_millis(300);
+
+/// One directory level of a pattern
+#[derive(Debug, Clone)]
+enum Segment {
+    /// `**`: any number of directories, including none
+    AnyDirs,
+    Name(Regex),
+}
+
+/// Files whose changes restart the command, as given with `--watch-exec 'src/**/*.rs'`
+#[derive(Debug, Clone)]
+pub struct ChangePattern {
+    /// The directory before the first wildcard, which is watched
+    dir: PathBuf,
+    /// The rest of the pattern, matched against paths under `dir`
+    segments: Vec<Segment>,
+}
+
+impl ChangePattern {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let components: Vec<String> = Path::new(value).components()
+            .filter(|component| *component != Component::CurDir)
+            .map(|component| component.as_os_str().to_string_lossy().into_owned())
+            .collect();
+        let Some(last) = components.len().checked_sub(1) else {
+            return Err(format!("invalid pattern: {} (e.g. 'src/**/*.rs')", value));
+        };
+        // A path without wildcards watches one file in its directory
+        let first_wildcard = components.iter().position(|component| component.contains(['*', '?', '['])).unwrap_or(last);
+        let dir: PathBuf = components[..first_wildcard].iter().collect();
+        let segments = components[first_wildcard..].iter()
+            .map(|component| match component.as_str() {
+                "**" => Ok(Segment::AnyDirs),
+                glob => Regex::new(&glob_regex(glob)).map(Segment::Name).map_err(|_| format!("invalid pattern: {}", value)),
+            })
+            .collect::<Result<_, _>>()?;
+        let dir = if dir.as_os_str().is_empty() { PathBuf::from(".") } else { dir };
+        Ok(Self { dir, segments })
+    }
+
+    /// Whether the pattern reaches below its directory
+    fn recursive(&self) -> bool {
+        self.segments.len() > 1 || matches!(self.segments[..], [Segment::AnyDirs])
+    }
+
+    /// Whether `path`, relative to the pattern's directory, matches
+    fn matches(&self, path: &Path) -> bool {
+        let parts: Vec<String> = path.components().map(|part| part.as_os_str().to_string_lossy().into_owned()).collect();
+        let parts: Vec<&str> = parts.iter().map(String::as_str).collect();
+        matches_segments(&self.segments, &parts)
+    }
+}
+
+fn matches_segments(segments: &[Segment], parts: &[&str]) -> bool {
+    match segments.split_first() {
+        None => parts.is_empty(),
+        Some((Segment::AnyDirs, rest)) => (0..=parts.len()).any(|skip| matches_segments(rest, &parts[skip..])),
+        Some((Segment::Name(name), rest)) => parts.split_first()
+            .is_some_and(|(part, parts)| name.is_match(part) && matches_segments(rest, parts)),
+    }
+}
+
+/// Restart the command whenever files matching `patterns` are created, changed or removed
+pub fn start(patterns: &[ChangePattern], process: ProcessHandle) -> Result<()> {
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| {
+        // Reading files, as a build or test run does, isn't a change
+        if let Ok(event) = event {
+            if matches!(event.kind, EventKind::Create(_) | EventKind::Remove(_))
+                || matches!(event.kind, EventKind::Modify(kind) if !matches!(kind, ModifyKind::Metadata(_)))
+            {
+                for path in event.paths {
+                    let _ = tx.send(path);
+                }
+            }
+        }
+    })?;
+    // Paths in events are matched under the watched directories' full paths
+    let mut watched = Vec::new();
+    for pattern in patterns {
+        let mode = if pattern.recursive() { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        watcher.watch(&pattern.dir, mode).with_context(|| format!("Failed to watch {}", pattern.dir.display()))?;
+        let root = fs::canonicalize(&pattern.dir).unwrap_or_else(|_| pattern.dir.clone());
+        watched.push((root, pattern.clone()));
+    }
+
+    thread::spawn(move || {
+        // Events stop when the watcher is dropped
+        let _watcher = watcher;
+        let changed = |path: &Path| watched.iter().find_map(|(root, pattern)| {
+            let relative = path.strip_prefix(root).ok()?;
+            // Shown the way the pattern names it, without a leading `./`
+            pattern.matches(relative).then(|| pattern.dir.join(relative)
+                .components()
+                .filter(|component| *component != Component::CurDir)
+                .collect::<PathBuf>())
+        });
+        while let Ok(path) = rx.recv() {
+            let Some(mut last_change) = changed(&path) else {
+                continue;
+            };
+            // Other files changing, such as build output, don't hold the restart back
+            let mut deadline = Instant::now() + SETTLE;
+            loop {
+                let remaining = deadline.saturating_duration_since(Instant::now());
+                if remaining.is_zero() {
+                    break;
+                }
+                let Ok(path) = rx.recv_timeout(remaining) else {
+                    break;
+                };
+                if let Some(change) = changed(&path) {
+                    last_change = change;
+                    deadline = Instant::now() + SETTLE;
+                }
+            }
+            if !process.changed(last_change.display().to_string()) {
+                return;
+            }
+        }
+    });
+    Ok(())
+}
@@ -0,0 +1,51 @@
+use std::fs;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use anyhow::{Context, Result};
+use crate::log_entry::LogEntry;
+use crate::settings::LogSettings;
+
+/// Join entries into text, one per line, using the yank format from settings
+pub fn entries_text(entries: &[&LogEntry], settings: &LogSettings) -> String {
+    entries.iter()
+        .map(|entry| entry.yank_text(settings))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Write entries to a file, replacing any existing contents
+pub fn write_entries(path: &str, entries: &[&LogEntry], settings: &LogSettings) -> Result<()> {
+    let mut text = entries_text(entries, settings);
+    text.push('\n');
+    fs::write(path, text).with_context(|| format!("Failed to write {}", path))
+}
+
+/// Pipe entries to a shell command and return the first line of its output
+pub fn pipe_entries(command: &str, entries: &[&LogEntry], settings: &LogSettings) -> Result<String> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run {}", command))?;
+
+    let mut text = entries_text(entries, settings);
+    text.push('\n');
+    if let Some(mut stdin) = child.stdin.take() {
+        // A command that exits without reading everything isn't an error
+        let _ = stdin.write_all(text.as_bytes());
+    }
+
+    let output = child.wait_with_output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let first_line = stdout.lines().chain(stderr.lines()).next().unwrap_or("").to_string();
+
+    if output.status.success() {
+        Ok(first_line)
+    } else {
+        anyhow::bail!("{} ({})", first_line, output.status)
+    }
+}
@@ -0,0 +1,264 @@
+use std::cmp::Ordering;
+use std::fmt;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+
+/// A typed value extracted from a structured (JSON/logfmt) log line
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FieldValue {
+    Null,
+    Bool(bool),
+    Int(i64),
+    Float(f64),
+    Str(String),
+}
+
+impl FieldValue {
+    /// Coerce a bare string (e.g. a logfmt value) into the most specific type
+    pub fn coerce(raw: &str) -> Self {
+        match raw {
+            "true" => return FieldValue::Bool(true),
+            "false" => return FieldValue::Bool(false),
+            "null" => return FieldValue::Null,
+            _ => {}
+        }
+        if let Ok(i) = raw.parse::<i64>() {
+            return FieldValue::Int(i);
+        }
+        if let Ok(f) = raw.parse::<f64>() {
+            if f.is_finite() {
+                return FieldValue::Float(f);
+            }
+        }
+        FieldValue::Str(raw.to_string())
+    }
+
+    /// Convert a JSON scalar, falling back to its JSON text for arrays
+    fn from_json(value: &Value) -> Self {
+        match value {
+            Value::Null => FieldValue::Null,
+            Value::Bool(b) => FieldValue::Bool(*b),
+            Value::Number(n) => n.as_i64()
+                .map(FieldValue::Int)
+                .or_else(|| n.as_f64().map(FieldValue::Float))
+                .unwrap_or(FieldValue::Null),
+            Value::String(s) => FieldValue::Str(s.clone()),
+            other => FieldValue::Str(other.to_string()),
+        }
+    }
+
+    /// Numeric view of the value, if it has one
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            FieldValue::Int(i) => Some(*i as f64),
+            FieldValue::Float(f) => Some(*f),
+            _ => None,
+        }
+    }
+
+    /// Compare two values, numerically when both are numbers
+    pub fn compare(&self, other: &FieldValue) -> Option<Ordering> {
+        match (self, other) {
+            (FieldValue::Int(a), FieldValue::Int(b)) => Some(a.cmp(b)),
+            (a, b) if a.as_f64().is_some() && b.as_f64().is_some() => {
+                a.as_f64()?.partial_cmp(&b.as_f64()?)
+            },
+            (FieldValue::Bool(a), FieldValue::Bool(b)) => Some(a.cmp(b)),
+            (FieldValue::Null, FieldValue::Null) => Some(Ordering::Equal),
+            (FieldValue::Str(a), FieldValue::Str(b)) => Some(a.cmp(b)),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for FieldValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldValue::Null => write!(f, "null"),
+            FieldValue::Bool(b) => write!(f, "{}", b),
+            FieldValue::Int(i) => write!(f, "{}", i),
+            FieldValue::Float(x) => write!(f, "{}", x),
+            FieldValue::Str(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+/// Flatten a JSON object into dotted field names (`http.status`)
+pub fn extract_json(json: &Value) -> Vec<(String, FieldValue)> {
+    let mut fields = Vec::new();
+    if let Value::Object(map) = json {
+        for (key, value) in map {
+            flatten_json(key.clone(), value, &mut fields);
+        }
+    }
+    fields
+}
+
+fn flatten_json(prefix: String, value: &Value, fields: &mut Vec<(String, FieldValue)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten_json(format!("{}.{}", prefix, key), value, fields);
+            }
+        },
+        _ => fields.push((prefix, FieldValue::from_json(value))),
+    }
+}
+
+/// Extract `key=value` / `key="quoted value"` pairs from a logfmt line
+/// Bare words are ignored so ordinary prose containing a stray `=` doesn't
+/// sprout bogus boolean fields
+pub fn extract_logfmt(line: &str) -> Vec<(String, FieldValue)> {
+    let mut fields = Vec::new();
+    let mut chars = line.char_indices().peekable();
+
+    while let Some(&(start, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+            continue;
+        }
+
+        // Read the key up to '=' or whitespace
+        let mut key_end = start;
+        while let Some(&(i, c)) = chars.peek() {
+            if c == '=' || c.is_whitespace() {
+                break;
+            }
+            key_end = i + c.len_utf8();
+            chars.next();
+        }
+        let key = &line[start..key_end];
+
+        if chars.peek().map(|&(_, c)| c) != Some('=') {
+            continue;
+        }
+        chars.next();
+
+        // Read the value, quoted or bare
+        let value = if chars.peek().map(|&(_, c)| c) == Some('"') {
+            chars.next();
+            let mut value = String::new();
+            while let Some((_, c)) = chars.next() {
+                match c {
+                    '\\' => {
+                        if let Some((_, escaped)) = chars.next() {
+                            value.push(escaped);
+                        }
+                    },
+                    '"' => break,
+                    _ => value.push(c),
+                }
+            }
+            FieldValue::Str(value)
+        } else {
+            let value_start = chars.peek().map(|&(i, _)| i).unwrap_or(line.len());
+            let mut value_end = value_start;
+            while let Some(&(i, c)) = chars.peek() {
+                if c.is_whitespace() {
+                    break;
+                }
+                value_end = i + c.len_utf8();
+                chars.next();
+            }
+            FieldValue::coerce(&line[value_start..value_end])
+        };
+
+        if !key.is_empty() {
+            fields.push((key.to_string(), value));
+        }
+    }
+
+    fields
+}
+
+/// Comparison operators supported by `:where`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+/// A single `field<op>value` condition
+#[derive(Debug, Clone)]
+pub struct FieldCondition {
+    pub field: String,
+    pub op: CompareOp,
+    pub value: FieldValue,
+}
+
+impl FieldCondition {
+    /// Parse an expression like `duration_ms>250` or `level=error`
+    pub fn parse(expr: &str) -> Result<Self, String> {
+        // Two-character operators must be tried first
+        const OPS: [(&str, CompareOp); 7] = [
+            ("==", CompareOp::Eq),
+            ("!=", CompareOp::Ne),
+            ("<=", CompareOp::Le),
+            (">=", CompareOp::Ge),
+            ("=", CompareOp::Eq),
+            ("<", CompareOp::Lt),
+            (">", CompareOp::Gt),
+        ];
+
+        let (pos, token, op) = OPS.iter()
+            .filter_map(|(token, op)| expr.find(token).map(|pos| (pos, *token, *op)))
+            .min_by_key(|(pos, token, _)| (*pos, std::cmp::Reverse(token.len())))
+            .ok_or_else(|| format!("Invalid condition: {} (expected field<op>value)", expr))?;
+
+        let field = expr[..pos].trim();
+        let value = expr[pos + token.len()..].trim();
+        if field.is_empty() || value.is_empty() {
+            return Err(format!("Invalid condition: {}", expr));
+        }
+
+        Ok(Self {
+            field: field.to_string(),
+            op,
+            value: FieldValue::coerce(value.trim_matches('"')),
+        })
+    }
+
+    /// Check a field value against this condition
+    pub fn matches(&self, actual: Option<&FieldValue>) -> bool {
+        let actual = match actual {
+            Some(actual) => actual,
+            // Missing fields only satisfy "not equal"
+            None => return self.op == CompareOp::Ne,
+        };
+
+        let ordering = actual.compare(&self.value)
+            // Mixed types fall back to comparing the displayed text
+            .or_else(|| match self.op {
+                CompareOp::Eq | CompareOp::Ne => Some(actual.to_string().cmp(&self.value.to_string())),
+                _ => None,
+            });
+
+        match (self.op, ordering) {
+            (CompareOp::Eq, Some(o)) => o == Ordering::Equal,
+            (CompareOp::Ne, Some(o)) => o != Ordering::Equal,
+            (CompareOp::Lt, Some(o)) => o == Ordering::Less,
+            (CompareOp::Le, Some(o)) => o != Ordering::Greater,
+            (CompareOp::Gt, Some(o)) => o == Ordering::Greater,
+            (CompareOp::Ge, Some(o)) => o != Ordering::Less,
+            (_, None) => false,
+        }
+    }
+}
+
+impl fmt::Display for FieldCondition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self.op {
+            CompareOp::Eq => "=",
+            CompareOp::Ne => "!=",
+            CompareOp::Lt => "<",
+            CompareOp::Le => "<=",
+            CompareOp::Gt => ">",
+            CompareOp::Ge => ">=",
+        };
+        write!(f, "{}{}{}", self.field, op, self.value)
+    }
+}
@@ -1,143 +1,1197 @@
-use std::path::{Path, PathBuf};
-use std::sync::mpsc::Sender;
-use std::time::SystemTime;
-use anyhow::{Result, Context};
-use notify::{Watcher, RecursiveMode, Event, EventKind};
-use std::io::{self, BufReader, BufRead, Seek, SeekFrom};
-use std::fs::File;
-use chrono::Local;
-use crate::log_entry::LogEntry;
+use std::collections::{HashMap, HashSet};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::os::unix::fs::MetadataExt;
+use std::sync::mpsc::{RecvTimeoutError, Sender, SyncSender};
+use std::time::{Duration, Instant};
+use anyhow::{bail, Result, Context};
+use chrono::{DateTime, Local};
+use notify::{Watcher, RecursiveMode};
+use regex::Regex;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::fs::{File, Metadata};
+use std::os::unix::fs::{FileExt, FileTypeExt};
+use encoding_rs::Encoding;
+use crate::batch::EntryBatcher;
+use crate::checkpoints::Checkpoints;
+use crate::encoding::{self, Encodings, Text};
+use crate::line_reader::cr_lines;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin};
+use crate::settings::parse_duration;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// Lines read from the end of each file at startup, unless `--tail-lines` says otherwise
+pub const DEFAULT_TAIL_LINES: usize = 10_000;
+
+/// How long a rotated file is still read, for lines written to it just before rotation
+const ROTATED_GRACE: Duration = Duration::from_secs(5);
+
+/// How often to look for a file that doesn't exist yet, when its directory doesn't either
+const MISSING_POLL: Duration = Duration::from_secs(1);
+
+/// How often rotated files and unfinished lines are checked, whatever else is happening
+const CHECK_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How often checkpoints are saved with `--resume`, besides when oxtail exits
+const CHECKPOINT_INTERVAL: Duration = Duration::from_secs(5);
+
+/// Bytes at the start of a file checked for binary data
+const BINARY_CHECK: usize = 8000;
+
+/// How often the next chunk of a large file's existing lines is read, so they come in at
+/// `--load-rate` instead of all at once
+const LOAD_INTERVAL: Duration = Duration::from_millis(50);
+
+/// How long to wait before reopening a character device that reached its end
+const REOPEN_DELAY: Duration = Duration::from_secs(1);
+
+/// What a watched path stands for once the file it names is renamed or removed, like
+/// `tail --follow=name` and `tail --follow=descriptor`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Follow {
+    /// Whatever file has the name: a new file created in its place (e.g. by logrotate)
+    /// is read from the beginning
+    Name,
+    /// The file that was opened, wherever it's moved to
+    Descriptor,
+}
+
+/// What to do with a file holding binary data, which would garble the screen shown as text
+#[derive(Debug, Clone, Copy, PartialEq, Eq, clap::ValueEnum)]
+pub enum Binary {
+    /// Leave it out, with a note on the prompt line
+    Skip,
+    /// Show it with every byte that isn't printable ASCII escaped, e.g. `\x00`
+    Escape,
+}
+
+/// How files are read and followed
+#[derive(Debug, Clone)]
+pub struct WatchOptions {
+    /// Lines read from the end of each file at first, or all of them if 0
+    pub tail_lines: usize,
+    pub follow: Follow,
+    pub poll: Vec<PollSetting>,
+    /// How long an unfinished last line waits for the rest before it's shown anyway
+    pub partial_timeout: Duration,
+    /// Encodings of files that aren't UTF-8, when they don't start with a byte order mark
+    pub encodings: Encodings,
+    /// Blank lines are entries too, instead of being skipped
+    pub keep_empty_lines: bool,
+    pub binary: Binary,
+    /// A symlink is followed to whatever it points to now, even following by descriptor, and
+    /// pointing it elsewhere switches to the new target's last lines
+    pub resolve_symlinks: bool,
+    /// Bytes of a file's existing lines read per second, so a large file doesn't flood the UI
+    pub load_rate: usize,
+    /// With `--resume`, where to keep how far each file was read, to carry on from there
+    pub checkpoints: Option<PathBuf>,
+}
+
+impl WatchOptions {
+    /// How often to check a file or pattern directory, if it's polled
+    fn poll_interval(&self, path: &Path) -> Option<Duration> {
+        let setting = |path: Option<&Path>| self.poll.iter().rev().find(|poll| poll.path.as_deref() == path);
+        setting(Some(path)).or_else(|| setting(None)).map(|poll| poll.interval)
+    }
+}
+
+/// Checking files on a timer instead of waiting for filesystem events, which network mounts
+/// and some container volumes never send: `2s` for every file, or `app.log=2s` for one file
+/// or pattern directory
+#[derive(Debug, Clone)]
+pub struct PollSetting {
+    path: Option<PathBuf>,
+    interval: Duration,
+}
+
+impl PollSetting {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (path, interval) = match value.rsplit_once('=') {
+            Some((path, interval)) => (Some(normalize(Path::new(path))), interval),
+            None => (None, value),
+        };
+        let interval = parse_duration(interval)
+            .filter(|interval| !interval.is_zero())
+            .ok_or_else(|| format!("invalid interval: {} (e.g. 2s, 500ms)", interval))?;
+        Ok(Self { path, interval })
+    }
+}
+
+/// What a poll looks at
+enum Polled {
+    File(PathBuf),
+    Pattern(FilePattern),
+}
+
+/// A file or pattern directory checked on a timer
+struct Poll {
+    target: Polled,
+    interval: Duration,
+    due: Instant,
+}
+
+/// Compressed formats, told apart by extension. Compressed files are rotated history, so
+/// they're read whole once instead of being followed
+#[derive(Debug, Clone, Copy)]
+enum Compression {
+    Gzip,
+    Zstd,
+    Bzip2,
+}
+
+impl Compression {
+    fn of(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "gz" => Some(Self::Gzip),
+            "zst" | "zstd" => Some(Self::Zstd),
+            "bz2" => Some(Self::Bzip2),
+            _ => None,
+        }
+    }
+}
 
 struct FileState {
-    last_modified: SystemTime,
-    last_size: u64,
+    /// The file as opened. Following by name, it's swapped for the file that replaces it
+    file: File,
+    /// Device and inode of `file`, to notice when the path names a different file
+    identity: (u64, u64),
+    /// Encoding of the file's text, when it isn't UTF-8
+    encoding: Option<&'static Encoding>,
+    /// The file holds binary data, so it's skipped or shown escaped
+    binary: bool,
     last_position: u64,
+    /// Offset of the earliest line read so far; earlier lines come in with `:load_more`
+    loaded_from: u64,
+    /// The file this one replaced, still read for a little while
+    rotated: Option<RotatedFile>,
+    /// The next line read replaces the last one shown: that ended with `\r`, or was shown
+    /// before it was finished
+    redraw_next: bool,
+    /// A last line that hasn't been finished yet, after `last_position`
+    partial: Option<PartialLine>,
+    /// Where the file's existing lines end, while they're still being read a chunk at a time
+    loading: Option<u64>,
+}
+
+/// The unfinished end of a file, not read until the writer finishes the line or goes quiet
+struct PartialLine {
+    /// Size of the file when the line last grew
+    size: u64,
+    since: Instant,
+    shown: bool,
+}
+
+/// A file that was rotated away, read until `until` in case something is still writing to it
+struct RotatedFile {
+    file: File,
+    position: u64,
+    until: Instant,
+}
+
+impl FileState {
+    fn text(&self) -> Text {
+        if self.binary { Text::Escaped } else { Text::Encoded(self.encoding) }
+    }
+
+    /// Binary data that isn't read at all
+    fn skipped(&self, options: &WatchOptions) -> bool {
+        self.binary && options.binary == Binary::Skip
+    }
+}
+
+fn identity(metadata: &Metadata) -> (u64, u64) {
+    (metadata.dev(), metadata.ino())
+}
+
+/// Messages for the watcher thread: file events, and requests from the UI
+enum WatchMessage {
+    Event(notify::Result<notify::Event>),
+    LoadMore(Option<String>),
+    /// Start watching another file
+    Open(PathBuf),
+    /// Stop watching a file, by source name, answering whether it was being watched
+    Close(String, Sender<bool>),
+    /// Save checkpoints before oxtail exits, answering once they're written
+    Finish(Sender<Result<()>>),
+}
+
+/// Handle on the thread watching the files
+#[derive(Clone)]
+pub struct FileWatcher {
+    tx: Sender<WatchMessage>,
+}
+
+impl FileWatcher {
+    /// Read earlier lines of the named file, or of every file that wasn't read in full
+    pub fn load_more(&self, source: Option<String>) {
+        let _ = self.tx.send(WatchMessage::LoadMore(source));
+    }
+
+    /// Start reading and following a file, as if it had been named on the command line
+    pub fn open(&self, path: PathBuf) {
+        let _ = self.tx.send(WatchMessage::Open(path));
+    }
+
+    /// Stop following the file a source reads, returning false if no file has that name
+    pub fn close(&self, source: &str) -> bool {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        self.tx.send(WatchMessage::Close(source.to_string(), done_tx)).is_ok()
+            && done_rx.recv_timeout(Duration::from_secs(2)).unwrap_or(false)
+    }
+
+    /// Save how far each file was read, for `--resume`, waiting a moment for it to be done
+    pub fn finish(&self) -> Result<()> {
+        let (done_tx, done_rx) = std::sync::mpsc::channel();
+        if self.tx.send(WatchMessage::Finish(done_tx)).is_ok() {
+            if let Ok(result) = done_rx.recv_timeout(Duration::from_secs(2)) {
+                return result;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Files in a directory whose names match a glob (`*`, `?`, `[...]`), including files
+/// created after oxtail starts
+#[derive(Debug, Clone)]
+pub struct FilePattern {
+    /// Directory the files are in, empty for the current directory
+    dir: PathBuf,
+    glob: String,
+    name: Regex,
 }
 
-pub fn start_watching(files: Vec<PathBuf>, tx: Sender<LogEntry>) -> Result<()> {
-    // First, read the current contents of all files
+impl FilePattern {
+    /// The pattern in a path like `logs/*.log`, or None if the path has no wildcards
+    pub fn parse(path: &Path) -> Result<Option<Self>> {
+        let path = normalize(path);
+        let Some(glob) = path.file_name().and_then(|name| name.to_str()).filter(|name| has_wildcards(name)) else {
+            return Ok(None);
+        };
+        let dir = path.parent().unwrap_or(Path::new(""));
+        if has_wildcards(&dir.to_string_lossy()) {
+            bail!("Wildcards are only supported in file names: {}", path.display());
+        }
+        Self::new(dir, glob).map(Some)
+    }
+
+    /// Every file in a directory
+    pub fn directory(dir: &Path) -> Result<Self> {
+        if !dir.is_dir() {
+            bail!("Not a directory: {}", dir.display());
+        }
+        Self::new(&normalize(dir), "*")
+    }
+
+    fn new(dir: &Path, glob: &str) -> Result<Self> {
+        let name = Regex::new(&glob_regex(glob)).with_context(|| format!("Invalid pattern: {}", glob))?;
+        Ok(Self { dir: dir.to_path_buf(), glob: glob.to_string(), name })
+    }
+
+    /// Directory to list and watch
+    fn watch_dir(&self) -> &Path {
+        if self.dir.as_os_str().is_empty() { Path::new(".") } else { &self.dir }
+    }
+
+    /// Whether a (normalized) path is one of the pattern's files. Like in a shell,
+    /// wildcards don't match a leading `.`
+    fn matches(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        path.parent() == Some(self.dir.as_path())
+            && (!name.starts_with('.') || self.glob.starts_with('.'))
+            && self.name.is_match(name)
+    }
+
+    /// Matching files that exist now, in name order
+    fn existing(&self) -> Result<Vec<PathBuf>> {
+        let entries = std::fs::read_dir(self.watch_dir())
+            .with_context(|| format!("Failed to read directory {}", self.watch_dir().display()))?;
+        let mut files: Vec<PathBuf> = entries
+            .filter_map(|entry| entry.ok())
+            .map(|entry| normalize(&entry.path()))
+            .filter(|path| self.matches(path) && path.is_file())
+            .collect();
+        files.sort();
+        Ok(files)
+    }
+}
+
+fn has_wildcards(text: &str) -> bool {
+    text.contains(['*', '?', '['])
+}
+
+/// Anchored regex for a file name glob
+pub fn glob_regex(glob: &str) -> String {
+    let mut regex = String::from("^");
+    let mut chars = glob.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match ch {
+            '*' => regex.push_str(".*"),
+            '?' => regex.push('.'),
+            '[' => {
+                regex.push('[');
+                if chars.next_if(|&ch| ch == '!' || ch == '^').is_some() {
+                    regex.push('^');
+                }
+                for ch in chars.by_ref() {
+                    if ch == ']' {
+                        break;
+                    }
+                    if ch == '\\' || ch == '[' {
+                        regex.push('\\');
+                    }
+                    regex.push(ch);
+                }
+                regex.push(']');
+            },
+            _ => regex.push_str(&regex::escape(&ch.to_string())),
+        }
+    }
+    regex.push('$');
+    regex
+}
+
+/// A path without `.` components, so the same file named as `app.log` or `./app.log`
+/// (as a directory watch reports it) is recognized
+fn normalize(path: &Path) -> PathBuf {
+    path.components().filter(|component| *component != Component::CurDir).collect()
+}
+
+/// Start watching `files` and the files matching `patterns`, first reading their last
+/// lines. Files matching a pattern later are picked up as they're created
+pub fn start_watching(
+    files: Vec<PathBuf>,
+    patterns: Vec<FilePattern>,
+    options: WatchOptions,
+    tx: SyncSender<Vec<LogEntry>>,
+    status: SourceStatusRegistry,
+) -> Result<FileWatcher> {
+    let mut files: Vec<PathBuf> = files.iter().map(|file| normalize(file)).collect();
+    for pattern in &patterns {
+        for file in pattern.existing()? {
+            if !files.contains(&file) {
+                files.push(file);
+            }
+        }
+    }
+
+    // Named pipes and character devices are read as streams, each on a thread of its own
+    let (streams, files): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter().partition(|file| is_stream(file));
+    for stream in streams {
+        read_stream(stream, &options, tx.clone(), status.clone());
+    }
+
+    // Report unreadable files up front; their contents are read on the watcher thread, since
+    // the UI has to be running to take entries off the channel. Files that don't exist yet
+    // are read once they're created, like `tail -F`
+    let mut missing = Vec::new();
     for file in &files {
-        read_file_contents(file, &tx)?;
+        match File::open(file) {
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound && Compression::of(file).is_none() => {
+                missing.push(file.clone());
+            },
+            result => {
+                result.with_context(|| format!("Failed to open {}", file.display()))?;
+            },
+        }
     }
+    let (compressed, files): (Vec<PathBuf>, Vec<PathBuf>) = files.into_iter()
+        .partition(|file| Compression::of(file).is_some());
 
-    // Create a channel for notify events
+    // File events and UI requests arrive on one channel
     let (watcher_tx, watcher_rx) = std::sync::mpsc::channel();
+    let events_tx = watcher_tx.clone();
 
     // Create a watcher
-    let mut watcher = notify::recommended_watcher(watcher_tx)?;
+    let mut watcher = notify::recommended_watcher(move |event| {
+        let _ = events_tx.send(WatchMessage::Event(event));
+    })?;
 
-    // Start watching each file
+    // Start watching each file, and the directories new files may turn up in, unless
+    // they're polled instead
+    let mut polls = Vec::new();
+    let mut poll = |target: Polled, interval: Duration| polls.push(Poll { target, interval, due: Instant::now() + interval });
+    let mut dirs: Vec<&Path> = Vec::new();
     for file in &files {
-        watcher.watch(file, RecursiveMode::NonRecursive)?;
+        let dir = match file.parent() {
+            Some(dir) if !dir.as_os_str().is_empty() => dir,
+            _ => Path::new("."),
+        };
+        match options.poll_interval(file) {
+            Some(interval) => poll(Polled::File(file.clone()), interval),
+            // A file that's yet to be created turns up in its directory, if there is one
+            None if missing.contains(file) => {
+                if dir.is_dir() {
+                    dirs.push(dir);
+                } else {
+                    poll(Polled::File(file.clone()), MISSING_POLL);
+                }
+            },
+            None => {
+                watcher.watch(file, RecursiveMode::NonRecursive)?;
+                // A file replacing a watched one, or a symlink being pointed elsewhere, is only
+                // seen by watching its directory
+                if options.follow == Follow::Name || (options.resolve_symlinks && file.is_symlink()) {
+                    dirs.push(dir);
+                }
+            },
+        }
+        let state = if missing.contains(file) { SourceState::Waiting } else { SourceState::Running };
+        status.set(&source_name(file), state);
+    }
+    for file in &compressed {
+        status.set(&source_name(file), SourceState::Running);
+    }
+    for pattern in &patterns {
+        match options.poll_interval(&pattern.dir) {
+            Some(interval) => poll(Polled::Pattern(pattern.clone()), interval),
+            None => dirs.push(pattern.watch_dir()),
+        }
+    }
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        watcher.watch(dir, RecursiveMode::NonRecursive)
+            .with_context(|| format!("Failed to watch {}", dir.display()))?;
     }
 
     // Spawn a thread to handle file changes
     std::thread::spawn(move || {
-        let mut file_states: std::collections::HashMap<PathBuf, FileState> = std::collections::HashMap::new();
-
-        for res in watcher_rx {
-            match res {
-                Ok(event) => {
-                    if event.kind.is_modify() {
-                        for path in event.paths {
-                            // Check if the file was modified since our last read
-                            let metadata = match std::fs::metadata(&path) {
-                                Ok(m) => m,
-                                Err(_) => continue,
-                            };
-
-                            let modified = metadata.modified().unwrap_or(SystemTime::now());
-                            let current_size = metadata.len();
-
-                            let state = file_states.entry(path.clone()).or_insert(FileState {
-                                last_modified: modified,
-                                last_size: current_size,
-                                last_position: 0,
-                            });
-
-                            // Skip if modification time hasn't changed
-                            if state.last_modified >= modified {
-                                continue;
-                            }
+        // Watching stops when the watcher is dropped
+        let mut watcher = watcher;
+        // Files can be opened and closed as oxtail runs
+        let mut files = files;
+        let mut polls = polls;
+        let mut closed: HashSet<PathBuf> = HashSet::new();
+        let mut file_states: HashMap<PathBuf, FileState> = HashMap::new();
+        let mut checkpoints = options.checkpoints.as_deref().map(Checkpoints::load);
 
-                            // Update the state
-                            state.last_modified = modified;
+        // First, read the history in compressed files, then the end of each file
+        for file in &compressed {
+            if let Err(e) = read_compressed(file, &options, &tx, &status) {
+                status.announce(format!("Error: Failed to read {}: {:#}", file.display(), e));
+            }
+            status.set(&source_name(file), SourceState::Stopped);
+        }
+        for file in files.iter().filter(|file| !missing.contains(file)) {
+            match read_file_contents(file, &options, checkpoints.as_ref(), &tx, &status) {
+                Ok(state) => {
+                    file_states.insert(file.clone(), state);
+                },
+                Err(e) => status.announce(format!("Error: Failed to read {}: {:#}", file.display(), e)),
+            }
+        }
 
-                            // Handle file truncation
-                            if current_size < state.last_size {
-                                state.last_position = 0;
+        // Wake up often enough for the most frequent poll
+        let mut tick = polls.iter().map(|poll| poll.interval).fold(CHECK_INTERVAL, Duration::min);
+        let mut checked = Instant::now();
+        let mut loaded = Instant::now();
+        let mut saved = Instant::now();
+        loop {
+            let now = Instant::now();
+            // Rotated files may still get a last few lines, and unfinished lines may time out,
+            // without any event for them
+            if now.duration_since(checked) >= CHECK_INTERVAL {
+                checked = now;
+                for (path, state) in file_states.iter_mut() {
+                    let mut result = read_rotated(path, &options, &tx, state);
+                    if state.partial.as_ref().is_some_and(|partial| !partial.shown) {
+                        result = result.and_then(|_| read_new_content(path, &options, &tx, state));
+                    }
+                    if let Err(e) = result {
+                        status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e));
+                    }
+                }
+            }
+            // Large files keep loading between events, a chunk at a time
+            let loading = file_states.values().any(|state| state.loading.is_some());
+            if loading && now.duration_since(loaded) >= LOAD_INTERVAL {
+                loaded = now;
+                for (path, state) in file_states.iter_mut().filter(|(_, state)| state.loading.is_some()) {
+                    if let Err(e) = load_chunk(path, &options, &tx, &status, state) {
+                        status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e));
+                        state.loading = None;
+                        status.set_loading(&source_name(path), None);
+                    }
+                }
+            }
+            if let (Some(checkpoints), Some(path)) = (&mut checkpoints, &options.checkpoints) {
+                if now.duration_since(saved) >= CHECKPOINT_INTERVAL {
+                    saved = now;
+                    // The screen belongs to the UI
+                    if let Err(e) = save_checkpoints(path, checkpoints, &file_states) {
+                        status.announce(format!("Error: {:#}", e));
+                    }
+                }
+            }
+            for poll in polls.iter_mut().filter(|poll| poll.due <= now) {
+                poll.due = now + poll.interval;
+                match &poll.target {
+                    Polled::File(path) => match file_states.get_mut(path) {
+                        Some(state) => {
+                            if let Err(e) = follow_file(path, &options, &tx, &status, state) {
+                                status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e));
                             }
-                            state.last_size = current_size;
+                        },
+                        None => add_file(path, true, &options, &tx, &status, &mut file_states),
+                    },
+                    Polled::Pattern(pattern) => match pattern.existing() {
+                        Ok(paths) => {
+                            for path in paths {
+                                if !file_states.contains_key(&path) && !closed.contains(&path) {
+                                    add_file(&path, files.contains(&path), &options, &tx, &status, &mut file_states);
+                                }
+                            }
+                        },
+                        Err(e) => status.announce(format!("Error: {:#}", e)),
+                    },
+                }
+            }
 
-                            // Read new content
-                            if let Err(e) = read_new_content(&path, &tx, state) {
-                                eprintln!("Error reading file {}: {}", path.display(), e);
+            let wait = if loading { LOAD_INTERVAL.min(tick) } else { tick };
+            let message = match watcher_rx.recv_timeout(wait) {
+                Ok(message) => message,
+                Err(RecvTimeoutError::Timeout) => continue,
+                Err(RecvTimeoutError::Disconnected) => break,
+            };
+            match message {
+                WatchMessage::Event(Ok(event)) => {
+                    if event.kind.is_access() {
+                        continue;
+                    }
+                    for path in event.paths.iter().map(|path| normalize(path)) {
+                        // A file we aren't reading yet: one that's new in a watched
+                        // directory, or that couldn't be read at first
+                        if !file_states.contains_key(&path) {
+                            let listed = files.contains(&path);
+                            let matched = !closed.contains(&path) && patterns.iter().any(|pattern| pattern.matches(&path));
+                            if listed || matched {
+                                add_file(&path, listed, &options, &tx, &status, &mut file_states);
                             }
+                            continue;
+                        }
+
+                        let Some(state) = file_states.get_mut(&path) else {
+                            continue;
+                        };
+                        match follow_file(&path, &options, &tx, &status, state) {
+                            // Writes to the new file are only seen once the path is watched again,
+                            // when it isn't in the same directory
+                            Ok(true) => {
+                                let _ = watcher.unwatch(&path);
+                                if let Err(e) = watcher.watch(&path, RecursiveMode::NonRecursive) {
+                                    status.announce(format!("Error: Failed to watch {}: {}", path.display(), e));
+                                }
+                            },
+                            Ok(false) => {},
+                            Err(e) => status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e)),
                         }
                     }
                 }
-                Err(e) => eprintln!("Watch error: {}", e),
+                WatchMessage::Event(Err(e)) => status.announce(format!("Error: Failed to watch for changes: {}", e)),
+                WatchMessage::LoadMore(source) => {
+                    for (path, state) in file_states.iter_mut() {
+                        if source.as_ref().is_some_and(|source| *source != source_name(path)) {
+                            continue;
+                        }
+                        if let Err(e) = read_earlier_lines(path, &options, &tx, state) {
+                            status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e));
+                        }
+                    }
+                },
+                WatchMessage::Open(path) => {
+                    let path = normalize(&path);
+                    if files.contains(&path) || file_states.contains_key(&path) {
+                        status.announce(format!("{} is already open", path.display()));
+                        continue;
+                    }
+                    closed.remove(&path);
+                    if let Err(e) = open_file(&path, &options, &tx, &status, &mut watcher, &mut polls, &mut file_states) {
+                        status.announce(format!("{:#}", e));
+                        continue;
+                    }
+                    // Pipes and compressed files are read once, with nothing to close
+                    if file_states.contains_key(&path) {
+                        files.push(path);
+                    }
+                    tick = polls.iter().map(|poll| poll.interval).fold(CHECK_INTERVAL, Duration::min);
+                },
+                WatchMessage::Close(source, done) => {
+                    // Save where the file was read up to before forgetting about it
+                    if let (Some(checkpoints), Some(path)) = (&mut checkpoints, &options.checkpoints) {
+                        if let Err(e) = save_checkpoints(path, checkpoints, &file_states) {
+                            status.announce(format!("Error: {:#}", e));
+                        }
+                    }
+                    let paths: Vec<PathBuf> = files.iter().chain(file_states.keys())
+                        .filter(|path| source_name(path) == source)
+                        .cloned()
+                        .collect();
+                    for path in &paths {
+                        file_states.remove(path);
+                        let _ = watcher.unwatch(path);
+                        closed.insert(path.clone());
+                    }
+                    files.retain(|file| !paths.contains(file));
+                    polls.retain(|poll| !matches!(&poll.target, Polled::File(file) if paths.contains(file)));
+                    if !paths.is_empty() {
+                        status.set(&source, SourceState::Stopped);
+                        status.set_loading(&source, None);
+                    }
+                    let _ = done.send(!paths.is_empty());
+                },
+                WatchMessage::Finish(done) => {
+                    // The screen is gone by now, so the error goes back to be printed
+                    let result = match (&mut checkpoints, &options.checkpoints) {
+                        (Some(checkpoints), Some(path)) => save_checkpoints(path, checkpoints, &file_states),
+                        _ => Ok(()),
+                    };
+                    let _ = done.send(result);
+                },
             }
         }
     });
 
+    Ok(FileWatcher { tx: watcher_tx })
+}
+
+/// Start reading a file that's new in a watched directory, or that didn't exist or couldn't
+/// be read at first
+fn add_file(
+    path: &Path,
+    listed: bool,
+    options: &WatchOptions,
+    tx: &SyncSender<Vec<LogEntry>>,
+    status: &SourceStatusRegistry,
+    file_states: &mut HashMap<PathBuf, FileState>,
+) {
+    // Compressed files turning up later hold lines already read as they were written
+    if Compression::of(path).is_some() || !path.is_file() {
+        return;
+    }
+    match read_file_contents(path, options, None, tx, status) {
+        Ok(state) => {
+            file_states.insert(path.to_path_buf(), state);
+            status.set(&source_name(path), SourceState::Running);
+            if listed {
+                status.announce(format!("{} was created", path.display()));
+            } else {
+                status.announce(format!("Watching new file {}", path.display()));
+            }
+        },
+        Err(e) => status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e)),
+    }
+}
+
+/// Start reading a file opened with `:open`, watching or polling it like the files oxtail
+/// started with
+fn open_file(
+    path: &Path,
+    options: &WatchOptions,
+    tx: &SyncSender<Vec<LogEntry>>,
+    status: &SourceStatusRegistry,
+    watcher: &mut notify::RecommendedWatcher,
+    polls: &mut Vec<Poll>,
+    file_states: &mut HashMap<PathBuf, FileState>,
+) -> Result<()> {
+    if is_stream(path) {
+        read_stream(path.to_path_buf(), options, tx.clone(), status.clone());
+    } else if Compression::of(path).is_some() {
+        status.set(&source_name(path), SourceState::Running);
+        read_compressed(path, options, tx, status)?;
+        status.set(&source_name(path), SourceState::Stopped);
+    } else {
+        let state = read_file_contents(path, options, None, tx, status)?;
+        match options.poll_interval(path) {
+            Some(interval) => polls.push(Poll { target: Polled::File(path.to_path_buf()), interval, due: Instant::now() + interval }),
+            None => {
+                watcher.watch(path, RecursiveMode::NonRecursive)
+                    .with_context(|| format!("Failed to watch {}", path.display()))?;
+                if options.follow == Follow::Name || (options.resolve_symlinks && path.is_symlink()) {
+                    let dir = path.parent().filter(|dir| !dir.as_os_str().is_empty()).unwrap_or(Path::new("."));
+                    watcher.watch(dir, RecursiveMode::NonRecursive)
+                        .with_context(|| format!("Failed to watch {}", dir.display()))?;
+                }
+            },
+        }
+        file_states.insert(path.to_path_buf(), state);
+        status.set(&source_name(path), SourceState::Running);
+    }
+    status.announce(format!("Opened {}", path.display()));
     Ok(())
 }
 
-fn read_file_contents(path: &Path, tx: &Sender<LogEntry>) -> Result<()> {
-    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
-    let reader = BufReader::new(file);
-    let source = path.file_name()
+/// Whether a path is a named pipe or character device, which can't be tailed like a file:
+/// opening a pipe waits for a writer, and neither has an end to seek to
+fn is_stream(path: &Path) -> bool {
+    std::fs::metadata(path).is_ok_and(|metadata| {
+        let file_type = metadata.file_type();
+        file_type.is_fifo() || file_type.is_char_device()
+    })
+}
+
+/// Read a named pipe or character device until it closes, then open it again for the next
+/// writer, for as long as oxtail runs
+fn read_stream(path: PathBuf, options: &WatchOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let source: Arc<str> = source_name(&path).into();
+    let encoding = options.encodings.get(&source);
+    let keep_empty_lines = options.keep_empty_lines;
+    status.set(&source, SourceState::Running);
+    std::thread::spawn(move || {
+        let mut batcher = EntryBatcher::new(tx).with_origin(Some(file_origin(&path)));
+        'reopen: loop {
+            // Opening a pipe waits until something opens it to write
+            let file = match File::open(&path) {
+                Ok(file) => file,
+                Err(e) => {
+                    // The screen belongs to the UI
+                    status.announce(format!("Error: Failed to open {}: {:#}", path.display(), e));
+                    break;
+                },
+            };
+            let mut lines = cr_lines(BufReader::new(encoding::decode(file, encoding)));
+            while let Some(line) = lines.next() {
+                match line {
+                    Ok(line) if keep_empty_lines || !line.text.is_empty() => {
+                        let mut entry = LogEntry::new(source.clone(), line.text);
+                        entry.redraw = line.redraw;
+                        // Send what's collected before waiting on the writer for more
+                        if batcher.push(entry).is_err() || (!lines.is_buffered() && batcher.flush().is_err()) {
+                            break 'reopen; // Channel closed
+                        }
+                    },
+                    Ok(_) => continue,
+                    Err(e) => {
+                        status.announce(format!("Error: Failed to read {}: {:#}", path.display(), e));
+                        break;
+                    },
+                }
+            }
+            if batcher.flush().is_err() {
+                break;
+            }
+            // The last writer closed the pipe. A device at its end would be reopened straight
+            // away, so give it a moment
+            if !std::fs::metadata(&path).is_ok_and(|metadata| metadata.file_type().is_fifo()) {
+                std::thread::sleep(REOPEN_DELAY);
+            }
+        }
+        status.set(&source, SourceState::Stopped);
+    });
+}
+
+/// Where a file's entries come from: its absolute `path`, for `:where`
+fn file_origin(path: &Path) -> Origin {
+    let path = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    log_entry::origin([("path", FieldValue::Str(path.display().to_string()))])
+}
+
+/// Source name used for a watched file
+fn source_name(path: &Path) -> String {
+    path.file_name()
         .and_then(|n| n.to_str())
         .unwrap_or("unknown")
-        .to_string();
+        .to_string()
+}
 
-    for line in reader.lines() {
-        let content = line.with_context(|| format!("Failed to read line from {}", path.display()))?;
-        if !content.is_empty() {
-            tx.send(LogEntry {
-                timestamp: Local::now(),
-                source: source.clone(),
-                content: content.clone(),
-                content_plain: content,
-                line_number: 0,  // Will be set by LogSource
-                is_json: false,  // Let LogEntry handle JSON detection
-            })?;
+/// Offset of the start of the last `lines` lines before `end`
+/// Reads backwards a chunk at a time, so only the end of a large file is touched
+fn tail_offset(file: &mut File, end: u64, lines: usize, encoding: Option<&'static Encoding>) -> Result<u64> {
+    const CHUNK: u64 = 64 * 1024;
+    // Line breaks are found a character at a time, which is two bytes in UTF-16
+    let newline = encoding::newline(encoding);
+    let width = newline.len() as u64;
+    let mut buffer = vec![0; CHUNK as usize];
+    let mut position = end - end % width;
+    // The newline at the very end finishes the last line rather than starting one
+    let mut newlines = 0;
+    let mut skip_last = true;
+    while position > 0 {
+        let start = position.saturating_sub(CHUNK);
+        let chunk = &mut buffer[..(position - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        for (index, character) in chunk.chunks_exact(width as usize).enumerate().rev() {
+            if character != newline {
+                skip_last = false;
+                continue;
+            }
+            if std::mem::take(&mut skip_last) {
+                continue;
+            }
+            newlines += 1;
+            if newlines == lines {
+                return Ok(start + (index as u64 + 1) * width);
+            }
         }
+        position = start;
+    }
+    Ok(0)
+}
+
+/// The encoding a file's byte order mark names, or else the one it was given
+fn file_encoding(file: &File, configured: Option<&'static Encoding>) -> Result<Option<&'static Encoding>> {
+    let mut bom = [0; 3];
+    let read = file.read_at(&mut bom, 0)?;
+    Ok(encoding::from_bom(&bom[..read]).or(configured))
+}
+
+/// Whether a file holds binary data, going by its first bytes
+fn file_binary(file: &File, encoding: Option<&'static Encoding>) -> Result<bool> {
+    let mut start = vec![0; BINARY_CHECK];
+    let read = file.read_at(&mut start, 0)?;
+    Ok(encoding::is_binary(&start[..read], encoding))
+}
+
+/// Tell the user a file holds binary data, and what's done with it
+fn announce_binary(path: &Path, options: &WatchOptions, status: &SourceStatusRegistry) {
+    match options.binary {
+        Binary::Skip => status.announce(format!("{} looks like binary data; skipped (--binary escape shows it)", source_name(path))),
+        Binary::Escape => status.announce(format!("{} looks like binary data; showing it escaped", source_name(path))),
     }
+}
 
+/// Offset just past the last line break between `start` and `end` (`start` if there's none),
+/// and whether that break is a `\r`
+fn line_end(file: &File, start: u64, end: u64, encoding: Option<&'static Encoding>) -> Result<(u64, bool)> {
+    const CHUNK: u64 = 64 * 1024;
+    let newline = encoding::newline(encoding);
+    let carriage_return = encoding::carriage_return(encoding);
+    let width = newline.len() as u64;
+    let mut buffer = vec![0; CHUNK as usize];
+    let mut position = end - end % width;
+    while position > start {
+        let chunk_start = position.saturating_sub(CHUNK).max(start);
+        let chunk = &mut buffer[..(position - chunk_start) as usize];
+        file.read_exact_at(chunk, chunk_start)?;
+        let found = chunk.chunks_exact(width as usize)
+            .rposition(|character| character == newline || character == carriage_return);
+        if let Some(index) = found {
+            let after = chunk_start + (index as u64 + 1) * width;
+            return Ok((after, chunk[index * width as usize..].starts_with(carriage_return)));
+        }
+        position = chunk_start;
+    }
+    Ok((start, false))
+}
+
+/// Read `reader` into entries of the file at `path`, dated `timestamp` if given rather than
+/// when they're read. With `redraw_first`, the first line replaces the last one read from the
+/// source
+fn send_lines(
+    reader: impl Read,
+    text: Text,
+    path: &Path,
+    placement: Placement,
+    redraw_first: bool,
+    keep_empty_lines: bool,
+    tx: &SyncSender<Vec<LogEntry>>,
+) -> Result<()> {
+    let source: Arc<str> = source_name(path).into();
+    let mut batcher = EntryBatcher::new(tx.clone()).with_origin(Some(file_origin(path)));
+    let mut lines = cr_lines(BufReader::new(text.reader(reader)));
+    if redraw_first {
+        lines = lines.redraw_first();
+    }
+    let mut earlier = Vec::new();
+    for line in lines {
+        let line = line?;
+        if keep_empty_lines || !line.text.is_empty() {
+            // Line number will be set by LogSource
+            let mut entry = LogEntry::new(source.clone(), line.text);
+            // Lines from before the latest are new entries, never redraws of it
+            match placement {
+                Placement::Arrival => entry.redraw = line.redraw,
+                Placement::At(timestamp) => entry.set_timestamp_millis(timestamp),
+                Placement::Earlier => {
+                    entry.earlier = true;
+                    earlier.push(entry);
+                    continue;
+                },
+            }
+            batcher.push(entry)?;
+        }
+    }
+    // Each earlier line goes before the source's first entry, so the last goes in first
+    for entry in earlier.into_iter().rev() {
+        batcher.push(entry)?;
+    }
+    batcher.flush()?;
     Ok(())
 }
 
-fn read_new_content(path: &Path, tx: &Sender<LogEntry>, state: &mut FileState) -> Result<()> {
-    let mut file = File::open(path)?;
-    
-    // First seek to the last position
-    file.seek(SeekFrom::Start(state.last_position))?;
-    
-    // Create reader after getting current position
-    let reader = BufReader::new(&file);
-    let source = path.file_name()
-        .and_then(|n| n.to_str())
-        .unwrap_or("unknown")
-        .to_string();
+/// Where the lines a read sends go in the log
+#[derive(Debug, Clone, Copy)]
+enum Placement {
+    /// As they arrive
+    Arrival,
+    /// At a time of their own, such as a compressed file's modification time
+    At(i64),
+    /// Before the source's first entry, as lines loaded with `:load_more` do
+    Earlier,
+}
+
+/// Note how far each file has been read, saving the checkpoints if that's changed
+fn save_checkpoints(path: &Path, checkpoints: &mut Checkpoints, file_states: &HashMap<PathBuf, FileState>) -> Result<()> {
+    let before = checkpoints.clone();
+    for (file, state) in file_states {
+        checkpoints.set(file, state.identity, state.last_position);
+    }
+    if *checkpoints != before {
+        checkpoints.save(path)?;
+    }
+    Ok(())
+}
+
+/// Start reading the last `tail_lines` lines of a file (all of it if 0), or from a checkpoint.
+/// Lines past the first chunk are read later, a chunk at a time
+fn read_file_contents(
+    path: &Path,
+    options: &WatchOptions,
+    checkpoints: Option<&Checkpoints>,
+    tx: &SyncSender<Vec<LogEntry>>,
+    status: &SourceStatusRegistry,
+) -> Result<FileState> {
+    let mut file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let metadata = file.metadata()?;
+    let encoding = file_encoding(&file, options.encodings.get(&source_name(path)))?;
+    // Binary data has lines of bytes rather than of characters
+    let binary = file_binary(&file, encoding)?;
+    let encoding = if binary { None } else { encoding };
+    if binary {
+        announce_binary(path, options, status);
+    }
+    // Resuming carries on where the last session stopped, unless the file was truncated since
+    let resumed = checkpoints
+        .and_then(|checkpoints| checkpoints.get(path, identity(&metadata)))
+        .filter(|offset| *offset <= metadata.len());
+    let loaded_from = match (resumed, options.tail_lines) {
+        (Some(offset), _) => offset,
+        (None, 0) => 0,
+        (None, lines) => tail_offset(&mut file, metadata.len(), lines, encoding)?,
+    };
+
+    let mut state = FileState {
+        file,
+        identity: identity(&metadata),
+        encoding,
+        binary,
+        last_position: loaded_from,
+        loaded_from,
+        rotated: None,
+        redraw_next: false,
+        partial: None,
+        loading: Some(metadata.len()),
+    };
+    load_chunk(path, options, tx, status, &mut state)
+        .with_context(|| format!("Failed to read line from {}", path.display()))?;
+    Ok(state)
+}
+
+/// Read the next chunk of a file's existing lines and note how far along they are. Once
+/// they're all read, the file is followed as usual
+fn load_chunk(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, status: &SourceStatusRegistry, state: &mut FileState) -> Result<()> {
+    let Some(end) = state.loading else {
+        return Ok(());
+    };
+    if state.skipped(options) {
+        state.loading = None;
+        return Ok(());
+    }
+    let source: Arc<str> = source_name(path).into();
+    let start = state.last_position;
+    let chunk = (options.load_rate as u64 * LOAD_INTERVAL.as_millis() as u64 / 1000).max(1);
+    let mut limit = end.min(start + chunk);
+    // A chunk stops after its last whole line, and grows to fit a line longer than a chunk
+    let (mut stop, mut after_cr) = line_end(&state.file, start, limit, state.encoding)?;
+    while stop == start && limit < end {
+        limit = end.min(limit + chunk);
+        (stop, after_cr) = line_end(&state.file, start, limit, state.encoding)?;
+    }
+    // Progress is updated before the lines are sent, so the UI sees it when they arrive
+    let finished = limit == end;
+    if finished {
+        state.loading = None;
+        status.set_loading(&source, None);
+    } else {
+        status.set_loading(&source, Some((stop - state.loaded_from, end - state.loaded_from)));
+    }
+    if stop > start {
+        state.file.seek(SeekFrom::Start(start))?;
+        send_lines((&state.file).take(stop - start), state.text(), path, Placement::Arrival, state.redraw_next, options.keep_empty_lines, tx)?;
+        state.last_position = stop;
+        state.redraw_next = after_cr;
+    }
+    if finished {
+        // Whatever was written meanwhile, and an unfinished last line
+        read_new_content(path, options, tx, state)?;
+    }
+    Ok(())
+}
 
-    for line in reader.lines() {
-        let content = line?;
-        if !content.is_empty() {
-            tx.send(LogEntry {
-                timestamp: Local::now(),
-                source: source.clone(),
-                content: content.clone(),
-                content_plain: content,
-                line_number: 0,  // Will be set by LogSource
-                is_json: false,  // Let LogEntry handle JSON detection
-            })?;
+/// Read all of a compressed file. Its lines are dated when the file was last written, so
+/// older rotations sort ahead of newer ones and of the live file
+fn read_compressed(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, status: &SourceStatusRegistry) -> Result<()> {
+    let file = File::open(path).with_context(|| format!("Failed to open {}", path.display()))?;
+    let modified = DateTime::<Local>::from(file.metadata()?.modified()?).timestamp_millis();
+    let reader: Box<dyn Read> = match Compression::of(path) {
+        Some(Compression::Gzip) => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Some(Compression::Zstd) => Box::new(zstd::Decoder::new(file)?),
+        Some(Compression::Bzip2) => Box::new(bzip2::read::MultiBzDecoder::new(file)),
+        None => Box::new(file),
+    };
+    let source: Arc<str> = source_name(path).into();
+    // Decompressed text can start with a byte order mark too
+    let mut reader = BufReader::new(reader);
+    let encoding = encoding::from_bom(reader.fill_buf()?).or(options.encodings.get(&source));
+    let text = if encoding::is_binary(reader.fill_buf()?, encoding) {
+        announce_binary(path, options, status);
+        if options.binary == Binary::Skip {
+            return Ok(());
         }
+        Text::Escaped
+    } else {
+        Text::Encoded(encoding)
+    };
+    send_lines(reader, text, path, Placement::At(modified), false, options.keep_empty_lines, tx)
+        .with_context(|| format!("Failed to decompress {}", path.display()))
+}
+
+/// Read up to `lines` more lines from before the earliest one read so far
+fn read_earlier_lines(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, state: &mut FileState) -> Result<()> {
+    if state.loaded_from == 0 || state.skipped(options) {
+        return Ok(());
+    }
+    let end = state.loaded_from;
+    let start = tail_offset(&mut state.file, end, options.tail_lines.max(1), state.encoding)?;
+    state.file.seek(SeekFrom::Start(start))?;
+    send_lines((&state.file).take(end - start), state.text(), path, Placement::Earlier, false, options.keep_empty_lines, tx)?;
+    state.loaded_from = start;
+    Ok(())
+}
+
+/// Catch up with a watched file after an event for its path. Following by name, a new
+/// file in the old one's place is read from the beginning once the old one is finished.
+/// Returns whether the path now names another file
+fn follow_file(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, status: &SourceStatusRegistry, state: &mut FileState) -> Result<bool> {
+    read_rotated(path, options, tx, state)?;
+    read_new_content(path, options, tx, state)?;
+    let repointed = options.resolve_symlinks && path.is_symlink();
+    if options.follow == Follow::Descriptor && !repointed {
+        return Ok(false);
+    }
+    // A missing path is a file renamed or removed without a replacement yet
+    let Ok(metadata) = std::fs::metadata(path) else {
+        return Ok(false);
+    };
+    if identity(&metadata) == state.identity {
+        return Ok(false);
+    }
+
+    let mut file = File::open(path)?;
+    // A new file without a byte order mark yet is taken to be in the old one's encoding
+    let encoding = file_encoding(&file, state.encoding)?;
+    let binary = file_binary(&file, encoding)?;
+    let encoding = if binary { None } else { encoding };
+    // A symlink's new target is usually a file of its own rather than a fresh one, so it
+    // opens at its last lines like any file does
+    let start = if repointed && options.tail_lines > 0 {
+        tail_offset(&mut file, metadata.len(), options.tail_lines, encoding)?
+    } else {
+        0
+    };
+    let old = std::mem::replace(&mut state.file, file);
+    state.rotated = Some(RotatedFile { file: old, position: state.last_position, until: Instant::now() + ROTATED_GRACE });
+    state.identity = identity(&metadata);
+    state.encoding = encoding;
+    state.binary = binary;
+    state.last_position = start;
+    // Earlier lines of the old file can't be loaded any more, but the new target's can
+    state.loaded_from = start;
+    state.redraw_next = false;
+    state.partial = None;
+    if state.loading.take().is_some() {
+        status.set_loading(&source_name(path), None);
+    }
+    if repointed {
+        let target = std::fs::read_link(path).unwrap_or_default();
+        status.announce(format!("{} now points to {}", path.display(), target.display()));
+    } else {
+        status.announce(format!("{} was rotated; reading the new file", source_name(path)));
+    }
+    if binary {
+        announce_binary(path, options, status);
+    }
+    read_new_content(path, options, tx, state)?;
+    Ok(true)
+}
+
+/// Read any last lines written to the file a rotation replaced, letting it go once
+/// it's had time to settle
+fn read_rotated(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, state: &mut FileState) -> Result<()> {
+    if state.skipped(options) {
+        return Ok(());
+    }
+    let text = state.text();
+    let Some(rotated) = &mut state.rotated else {
+        return Ok(());
+    };
+    rotated.file.seek(SeekFrom::Start(rotated.position))?;
+    send_lines(&rotated.file, text, path, Placement::Arrival, false, options.keep_empty_lines, tx)?;
+    rotated.position = rotated.file.stream_position()?;
+    if Instant::now() >= rotated.until {
+        state.rotated = None;
+    }
+    Ok(())
+}
+
+/// Read the lines added to the open file since the last read, starting over if it was
+/// truncated (e.g. by `copytruncate`). A last line still being written is left for the next
+/// read, so it isn't shown in pieces, unless the file has been quiet for the partial timeout
+fn read_new_content(path: &Path, options: &WatchOptions, tx: &SyncSender<Vec<LogEntry>>, state: &mut FileState) -> Result<()> {
+    // New lines wait until the existing ones are all read
+    if state.loading.is_some() || state.skipped(options) {
+        return Ok(());
+    }
+    let size = state.file.metadata()?.len();
+    if size < state.last_position {
+        state.last_position = 0;
+        state.loaded_from = 0;
+        state.redraw_next = false;
+        state.partial = None;
+    }
+    if size == state.last_position {
+        return Ok(());
     }
 
-    // Get the current position after reading
-    state.last_position = file.stream_position()?;
+    let text = state.text();
+    let (end, after_cr) = line_end(&state.file, state.last_position, size, state.encoding)?;
+    if end > state.last_position {
+        state.file.seek(SeekFrom::Start(state.last_position))?;
+        send_lines((&state.file).take(end - state.last_position), text, path, Placement::Arrival, state.redraw_next, options.keep_empty_lines, tx)?;
+        state.last_position = end;
+        state.redraw_next = after_cr;
+        state.partial = None;
+    }
+    if end == size {
+        return Ok(());
+    }
 
+    match &mut state.partial {
+        Some(partial) if partial.size == size => {
+            if !partial.shown && partial.since.elapsed() >= options.partial_timeout {
+                // Shown as it is, and replaced once it's finished
+                state.file.seek(SeekFrom::Start(state.last_position))?;
+                send_lines((&state.file).take(size - state.last_position), text, path, Placement::Arrival, state.redraw_next, options.keep_empty_lines, tx)?;
+                partial.shown = true;
+                state.redraw_next = true;
+            }
+        },
+        _ => state.partial = Some(PartialLine { size, since: Instant::now(), shown: false }),
+    }
     Ok(())
-}
\ No newline at end of file
+}
@@ -0,0 +1,182 @@
+use std::collections::HashMap;
+use std::io::{self, BufReader, Cursor, Read};
+use std::mem;
+use std::net::TcpStream;
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
+use flate2::read::MultiGzDecoder;
+use rmpv::Value;
+use crate::batch::EntryBatcher;
+use crate::log_entry::LogEntry;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// A message of the Fluentd forward protocol
+/// (https://github.com/fluent/fluentd/wiki/Forward-Protocol-Specification-v1)
+struct Message {
+    tag: String,
+    /// Each event's time, in milliseconds since the Unix epoch, and record
+    events: Vec<(Option<i64>, Value)>,
+    /// Sent back once the events are taken in, when the sender asks for acknowledgements
+    chunk: Option<Value>,
+}
+
+/// Take in the messages a Fluentd or Fluent Bit `forward` output sends over a connection until
+/// it closes. Each record becomes a JSON entry of the source named after its tag
+pub fn serve(stream: TcpStream, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let peer = stream.peer_addr().map(|peer| peer.ip().to_string()).unwrap_or_default();
+    let mut reader = BufReader::new(&stream);
+    let mut batcher = EntryBatcher::new(tx);
+    let mut sources: HashMap<String, Arc<str>> = HashMap::new();
+    loop {
+        let message = match rmpv::decode::read_value(&mut reader) {
+            Ok(value) => parse(value),
+            Err(rmpv::decode::Error::InvalidMarkerRead(err)) if err.kind() == io::ErrorKind::UnexpectedEof => return,
+            Err(err) => Err(err.to_string()),
+        };
+        let message = match message {
+            Ok(message) => message,
+            Err(err) => {
+                status.announce(format!("Dropped forward connection from {}: {}", peer, err));
+                return;
+            },
+        };
+
+        let source = sources.entry(message.tag).or_insert_with_key(|tag| {
+            status.set(tag, SourceState::Running);
+            tag.as_str().into()
+        });
+        for (time, record) in message.events {
+            let mut entry = LogEntry::new(source.clone(), to_json(record).to_string());
+            if let Some(time) = time {
+                entry.set_timestamp_millis(time);
+            }
+            entry.host.get_or_insert_with(|| peer.clone());
+            if batcher.push(entry).is_err() {
+                return;
+            }
+        }
+        // An acknowledgement promises the events arrived, so they're sent on first
+        if (message.chunk.is_some() || reader.buffer().is_empty()) && batcher.flush().is_err() {
+            return;
+        }
+        if let Some(chunk) = message.chunk {
+            let ack = Value::Map(vec![(Value::from("ack"), chunk)]);
+            if rmpv::encode::write_value(&mut &stream, &ack).is_err() {
+                return;
+            }
+        }
+    }
+}
+
+/// Read a message in any of the protocol's modes: Message (`[tag, time, record]`), Forward
+/// (`[tag, [[time, record], ...]]`) or PackedForward (`[tag, <msgpack events>]`, optionally
+/// gzipped). Each may end with an options map
+fn parse(message: Value) -> Result<Message, String> {
+    let Value::Array(mut parts) = message else {
+        return Err("not a forward protocol message".to_string());
+    };
+    if parts.len() < 2 {
+        return Err("message has no events".to_string());
+    }
+    let tag = match &parts[0] {
+        Value::String(tag) => tag.as_str().unwrap_or_default().to_string(),
+        _ => return Err("message has no tag".to_string()),
+    };
+    let options_at = if is_time(&parts[1]) { 3 } else { 2 };
+    let options = match parts.get(options_at) {
+        Some(Value::Map(options)) => options.clone(),
+        _ => Vec::new(),
+    };
+    let option = |name: &str| options.iter().find(|(key, _)| key.as_str() == Some(name)).map(|(_, value)| value.clone());
+
+    let events = match mem::replace(&mut parts[1], Value::Nil) {
+        time if is_time(&time) => {
+            let record = parts.get_mut(2).map(|record| mem::replace(record, Value::Nil)).unwrap_or(Value::Nil);
+            vec![(timestamp(&time), record)]
+        },
+        Value::Array(events) => events.into_iter().filter_map(event).collect(),
+        Value::Binary(packed) => unpack(packed, option("compressed").as_ref().and_then(Value::as_str) == Some("gzip"))?,
+        Value::String(packed) => unpack(packed.into_bytes(), false)?,
+        _ => return Err("message has no events".to_string()),
+    };
+    Ok(Message { tag, events, chunk: option("chunk") })
+}
+
+/// Read the events packed one after another into a PackedForward message
+fn unpack(packed: Vec<u8>, gzipped: bool) -> Result<Vec<(Option<i64>, Value)>, String> {
+    let packed = if gzipped {
+        let mut unpacked = Vec::new();
+        MultiGzDecoder::new(packed.as_slice()).read_to_end(&mut unpacked).map_err(|err| format!("invalid gzip data: {}", err))?;
+        unpacked
+    } else {
+        packed
+    };
+    let mut reader = Cursor::new(packed.as_slice());
+    let mut events = Vec::new();
+    while (reader.position() as usize) < packed.len() {
+        let value = rmpv::decode::read_value(&mut reader).map_err(|err| format!("invalid packed events: {}", err))?;
+        events.extend(event(value));
+    }
+    Ok(events)
+}
+
+/// An event, `[time, record]`
+fn event(value: Value) -> Option<(Option<i64>, Value)> {
+    let Value::Array(mut parts) = value else {
+        return None;
+    };
+    if parts.len() < 2 {
+        return None;
+    }
+    let record = parts.swap_remove(1);
+    Some((timestamp(&parts[0]), record))
+}
+
+fn is_time(value: &Value) -> bool {
+    matches!(value, Value::Integer(_) | Value::F64(_) | Value::F32(_) | Value::Ext(0, _))
+}
+
+/// An event's time in milliseconds: whole seconds, or the EventTime extension's seconds and
+/// nanoseconds
+fn timestamp(value: &Value) -> Option<i64> {
+    match value {
+        Value::Integer(seconds) => seconds.as_i64().map(|seconds| seconds * 1000),
+        Value::F64(seconds) => Some((seconds * 1000.0) as i64),
+        Value::F32(seconds) => Some((*seconds as f64 * 1000.0) as i64),
+        Value::Ext(0, data) if data.len() == 8 => {
+            let seconds = u32::from_be_bytes(data[..4].try_into().ok()?) as i64;
+            let nanos = u32::from_be_bytes(data[4..].try_into().ok()?) as i64;
+            Some(seconds * 1000 + nanos / 1_000_000)
+        },
+        _ => None,
+    }
+}
+
+/// A record as JSON, so its fields work as any JSON log line's do. Binary values are taken to
+/// be text, as Fluent Bit sends many strings that way
+fn to_json(value: Value) -> serde_json::Value {
+    match value {
+        Value::Nil | Value::Ext(..) => serde_json::Value::Null,
+        Value::Boolean(value) => value.into(),
+        Value::Integer(value) => match value.as_i64() {
+            Some(value) => value.into(),
+            None => value.as_u64().map_or(serde_json::Value::Null, Into::into),
+        },
+        Value::F32(value) => (value as f64).into(),
+        Value::F64(value) => value.into(),
+        Value::String(value) => String::from_utf8_lossy(value.as_bytes()).into(),
+        Value::Binary(value) => String::from_utf8_lossy(&value).into(),
+        Value::Array(values) => values.into_iter().map(to_json).collect(),
+        Value::Map(entries) => entries.into_iter()
+            .map(|(key, value)| {
+                let key = match key {
+                    Value::String(key) => String::from_utf8_lossy(key.as_bytes()).into_owned(),
+                    Value::Binary(key) => String::from_utf8_lossy(&key).into_owned(),
+                    key => key.to_string(),
+                };
+                (key, to_json(value))
+            })
+            .collect::<serde_json::Map<_, _>>()
+            .into(),
+    }
+}
@@ -0,0 +1,55 @@
+use std::sync::Arc;
+use regex::Regex;
+use crate::file_watcher::glob_regex;
+
+/// Sources named by a wildcard pattern, shown, hidden and colored together under one name,
+/// as given with `--group api=api*.log`
+#[derive(Debug, Clone)]
+pub struct SourceGroup {
+    name: String,
+    pattern: Regex,
+}
+
+impl SourceGroup {
+    /// Parse `NAME=PATTERN`, matching source names like `api*.log` or `stdout`
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (name, glob) = value.split_once('=')
+            .filter(|(name, glob)| !name.trim().is_empty() && !glob.is_empty())
+            .ok_or_else(|| format!("invalid group: {} (e.g. api=api*.log)", value))?;
+        // Source names are matched without regard to case, as everywhere else
+        let pattern = Regex::new(&format!("(?i){}", glob_regex(glob))).map_err(|_| format!("invalid pattern: {}", glob))?;
+        Ok(Self { name: name.trim().to_string(), pattern })
+    }
+}
+
+/// The groups sources belong to. A source is in the first group whose pattern matches it
+#[derive(Debug, Clone, Default)]
+pub struct SourceGroups {
+    groups: Arc<Vec<SourceGroup>>,
+}
+
+impl SourceGroups {
+    pub fn new(groups: Vec<SourceGroup>) -> Self {
+        Self { groups: Arc::new(groups) }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.groups.is_empty()
+    }
+
+    /// The position and name of the group a source is in, if any
+    pub fn group_of(&self, source: &str) -> Option<(usize, &str)> {
+        self.groups.iter()
+            .position(|group| group.pattern.is_match(source))
+            .map(|index| (index, self.groups[index].name.as_str()))
+    }
+
+    /// Whether `name` is a group rather than a source
+    pub fn contains(&self, name: &str) -> bool {
+        self.groups.iter().any(|group| group.name.eq_ignore_ascii_case(name))
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.groups.iter().map(|group| group.name.as_str())
+    }
+}
@@ -0,0 +1,155 @@
+use std::io::{BufRead, BufReader, Read};
+use std::sync::mpsc::SyncSender;
+use std::time::Duration;
+use anyhow::{bail, Result};
+use crate::batch::EntryBatcher;
+use crate::line_reader::{cr_lines, CrLines};
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin};
+use crate::reconnect::{self, Closed, Session};
+use crate::source_status::SourceStatusRegistry;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A log stream served over HTTP, as given with `--http-stream`
+#[derive(Debug, Clone)]
+pub struct StreamUrl(String);
+
+impl StreamUrl {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if !(value.starts_with("http://") || value.starts_with("https://")) {
+            return Err(format!("not an http:// or https:// URL: {}", value));
+        }
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn source(&self) -> String {
+        source_name(&self.0)
+    }
+}
+
+/// The source a stream from a URL is shown as: its host and path, e.g. `logs.example.com/v1/tail`
+pub fn source_name(url: &str) -> String {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let rest = rest.split(['?', '#']).next().unwrap_or(rest);
+    rest.trim_end_matches('/').to_string()
+}
+
+/// Read a log stream from `url`, connected again whenever it ends or fails: Server-Sent
+/// Events, whose events' data become entries, or any other response, such as chunked text or
+/// JSON lines, a line at a time. `bearer_token` is sent as `Authorization: Bearer <token>`
+pub fn start_streaming(url: &StreamUrl, bearer_token: Option<String>, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let stream = HttpStream {
+        url: url.clone(),
+        bearer_token,
+        agent: ureq::AgentBuilder::new().timeout_connect(CONNECT_TIMEOUT).build(),
+        last_event_id: None,
+    };
+    reconnect::start(stream, tx, status);
+}
+
+struct HttpStream {
+    url: StreamUrl,
+    bearer_token: Option<String>,
+    agent: ureq::Agent,
+    /// Where an event stream got to, so it can carry on from there after reconnecting
+    last_event_id: Option<String>,
+}
+
+impl reconnect::Source for HttpStream {
+    fn name(&self) -> String {
+        self.url.source()
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        Some(log_entry::origin([("url", FieldValue::Str(self.url.0.clone()))]))
+    }
+
+    fn run(&mut self, session: &mut Session) -> Result<String> {
+        let mut request = self.agent.get(&self.url.0).set("Accept", "text/event-stream, */*;q=0.5");
+        if let Some(token) = &self.bearer_token {
+            request = request.set("Authorization", &format!("Bearer {}", token));
+        }
+        if let Some(id) = &self.last_event_id {
+            request = request.set("Last-Event-ID", id);
+        }
+
+        let response = match request.call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(code, response)) => bail!("HTTP {} {}", code, response.status_text()),
+            Err(err) => return Err(err.into()),
+        };
+        session.connected();
+        let source = self.name();
+        let events = response.content_type() == "text/event-stream";
+        let reader = BufReader::new(response.into_reader());
+        let ended = if events {
+            read_events(reader, &source, session, &mut self.last_event_id)?
+        } else {
+            read_lines(cr_lines(reader), &source, &mut session.batcher)?
+        };
+        Ok(ended)
+    }
+}
+
+/// Read a response a line at a time, returning why it ended
+fn read_lines(mut lines: CrLines<BufReader<impl Read>>, source: &str, batcher: &mut EntryBatcher) -> Result<String, Closed> {
+    while let Some(line) = lines.next() {
+        let line = match line {
+            Ok(line) => line,
+            Err(err) => return Ok(err.to_string()),
+        };
+        if line.text.is_empty() {
+            continue;
+        }
+        let mut entry = LogEntry::new(source, line.text);
+        entry.redraw = line.redraw;
+        // Send what's collected before waiting on the server for more
+        batcher.push(entry)?;
+        if !lines.is_buffered() {
+            batcher.flush()?;
+        }
+    }
+    Ok("stream ended".to_string())
+}
+
+/// Read Server-Sent Events, adding the data of each as entries, a line of data to an entry.
+/// Keeps the last event ID, and waits as long as the server asks before connecting again
+fn read_events(mut reader: BufReader<impl Read>, source: &str, session: &mut Session, last_event_id: &mut Option<String>) -> Result<String, Closed> {
+    let mut data: Vec<String> = Vec::new();
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match reader.read_until(b'\n', &mut line) {
+            Ok(0) => return Ok("stream ended".to_string()),
+            Ok(_) => {},
+            Err(err) => return Ok(err.to_string()),
+        }
+        let text = String::from_utf8_lossy(&line);
+        let text = text.trim_end_matches('\n').trim_end_matches('\r');
+        // A blank line ends an event
+        if text.is_empty() {
+            for data in data.drain(..).filter(|data| !data.is_empty()) {
+                session.batcher.push(LogEntry::new(source, data))?;
+            }
+            if reader.buffer().is_empty() {
+                session.batcher.flush()?;
+            }
+            continue;
+        }
+        let (field, value) = text.split_once(':').unwrap_or((text, ""));
+        let value = value.strip_prefix(' ').unwrap_or(value);
+        match field {
+            "data" => data.push(value.to_string()),
+            "id" if !value.contains('\0') => *last_event_id = Some(value.to_string()),
+            "retry" => {
+                if let Ok(millis) = value.parse() {
+                    session.retry_after(Duration::from_millis(millis));
+                }
+            },
+            // Comments (lines starting with `:`) keep the connection alive; event names and
+            // unknown fields carry nothing to show
+            _ => {},
+        }
+    }
+}
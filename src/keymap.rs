@@ -0,0 +1,296 @@
+use termion::event::Key;
+
+/// Everything a key can be bound to in normal (non-prompt) mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Action {
+    Quit,
+    Suspend,
+    CommandMode,
+    Help,
+    ToggleRaw,
+    TogglePause,
+    Follow,
+    ScrollDown,
+    ScrollUp,
+    PageDown,
+    PageUp,
+    JumpStart,
+    JumpEnd,
+    NextTab,
+    PrevTab,
+    ToggleSidebar,
+    CopySelection,
+    ToggleCursor,
+    Yank,
+    VisualMode,
+    ToggleSource,
+    JumpToAlert,
+    InputMode,
+    Mark,
+    Escape,
+}
+
+impl Action {
+    /// Every action, in the order they appear in the default table
+    pub const ALL: &'static [Action] = &[
+        Action::Quit,
+        Action::Suspend,
+        Action::CommandMode,
+        Action::Help,
+        Action::ToggleRaw,
+        Action::TogglePause,
+        Action::Follow,
+        Action::ScrollDown,
+        Action::ScrollUp,
+        Action::PageDown,
+        Action::PageUp,
+        Action::JumpStart,
+        Action::JumpEnd,
+        Action::NextTab,
+        Action::PrevTab,
+        Action::ToggleSidebar,
+        Action::CopySelection,
+        Action::ToggleCursor,
+        Action::Yank,
+        Action::VisualMode,
+        Action::ToggleSource,
+        Action::JumpToAlert,
+        Action::InputMode,
+        Action::Mark,
+        Action::Escape,
+    ];
+
+    /// Name used for the action in the `[keys]` config table
+    pub fn name(&self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Suspend => "suspend",
+            Action::CommandMode => "command_mode",
+            Action::Help => "help",
+            Action::ToggleRaw => "toggle_raw",
+            Action::TogglePause => "toggle_pause",
+            Action::Follow => "follow",
+            Action::ScrollDown => "scroll_down",
+            Action::ScrollUp => "scroll_up",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::JumpStart => "jump_start",
+            Action::JumpEnd => "jump_end",
+            Action::NextTab => "next_tab",
+            Action::PrevTab => "prev_tab",
+            Action::ToggleSidebar => "toggle_sidebar",
+            Action::CopySelection => "copy_selection",
+            Action::ToggleCursor => "toggle_cursor",
+            Action::Yank => "yank",
+            Action::VisualMode => "visual_mode",
+            Action::ToggleSource => "toggle_source",
+            Action::JumpToAlert => "jump_to_alert",
+            Action::InputMode => "input_mode",
+            Action::Mark => "mark",
+            Action::Escape => "escape",
+        }
+    }
+
+    /// Look up an action by its config name
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+
+    /// Human-readable description shown in the help overlay
+    pub fn description(&self) -> &'static str {
+        match self {
+            Action::Quit => "Quit the application",
+            Action::Suspend => "Suspend to the shell (resume with fg)",
+            Action::CommandMode => "Enter command mode",
+            Action::Help => "Show this help",
+            Action::ToggleRaw => "Toggle raw ANSI display",
+            Action::TogglePause => "Pause/resume following",
+            Action::Follow => "Follow the end of the log (again to stop)",
+            Action::ScrollDown => "Scroll down one line",
+            Action::ScrollUp => "Scroll up one line",
+            Action::PageDown => "Scroll down one page",
+            Action::PageUp => "Scroll up one page",
+            Action::JumpStart => "Jump to the first line",
+            Action::JumpEnd => "Jump to the last line and follow",
+            Action::NextTab => "Next tab",
+            Action::PrevTab => "Previous tab",
+            Action::ToggleSidebar => "Toggle the source sidebar",
+            Action::CopySelection => "Copy the mouse selection to the clipboard",
+            Action::ToggleCursor => "Toggle the line cursor",
+            Action::Yank => "Copy the selected entries to the clipboard",
+            Action::VisualMode => "Start/end a visual line selection",
+            Action::ToggleSource => "Toggle the highlighted source in the sidebar",
+            Action::JumpToAlert => "Jump to the entry in the alert bar",
+            Action::InputMode => "Type lines to send to the command's stdin (Esc to stop)",
+            Action::Mark => "Insert a numbered divider into the log (:mark <text> to name it)",
+            Action::Escape => "Leave cursor mode and clear the selection",
+        }
+    }
+}
+
+/// Table mapping keys to actions
+#[derive(Debug, Clone)]
+pub struct KeyMap {
+    bindings: Vec<(Key, Action)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self {
+            bindings: vec![
+                (Key::Char('q'), Action::Quit),
+                (Key::Ctrl('z'), Action::Suspend),
+                (Key::Char(':'), Action::CommandMode),
+                (Key::Char('?'), Action::Help),
+                (Key::Char('r'), Action::ToggleRaw),
+                (Key::Char('p'), Action::TogglePause),
+                // Like less +F
+                (Key::Char('F'), Action::Follow),
+                // Vim-style navigation alongside the arrows
+                (Key::Char('j'), Action::ScrollDown),
+                (Key::Down, Action::ScrollDown),
+                (Key::Char('k'), Action::ScrollUp),
+                (Key::Up, Action::ScrollUp),
+                (Key::PageDown, Action::PageDown),
+                (Key::PageUp, Action::PageUp),
+                (Key::Char('g'), Action::JumpStart),
+                (Key::Char('<'), Action::JumpStart),
+                (Key::Char('G'), Action::JumpEnd),
+                (Key::Char('>'), Action::JumpEnd),
+                (Key::Char('\t'), Action::NextTab),
+                (Key::BackTab, Action::PrevTab),
+                (Key::Char('s'), Action::ToggleSidebar),
+                (Key::Char('c'), Action::CopySelection),
+                (Key::Char('v'), Action::ToggleCursor),
+                (Key::Char('y'), Action::Yank),
+                (Key::Char('V'), Action::VisualMode),
+                (Key::Char(' '), Action::ToggleSource),
+                (Key::Char('a'), Action::JumpToAlert),
+                (Key::Char('i'), Action::InputMode),
+                (Key::Char('m'), Action::Mark),
+                (Key::Esc, Action::Escape),
+            ],
+        }
+    }
+}
+
+impl KeyMap {
+    /// Default bindings with the actions named in `overrides` rebound to the given keys
+    /// Rebinding an action replaces all of its default keys, and a key bound to a new
+    /// action is taken away from whatever it did before
+    pub fn with_overrides(overrides: &[(String, Vec<String>)]) -> Result<Self, String> {
+        let mut rebound: Vec<(Action, Vec<Key>)> = Vec::new();
+        for (name, keys) in overrides {
+            let action = Action::from_name(name)
+                .ok_or_else(|| format!("Unknown action in [keys]: {}", name))?;
+            let keys = keys.iter()
+                .map(|key| parse_key(key).ok_or_else(|| format!("Unknown key for {}: {}", name, key)))
+                .collect::<Result<Vec<_>, _>>()?;
+            rebound.push((action, keys));
+        }
+
+        let new_keys: Vec<Key> = rebound.iter().flat_map(|(_, keys)| keys.iter().copied()).collect();
+        let mut bindings = Vec::new();
+        for (key, action) in Self::default().bindings {
+            match rebound.iter_mut().find(|(a, _)| *a == action) {
+                // Emit the new keys where the action's first default binding was
+                Some((_, keys)) => bindings.extend(keys.drain(..).map(|key| (key, action))),
+                None if new_keys.contains(&key) => {},
+                None => bindings.push((key, action)),
+            }
+        }
+        Ok(Self { bindings })
+    }
+
+    /// Look up the action bound to a key
+    pub fn action_for(&self, key: Key) -> Option<Action> {
+        self.bindings.iter()
+            .find(|(bound, _)| *bound == key)
+            .map(|(_, action)| *action)
+    }
+
+    /// All actions with their bound keys, in table order
+    pub fn describe(&self) -> Vec<(Action, Vec<Key>)> {
+        let mut described: Vec<(Action, Vec<Key>)> = Vec::new();
+        for (key, action) in &self.bindings {
+            match described.iter_mut().find(|(a, _)| a == action) {
+                Some((_, keys)) => keys.push(*key),
+                None => described.push((*action, vec![*key])),
+            }
+        }
+        described
+    }
+}
+
+/// Display form of a key, e.g. `Ctrl-f` or `PageUp`
+pub fn key_label(key: &Key) -> String {
+    match key {
+        Key::Char(' ') => "Space".to_string(),
+        Key::Char('\t') => "Tab".to_string(),
+        Key::Char('\n') => "Enter".to_string(),
+        Key::Char(c) => c.to_string(),
+        Key::Ctrl(c) => format!("Ctrl-{}", c),
+        Key::Alt(c) => format!("Alt-{}", c),
+        Key::F(n) => format!("F{}", n),
+        Key::BackTab => "Shift-Tab".to_string(),
+        Key::Backspace => "Backspace".to_string(),
+        Key::Delete => "Delete".to_string(),
+        Key::Insert => "Insert".to_string(),
+        Key::Left => "Left".to_string(),
+        Key::Right => "Right".to_string(),
+        Key::Up => "Up".to_string(),
+        Key::Down => "Down".to_string(),
+        Key::Home => "Home".to_string(),
+        Key::End => "End".to_string(),
+        Key::PageUp => "PageUp".to_string(),
+        Key::PageDown => "PageDown".to_string(),
+        Key::Esc => "Esc".to_string(),
+        _ => "?".to_string(),
+    }
+}
+
+/// Parse a key from config, e.g. `q`, `G`, `ctrl-f`, `alt-x`, `pagedown`, `space` or `f5`
+pub fn parse_key(text: &str) -> Option<Key> {
+    let mut chars = text.chars();
+    if let (Some(c), None) = (chars.next(), chars.next()) {
+        return Some(Key::Char(c));
+    }
+
+    let lower = text.to_lowercase();
+    let single = |rest: &str| {
+        let mut chars = rest.chars();
+        match (chars.next(), chars.next()) {
+            (Some(c), None) => Some(c),
+            _ => None,
+        }
+    };
+    if let Some(rest) = lower.strip_prefix("ctrl-").or_else(|| lower.strip_prefix("c-")) {
+        return single(rest).map(Key::Ctrl);
+    }
+    if let Some(rest) = text.get(4..).filter(|_| lower.starts_with("alt-")) {
+        return single(rest).map(Key::Alt);
+    }
+    if let Some(n) = lower.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+        return Some(Key::F(n));
+    }
+
+    match lower.as_str() {
+        "space" => Some(Key::Char(' ')),
+        "tab" => Some(Key::Char('\t')),
+        "enter" | "return" => Some(Key::Char('\n')),
+        "shift-tab" | "backtab" => Some(Key::BackTab),
+        "esc" | "escape" => Some(Key::Esc),
+        "backspace" => Some(Key::Backspace),
+        "delete" | "del" => Some(Key::Delete),
+        "insert" => Some(Key::Insert),
+        "left" => Some(Key::Left),
+        "right" => Some(Key::Right),
+        "up" => Some(Key::Up),
+        "down" => Some(Key::Down),
+        "home" => Some(Key::Home),
+        "end" => Some(Key::End),
+        "pageup" | "pgup" => Some(Key::PageUp),
+        "pagedown" | "pgdn" => Some(Key::PageDown),
+        _ => None,
+    }
+}
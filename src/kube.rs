@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+use std::io::{self, BufRead, BufReader, PipeWriter, Write};
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::Duration;
+use anyhow::{bail, Context, Result};
+use crate::fields::FieldValue;
+use crate::kubeconfig::Cluster;
+use crate::log_entry::{self, LogEntry};
+use crate::source_status::{SourceState, SourceStatusRegistry};
+use crate::stdin_reader::{self, StreamOptions};
+
+/// How often the pods are listed again, to pick up ones started by scaling or a rollout
+const POLL: Duration = Duration::from_secs(2);
+
+/// Pods to follow, as given with `--kube`
+#[derive(Debug, Clone)]
+pub enum KubeTarget {
+    /// The pods of a workload such as `deploy/my-api` or `sts/db`, by its selector
+    Workload(String),
+    /// One pod, `pod/my-api-7d9f`
+    Pod(String),
+    /// A label selector, `app=my-api`
+    Labels(String),
+}
+
+impl KubeTarget {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if value.contains('=') {
+            return Ok(Self::Labels(value.to_string()));
+        }
+        match value.split_once('/') {
+            Some(("pod" | "pods" | "po", name)) if !name.is_empty() => Ok(Self::Pod(name.to_string())),
+            Some((kind, name)) if !name.is_empty() => match workload_resource(kind) {
+                Some(_) => Ok(Self::Workload(value.to_string())),
+                None => Err(format!("unknown workload kind: {} (deploy, sts, ds, rs or job)", kind)),
+            },
+            _ => Err(format!("invalid target: {} (e.g. deploy/my-api, pod/my-api-7d9f or app=my-api)", value)),
+        }
+    }
+}
+
+/// How pods are looked up and their logs read
+#[derive(Debug, Clone)]
+pub struct KubeOptions {
+    /// `-n`; the current context's namespace otherwise
+    pub namespace: Option<String>,
+    /// Lines each pod running at the start shows from before (0 shows all of them)
+    pub tail_lines: usize,
+    pub stream: StreamOptions,
+}
+
+/// Pods matching a target, as the API selects them
+enum PodSelector {
+    Labels(String),
+    Name(String),
+}
+
+/// A pod as last listed
+struct Pod {
+    name: String,
+    running: bool,
+    /// Restarts of its containers, so a restarted container is followed again
+    restarts: u64,
+    containers: Vec<String>,
+}
+
+/// The cluster and the namespace the pods are in
+struct Api {
+    cluster: Cluster,
+    namespace: String,
+}
+
+/// Where the API keeps workloads of a kind, by the names kubectl knows it by: its API group
+/// and version, and its resource
+fn workload_resource(kind: &str) -> Option<(&'static str, &'static str)> {
+    // `deployment.apps` is a deployment too
+    let kind = kind.split('.').next().unwrap_or(kind).to_lowercase();
+    Some(match kind.as_str() {
+        "deploy" | "deployment" | "deployments" => ("apis/apps/v1", "deployments"),
+        "sts" | "statefulset" | "statefulsets" => ("apis/apps/v1", "statefulsets"),
+        "ds" | "daemonset" | "daemonsets" => ("apis/apps/v1", "daemonsets"),
+        "rs" | "replicaset" | "replicasets" => ("apis/apps/v1", "replicasets"),
+        "job" | "jobs" => ("apis/batch/v1", "jobs"),
+        _ => return None,
+    })
+}
+
+/// Follow the logs of every pod matching `target`, each as a source named after the pod,
+/// through the Kubernetes API of the cluster kubectl is set up for. The target is looked up
+/// here, so an unreachable cluster or an unknown workload is reported before the UI starts
+pub fn start_following(target: &KubeTarget, options: KubeOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) -> Result<()> {
+    let cluster = Cluster::load()?;
+    let namespace = options.namespace.clone().unwrap_or_else(|| cluster.namespace.clone());
+    let api = Arc::new(Api { cluster, namespace });
+    let selector = resolve(target, &api)?;
+    // A first listing also checks the cluster can be reached
+    let mut pods = list_pods(&selector, &api)?;
+
+    thread::spawn(move || {
+        // Restarts of each pod when it was last followed
+        let mut followed: HashMap<String, u64> = HashMap::new();
+        let mut last_error = None;
+        let mut first = true;
+        loop {
+            for pod in pods.iter().filter(|pod| pod.running) {
+                let following = status.get(&pod.name) == Some(SourceState::Running);
+                let seen = followed.get(&pod.name).copied();
+                if following || seen == Some(pod.restarts) {
+                    continue;
+                }
+                // Pods there from the start show their last lines, as files do; ones started
+                // later, or whose containers restarted, show everything since
+                let tail = (first && options.tail_lines > 0).then_some(options.tail_lines);
+                if let Err(err) = follow_pod(pod, tail, &api, &options, &tx, &status) {
+                    status.announce(format!("Failed to follow {}: {}", pod.name, err));
+                }
+                followed.insert(pod.name.clone(), pod.restarts);
+            }
+            first = false;
+
+            thread::sleep(POLL);
+            match list_pods(&selector, &api) {
+                Ok(listed) => {
+                    pods = listed;
+                    last_error = None;
+                },
+                Err(err) => {
+                    // Said once, not on every attempt while the cluster is unreachable
+                    let message = format!("{:#}", err);
+                    if last_error.as_ref() != Some(&message) {
+                        status.announce(message.clone());
+                        last_error = Some(message);
+                    }
+                },
+            }
+        }
+    });
+    Ok(())
+}
+
+/// The pods a target stands for. A workload's are found by the labels its selector matches
+fn resolve(target: &KubeTarget, api: &Api) -> Result<PodSelector> {
+    let workload = match target {
+        KubeTarget::Labels(labels) => return Ok(PodSelector::Labels(labels.clone())),
+        KubeTarget::Pod(name) => return Ok(PodSelector::Name(name.clone())),
+        KubeTarget::Workload(workload) => workload,
+    };
+    let (kind, name) = workload.split_once('/').unwrap_or_default();
+    let (group, resource) = workload_resource(kind).unwrap_or_default();
+    let object = api.cluster.get(&format!("/{}/namespaces/{}/{}/{}", group, api.namespace, resource, name), &[])
+        .with_context(|| format!("Failed to look up {}", workload))?;
+    let selector = object["spec"]["selector"]["matchLabels"].as_object()
+        .map(|labels| labels.iter()
+            .filter_map(|(key, value)| Some(format!("{}={}", key, value.as_str()?)))
+            .collect::<Vec<_>>()
+            .join(","))
+        .unwrap_or_default();
+    if selector.is_empty() {
+        bail!("{} has no selector labels to find its pods by; give them instead (e.g. --kube app=my-api)", workload);
+    }
+    Ok(PodSelector::Labels(selector))
+}
+
+fn list_pods(selector: &PodSelector, api: &Api) -> Result<Vec<Pod>> {
+    let field_selector;
+    let query = match selector {
+        PodSelector::Labels(labels) => ("labelSelector", labels.as_str()),
+        PodSelector::Name(name) => {
+            field_selector = format!("metadata.name={}", name);
+            ("fieldSelector", field_selector.as_str())
+        },
+    };
+    let listing = api.cluster.get(&format!("/api/v1/namespaces/{}/pods", api.namespace), &[query])
+        .context("Failed to list pods")?;
+    Ok(listing["items"].as_array().into_iter().flatten()
+        .filter_map(|pod| {
+            let name = pod["metadata"]["name"].as_str()?.to_string();
+            let running = pod["status"]["phase"] == "Running";
+            let restarts = pod["status"]["containerStatuses"].as_array().into_iter().flatten()
+                .filter_map(|container| container["restartCount"].as_u64())
+                .sum();
+            let containers = pod["spec"]["containers"].as_array().into_iter().flatten()
+                .filter_map(|container| Some(container["name"].as_str()?.to_string()))
+                .collect();
+            Some(Pod { name, running, restarts, containers })
+        })
+        .collect())
+}
+
+/// Start reading the logs of every container of a pod, from `tail` lines back (all of them
+/// without). Each container's log is a stream of its own; they're copied a line at a time into
+/// one pipe, read as the pod's source once all of them have been opened
+fn follow_pod(pod: &Pod, tail: Option<usize>, api: &Arc<Api>, options: &KubeOptions, tx: &SyncSender<Vec<LogEntry>>, status: &SourceStatusRegistry) -> Result<()> {
+    let path = format!("/api/v1/namespaces/{}/pods/{}/log", api.namespace, pod.name);
+    let tail = tail.map(|tail| tail.to_string());
+    let mut logs = Vec::new();
+    for container in &pod.containers {
+        let mut query = vec![("follow", "true"), ("container", container.as_str())];
+        if let Some(tail) = &tail {
+            query.push(("tailLines", tail.as_str()));
+        }
+        logs.push(api.cluster.stream(&path, &query)?);
+    }
+
+    let (reader, writer) = io::pipe()?;
+    for log in logs {
+        let writer = writer.try_clone()?;
+        thread::spawn(move || copy_lines(BufReader::new(log), writer));
+    }
+    let origin = log_entry::origin([
+        ("pod", FieldValue::Str(pod.name.clone())),
+        ("namespace", FieldValue::Str(api.namespace.clone())),
+    ]);
+    stdin_reader::start_reading(pod.name.clone(), reader, Some(origin), None, options.stream, tx.clone(), status.clone());
+    Ok(())
+}
+
+/// Copy a container's log into its pod's pipe whole lines at a time, so lines of the pod's
+/// containers aren't mixed up. Stops once the log ends, or oxtail is done reading the pipe
+fn copy_lines(mut log: impl BufRead, mut pipe: PipeWriter) {
+    let mut line = Vec::new();
+    loop {
+        line.clear();
+        match log.read_until(b'\n', &mut line) {
+            Ok(0) | Err(_) => return,
+            Ok(_) => {
+                if pipe.write_all(&line).is_err() {
+                    return;
+                }
+            },
+        }
+    }
+}
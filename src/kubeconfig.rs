@@ -0,0 +1,366 @@
+use std::env;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use anyhow::{anyhow, bail, Context, Result};
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64;
+use chrono::{DateTime, Utc};
+use rustls::pki_types::{CertificateDer, PrivateKeyDer};
+use rustls::pki_types::pem::PemObject;
+use rustls::{ClientConfig, RootCertStore};
+use serde::Deserialize;
+use serde_json::Value;
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a request that isn't a log stream may take
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Where a pod's service account is mounted, for oxtail running in the cluster
+const SERVICE_ACCOUNT: &str = "/var/run/secrets/kubernetes.io/serviceaccount";
+
+/// A kubeconfig file, or the parts of one oxtail uses
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct Kubeconfig {
+    clusters: Vec<NamedCluster>,
+    users: Vec<NamedUser>,
+    contexts: Vec<NamedContext>,
+    current_context: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedCluster {
+    name: String,
+    cluster: ClusterEntry,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedUser {
+    name: String,
+    user: UserEntry,
+}
+
+#[derive(Debug, Deserialize)]
+struct NamedContext {
+    name: String,
+    context: ContextEntry,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct ClusterEntry {
+    server: String,
+    certificate_authority: Option<PathBuf>,
+    certificate_authority_data: Option<String>,
+    #[serde(default)]
+    insecure_skip_tls_verify: bool,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct UserEntry {
+    client_certificate: Option<PathBuf>,
+    client_certificate_data: Option<String>,
+    client_key: Option<PathBuf>,
+    client_key_data: Option<String>,
+    token: Option<String>,
+    #[serde(rename = "tokenFile")]
+    token_file: Option<PathBuf>,
+    username: Option<String>,
+    password: Option<String>,
+    exec: Option<ExecConfig>,
+    auth_provider: Option<Value>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "kebab-case")]
+struct ContextEntry {
+    cluster: String,
+    user: String,
+    namespace: Option<String>,
+}
+
+/// A credential plugin, such as the one cloud providers' clusters are set up with
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExecConfig {
+    command: String,
+    #[serde(default)]
+    args: Option<Vec<String>>,
+    #[serde(default)]
+    env: Option<Vec<ExecEnv>>,
+    api_version: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ExecEnv {
+    name: String,
+    value: String,
+}
+
+/// How requests say who they're from, besides a client certificate
+enum Credentials {
+    None,
+    Token(String),
+    /// A token read again for each request, as service account tokens are replaced over time
+    TokenFile(PathBuf),
+    Basic(String),
+    /// A token from a credential plugin, kept until it expires
+    Exec(ExecConfig, Mutex<Option<(String, Option<DateTime<Utc>>)>>),
+}
+
+impl Credentials {
+    /// The `Authorization` header requests are sent with, if any
+    fn authorization(&self) -> Result<Option<String>> {
+        Ok(match self {
+            Self::None => None,
+            Self::Token(token) => Some(format!("Bearer {}", token)),
+            Self::TokenFile(path) => {
+                let token = fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+                Some(format!("Bearer {}", token.trim()))
+            },
+            Self::Basic(encoded) => Some(format!("Basic {}", encoded)),
+            Self::Exec(exec, cached) => {
+                let mut cached = cached.lock().unwrap();
+                let fresh = cached.as_ref().is_some_and(|(_, expires)| expires.is_none_or(|expires| expires > Utc::now()));
+                if !fresh {
+                    *cached = Some(run_exec(exec)?);
+                }
+                cached.as_ref().map(|(token, _)| format!("Bearer {}", token))
+            },
+        })
+    }
+}
+
+/// A Kubernetes API server and how to talk to it, as kubectl would: the current context of
+/// the kubeconfig (`$KUBECONFIG`, `~/.kube/config` otherwise), or the pod's service account
+/// when oxtail runs in the cluster
+pub struct Cluster {
+    server: String,
+    agent: ureq::Agent,
+    credentials: Credentials,
+    /// Namespace of the current context, `default` if it has none
+    pub namespace: String,
+}
+
+impl Cluster {
+    pub fn load() -> Result<Self> {
+        let paths: Vec<PathBuf> = match env::var_os("KUBECONFIG") {
+            Some(paths) => env::split_paths(&paths).filter(|path| !path.as_os_str().is_empty()).collect(),
+            None => env::var_os("HOME").map(|home| Path::new(&home).join(".kube").join("config"))
+                .filter(|path| path.exists())
+                .into_iter()
+                .collect(),
+        };
+        if paths.is_empty() {
+            if env::var_os("KUBERNETES_SERVICE_HOST").is_some() {
+                return Self::in_cluster();
+            }
+            bail!("No kubeconfig to find the cluster with: ~/.kube/config doesn't exist and KUBECONFIG isn't set");
+        }
+        Self::from_kubeconfig(&paths)
+    }
+
+    /// The current context of the kubeconfig files given, where the first file to set
+    /// something wins, as with kubectl
+    fn from_kubeconfig(paths: &[PathBuf]) -> Result<Self> {
+        let mut config = Kubeconfig::default();
+        let mut found = false;
+        for path in paths {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => continue,
+                Err(err) => return Err(err).with_context(|| format!("Failed to read {}", path.display())),
+            };
+            found = true;
+            let mut file: Kubeconfig = serde_yaml::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))?;
+            // Files named in a kubeconfig are relative to it
+            let dir = path.parent().unwrap_or(Path::new("."));
+            for cluster in &mut file.clusters {
+                relative_to(dir, &mut cluster.cluster.certificate_authority);
+            }
+            for user in &mut file.users {
+                relative_to(dir, &mut user.user.client_certificate);
+                relative_to(dir, &mut user.user.client_key);
+                relative_to(dir, &mut user.user.token_file);
+                if let Some(exec) = &mut user.user.exec {
+                    if exec.command.contains('/') {
+                        exec.command = dir.join(&exec.command).to_string_lossy().into_owned();
+                    }
+                }
+            }
+            config.current_context = config.current_context.or(file.current_context.filter(|name| !name.is_empty()));
+            config.clusters.extend(file.clusters);
+            config.users.extend(file.users);
+            config.contexts.extend(file.contexts);
+        }
+
+        if !found {
+            bail!("No kubeconfig to find the cluster with: {} doesn't exist", env::join_paths(paths).unwrap_or_default().to_string_lossy());
+        }
+        let name = config.current_context
+            .context("The kubeconfig has no current context (set one with kubectl config use-context)")?;
+        let context = config.contexts.into_iter().find(|context| context.name == name)
+            .with_context(|| format!("Context {} isn't in the kubeconfig", name))?
+            .context;
+        let cluster = config.clusters.into_iter().find(|cluster| cluster.name == context.cluster)
+            .with_context(|| format!("Cluster {} isn't in the kubeconfig", context.cluster))?
+            .cluster;
+        let user = config.users.into_iter().find(|user| user.name == context.user)
+            .map(|user| user.user)
+            .unwrap_or_default();
+        if cluster.insecure_skip_tls_verify {
+            bail!("Cluster {} skips verifying its certificate (insecure-skip-tls-verify), which oxtail doesn't do", context.cluster);
+        }
+        if user.auth_provider.is_some() {
+            bail!("User {} authenticates with an auth-provider, which oxtail doesn't support; use an exec plugin instead", context.user);
+        }
+
+        let ca = read_data(cluster.certificate_authority_data.as_deref(), cluster.certificate_authority.as_deref())?;
+        let certificate = read_data(user.client_certificate_data.as_deref(), user.client_certificate.as_deref())?;
+        let key = read_data(user.client_key_data.as_deref(), user.client_key.as_deref())?;
+        let credentials = if let Some(token) = user.token {
+            Credentials::Token(token)
+        } else if let Some(path) = user.token_file {
+            Credentials::TokenFile(path)
+        } else if let Some(exec) = user.exec {
+            Credentials::Exec(exec, Mutex::new(None))
+        } else if let (Some(username), Some(password)) = (user.username, user.password) {
+            Credentials::Basic(BASE64.encode(format!("{}:{}", username, password)))
+        } else {
+            Credentials::None
+        };
+        Ok(Self {
+            agent: agent(ca.as_deref(), certificate.as_deref().zip(key.as_deref()))?,
+            server: cluster.server,
+            credentials,
+            namespace: context.namespace.unwrap_or_else(|| "default".to_string()),
+        })
+    }
+
+    /// The API server as seen from a pod, with its service account's token
+    fn in_cluster() -> Result<Self> {
+        let host = env::var("KUBERNETES_SERVICE_HOST").unwrap_or_default();
+        let port = env::var("KUBERNETES_SERVICE_PORT").unwrap_or_else(|_| "443".to_string());
+        let host = if host.contains(':') { format!("[{}]", host) } else { host };
+        let dir = Path::new(SERVICE_ACCOUNT);
+        let ca = fs::read(dir.join("ca.crt")).context("Failed to read the service account's CA certificate")?;
+        let namespace = fs::read_to_string(dir.join("namespace")).map(|namespace| namespace.trim().to_string());
+        Ok(Self {
+            server: format!("https://{}:{}", host, port),
+            agent: agent(Some(&ca), None)?,
+            credentials: Credentials::TokenFile(dir.join("token")),
+            namespace: namespace.unwrap_or_else(|_| "default".to_string()),
+        })
+    }
+
+    /// GET an API path, such as `/api/v1/namespaces/prod/pods`, and return the object sent
+    pub fn get(&self, path: &str, query: &[(&str, &str)]) -> Result<Value> {
+        let response = self.call(path, query, Some(REQUEST_TIMEOUT))?;
+        Ok(serde_json::from_reader(response.into_reader())?)
+    }
+
+    /// GET an API path whose response goes on for as long as it's read, such as a followed log
+    pub fn stream(&self, path: &str, query: &[(&str, &str)]) -> Result<Box<dyn Read + Send + Sync>> {
+        Ok(self.call(path, query, None)?.into_reader())
+    }
+
+    fn call(&self, path: &str, query: &[(&str, &str)], timeout: Option<Duration>) -> Result<ureq::Response> {
+        let mut request = self.agent.get(&format!("{}{}", self.server.trim_end_matches('/'), path));
+        for (name, value) in query {
+            request = request.query(name, value);
+        }
+        if let Some(timeout) = timeout {
+            request = request.timeout(timeout);
+        }
+        if let Some(authorization) = self.credentials.authorization()? {
+            request = request.set("Authorization", &authorization);
+        }
+        match request.call() {
+            Ok(response) => Ok(response),
+            // The API server says what went wrong in a Status object
+            Err(ureq::Error::Status(code, response)) => {
+                let text = response.status_text().to_string();
+                let status: Value = serde_json::from_reader(response.into_reader()).unwrap_or_default();
+                bail!("{} (HTTP {})", status["message"].as_str().unwrap_or(&text), code)
+            },
+            Err(err) => Err(err.into()),
+        }
+    }
+}
+
+fn relative_to(dir: &Path, path: &mut Option<PathBuf>) {
+    if let Some(path) = path {
+        *path = dir.join(&*path);
+    }
+}
+
+/// PEM given inline, base64 encoded, or in a file
+fn read_data(data: Option<&str>, path: Option<&Path>) -> Result<Option<Vec<u8>>> {
+    if let Some(data) = data {
+        return Ok(Some(BASE64.decode(data.trim()).context("Invalid base64 in the kubeconfig")?));
+    }
+    path.map(|path| fs::read(path).with_context(|| format!("Failed to read {}", path.display())))
+        .transpose()
+}
+
+/// An agent trusting the cluster's CA, the usual roots if it has none, and presenting the
+/// client certificate and key given
+fn agent(ca: Option<&[u8]>, client: Option<(&[u8], &[u8])>) -> Result<ureq::Agent> {
+    let mut roots = RootCertStore::empty();
+    match ca {
+        Some(ca) => {
+            for certificate in CertificateDer::pem_slice_iter(ca) {
+                let certificate = certificate.map_err(|err| anyhow!("Invalid CA certificate: {}", err))?;
+                roots.add(certificate).context("Invalid CA certificate")?;
+            }
+        },
+        None => roots.extend(webpki_roots::TLS_SERVER_ROOTS.iter().cloned()),
+    }
+    let builder = ClientConfig::builder_with_provider(Arc::new(rustls::crypto::ring::default_provider()))
+        .with_safe_default_protocol_versions()?
+        .with_root_certificates(roots);
+    let config = match client {
+        Some((certificate, key)) => {
+            let chain = CertificateDer::pem_slice_iter(certificate)
+                .collect::<Result<Vec<_>, _>>()
+                .map_err(|err| anyhow!("Invalid client certificate: {}", err))?;
+            let key = PrivateKeyDer::from_pem_slice(key).map_err(|err| anyhow!("Invalid client key: {}", err))?;
+            builder.with_client_auth_cert(chain, key).context("Invalid client certificate")?
+        },
+        None => builder.with_no_client_auth(),
+    };
+    Ok(ureq::AgentBuilder::new()
+        .timeout_connect(CONNECT_TIMEOUT)
+        .tls_config(Arc::new(config))
+        .build())
+}
+
+/// Run a credential plugin for a token, and when it runs out
+fn run_exec(exec: &ExecConfig) -> Result<(String, Option<DateTime<Utc>>)> {
+    let mut command = Command::new(&exec.command);
+    command.args(exec.args.iter().flatten())
+        .env("KUBERNETES_EXEC_INFO", format!(r#"{{"apiVersion":"{}","kind":"ExecCredential","spec":{{"interactive":false}}}}"#, exec.api_version))
+        .stdin(Stdio::null())
+        .stderr(Stdio::piped());
+    for variable in exec.env.iter().flatten() {
+        command.env(&variable.name, &variable.value);
+    }
+    let output = command.output().with_context(|| format!("Failed to run {} for credentials", exec.command))?;
+    if !output.status.success() {
+        bail!("{}: {}", exec.command, String::from_utf8_lossy(&output.stderr).trim());
+    }
+    let credential: Value = serde_json::from_slice(&output.stdout)
+        .with_context(|| format!("{} didn't write an ExecCredential", exec.command))?;
+    let token = credential["status"]["token"].as_str()
+        .with_context(|| format!("{} didn't give a token", exec.command))?;
+    let expires = credential["status"]["expirationTimestamp"].as_str()
+        .and_then(|time| DateTime::parse_from_rfc3339(time).ok())
+        .map(|time| time.with_timezone(&Utc));
+    Ok((token.to_string(), expires))
+}
@@ -0,0 +1,47 @@
+//! oxtail's modules, shared by the binary and the benches
+pub mod aliases;
+pub mod control;
+pub mod process_handler;
+pub mod pty;
+pub mod resource_usage;
+pub mod exec_watch;
+pub mod ansi;
+pub mod batch;
+pub mod checkpoints;
+pub mod benchmark;
+pub mod ui;
+pub mod settings;
+pub mod silence;
+pub mod clipboard;
+pub mod commands;
+pub mod keymap;
+pub mod decoders;
+pub mod encoding;
+pub mod export;
+pub mod fields;
+pub mod groups;
+pub mod log_entry;
+pub mod log_storage;
+pub mod pattern;
+pub mod widgets;
+pub mod file_watcher;
+pub mod stdin_reader;
+pub mod listener;
+pub mod forward;
+pub mod kube;
+pub mod kubeconfig;
+pub mod reconnect;
+pub mod http_stream;
+pub mod websocket;
+pub mod redis_reader;
+pub mod serial;
+pub mod line_reader;
+pub mod source_status;
+pub mod spill;
+pub mod synthetic;
+pub mod tabs;
+pub mod throttle;
+pub mod config;
+pub mod xdg;
+pub mod theme;
+pub mod terminal_title;
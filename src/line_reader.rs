@@ -0,0 +1,129 @@
+use std::io::{self, BufRead, BufReader, Read};
+
+/// A line of input, with whether it redraws the line before it
+#[derive(Debug, Clone, PartialEq)]
+pub struct InputLine {
+    pub text: String,
+    /// The line followed a lone `\r`, so it overwrites the previous line in place
+    /// (how progress bars and spinners update themselves)
+    pub redraw: bool,
+}
+
+/// Iterator over the lines of a reader that splits on `\n` and on a lone `\r`
+/// `\r\n` is an ordinary line ending
+pub struct CrLines<R> {
+    reader: R,
+    /// The next line follows a lone `\r`
+    redraw_next: bool,
+    /// The last delimiter was `\r`, so an immediate `\n` just completes a `\r\n`
+    after_cr: bool,
+    done: bool,
+}
+
+/// Read lines from `reader`, reporting `\r` redraws instead of merging them into one line
+pub fn cr_lines<R: BufRead>(reader: R) -> CrLines<R> {
+    CrLines {
+        reader,
+        redraw_next: false,
+        after_cr: false,
+        done: false,
+    }
+}
+
+impl<R: BufRead> CrLines<R> {
+    /// Report the first line as a redraw, for input that carries on from a `\r` or from a
+    /// line shown before it was finished
+    pub fn redraw_first(mut self) -> Self {
+        self.redraw_next = true;
+        self
+    }
+
+    /// Read up to the next `\n` or `\r`, returning the delimiter (None at end of input)
+    fn read_segment(&mut self, segment: &mut Vec<u8>) -> io::Result<Option<u8>> {
+        loop {
+            let available = match self.reader.fill_buf() {
+                Ok(available) => available,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            if available.is_empty() {
+                return Ok(None);
+            }
+            match available.iter().position(|b| *b == b'\n' || *b == b'\r') {
+                Some(index) => {
+                    let delimiter = available[index];
+                    segment.extend_from_slice(&available[..index]);
+                    self.reader.consume(index + 1);
+                    return Ok(Some(delimiter));
+                },
+                None => {
+                    let len = available.len();
+                    segment.extend_from_slice(available);
+                    self.reader.consume(len);
+                },
+            }
+        }
+    }
+}
+
+impl<R: Read> CrLines<BufReader<R>> {
+    /// Whether more input is already buffered, so the next line can be read without waiting.
+    /// The `\n` completing a `\r\n` doesn't count, as it's no line of its own
+    pub fn is_buffered(&self) -> bool {
+        let buffer = self.reader.buffer();
+        !(buffer.is_empty() || self.after_cr && buffer == b"\n")
+    }
+}
+
+impl<R: BufRead> Iterator for CrLines<R> {
+    type Item = io::Result<InputLine>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while !self.done {
+            let mut segment = Vec::new();
+            let delimiter = match self.read_segment(&mut segment) {
+                Ok(delimiter) => delimiter,
+                Err(err) => {
+                    self.done = true;
+                    return Some(Err(err));
+                },
+            };
+            let redraw = self.redraw_next;
+
+            match delimiter {
+                // The `\n` of a `\r\n`: the line was already returned at the `\r`
+                Some(b'\n') if segment.is_empty() && self.after_cr => {
+                    self.after_cr = false;
+                    self.redraw_next = false;
+                },
+                Some(b'\n') => {
+                    self.after_cr = false;
+                    self.redraw_next = false;
+                    return Some(Ok(input_line(&segment, redraw)));
+                },
+                Some(_) => {
+                    self.after_cr = true;
+                    // A leading `\r` (or several in a row) has nothing to show yet
+                    if !segment.is_empty() {
+                        self.redraw_next = true;
+                        return Some(Ok(input_line(&segment, redraw)));
+                    }
+                },
+                None => {
+                    self.done = true;
+                    if !segment.is_empty() {
+                        return Some(Ok(input_line(&segment, redraw)));
+                    }
+                },
+            }
+        }
+        None
+    }
+}
+
+fn input_line(segment: &[u8], redraw: bool) -> InputLine {
+    InputLine {
+        text: String::from_utf8_lossy(segment).into_owned(),
+        redraw,
+    }
+}
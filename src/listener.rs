@@ -0,0 +1,130 @@
+use std::io;
+use std::net::{SocketAddr, TcpListener, UdpSocket};
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::Duration;
+use anyhow::{Context, Result};
+use crate::batch::EntryBatcher;
+use crate::forward;
+use crate::log_entry::LogEntry;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// How long to wait for more datagrams before sending the ones received, so a burst of
+/// messages reaches the UI in a few batches
+const BATCH_WINDOW: Duration = Duration::from_millis(10);
+
+/// Largest datagram syslog can be sent in over UDP
+const MAX_DATAGRAM: usize = 65535;
+
+/// What a listener takes in
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Protocol {
+    /// Syslog messages (RFC3164 or RFC5424), one or more lines per UDP datagram
+    SyslogUdp,
+    /// The Fluentd forward protocol (msgpack over TCP) that Fluentd and Fluent Bit send with
+    /// their `forward` outputs
+    FluentForward,
+}
+
+/// An address to take logs in on, as given with `--listen syslog-udp://0.0.0.0:514`
+#[derive(Debug, Clone)]
+pub struct ListenAddress {
+    protocol: Protocol,
+    address: SocketAddr,
+}
+
+impl ListenAddress {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (scheme, address) = value.split_once("://")
+            .ok_or_else(|| format!("invalid address: {} (e.g. syslog-udp://0.0.0.0:514)", value))?;
+        let protocol = match scheme {
+            "syslog-udp" => Protocol::SyslogUdp,
+            "fluent-forward" => Protocol::FluentForward,
+            _ => return Err(format!("unknown protocol: {} (syslog-udp or fluent-forward)", scheme)),
+        };
+        let address = address.parse()
+            .map_err(|_| format!("invalid address: {} (e.g. 0.0.0.0:514 or [::]:514)", address))?;
+        Ok(Self { protocol, address })
+    }
+
+    /// The source the messages are shown under, e.g. `syslog:514`. Forwarded records are
+    /// shown under their tags instead, so this only names the listener
+    pub fn source(&self) -> String {
+        match self.protocol {
+            Protocol::SyslogUdp => format!("syslog:{}", self.address.port()),
+            Protocol::FluentForward => format!("forward:{}", self.address.port()),
+        }
+    }
+}
+
+/// Start taking in messages on `address`. Binding happens here, so a port that's taken or
+/// needs root is reported before the UI starts
+pub fn start_listening(address: &ListenAddress, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) -> Result<()> {
+    let context = || format!("Failed to listen on {} (ports below 1024 need root)", address.address);
+    match address.protocol {
+        Protocol::SyslogUdp => listen_syslog(UdpSocket::bind(address.address).with_context(context)?, address.source(), tx, status),
+        Protocol::FluentForward => listen_forward(TcpListener::bind(address.address).with_context(context)?, address.source(), tx, status),
+    }
+    Ok(())
+}
+
+/// Read syslog datagrams in a thread of its own, as the `syslog:<port>` source
+fn listen_syslog(socket: UdpSocket, source: String, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    status.set(&source, SourceState::Running);
+
+    thread::spawn(move || {
+        let mut batcher = EntryBatcher::new(tx);
+        let mut buffer = vec![0; MAX_DATAGRAM];
+        let mut pending = false;
+        loop {
+            // Wait as long as it takes for the first datagram of a batch, then briefly for more
+            let _ = socket.set_read_timeout(pending.then_some(BATCH_WINDOW));
+            let (len, sender) = match socket.recv_from(&mut buffer) {
+                Ok(received) => received,
+                Err(err) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                    if batcher.flush().is_err() {
+                        break;
+                    }
+                    pending = false;
+                    continue;
+                },
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => {
+                    status.announce(format!("Stopped listening on {}: {}", source, err));
+                    break;
+                },
+            };
+            let text = String::from_utf8_lossy(&buffer[..len]);
+            for line in text.lines().filter(|line| !line.trim().is_empty()) {
+                let mut entry = LogEntry::new(source.as_str(), line);
+                // Devices often leave the hostname out; where it came from says as much
+                entry.host.get_or_insert_with(|| sender.ip().to_string());
+                if batcher.push(entry).is_err() {
+                    return;
+                }
+                pending = true;
+            }
+        }
+        status.set(&source, SourceState::Stopped);
+    });
+}
+
+/// Accept connections from Fluentd and Fluent Bit, each read in a thread of its own
+fn listen_forward(listener: TcpListener, source: String, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            match stream {
+                Ok(stream) => {
+                    let (tx, status) = (tx.clone(), status.clone());
+                    thread::spawn(move || forward::serve(stream, tx, status));
+                },
+                // A connection dropped before it was accepted leaves the listener as it was
+                Err(err) if matches!(err.kind(), io::ErrorKind::Interrupted | io::ErrorKind::ConnectionAborted) => continue,
+                Err(err) => {
+                    status.announce(format!("Stopped listening on {}: {}", source, err));
+                    break;
+                },
+            }
+        }
+    });
+}
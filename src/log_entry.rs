@@ -1,19 +1,136 @@
-use chrono::{DateTime, Local};
-use crate::settings::LogSettings;
+use std::borrow::Cow;
+use std::ops::Range;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use chrono::{DateTime, Local, TimeZone};
+use ratatui::style::Style;
+use crate::settings::{LogSettings, YankFormat};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use strip_ansi_escapes::strip;
+use crate::ansi;
+use crate::decoders;
+use crate::fields::{self, FieldValue};
+
+/// Fields saying where entries came from, such as the file, command, pod or URL, shared by
+/// all the entries from there
+pub type Origin = Arc<[(String, FieldValue)]>;
+
+/// An origin of the given fields
+pub fn origin<const N: usize>(fields: [(&str, FieldValue); N]) -> Origin {
+    fields.into_iter().map(|(name, value)| (name.to_string(), value)).collect()
+}
+
+/// Syslog severity levels (RFC5424), most severe first
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Severity {
+    Emergency,
+    Alert,
+    Critical,
+    Error,
+    Warning,
+    Notice,
+    Info,
+    Debug,
+}
+
+impl Severity {
+    /// Convert a numeric syslog severity (0-7)
+    pub fn from_code(code: u8) -> Option<Self> {
+        match code {
+            0 => Some(Severity::Emergency),
+            1 => Some(Severity::Alert),
+            2 => Some(Severity::Critical),
+            3 => Some(Severity::Error),
+            4 => Some(Severity::Warning),
+            5 => Some(Severity::Notice),
+            6 => Some(Severity::Info),
+            7 => Some(Severity::Debug),
+            _ => None,
+        }
+    }
+
+    /// A severity by name, as `:level` takes it: `warn`, `warning` and `WARN` all mean Warning
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "emerg" | "emergency" => Some(Severity::Emergency),
+            "alert" => Some(Severity::Alert),
+            "crit" | "critical" => Some(Severity::Critical),
+            "err" | "error" => Some(Severity::Error),
+            "warn" | "warning" => Some(Severity::Warning),
+            "notice" => Some(Severity::Notice),
+            "info" => Some(Severity::Info),
+            "debug" => Some(Severity::Debug),
+            _ => None,
+        }
+    }
+
+    /// Short label used when displaying the level
+    pub fn label(&self) -> &'static str {
+        match self {
+            Severity::Emergency => "EMERG",
+            Severity::Alert => "ALERT",
+            Severity::Critical => "CRIT",
+            Severity::Error => "ERROR",
+            Severity::Warning => "WARN",
+            Severity::Notice => "NOTICE",
+            Severity::Info => "INFO",
+            Severity::Debug => "DEBUG",
+        }
+    }
+}
+
+/// Severity names `:level` offers, most severe first
+pub const SEVERITY_NAMES: &[&str] = &["emerg", "alert", "crit", "error", "warn", "notice", "info", "debug"];
+
+/// Source of the dividers added with `:mark`, which are drawn across the log rather than as lines
+pub const MARK_SOURCE: &str = "mark";
+
+/// Sequence number for the next entry created
+static NEXT_SEQUENCE: AtomicU64 = AtomicU64::new(0);
 
 pub struct LogEntry {
-    pub timestamp: DateTime<Local>,
-    pub source: String,      // e.g., "stdout", "stderr", "file.log"
+    timestamp: i64,          // Arrival time in milliseconds since the Unix epoch
+    sequence: u64,           // Order of creation across all sources, for entries with the same timestamp
+    pub source: Arc<str>,    // e.g., "stdout", "stderr", "file.log"; shared by a source's entries in storage
     pub content: String,     // The actual log message
-    pub content_plain: String, // content with ANSI codes stripped out
+    content_plain: Option<String>, // content with ANSI codes stripped out, if that differs from content
     pub is_json: bool,       // true if the content is JSON
-    pub line_number: usize,  // The line number within this stream
+    pub line_number: i64,    // The line number within this stream
+    pub host: Option<String>,       // Originating host, from structured envelopes
+    pub app_name: Option<String>,   // Originating application, from structured envelopes
+    pub severity: Option<Severity>, // Syslog-style severity, if known
+    pub fields: Vec<(String, FieldValue)>, // Typed fields parsed from JSON/logfmt content
+    pub origin: Option<Origin>,    // Where the entry came from, as fields
+    ansi_segments: Vec<(Style, Range<usize>)>, // content split at its ANSI styling, as ranges of it; empty if it has none
+    pub redraw: bool,        // true if this replaces the source's previous entry (a `\r` progress update)
+    pub earlier: bool,       // true if this goes before the source's first entry (a line loaded with :load_more)
+}
+
+/// An entry as written to a spill file. The source comes from where it's stored and the
+/// ANSI segments are parsed again when it's read back
+#[derive(Serialize, Deserialize)]
+struct StoredEntry<'a> {
+    timestamp: i64,
+    sequence: u64,
+    content: Cow<'a, str>,
+    content_plain: Option<Cow<'a, str>>,
+    is_json: bool,
+    line_number: i64,
+    host: Option<Cow<'a, str>>,
+    app_name: Option<Cow<'a, str>>,
+    severity: Option<Severity>,
+    fields: Cow<'a, [(String, FieldValue)]>,
+    origin: Option<Cow<'a, [(String, FieldValue)]>>,
+}
+
+/// Local time for a timestamp in milliseconds since the Unix epoch
+pub fn local_time(millis: i64) -> DateTime<Local> {
+    Local.timestamp_millis_opt(millis).single().unwrap_or_default()
 }
 
 impl LogEntry {
-    pub fn new(source: impl Into<String>, content: impl Into<String>) -> Self {
+    pub fn new(source: impl Into<Arc<str>>, content: impl Into<String>) -> Self {
         let content_str = content.into();
         
         // Strip ANSI escape codes to get plain text content
@@ -21,23 +138,234 @@ impl LogEntry {
         let content_plain = String::from_utf8_lossy(&stripped_bytes).to_string();
         
         // Check if content is valid JSON
-        let is_json = serde_json::from_str::<Value>(&content_plain)
-            .map(|_| true)
-            .unwrap_or(false);
-            
-        Self {
-            timestamp: Local::now(),
+        let json = serde_json::from_str::<Value>(&content_plain).ok();
+        let is_json = json.is_some();
+
+        let mut entry = Self {
+            timestamp: Local::now().timestamp_millis(),
+            sequence: NEXT_SEQUENCE.fetch_add(1, Ordering::Relaxed),
             source: source.into(),
+            // Most lines have no ANSI codes, so the plain text is only kept when it differs
+            content_plain: (content_plain != content_str).then_some(content_plain),
             content: content_str,
-            content_plain,
             is_json,
             line_number: 0, // Default value, should be set later
+            host: None,
+            app_name: None,
+            severity: None,
+            fields: Vec::new(),
+            origin: None,
+            ansi_segments: Vec::new(),
+            redraw: false,
+            earlier: false,
+        };
+
+        // Unwrap GELF/syslog envelopes so we show the message rather than the wrapper
+        if let Some(decoded) = decoders::decode(entry.content_plain(), json.as_ref()) {
+            entry.apply_decoded(decoded);
+        }
+
+        // Pull out typed fields so `:where` can compare numbers as numbers
+        entry.fields = match &json {
+            Some(json) => fields::extract_json(json),
+            None if entry.content_plain().contains('=') => fields::extract_logfmt(entry.content_plain()),
+            None => Vec::new(),
+        };
+
+        // Parse the ANSI styling once so rendering can use styled spans
+        if entry.content.contains('\x1b') {
+            entry.ansi_segments = ansi::parse(&entry.content);
+        }
+
+        entry
+    }
+
+    /// When the entry arrived
+    pub fn timestamp(&self) -> DateTime<Local> {
+        local_time(self.timestamp)
+    }
+
+    /// When the entry arrived, in milliseconds since the Unix epoch
+    pub fn timestamp_millis(&self) -> i64 {
+        self.timestamp
+    }
+
+    pub fn set_timestamp_millis(&mut self, millis: i64) {
+        self.timestamp = millis;
+    }
+
+    /// Where the entry goes among entries with the same timestamp: they're kept in the
+    /// order they were created, whichever source they came from
+    pub fn sequence(&self) -> u64 {
+        self.sequence
+    }
+
+    pub fn set_sequence(&mut self, sequence: u64) {
+        self.sequence = sequence;
+    }
+
+    /// Approximate bytes the entry takes up in memory, counting what it owns on the heap
+    pub fn memory_size(&self) -> usize {
+        let strings = [Some(&self.content), self.content_plain.as_ref(), self.host.as_ref(), self.app_name.as_ref()];
+        let fields: usize = self.fields.iter()
+            .map(|(name, value)| std::mem::size_of::<(String, FieldValue)>() + name.capacity() + match value {
+                FieldValue::Str(text) => text.capacity(),
+                _ => 0,
+            })
+            .sum();
+        let segments = self.ansi_segments.capacity() * std::mem::size_of::<(Style, Range<usize>)>();
+        std::mem::size_of::<Self>()
+            + strings.iter().flatten().map(|s| s.capacity()).sum::<usize>()
+            + fields
+            + segments
+    }
+
+    /// Serialize the entry for a spill file
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let stored = StoredEntry {
+            timestamp: self.timestamp,
+            sequence: self.sequence,
+            content: Cow::Borrowed(&self.content),
+            content_plain: self.content_plain.as_deref().map(Cow::Borrowed),
+            is_json: self.is_json,
+            line_number: self.line_number,
+            host: self.host.as_deref().map(Cow::Borrowed),
+            app_name: self.app_name.as_deref().map(Cow::Borrowed),
+            severity: self.severity,
+            fields: Cow::Borrowed(&self.fields),
+            origin: self.origin.as_deref().map(Cow::Borrowed),
+        };
+        serde_json::to_vec(&stored).expect("entries always serialize")
+    }
+
+    /// Read back an entry written by `to_bytes`
+    pub fn from_bytes(source: Arc<str>, bytes: &[u8]) -> Option<Self> {
+        let stored: StoredEntry = serde_json::from_slice(bytes).ok()?;
+        let content = stored.content.into_owned();
+        let ansi_segments = if content.contains('\x1b') { ansi::parse(&content) } else { Vec::new() };
+        Some(Self {
+            timestamp: stored.timestamp,
+            sequence: stored.sequence,
+            source,
+            content,
+            content_plain: stored.content_plain.map(Cow::into_owned),
+            is_json: stored.is_json,
+            line_number: stored.line_number,
+            host: stored.host.map(Cow::into_owned),
+            app_name: stored.app_name.map(Cow::into_owned),
+            severity: stored.severity,
+            fields: stored.fields.into_owned(),
+            origin: stored.origin.map(|origin| origin.into()),
+            ansi_segments,
+            redraw: false,
+            earlier: false,
+        })
+    }
+
+    /// Whether the entry is a divider added with `:mark`
+    pub fn is_mark(&self) -> bool {
+        &*self.source == MARK_SOURCE
+    }
+
+    /// Content with ANSI codes stripped out
+    pub fn content_plain(&self) -> &str {
+        self.content_plain.as_deref().unwrap_or(&self.content)
+    }
+
+    /// Text to copy for this entry in the requested format
+    pub fn yank_text(&self, settings: &LogSettings) -> String {
+        // Dividers stand out in a file as they do on screen, whatever the format
+        if self.is_mark() {
+            return format!("── {} {} ──", self.timestamp().format("%Y-%m-%d %H:%M:%S"), self.content_plain());
+        }
+        match settings.yank_format {
+            YankFormat::Raw => self.content.clone(),
+            YankFormat::Plain => self.content_plain().to_string(),
+            YankFormat::Formatted => self.format(settings, None),
+        }
+    }
+
+    /// The entry's severity, or else the one named by a parsed `level` field (`"level":"warn"`)
+    pub fn level(&self) -> Option<Severity> {
+        self.severity.or_else(|| match self.field("level")?.as_ref() {
+            FieldValue::Str(level) => Severity::parse(level),
+            _ => None,
+        })
+    }
+
+    /// Look up a field by name: one parsed from the content, or else one saying where the
+    /// entry came from: its origin's (e.g. `path`, `pid`, `pod`), the `host` and `app` of a
+    /// decoded envelope, or its `source`
+    pub fn field(&self, name: &str) -> Option<Cow<'_, FieldValue>> {
+        let origin = self.origin.iter().flat_map(|origin| origin.iter());
+        if let Some((_, value)) = self.fields.iter().chain(origin).find(|(key, _)| key == name) {
+            return Some(Cow::Borrowed(value));
         }
+        let value = match name {
+            "host" => self.host.clone()?,
+            "app" => self.app_name.clone()?,
+            "source" => self.source.to_string(),
+            _ => return None,
+        };
+        Some(Cow::Owned(FieldValue::Str(value)))
+    }
+
+    /// Replace the envelope with its decoded message and standard fields
+    pub fn apply_decoded(&mut self, decoded: decoders::DecodedMessage) {
+        self.content_plain = None;
+        self.content = decoded.message;
+        self.is_json = false;
+        self.host = decoded.host;
+        self.app_name = decoded.app_name;
+        self.severity = decoded.severity;
     }
     
     // Format the entry according to settings
     pub fn format(&self, settings: &LogSettings, _line_number: Option<usize>) -> String {
+        // Choose between raw content (with ANSI codes) or plain content
+        let display_content = if settings.show_raw {
+            &self.content
+        } else {
+            self.content_plain()
+        };
+        format!("{}{}", self.prefix(settings), display_content)
+    }
+
+    /// Metadata columns and file type indicator shown before the content
+    pub fn prefix(&self, settings: &LogSettings) -> String {
+        let (parts, _) = self.meta_parts(settings);
+        let mut prefix = parts.join(" ");
+        if !prefix.is_empty() {
+            prefix.push(' ');
+        }
+        if settings.show_file_type {
+            prefix.push_str(if self.is_json { "\u{e60b} " } else { "  " });
+        }
+        prefix
+    }
+
+    /// Content as styled segments: the original ANSI styling in raw mode, otherwise plain text
+    pub fn styled_content(&self, settings: &LogSettings) -> Vec<(Style, &str)> {
+        if settings.show_raw && !self.ansi_segments.is_empty() {
+            self.ansi_segments.iter().map(|(style, range)| (*style, &self.content[range.clone()])).collect()
+        } else {
+            vec![(Style::default(), self.content_plain())]
+        }
+    }
+
+    /// Column range of the `[SOURCE]` label in the formatted line, if labels are shown
+    pub fn source_label_columns(&self, settings: &LogSettings) -> Option<std::ops::Range<usize>> {
+        let (parts, label_index) = self.meta_parts(settings);
+        let label_index = label_index?;
+        // Each preceding part is followed by a single space
+        let start: usize = parts[..label_index].iter().map(|p| p.chars().count() + 1).sum();
+        Some(start..start + parts[label_index].chars().count())
+    }
+
+    /// The metadata columns shown before the content, and which of them is the source label
+    fn meta_parts(&self, settings: &LogSettings) -> (Vec<String>, Option<usize>) {
         let mut parts = Vec::new();
+        let mut label_index = None;
 
         // Add line number if enabled
         if settings.show_line_numbers {
@@ -46,38 +374,34 @@ impl LogEntry {
         
         // Add timestamp if enabled
         if settings.show_time {
-            parts.push(format!("[{}]", self.timestamp.format("%Y-%m-%d %H:%M:%S")));
+            parts.push(format!("[{}]", self.timestamp().format("%Y-%m-%d %H:%M:%S")));
         }
         
         // Add source label if enabled
         if settings.show_source_labels {
+            label_index = Some(parts.len());
             parts.push(format!("[{}]", self.source.to_uppercase()));
+
+            // Decoded envelopes carry their own origin
+            match (&self.host, &self.app_name) {
+                (Some(host), Some(app)) => parts.push(format!("[{}/{}]", host, app)),
+                (Some(host), None) => parts.push(format!("[{}]", host)),
+                (None, Some(app)) => parts.push(format!("[{}]", app)),
+                (None, None) => {},
+            }
         }
-        
-        // Choose between raw content (with ANSI codes) or plain content
-        let display_content = if settings.show_raw {
-            &self.content
-        } else {
-            &self.content_plain
-        };
-        
-        // Add the content with file type indicator if enabled
-        let content_with_type = if settings.show_file_type {
-            if self.is_json {
-                format!("\u{e60b} {}", display_content)
-            } else {
-                format!("  {}", display_content)
+
+        // Add severity level if enabled and known
+        if settings.show_level {
+            if let Some(severity) = self.severity {
+                parts.push(format!("[{:<6}]", severity.label()));
             }
-        } else {
-            display_content.clone()
-        };
-        
-        parts.push(content_with_type);
-        
-        parts.join(" ")
+        }
+
+        (parts, label_index)
     }
 
     pub fn get_content_plain_len(&self) -> usize {
-        self.content_plain.len()
+        self.content_plain().len()
     }
 }
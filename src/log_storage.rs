@@ -1,38 +1,255 @@
-use std::collections::HashMap;
-use regex::Regex;
-use crate::log_entry::LogEntry;
-use crate::settings::LogSettings;
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap, VecDeque};
+use std::ops::Range;
+use std::sync::{Arc, RwLock};
+use std::sync::mpsc::{self, Receiver, TryRecvError};
+use std::thread;
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local};
+use crate::log_entry::{LogEntry, Severity};
+use crate::settings::{format_size, LogSettings};
+use crate::fields::FieldCondition;
+use crate::groups::SourceGroups;
+use crate::pattern::Pattern;
+use crate::spill::{SpillFile, SpillReader, SpillRef};
+
+/// Limits on how many entries are kept; the oldest are evicted first
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Retention {
+    /// Cap on entries across all sources
+    pub max_lines: Option<usize>,
+    /// Cap on entries from any one source
+    pub max_lines_per_source: Option<usize>,
+    /// Cap on the approximate bytes taken by entries in memory
+    pub max_memory: Option<usize>,
+}
+
+/// A stored entry: in memory, or moved out to the spill file
+#[derive(Clone)]
+enum Slot {
+    /// Shared so a background filter can read it while new entries arrive
+    Resident(Arc<LogEntry>),
+    Spilled { at: SpillRef, timestamp: i64, sequence: u64 },
+}
+
+impl Slot {
+    fn timestamp_millis(&self) -> i64 {
+        match self {
+            Slot::Resident(entry) => entry.timestamp_millis(),
+            Slot::Spilled { timestamp, .. } => *timestamp,
+        }
+    }
+
+    fn sequence(&self) -> u64 {
+        match self {
+            Slot::Resident(entry) => entry.sequence(),
+            Slot::Spilled { sequence, .. } => *sequence,
+        }
+    }
+
+    fn resident(&self) -> Option<&LogEntry> {
+        match self {
+            Slot::Resident(entry) => Some(entry),
+            Slot::Spilled { .. } => None,
+        }
+    }
+}
+
+/// Read an entry back from the spill file
+fn read_spilled(spill: &SpillReader, source: &Arc<str>, at: SpillRef) -> Option<LogEntry> {
+    let bytes = spill.read(at).ok()?;
+    LogEntry::from_bytes(source.clone(), &bytes)
+}
+
+/// Window over which a source's ingest rate is measured
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// How many entries a source added in each second of the last minute, for its ingest rate
+#[derive(Debug, Default)]
+struct Arrivals {
+    seconds: VecDeque<(Instant, usize)>,
+}
+
+impl Arrivals {
+    fn record(&mut self) {
+        let now = Instant::now();
+        match self.seconds.back_mut() {
+            Some((start, count)) if now.duration_since(*start) < Duration::from_secs(1) => *count += 1,
+            _ => self.seconds.push_back((now, 1)),
+        }
+        while self.seconds.front().is_some_and(|(start, _)| now.duration_since(*start) > RATE_WINDOW) {
+            self.seconds.pop_front();
+        }
+    }
+
+    fn per_minute(&self) -> usize {
+        self.seconds.iter()
+            .filter(|(start, _)| start.elapsed() <= RATE_WINDOW)
+            .map(|(_, count)| count)
+            .sum()
+    }
+}
 
 /// Manages log entries from a single source
 pub struct LogSource {
-    name: String,
-    entries: Vec<LogEntry>,
-    next_line_number: usize,
+    /// Shared with every stored entry from the source
+    name: Arc<str>,
+    entries: VecDeque<Slot>,
+    /// Entries dropped from the front by the retention policy
+    evicted: usize,
+    /// Entries currently in the spill file rather than in memory
+    spilled: usize,
+    /// Approximate bytes taken by the entries in memory
+    memory: usize,
+    /// Line number of the entry at the front, whose numbers count up from there
+    first_line_number: i64,
+    /// Lowest line number given out since numbering started; below it is free for earlier
+    /// lines loaded later, once nothing has been dropped from the front
+    lowest_line_number: i64,
     has_new_entries: bool,
     visible: bool,
+    arrivals: Arrivals,
 }
 
 impl LogSource {
     pub fn new(name: String) -> Self {
         Self {
-            name,
-            entries: Vec::new(),
-            next_line_number: 1, // Start from 1 for human readability
+            name: name.into(),
+            entries: VecDeque::new(),
+            evicted: 0,
+            spilled: 0,
+            memory: 0,
+            first_line_number: 1, // Start from 1 for human readability
+            lowest_line_number: 1,
             has_new_entries: false,
             visible: true, // Default to visible
+            arrivals: Arrivals::default(),
         }
     }
     
     pub fn add_entry(&mut self, mut entry: LogEntry) -> &LogEntry {
-        entry.line_number = self.next_line_number;
-        self.next_line_number += 1;
-        self.entries.push(entry);
+        // A `\r` redraw updates the last entry in place, keeping its position in the log
+        entry.source = self.name.clone();
+        self.memory += entry.memory_size();
+        let next_line_number = self.next_line_number();
+        if let (true, Some(last)) = (entry.redraw, self.entries.back_mut()) {
+            entry.line_number = next_line_number - 1;
+            entry.set_timestamp_millis(last.timestamp_millis());
+            entry.set_sequence(last.sequence());
+            match last {
+                Slot::Resident(old) => self.memory -= old.memory_size(),
+                Slot::Spilled { .. } => self.spilled -= 1,
+            }
+            *last = Slot::Resident(Arc::new(entry));
+        } else {
+            entry.line_number = next_line_number;
+            self.entries.push_back(Slot::Resident(Arc::new(entry)));
+            self.arrivals.record();
+        }
         self.has_new_entries = true;
-        self.entries.last().unwrap()
+        match self.entries.back() {
+            Some(Slot::Resident(entry)) => entry,
+            _ => unreachable!("the entry was just stored in memory"),
+        }
+    }
+
+    /// Whether entries can go before the front one: not once entries were dropped from the
+    /// front, as they'd go before ones that are gone
+    fn can_prepend(&self) -> bool {
+        self.first_line_number == self.lowest_line_number
+    }
+
+    /// Add an entry from before the front one, such as a line loaded with `:load_more`,
+    /// numbered and dated just ahead of it
+    fn prepend_entry(&mut self, mut entry: LogEntry) -> &LogEntry {
+        entry.source = self.name.clone();
+        entry.line_number = self.first_line_number - 1;
+        if let Some(first) = self.entries.front() {
+            entry.set_timestamp_millis(first.timestamp_millis() - 1);
+            entry.set_sequence(first.sequence());
+        }
+        self.memory += entry.memory_size();
+        self.first_line_number -= 1;
+        self.lowest_line_number = self.first_line_number;
+        self.entries.push_front(Slot::Resident(Arc::new(entry)));
+        self.has_new_entries = true;
+        match self.entries.front() {
+            Some(Slot::Resident(entry)) => entry,
+            _ => unreachable!("the entry was just stored in memory"),
+        }
+    }
+
+    /// Line number the next entry added at the back gets
+    fn next_line_number(&self) -> i64 {
+        self.first_line_number + self.entries.len() as i64
+    }
+
+    /// Position in `entries` of a line number
+    fn position(&self, line_number: i64) -> Option<usize> {
+        usize::try_from(line_number - self.first_line_number).ok()
+    }
+
+    /// The entry with a given line number, if it's still retained
+    /// Line numbers are consecutive and only the oldest entries are evicted, so this is a lookup
+    fn get(&self, line_number: i64) -> Option<&Slot> {
+        self.entries.get(self.position(line_number)?)
+    }
+
+    /// Move an entry out of memory into the spill file
+    fn spill(&mut self, line_number: i64, spill: &mut SpillFile) -> std::io::Result<()> {
+        let Some(slot) = self.position(line_number).and_then(|index| self.entries.get_mut(index)) else {
+            return Ok(());
+        };
+        if let Slot::Resident(entry) = slot {
+            let at = spill.write(&entry.to_bytes())?;
+            self.memory -= entry.memory_size();
+            *slot = Slot::Spilled { at, timestamp: entry.timestamp_millis(), sequence: entry.sequence() };
+            self.spilled += 1;
+        }
+        Ok(())
+    }
+
+    /// Drop the oldest entry, returning its timestamp
+    fn evict_oldest(&mut self) -> Option<i64> {
+        let slot = self.entries.pop_front()?;
+        self.first_line_number += 1;
+        self.evicted += 1;
+        match &slot {
+            Slot::Resident(entry) => self.memory -= entry.memory_size(),
+            Slot::Spilled { .. } => self.spilled -= 1,
+        }
+        Some(slot.timestamp_millis())
+    }
+
+    /// Drop every entry, returning how many there were. With `renumber` line numbers
+    /// start over at 1
+    fn clear(&mut self, renumber: bool) -> usize {
+        let count = self.entries.len();
+        self.first_line_number = self.next_line_number();
+        self.entries.clear();
+        self.evicted = 0;
+        self.spilled = 0;
+        self.memory = 0;
+        self.has_new_entries = false;
+        if renumber {
+            self.first_line_number = 1;
+            self.lowest_line_number = 1;
+        }
+        count
+    }
+
+    /// Number of entries dropped by the retention policy
+    pub fn evicted(&self) -> usize {
+        self.evicted
+    }
+    
+    pub fn name(&self) -> &str {
+        &self.name
     }
     
     pub fn get_entries(&self, filter: &Filter) -> Vec<&LogEntry> {
         self.entries.iter()
+            .filter_map(Slot::resident)
             .filter(|e| filter.check(e))
             .collect()
     }
@@ -40,6 +257,30 @@ impl LogSource {
     pub fn len(&self) -> usize {
         self.entries.len()
     }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of entries in the spill file rather than in memory
+    pub fn spilled(&self) -> usize {
+        self.spilled
+    }
+
+    /// Approximate bytes taken by the source's entries in memory
+    pub fn memory(&self) -> usize {
+        self.memory
+    }
+
+    /// Timestamp of the newest entry kept, in milliseconds
+    pub fn last_timestamp_millis(&self) -> Option<i64> {
+        self.entries.back().map(Slot::timestamp_millis)
+    }
+
+    /// Entries added over the last minute
+    pub fn per_minute(&self) -> usize {
+        self.arrivals.per_minute()
+    }
     
     pub fn set_visible(&mut self, visible: bool) {
         self.visible = visible;
@@ -59,82 +300,758 @@ impl LogSource {
 }
 
 /// Encapsulates filtering logic for log entries
+#[derive(Clone)]
 pub struct Filter {
-    pub source_visibility: HashMap<String, bool>,
-    pub filter_in: Option<Regex>,
-    pub filter_out: Option<Regex>,
+    source_visibility: HashMap<String, bool>,
+    /// Sources without a visibility of their own follow their group's
+    groups: SourceGroups,
+    /// Whether each source seen so far is shown, worked out once per source rather than for
+    /// every entry. Shared by clones, such as a background filter's, and started afresh
+    /// when the settings change
+    shown: Arc<RwLock<HashMap<Arc<str>, bool>>>,
+    pub filter_in: Option<Pattern>,
+    pub filter_out: Option<Pattern>,
+    pub field_conditions: Vec<FieldCondition>,
+    /// Entries less severe than this are left out
+    pub min_severity: Option<Severity>,
+    pub time_range: Option<(DateTime<Local>, DateTime<Local>)>,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl Filter {
     pub fn new() -> Self {
-        let mut source_visibility = HashMap::new();
-        // Default visibility for stdout and stderr
-        source_visibility.insert("stdout".to_string(), true);
-        source_visibility.insert("stderr".to_string(), true);
-        
         Self {
-            source_visibility,
+            source_visibility: HashMap::new(),
+            groups: SourceGroups::default(),
+            shown: Arc::default(),
             filter_in: None,
             filter_out: None,
+            field_conditions: Vec::new(),
+            min_severity: None,
+            time_range: None,
         }
     }
     
     /// Check if an entry passes all filter criteria
     pub fn check(&self, entry: &LogEntry) -> bool {
-        // Check source visibility
-        if !self.source_visibility.get(&entry.source).copied().unwrap_or(true) {
+        if !self.is_shown(&entry.source) {
             return false;
         }
         
         // Check filter_in (entry must match)
-        if let Some(regex) = &self.filter_in {
-            if !regex.is_match(&entry.content_plain) {
+        if let Some(pattern) = &self.filter_in {
+            if !pattern.is_match(entry.content_plain()) {
                 return false;
             }
         }
         
         // Check filter_out (entry must NOT match)
-        if let Some(regex) = &self.filter_out {
-            if regex.is_match(&entry.content_plain) {
+        if let Some(pattern) = &self.filter_out {
+            if pattern.is_match(entry.content_plain()) {
+                return false;
+            }
+        }
+
+        // Check the level; entries without one, such as the lines of a stack trace, stay
+        if let Some(min) = self.min_severity {
+            if entry.level().is_some_and(|level| level > min) {
+                return false;
+            }
+        }
+
+        // Check the time range (inclusive)
+        if let Some((from, to)) = &self.time_range {
+            let timestamp = entry.timestamp_millis();
+            if timestamp < from.timestamp_millis() || timestamp > to.timestamp_millis() {
                 return false;
             }
         }
+
+        // Check structured field conditions (all must match)
+        if !self.field_conditions.iter().all(|c| c.matches(entry.field(&c.field).as_deref())) {
+            return false;
+        }
         
         true
     }
+
+    /// Whether a source's entries are shown, from the memo once it's been worked out
+    fn is_shown(&self, source: &Arc<str>) -> bool {
+        if let Some(shown) = self.shown.read().ok().and_then(|shown| shown.get(source).copied()) {
+            return shown;
+        }
+        // Settings store source names lowercased; a source without a visibility of its
+        // own follows its group's
+        let visible = |name: &str| self.source_visibility.get(name).copied();
+        let shown = visible(&source.to_lowercase())
+            .or_else(|| self.groups.group_of(source).and_then(|(_, group)| visible(&group.to_lowercase())))
+            .unwrap_or(true);
+        if let Ok(mut memo) = self.shown.write() {
+            memo.insert(source.clone(), shown);
+        }
+        shown
+    }
     
     /// Update filter from LogSettings
     pub fn update_from_settings(&mut self, settings: &LogSettings) {
-        // Update source visibility from settings
-        for (source, source_config) in &settings.sources {
-            self.source_visibility.insert(source.clone(), source_config.visible);
+        // Update source visibility from settings, which forget sources that follow a group again
+        self.source_visibility = settings.sources.iter()
+            .map(|(source, source_config)| (source.clone(), source_config.visible))
+            .collect();
+        self.groups = settings.groups.clone();
+        self.shown = Arc::default();
+        self.field_conditions = settings.field_conditions.clone();
+        self.filter_in = settings.filter_in.clone();
+        self.filter_out = settings.filter_out.clone();
+        self.min_severity = settings.min_severity;
+        self.time_range = settings.time_range;
+    }
+}
+
+/// Where an entry sits in the merged index: its source and per-source line number
+#[derive(Debug, Clone, Copy)]
+struct IndexKey {
+    timestamp: i64,
+    sequence: u64,
+    source: usize,
+    line_number: i64,
+}
+
+impl IndexKey {
+    /// What keys are sorted by. Sequence numbers break ties between entries stamped in
+    /// the same millisecond, so they always come out in the same order
+    fn order(&self) -> (i64, u64) {
+        (self.timestamp, self.sequence)
+    }
+
+    fn same_entry(&self, other: &IndexKey) -> bool {
+        self.source == other.source && self.line_number == other.line_number
+    }
+
+    fn id(&self) -> EntryId {
+        EntryId { source: self.source, line_number: self.line_number }
+    }
+}
+
+/// Width of a time bucket, in milliseconds
+const BUCKET_MILLIS: i64 = 1000;
+
+/// Number of retained entries in each second that has any, so questions about a span of
+/// time are answered per bucket rather than per entry
+#[derive(Debug, Default)]
+struct TimeBuckets {
+    counts: BTreeMap<i64, usize>,
+}
+
+impl TimeBuckets {
+    fn bucket(timestamp: i64) -> i64 {
+        timestamp.div_euclid(BUCKET_MILLIS)
+    }
+
+    fn add(&mut self, timestamp: i64) {
+        *self.counts.entry(Self::bucket(timestamp)).or_default() += 1;
+    }
+
+    fn remove(&mut self, timestamp: i64) {
+        let bucket = Self::bucket(timestamp);
+        if let Some(count) = self.counts.get_mut(&bucket) {
+            *count -= 1;
+            if *count == 0 {
+                self.counts.remove(&bucket);
+            }
+        }
+    }
+
+    /// Entries from the buckets overlapping `from..=to`
+    fn count(&self, from: i64, to: i64) -> usize {
+        if from > to {
+            return 0;
         }
+        self.counts.range(Self::bucket(from)..=Self::bucket(to)).map(|(_, count)| count).sum()
+    }
+}
+
+/// Identifies an entry across frames: its source and per-source line number
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct EntryId {
+    source: usize,
+    line_number: i64,
+}
+
+/// A change to the merged index other than keys appended at the end or dropped from the front
+/// Positions count from the first key ever indexed, so dropping keys doesn't move them
+#[derive(Debug, Clone, Copy)]
+enum IndexChange {
+    /// A late entry was merged in at this position
+    Inserted(usize, IndexKey),
+    /// The entry at this position was redrawn, so it may pass a filter it didn't before
+    Redrawn(usize, IndexKey),
+    /// Keys were removed from the middle, moving everything after them
+    Renumbered,
+}
+
+/// How many index changes are kept for views to catch up with; a view further behind is rebuilt
+const MAX_INDEX_CHANGES: usize = 1024;
+
+/// Indexes at least this big are filtered on a worker thread when a view is rebuilt
+const BACKGROUND_FILTER_MIN: usize = 50_000;
+
+/// Entries a background filter checks between handing back results
+const FILTER_CHUNK: usize = 10_000;
+
+/// Entries moved out of memory that are read back at once, like a visible window, are kept
+/// until this many have piled up
+const MAX_PAGED_ENTRIES: usize = 4096;
+
+/// A copy of the index for a background filter, with what it needs to read spilled entries
+struct Snapshot {
+    entries: Vec<(IndexKey, Slot)>,
+    names: Vec<Arc<str>>,
+    spill: Option<SpillReader>,
+}
+
+impl Snapshot {
+    fn check(&self, filter: &Filter, key: &IndexKey, slot: &Slot) -> bool {
+        match slot {
+            Slot::Resident(entry) => filter.check(entry),
+            Slot::Spilled { at, .. } => self.spill.as_ref()
+                .and_then(|spill| read_spilled(spill, &self.names[key.source], *at))
+                .is_some_and(|entry| filter.check(&entry)),
+        }
+    }
+
+    fn load(&self, key: &IndexKey, slot: &Slot) -> Option<Arc<LogEntry>> {
+        match slot {
+            Slot::Resident(entry) => Some(entry.clone()),
+            Slot::Spilled { at, .. } => read_spilled(self.spill.as_ref()?, &self.names[key.source], *at).map(Arc::new),
+        }
+    }
+}
+
+/// How far a background search got
+enum SearchProgress {
+    /// Entries checked without a match
+    Checked(usize),
+    /// The search is over, with the entry found if any
+    Done(Option<Arc<LogEntry>>),
+}
+
+/// A search for the newest entry a predicate matches, over a snapshot of the index on a
+/// worker thread so a large or spilled index doesn't hold up the UI. Dropping it stops
+/// the worker
+pub struct SearchJob {
+    results: Receiver<SearchProgress>,
+    checked: usize,
+    total: usize,
+}
+
+impl SearchJob {
+    fn start(snapshot: Snapshot, predicate: impl Fn(&LogEntry) -> bool + Send + 'static) -> Self {
+        let (tx, results) = mpsc::channel();
+        let total = snapshot.entries.len();
+        thread::spawn(move || {
+            for chunk in snapshot.entries.rchunks(FILTER_CHUNK) {
+                let found = chunk.iter()
+                    .rev()
+                    .filter_map(|(key, slot)| snapshot.load(key, slot))
+                    .find(|entry| predicate(entry));
+                if found.is_some() {
+                    let _ = tx.send(SearchProgress::Done(found));
+                    return;
+                }
+                // Nobody is waiting for the result any more
+                if tx.send(SearchProgress::Checked(chunk.len())).is_err() {
+                    return;
+                }
+            }
+            let _ = tx.send(SearchProgress::Done(None));
+        });
+        Self { results, checked: 0, total }
+    }
+
+    /// A search that was over as soon as it started
+    fn finished(found: Option<Arc<LogEntry>>) -> Self {
+        let (tx, results) = mpsc::channel();
+        let _ = tx.send(SearchProgress::Done(found));
+        Self { results, checked: 0, total: 0 }
+    }
+
+    /// Take in what the worker got through since the last poll. Once the search is over,
+    /// the entry found, if any
+    pub fn poll(&mut self) -> Option<Option<Arc<LogEntry>>> {
+        loop {
+            match self.results.try_recv() {
+                Ok(SearchProgress::Checked(checked)) => self.checked += checked,
+                Ok(SearchProgress::Done(found)) => return Some(found),
+                Err(TryRecvError::Empty) => return None,
+                Err(TryRecvError::Disconnected) => return Some(None),
+            }
+        }
+    }
+
+    /// Entries checked and total
+    pub fn progress(&self) -> (usize, usize) {
+        (self.checked, self.total)
+    }
+}
+
+/// Matches from one chunk of a background filter, in index order
+struct FilterChunk {
+    keys: Vec<IndexKey>,
+    /// Timestamp of the oldest entry checked, matching or not
+    oldest: i64,
+    checked: usize,
+}
+
+/// A filter running over a snapshot of the index on a worker thread, newest entries
+/// first so the end of the log fills in right away. Dropping it stops the worker
+#[derive(Debug)]
+struct FilterJob {
+    results: Receiver<FilterChunk>,
+    checked: usize,
+    total: usize,
+    /// Oldest timestamp checked so far; older entries still have to come in
+    covered_from: Option<i64>,
+}
+
+impl FilterJob {
+    fn start(snapshot: Snapshot, filter: Filter) -> Self {
+        let (tx, results) = mpsc::channel();
+        let total = snapshot.entries.len();
+        thread::spawn(move || {
+            for chunk in snapshot.entries.rchunks(FILTER_CHUNK) {
+                let keys = chunk.iter()
+                    .filter(|(key, slot)| snapshot.check(&filter, key, slot))
+                    .map(|(key, _)| *key)
+                    .collect();
+                let oldest = chunk[0].0.timestamp;
+                // The view was rebuilt or dropped, so nobody is waiting for the rest
+                if tx.send(FilterChunk { keys, oldest, checked: chunk.len() }).is_err() {
+                    break;
+                }
+            }
+        });
+        Self { results, checked: 0, total, covered_from: None }
+    }
+
+    /// Whether an entry with this timestamp is still waiting to be checked
+    fn is_pending(&self, timestamp: i64) -> bool {
+        self.covered_from.is_none_or(|from| timestamp <= from)
+    }
+}
+
+/// The entries of the merged index that pass a filter, kept in step with storage by
+/// `LogStorage::refresh_view`, which only looks at what changed since the last refresh
+#[derive(Debug, Default)]
+pub struct FilteredView {
+    keys: VecDeque<IndexKey>,
+    /// Index position up to which entries have been checked against the filter
+    scanned: usize,
+    /// Index changes applied so far
+    changes_seen: usize,
+    /// Entries evicted from storage as of the last refresh
+    evicted_seen: usize,
+    /// Whether the keys match the current filter; cleared when the filter changes
+    built: bool,
+    /// Older entries still being filtered in the background
+    job: Option<FilterJob>,
+}
+
+impl FilteredView {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check every entry again on the next refresh, e.g. after the filter changed
+    pub fn invalidate(&mut self) {
+        self.built = false;
+        self.job = None;
+    }
+
+    /// Entries checked and total, while a background filter is running
+    pub fn progress(&self) -> Option<(usize, usize)> {
+        self.job.as_ref().map(|job| (job.checked, job.total))
+    }
+}
+
+/// The entries of a refreshed view in timestamp order, read by position
+#[derive(Clone, Copy)]
+pub struct ViewEntries<'a> {
+    storage: &'a LogStorage,
+    keys: &'a VecDeque<IndexKey>,
+}
+
+impl<'a> ViewEntries<'a> {
+    pub fn len(&self) -> usize {
+        self.keys.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    /// The entry at a position. Spilled entries are only there once paged in with
+    /// `LogStorage::page_in`
+    pub fn get(&self, index: usize) -> Option<&'a LogEntry> {
+        self.keys.get(index).and_then(|key| self.storage.resolve(key))
+    }
+
+    /// Identity of the entry at a position, stable while it stays in storage
+    pub fn id(&self, index: usize) -> Option<EntryId> {
+        self.keys.get(index).map(IndexKey::id)
+    }
+
+    /// Arrival time of the entry at a position, in milliseconds since the Unix epoch
+    pub fn timestamp_millis(&self, index: usize) -> Option<i64> {
+        self.keys.get(index).map(|key| key.timestamp)
+    }
+
+    /// Entries in a range of positions, which must be paged in if any were spilled
+    pub fn range(&self, range: Range<usize>) -> impl Iterator<Item = &'a LogEntry> + 'a {
+        let storage = self.storage;
+        self.keys.range(range).filter_map(move |key| storage.resolve(key))
+    }
+
+    /// Number of leading entries whose timestamp satisfies `pred`, like `slice::partition_point`
+    pub fn partition_point(&self, mut pred: impl FnMut(i64) -> bool) -> usize {
+        self.keys.partition_point(|key| pred(key.timestamp))
+    }
+
+    /// Position of an entry, looked up by its timestamp
+    pub fn find(&self, id: EntryId, timestamp: i64) -> Option<usize> {
+        let first = self.partition_point(|t| t < timestamp);
+        self.keys.range(first..)
+            .take_while(|key| key.timestamp == timestamp)
+            .position(|key| key.id() == id)
+            .map(|position| first + position)
     }
 }
 
 /// Main component that aggregates log sources and handles filtering
 pub struct LogStorage {
-    sources: HashMap<String, LogSource>,
-    filter: Filter,
+    sources: Vec<LogSource>,
+    source_ids: HashMap<String, usize>,
+    /// Every entry across all sources in timestamp order (ties in sequence order), kept up to
+    /// date as entries arrive so reading the unified view never needs a sort
+    merged: VecDeque<IndexKey>,
+    /// Keys in `merged` whose entries have since been evicted
+    stale: usize,
+    /// Keys dropped from the front of `merged`, i.e. the position of its first key
+    front: usize,
+    /// Recent index changes for views to apply, and how many were discarded before them
+    changes: VecDeque<IndexChange>,
+    changes_start: usize,
     active_source: Option<String>,
+    retention: Retention,
+    /// Where entries go once more than `spill_after` are in memory, oldest first
+    spill: Option<SpillFile>,
+    spill_after: Option<usize>,
+    /// Index position of the next key to consider spilling
+    spill_next: usize,
+    /// Groups of sources from `--group`, which every tab starts with
+    groups: SourceGroups,
+    /// Something to tell the user about, like spilling stopping, until it's reported
+    warning: Option<String>,
+    /// Whether the memory cap has dropped entries since it was set
+    memory_pressure: bool,
+    /// Spilled entries read back by `page_in`
+    paged: HashMap<EntryId, Arc<LogEntry>>,
+    buckets: TimeBuckets,
+}
+
+impl Default for LogStorage {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl LogStorage {
     pub fn new() -> Self {
         Self {
-            sources: HashMap::new(),
-            filter: Filter::new(),
+            sources: Vec::new(),
+            source_ids: HashMap::new(),
+            merged: VecDeque::new(),
+            stale: 0,
+            front: 0,
+            changes: VecDeque::new(),
+            changes_start: 0,
             active_source: None,
+            retention: Retention::default(),
+            spill: None,
+            spill_after: None,
+            spill_next: 0,
+            groups: SourceGroups::default(),
+            warning: None,
+            memory_pressure: false,
+            paged: HashMap::new(),
+            buckets: TimeBuckets::default(),
+        }
+    }
+
+    /// Keep at most `max` entries in memory, moving older ones out to a temporary file
+    pub fn spill_after(&mut self, max: usize) -> std::io::Result<()> {
+        if self.spill.is_none() {
+            self.spill = Some(SpillFile::create()?);
+        }
+        self.spill_after = Some(max.max(1));
+        Ok(())
+    }
+
+    /// Spill the oldest entries in memory until there's room for one more
+    fn make_resident_room(&mut self) {
+        let (Some(max), Some(spill)) = (self.spill_after, &mut self.spill) else {
+            return;
+        };
+        let mut resident = self.sources.iter().map(|s| s.len() - s.spilled()).sum::<usize>();
+        while resident >= max {
+            let position = self.spill_next.max(self.front);
+            let Some(key) = self.merged.get(position - self.front) else {
+                break;
+            };
+            self.spill_next = position + 1;
+            let source = &mut self.sources[key.source];
+            let spilled = source.spilled();
+            if let Err(err) = source.spill(key.line_number, spill) {
+                // Entries already on disk can still be read back
+                self.spill_after = None;
+                self.warning = Some(format!("Stopped spilling to disk: {}", err));
+                break;
+            }
+            resident -= source.spilled() - spilled;
+        }
+    }
+
+    /// A warning for the user, reported once
+    pub fn take_warning(&mut self) -> Option<String> {
+        self.warning.take()
+    }
+
+    /// Approximate bytes taken by entries in memory
+    pub fn memory(&self) -> usize {
+        self.sources.iter().map(|s| s.memory()).sum()
+    }
+
+    /// Drop the oldest entries while the memory cap is exceeded, or would be with
+    /// `incoming` more bytes, warning the first time it happens
+    fn relieve_memory(&mut self, incoming: usize) {
+        let Some(max) = self.retention.max_memory else {
+            return;
+        };
+        let mut evicted = false;
+        while self.memory() + incoming > max && self.evict_oldest() {
+            evicted = true;
+        }
+        if evicted && !self.memory_pressure {
+            self.memory_pressure = true;
+            self.warning = Some(format!("Memory limit of {} reached: dropping the oldest entries", format_size(max)));
+        }
+    }
+
+    /// Entries in the spill file rather than in memory
+    pub fn spilled(&self) -> usize {
+        self.sources.iter().map(|s| s.spilled()).sum()
+    }
+
+    /// Read the spilled entries at a range of a view's positions back into memory, so
+    /// `ViewEntries` can hand them out until the next `release_pages`
+    pub fn page_in(&mut self, view: &FilteredView, range: Range<usize>) {
+        let Some(spill) = &self.spill else {
+            return;
+        };
+        let end = range.end.min(view.keys.len());
+        for key in view.keys.range(range.start.min(end)..end) {
+            let source = &self.sources[key.source];
+            if let Some(Slot::Spilled { at, .. }) = source.get(key.line_number) {
+                if let Entry::Vacant(slot) = self.paged.entry(key.id()) {
+                    if let Some(entry) = read_spilled(spill.reader(), &source.name, *at) {
+                        slot.insert(Arc::new(entry));
+                    }
+                }
+            }
         }
     }
+
+    /// Let go of paged-in entries once there are many, before paging in the next window
+    pub fn release_pages(&mut self) {
+        if self.paged.len() > MAX_PAGED_ENTRIES {
+            self.paged.clear();
+        }
+    }
+
+    pub fn set_groups(&mut self, groups: SourceGroups) {
+        self.groups = groups;
+    }
+
+    pub fn groups(&self) -> &SourceGroups {
+        &self.groups
+    }
+
+    pub fn retention(&self) -> Retention {
+        self.retention
+    }
+
+    /// Change the retention policy, evicting right away if the new caps are lower
+    pub fn set_retention(&mut self, retention: Retention) {
+        if retention.max_memory != self.retention.max_memory {
+            self.memory_pressure = false;
+        }
+        self.retention = retention;
+        if let Some(max) = retention.max_lines_per_source {
+            for id in 0..self.sources.len() {
+                while self.sources[id].len() > max {
+                    self.evict_from(id);
+                }
+            }
+        }
+        if let Some(max) = retention.max_lines {
+            while self.total_entries() > max && self.evict_oldest() {}
+        }
+        self.relieve_memory(0);
+    }
+
+    /// Drop the oldest entry of a source, leaving its key in the index to be cleaned up
+    fn evict_from(&mut self, id: usize) {
+        if let Some(timestamp) = self.sources[id].evict_oldest() {
+            self.buckets.remove(timestamp);
+            self.stale += 1;
+        }
+        self.compact();
+    }
+
+    /// Drop the oldest entry across all sources, returning false if there were none
+    fn evict_oldest(&mut self) -> bool {
+        match self.merged.front() {
+            Some(key) => {
+                let id = key.source;
+                self.evict_from(id);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Clear evicted keys off the front of the index, and out of the middle once they
+    /// make up half of it
+    fn compact(&mut self) {
+        while let Some(key) = self.merged.front() {
+            if self.contains(key) {
+                break;
+            }
+            self.merged.pop_front();
+            self.front += 1;
+            self.stale -= 1;
+        }
+        if self.stale > 0 && self.stale * 2 >= self.merged.len() {
+            let sources = &self.sources;
+            self.merged.retain(|key| sources[key.source].get(key.line_number).is_some());
+            self.stale = 0;
+            self.spill_next = self.front;
+            self.record_change(IndexChange::Renumbered);
+        }
+    }
+
+    /// Note a change for views to apply on their next refresh
+    fn record_change(&mut self, change: IndexChange) {
+        self.changes.push_back(change);
+        if self.changes.len() > MAX_INDEX_CHANGES {
+            self.changes.pop_front();
+            self.changes_start += 1;
+        }
+    }
+
+    /// Whether the entry an index key points to is still stored, in memory or not
+    fn contains(&self, key: &IndexKey) -> bool {
+        self.sources[key.source].get(key.line_number).is_some()
+    }
+
+    /// The entry an index key points to, if it hasn't been evicted and is in memory or paged in
+    fn resolve(&self, key: &IndexKey) -> Option<&LogEntry> {
+        match self.sources[key.source].get(key.line_number)? {
+            Slot::Resident(entry) => Some(entry),
+            Slot::Spilled { .. } => self.paged.get(&key.id()).map(|entry| entry.as_ref()),
+        }
+    }
+
+    /// The entry an index key points to, read from the spill file if it has to be
+    fn load(&self, key: &IndexKey) -> Option<Arc<LogEntry>> {
+        let source = &self.sources[key.source];
+        match source.get(key.line_number)? {
+            Slot::Resident(entry) => Some(entry.clone()),
+            Slot::Spilled { at, .. } => self.paged.get(&key.id()).cloned().or_else(|| {
+                read_spilled(self.spill.as_ref()?.reader(), &source.name, *at).map(Arc::new)
+            }),
+        }
+    }
+
+    /// Drop every entry from a source, returning how many there were. With `renumber` its
+    /// line numbers start over at 1
+    pub fn clear_source(&mut self, name: &str, renumber: bool) -> usize {
+        match self.source_ids.get(name) {
+            Some(&id) => self.clear_ids(&[id], renumber),
+            None => 0,
+        }
+    }
+
+    /// Drop every entry from every source, returning how many there were
+    pub fn clear(&mut self, renumber: bool) -> usize {
+        let ids: Vec<usize> = (0..self.sources.len()).collect();
+        self.clear_ids(&ids, renumber)
+    }
+
+    /// Empty the sources and take their keys out of the index, which views rebuild from.
+    /// Their dropped counts go too, since a clear starts them afresh
+    fn clear_ids(&mut self, ids: &[usize], renumber: bool) -> usize {
+        let mut count = 0;
+        for &id in ids {
+            for slot in &self.sources[id].entries {
+                self.buckets.remove(slot.timestamp_millis());
+            }
+            count += self.sources[id].clear(renumber);
+        }
+        // Renumbered sources reuse ids, so nothing paged in for them can be kept
+        self.paged.retain(|entry, _| !ids.contains(&entry.source));
+        let sources = &self.sources;
+        self.merged.retain(|key| sources[key.source].get(key.line_number).is_some());
+        self.stale = 0;
+        self.spill_next = self.front;
+        self.record_change(IndexChange::Renumbered);
+        count
+    }
+
+    /// Entries dropped by the retention policy across all sources
+    pub fn evicted(&self) -> usize {
+        self.sources.iter().map(|s| s.evicted()).sum()
+    }
     
+    /// Id of the named source, creating it if it's new
+    fn source_id(&mut self, name: &str) -> usize {
+        if let Some(id) = self.source_ids.get(name) {
+            return *id;
+        }
+        self.sources.push(LogSource::new(name.to_string()));
+        self.source_ids.insert(name.to_string(), self.sources.len() - 1);
+        self.sources.len() - 1
+    }
+
     pub fn add_source(&mut self, name: String) -> &mut LogSource {
-        self.sources.entry(name.clone()).or_insert_with(|| LogSource::new(name.clone()));
-        self.sources.get_mut(&name).unwrap()
+        let id = self.source_id(&name);
+        &mut self.sources[id]
+    }
+    
+    /// All sources, sorted by name
+    pub fn sources(&self) -> Vec<&LogSource> {
+        let mut sources: Vec<&LogSource> = self.sources.iter().collect();
+        sources.sort_by(|a, b| a.name.cmp(&b.name));
+        sources
     }
     
     pub fn get_source(&self, name: &str) -> Option<&LogSource> {
-        self.sources.get(name)
+        self.source_ids.get(name).map(|id| &self.sources[*id])
     }
     
     pub fn set_active_source(&mut self, name: Option<String>) {
@@ -145,50 +1062,273 @@ impl LogStorage {
         &self.active_source
     }
     
-    pub fn add_entry(&mut self, entry: LogEntry) {
-        let source_name = entry.source.clone();
-        let source = self.add_source(source_name);
-        source.add_entry(entry);
+    pub fn add_entry(&mut self, entry: LogEntry) -> &LogEntry {
+        let id = self.source_id(&entry.source);
+        // Make room first so the new entry is never the one evicted. Redraws replace an
+        // entry rather than adding one
+        let grows = !entry.redraw || self.sources[id].is_empty();
+        if grows {
+            if let Some(max) = self.retention.max_lines {
+                while self.total_entries() >= max.max(1) && self.evict_oldest() {}
+            }
+            if let Some(max) = self.retention.max_lines_per_source {
+                while self.sources[id].len() >= max.max(1) {
+                    self.evict_from(id);
+                }
+            }
+            self.relieve_memory(entry.memory_size());
+            self.make_resident_room();
+        }
+
+        let added = self.sources[id].add_entry(entry);
+        let key = IndexKey {
+            timestamp: added.timestamp_millis(),
+            sequence: added.sequence(),
+            source: id,
+            line_number: added.line_number,
+        };
+        if grows {
+            self.insert_key(key);
+        } else {
+            // A redrawn entry is back in memory, and any copy paged in is out of date
+            self.paged.remove(&key.id());
+            if let Some(position) = self.index_position(&key) {
+                self.spill_next = self.spill_next.min(self.front + position);
+                self.record_change(IndexChange::Redrawn(self.front + position, key));
+            }
+        }
+        match self.sources[id].entries.back() {
+            Some(Slot::Resident(entry)) => entry,
+            _ => unreachable!("the entry was just stored in memory"),
+        }
     }
-    
-    pub fn get_filtered_entries(&self) -> Vec<&LogEntry> {
-        let mut result = Vec::new();
-        
-        // Get entries from each source that pass the filter
-        for source in self.sources.values() {
-            result.extend(source.get_entries(&self.filter));
+
+    /// Add an entry from before its source's first one, such as a line loaded with
+    /// `:load_more`. As the oldest of the source's entries it's left out, rather than
+    /// evicting others, when the retention limits are reached or entries were already
+    /// dropped from the front of the source
+    pub fn add_earlier(&mut self, entry: LogEntry) -> Option<&LogEntry> {
+        let id = self.source_id(&entry.source);
+        let full = self.retention.max_lines.is_some_and(|max| self.total_entries() >= max.max(1))
+            || self.retention.max_lines_per_source.is_some_and(|max| self.sources[id].len() >= max.max(1))
+            || self.retention.max_memory.is_some_and(|max| self.memory() + entry.memory_size() > max);
+        if full || !self.sources[id].can_prepend() {
+            self.warning = Some(format!("Earlier lines of {} were left out: older entries were dropped or storage is full", entry.source));
+            return None;
+        }
+        self.make_resident_room();
+
+        let added = self.sources[id].prepend_entry(entry);
+        let key = IndexKey {
+            timestamp: added.timestamp_millis(),
+            sequence: added.sequence(),
+            source: id,
+            line_number: added.line_number,
+        };
+        self.insert_key(key);
+        match self.sources[id].entries.front() {
+            Some(Slot::Resident(entry)) => Some(entry),
+            _ => unreachable!("the entry was just stored in memory"),
         }
-        
-        // Sort by timestamp for a unified view
-        result.sort_by(|a, b| a.timestamp.cmp(&b.timestamp));
-        
-        result
     }
-    
-    pub fn update_filter_from_settings(&mut self, settings: &LogSettings) {
-        self.filter.update_from_settings(settings);
+
+    /// Put a new entry's key into the merged index, in timestamp order
+    fn insert_key(&mut self, key: IndexKey) {
+        self.buckets.add(key.timestamp);
+        // Entries almost always arrive in order; merge the rest into place
+        if self.merged.back().is_none_or(|last| last.order() <= key.order()) {
+            self.merged.push_back(key);
+        } else {
+            let position = self.merged.partition_point(|k| k.order() <= key.order());
+            self.merged.insert(position, key);
+            // A late entry older than what's been spilled goes out with the next spill
+            self.spill_next = self.spill_next.min(self.front + position);
+            self.record_change(IndexChange::Inserted(self.front + position, key));
+        }
+    }
+
+    /// Bring a view up to date with the entries added, redrawn and evicted since its last
+    /// refresh. Only those entries are checked against the filter, unless the view was
+    /// invalidated or fell too far behind, when every entry is checked again
+    pub fn refresh_view(&self, view: &mut FilteredView, filter: &Filter) {
+        if !view.built || view.changes_seen < self.changes_start {
+            return self.rebuild_view(view, filter);
+        }
+        let passes = |key: &IndexKey| self.load(key).is_some_and(|entry| filter.check(&entry));
+
+        for change in self.changes.range(view.changes_seen - self.changes_start..) {
+            let pending = |key: &IndexKey| view.job.as_ref().is_some_and(|job| job.is_pending(key.timestamp));
+            match *change {
+                // The background filter is working from a snapshot without this change
+                IndexChange::Inserted(position, key) | IndexChange::Redrawn(position, key)
+                    if position < view.scanned && pending(&key) => return self.rebuild_view(view, filter),
+                // Entries past `scanned` are picked up by the scan below
+                IndexChange::Inserted(position, key) if position < view.scanned => {
+                    view.scanned += 1;
+                    if passes(&key) {
+                        self.insert_into_view(view, key);
+                    }
+                },
+                IndexChange::Redrawn(position, key) if position < view.scanned => {
+                    let index = view.keys.partition_point(|k| k.order() < key.order());
+                    let found = view.keys.get(index).filter(|k| k.same_entry(&key)).map(|_| index);
+                    match (found, passes(&key)) {
+                        (Some(index), false) => {
+                            view.keys.remove(index);
+                        },
+                        (None, true) => self.insert_into_view(view, key),
+                        _ => {},
+                    }
+                },
+                IndexChange::Renumbered => return self.rebuild_view(view, filter),
+                _ => {},
+            }
+        }
+
+        // Older matches from the background filter go in front of what's there
+        if let Some(job) = &mut view.job {
+            loop {
+                match job.results.try_recv() {
+                    Ok(chunk) => {
+                        for key in chunk.keys.into_iter().rev() {
+                            if self.contains(&key) {
+                                view.keys.push_front(key);
+                            }
+                        }
+                        job.checked += chunk.checked;
+                        job.covered_from = Some(chunk.oldest);
+                    },
+                    Err(TryRecvError::Empty) => break,
+                    // The worker is done (or gave up)
+                    Err(TryRecvError::Disconnected) => {
+                        job.checked = job.total;
+                        break;
+                    },
+                }
+            }
+            if job.checked >= job.total {
+                view.job = None;
+            }
+        }
+
+        // Evicted entries leave from the front, except when a per-source cap drops them
+        // from the middle
+        while view.keys.front().is_some_and(|key| !self.contains(key)) {
+            view.keys.pop_front();
+        }
+        let evicted = self.evicted();
+        if self.stale > 0 && evicted != view.evicted_seen {
+            view.keys.retain(|key| self.contains(key));
+        }
+
+        let scanned = view.scanned.max(self.front);
+        view.keys.extend(self.merged.range(scanned - self.front..).filter(|key| passes(key)));
+        view.scanned = self.front + self.merged.len();
+        view.changes_seen = self.changes_start + self.changes.len();
+        view.evicted_seen = evicted;
+    }
+
+    /// Position of a key in the merged index, found by its order
+    fn index_position(&self, key: &IndexKey) -> Option<usize> {
+        let position = self.merged.partition_point(|k| k.order() < key.order());
+        self.merged.get(position).filter(|k| k.same_entry(key)).map(|_| position)
+    }
+
+    /// Add a key to a view in index order
+    fn insert_into_view(&self, view: &mut FilteredView, key: IndexKey) {
+        let position = view.keys.partition_point(|k| k.order() < key.order());
+        view.keys.insert(position, key);
+    }
+
+    /// Index positions of the entries from `from` to `to` (inclusive), found by binary search
+    fn time_positions(&self, from: i64, to: i64) -> Range<usize> {
+        let start = self.merged.partition_point(|key| key.timestamp < from);
+        let end = self.merged.partition_point(|key| key.timestamp <= to);
+        start..end.max(start)
+    }
+
+    /// Entries retained from `from` to `to`, counted by whole seconds
+    pub fn count_between(&self, from: DateTime<Local>, to: DateTime<Local>) -> usize {
+        self.buckets.count(from.timestamp_millis(), to.timestamp_millis())
+    }
+
+    /// A copy of part of the index for a worker thread
+    fn snapshot(&self, positions: Range<usize>) -> Snapshot {
+        Snapshot {
+            entries: self.merged.range(positions)
+                .filter_map(|key| self.sources[key.source].get(key.line_number).map(|slot| (*key, slot.clone())))
+                .collect(),
+            names: self.sources.iter().map(|source| source.name.clone()).collect(),
+            spill: self.spill.as_ref().map(|spill| spill.reader().clone()),
+        }
+    }
+
+    /// Check every entry against the filter, on a worker thread if there are many. With a
+    /// time range, only the entries inside it are looked at
+    fn rebuild_view(&self, view: &mut FilteredView, filter: &Filter) {
+        let positions = match filter.time_range {
+            Some((from, to)) => self.time_positions(from.timestamp_millis(), to.timestamp_millis()),
+            None => 0..self.merged.len(),
+        };
+        if positions.len() < BACKGROUND_FILTER_MIN {
+            view.keys = self.merged.range(positions)
+                .filter(|key| self.load(key).is_some_and(|entry| filter.check(&entry)))
+                .copied()
+                .collect();
+            view.job = None;
+        } else {
+            view.keys.clear();
+            view.job = Some(FilterJob::start(self.snapshot(positions), filter.clone()));
+        }
+        view.scanned = self.front + self.merged.len();
+        view.changes_seen = self.changes_start + self.changes.len();
+        view.evicted_seen = self.evicted();
+        view.built = true;
+    }
+
+    /// The entries of a view, as of its last refresh
+    pub fn view_entries<'a>(&'a self, view: &'a FilteredView) -> ViewEntries<'a> {
+        ViewEntries { storage: self, keys: &view.keys }
     }
     
+    /// Look for the most recent entry from any source that satisfies `predicate`: right
+    /// away if there are few entries, or else on a worker thread
+    pub fn search_latest(&self, predicate: impl Fn(&LogEntry) -> bool + Send + 'static) -> SearchJob {
+        if self.merged.len() < BACKGROUND_FILTER_MIN {
+            let found = self.merged.iter()
+                .rev()
+                .filter_map(|key| self.load(key))
+                .find(|entry| predicate(entry));
+            return SearchJob::finished(found);
+        }
+        SearchJob::start(self.snapshot(0..self.merged.len()), predicate)
+    }
+
+    /// Identity of a source's entry, for finding it in a view
+    pub fn entry_id(&self, source: &str, line_number: i64) -> Option<EntryId> {
+        self.source_ids.get(source).map(|&source| EntryId { source, line_number })
+    }
+
     pub fn total_entries(&self) -> usize {
-        self.sources.values().map(|s| s.len()).sum()
+        self.sources.iter().map(|s| s.len()).sum()
     }
     
     // Check if there are new entries in any visible source that are currently filtered in
     pub fn has_new_visible_entries(&self) -> bool {
         if let Some(active) = &self.active_source {
-            if let Some(source) = self.sources.get(active) {
+            if let Some(source) = self.get_source(active) {
                 return source.has_new_entries() && source.is_visible();
             }
         }
         
         // If no active source, check all visible sources
-        self.sources.values()
+        self.sources.iter()
             .any(|source| source.has_new_entries() && source.is_visible())
     }
     
     // Clear the new entries flags on all sources
     pub fn clear_new_entries_flags(&mut self) {
-        for source in self.sources.values_mut() {
+        for source in self.sources.iter_mut() {
             source.clear_new_entries_flag();
         }
     }
@@ -1,17 +1,13 @@
 use clap::Parser;
 use clap::CommandFactory;
-use std::sync::mpsc;
 use anyhow::{Result, Context};
-use std::path::PathBuf;
-mod process_handler;
-mod ui;
-mod settings;
-mod commands;
-mod log_entry;
-mod log_storage;
-mod widgets;
-mod file_watcher;
-mod stdin_reader;
+use std::os::fd::RawFd;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use oxtail::{control, process_handler, exec_watch, batch, checkpoints, benchmark, ui, settings,
+    commands, encoding, fields, groups, log_entry, log_storage, pattern, file_watcher,
+    stdin_reader, listener, kube, http_stream, websocket, redis_reader, serial, source_status,
+    synthetic, config, xdg};
 
 #[derive(Parser, Debug)]
 #[command(
@@ -22,31 +18,359 @@ mod stdin_reader;
     
 Key Bindings:
   - q: Quit the application
+  - Ctrl-Z: Suspend to the shell (resume with fg)
   - : (colon): Enter command mode
+  - F: Follow the end of the log (again to stop); see :set autoresume
+  - ?: Show all key bindings and commands
+  - Mouse drag: Select text; c: Copy the selection to the clipboard
+  - v: Toggle the line cursor; V: Visual line selection; y: Copy the selected entries (see :set yank)
   - Up Arrow: Scroll up one line
   - Down Arrow: Scroll down one line
   - PageUp: Scroll up one page
   - PageDown: Scroll down one page
+  - Tab / Shift-Tab: Next / previous tab
+  - a: Jump to the entry in the alert bar
+  - i: Send typed lines to the command's stdin (Esc to stop)
+  - s: Toggle the source sidebar (arrows to move, Space to toggle visibility)
     
 Commands:
-  - :show_source stdout/stderr/file/<filename>/stdin
-  - :hide_source stdout/stderr/file/<filename>/stdin
-  - :show_meta time/source/lines
-  - :hide_meta time/source/lines
+  - :show_source <command>:stdout/<command>:stderr/<filename>/stdin/<group>
+  - :hide_source <command>:stdout/<command>:stderr/<filename>/stdin/<group>
+  - :show_meta time/source/lines/level/delta
+  - :hide_meta time/source/lines/level/delta
+  - :where <field><op><value>... (e.g. :where duration_ms>250)
+  - :filter_in <regex> / :filter_out <regex> / :level <severity> (alone, each clears)
+  - :tab new [name] / next / prev / rename <name> / close / <n>
+  - :split <n> / :split (show tab n beside the active tab) / :sync (scroll split panes together by timestamp)
+  - :layout grid/rows/columns (one pane per visible source)
+  - :sources / :filters (listed in a pane; Esc closes it)
+  - :write <path> / :pipe <command> / :narrow / :widen (act on the selected entries)
+  - :load_more [file] (read earlier lines of files opened with --tail-lines)
+  - :open <path> / :close <file> [drop] (follow another file, or stop following one)
+  - :restart (stop the command given after -- and start it again)
+  - :term / :int / :kill / :signal <SIG> (send a signal to the command)
+  - :alert <regex> (headline bar for matching entries; :alert alone clears)
+  - :highlight <regex>[:<color>] (e.g. :highlight timeout:red; :highlight alone clears)
+  - :theme neon/solarized-dark/light/<name from config>
     
 Usage:
   oxtail [FILES]... [-- COMMAND [ARGS]...]
 For example:
   oxtail a.log b.log -- ./server
+  oxtail 'logs/*.log' (also picks up matching files created later)
+  oxtail --dir ./logs
+  oxtail --group api='api*.log' --group db='postgres*.log' logs/*.log (then :hide_source db)
+  oxtail app.log.2.gz app.log.1.gz app.log (rotated history, then the live file)
+  oxtail --poll 2s /mnt/nfs/app.log (for mounts that don't report changes)
+  oxtail --encoding legacy.log=shift_jis legacy.log app.log
+  oxtail --tail-lines 0 --load-rate 64M huge.log (read all of it, 64MB a second)
+  oxtail --resume app.log (carry on where the last --resume session stopped)
+  oxtail --init-config (write a commented config file to start from)
+  oxtail --follow descriptor app.log (stay with app.log after it's rotated away)
   oxtail error.log -- npm start
+  oxtail --restart-on-exit=2s -- cargo run (start it again 2s after it exits)
+  oxtail --every 5s -- curl -s localhost:8080/health (run it again 5s after each run)
+  oxtail --watch-exec 'src/**/*.rs' -- cargo test (run it again when a source file changes)
+  oxtail --propagate-exit -- make test (quit when it's done, with its exit code)
+  oxtail --pty -- npm test (colors and line-at-a-time output, as in a terminal)
+  oxtail --cwd web --env PORT=3000 --shell -- 'npm run build && npm start'
   oxtail app.log test.log
-  cat log.txt | oxtail"
+  cat log.txt | oxtail (stays open after the input ends, for scrolling and searching)
+  make 2>&1 | oxtail --exit-on-eof --write-on-exit build.log
+  kubectl logs -f --timestamps api | oxtail --stdin-name api --stdin-timestamps app.log
+  oxtail --kube deploy/my-api -n prod (every pod of the deployment, as they come and go)
+  OXTAIL_BEARER_TOKEN=... oxtail --http-stream https://api.example.com/v1/logs/stream
+  oxtail --ws wss://logs.example.com/tail
+  oxtail --redis redis://localhost/logs (a stream, or a pub/sub channel)
+  sudo oxtail --listen syslog-udp://0.0.0.0:514 (devices and daemons that send syslog)
+  oxtail --listen fluent-forward://0.0.0.0:24224 (records from Fluent Bit, by tag)
+  oxtail --serial /dev/ttyUSB0:115200 (a board's console)
+  oxtail --control unix:///tmp/oxtail.ctl app.log (take commands from scripts)
+  oxtail --fd 3 --fd 4 3< <(journalctl -f) 4< <(kubectl logs -f web) (fd3 and fd4 sources)"
 )]
 struct Args {
-    /// Files to monitor
+    /// Files to monitor; files that don't exist yet are read once created. .gz, .zst and .bz2 files are read whole, once. Quote wildcards (e.g. 'logs/*.log') to also pick up matching files created later
     #[arg(value_parser = clap::value_parser!(PathBuf))]
     files: Vec<PathBuf>,
 
+    /// Watch every file in this directory, including files created later
+    #[arg(long, value_name = "DIR")]
+    dir: Vec<PathBuf>,
+
+    /// Put the sources matching a wildcard pattern in a named group, which :show_source and
+    /// :hide_source take as a whole and which is drawn in a color of its own (e.g. api=api*.log)
+    #[arg(long, value_name = "NAME=PATTERN", value_parser = groups::SourceGroup::parse)]
+    group: Vec<groups::SourceGroup>,
+
+    /// Keep at most this many entries across all sources, dropping the oldest
+    #[arg(long, value_name = "N")]
+    max_lines: Option<usize>,
+
+    /// Keep at most this many entries from each source, dropping the oldest
+    #[arg(long, value_name = "N")]
+    max_lines_per_source: Option<usize>,
+
+    /// Keep entries in memory under roughly this size (e.g. 512M, 2G), dropping the oldest
+    #[arg(long, value_name = "SIZE", value_parser = parse_size)]
+    max_memory: Option<usize>,
+
+    /// Read settings from this file instead of ~/.config/oxtail/config.toml
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+
+    /// Write a config file with every setting commented out, to the --config path or
+    /// ~/.config/oxtail/config.toml, and exit
+    #[arg(long)]
+    init_config: bool,
+
+    /// Hide a metadata column to begin with (time, source, lines, filetype, level, delta, ansi);
+    /// repeat for more. Like :hide_meta, and these flags take precedence over the config file
+    #[arg(long, value_name = "COLUMN", value_parser = clap::builder::PossibleValuesParser::new(commands::META_TYPES))]
+    hide_meta: Vec<String>,
+
+    /// Show a metadata column to begin with, like :show_meta; repeat for more
+    #[arg(long, value_name = "COLUMN", value_parser = clap::builder::PossibleValuesParser::new(commands::META_TYPES))]
+    show_meta: Vec<String>,
+
+    /// Hide a source or group to begin with, like :hide_source; repeat for more
+    #[arg(long, value_name = "SOURCE")]
+    hide_source: Vec<String>,
+
+    /// Only show entries whose fields match, like :where (e.g. --where 'status>=500'); repeat
+    /// for more conditions, all of which must match
+    #[arg(long = "where", value_name = "CONDITION", value_parser = parse_condition)]
+    conditions: Vec<String>,
+
+    /// Show the latest entry matching this regex in the alert bar, like :alert; repeat for more
+    #[arg(long, value_name = "REGEX", value_parser = parse_pattern)]
+    alert: Vec<String>,
+
+    /// Draw matches of a regex in a color, like :highlight (e.g. --highlight 'timeout:red');
+    /// repeat for more
+    #[arg(long, value_name = "REGEX[:COLOR]", value_parser = parse_highlight)]
+    highlight: Vec<String>,
+
+    /// Only show entries matching this regex, like :filter_in
+    #[arg(long, value_name = "REGEX", value_parser = parse_pattern)]
+    filter_in: Option<String>,
+
+    /// Hide entries matching this regex, like :filter_out
+    #[arg(long, value_name = "REGEX", value_parser = parse_pattern)]
+    filter_out: Option<String>,
+
+    /// Hide entries less severe than this, like :level (entries without a level stay)
+    #[arg(long, value_name = "SEVERITY", value_parser = parse_level)]
+    level: Option<String>,
+
+    /// Theme to start with, like :theme
+    #[arg(long, value_name = "NAME")]
+    theme: Option<String>,
+
+    /// Wrap long entries instead of truncating them, like :set wrap on
+    #[arg(long)]
+    wrap: bool,
+
+    /// Draw at most this many frames a second, like :set fps
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(u32).range(1..=commands::MAX_FPS as i64))]
+    fps: Option<u32>,
+
+    /// Start paused at the end instead of following new entries, as with Space
+    #[arg(long)]
+    no_follow: bool,
+
+    /// Run the commands in this file once the screen is up, one a line as typed after : (e.g.
+    /// hide_source stdout). Blank lines and lines starting with # are skipped
+    #[arg(long, value_name = "PATH")]
+    cmds: Option<PathBuf>,
+
+    /// Run a command once the screen is up, after those from --cmds (e.g. -c 'where status>=500');
+    /// repeat for more, run in order
+    #[arg(short = 'c', long = "command", value_name = "COMMAND")]
+    commands: Vec<String>,
+
+    /// Take commands from other programs on a Unix socket (e.g. unix:///tmp/oxtail.ctl), one
+    /// JSON-RPC request a line: command, open, filter, highlight or export
+    #[arg(long, value_name = "URL", value_parser = control::ControlAddress::parse)]
+    control: Option<control::ControlAddress>,
+
+    /// Start with only the last N lines of each file (0 reads them whole); :load_more reads earlier ones
+    #[arg(long, value_name = "N", default_value_t = file_watcher::DEFAULT_TAIL_LINES)]
+    tail_lines: usize,
+
+    /// Once a file is renamed or removed, keep reading whatever file takes its name (e.g. after
+    /// logrotate) or keep following the file that was opened, like tail --follow
+    #[arg(long, value_name = "HOW", value_enum, default_value_t = file_watcher::Follow::Name)]
+    follow: file_watcher::Follow,
+
+    /// How long a last line that's still being written waits for the rest before it's shown anyway
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "1s")]
+    partial_timeout: Duration,
+
+    /// Follow a symlinked file to whatever it points to now, switching to the new target when the
+    /// link is pointed elsewhere (e.g. current -> releases/<version>/app.log)
+    #[arg(long)]
+    resolve_symlinks: bool,
+
+    /// Read a log stream served over HTTP: Server-Sent Events, or chunked text or JSON lines,
+    /// connecting again whenever it ends or fails; repeat for more
+    #[arg(long, value_name = "URL", value_parser = http_stream::StreamUrl::parse)]
+    http_stream: Vec<http_stream::StreamUrl>,
+
+    /// Subscribe to a WebSocket sending log lines or JSON log events, keeping it alive with pings
+    /// and connecting again whenever it closes or fails; repeat for more
+    #[arg(long, value_name = "URL", value_parser = websocket::WsUrl::parse)]
+    ws: Vec<websocket::WsUrl>,
+
+    /// Read a Redis stream, or subscribe to a pub/sub channel or pattern, each channel as a source
+    /// of its own (e.g. redis://localhost/logs or redis://:password@host:6379/app.*); repeat for more
+    #[arg(long, value_name = "URL", value_parser = redis_reader::RedisUrl::parse)]
+    redis: Vec<redis_reader::RedisUrl>,
+
+    /// Token sent as `Authorization: Bearer <TOKEN>` to --http-stream and --ws URLs; best given
+    /// through the environment, where other users can't see it
+    #[arg(long, value_name = "TOKEN", env = "OXTAIL_BEARER_TOKEN", hide_env_values = true)]
+    bearer_token: Option<String>,
+
+    /// Take in logs sent over the network: syslog-udp://0.0.0.0:514 for RFC3164/RFC5424 syslog
+    /// over UDP, shown as the syslog:<port> source, or fluent-forward://0.0.0.0:24224 for
+    /// Fluentd and Fluent Bit forward outputs, shown by tag; repeat for more
+    #[arg(long, value_name = "URL", value_parser = listener::ListenAddress::parse)]
+    listen: Vec<listener::ListenAddress>,
+
+    /// Follow the logs of the pods of a workload, one pod or a label selector through the Kubernetes
+    /// API, each pod as a source of its own, including pods started later (e.g. deploy/my-api,
+    /// app=my-api). The cluster is kubectl's current context; pods are listed again every 2s
+    #[arg(long, value_name = "TARGET", value_parser = kube::KubeTarget::parse)]
+    kube: Vec<kube::KubeTarget>,
+
+    /// Namespace of the --kube pods, instead of the current context's
+    #[arg(short = 'n', long, value_name = "NAMESPACE", requires = "kube")]
+    namespace: Option<String>,
+
+    /// Name piped stdin goes by as a source (e.g. --stdin-name api, for kubectl logs -f api | oxtail)
+    #[arg(long, value_name = "LABEL", default_value = "stdin")]
+    stdin_name: String,
+
+    /// Date lines from stdin and --fd inputs by the ISO 8601 timestamp they start with, as written
+    /// by kubectl logs --timestamps, so they're ordered by when they were logged
+    #[arg(long)]
+    stdin_timestamps: bool,
+
+    /// Read a file descriptor inherited from the shell as a source of its own, like stdin, named
+    /// fd<N> (e.g. --fd 3 3< <(journalctl -f)); repeat for more
+    #[arg(long, value_name = "N", value_parser = clap::value_parser!(RawFd).range(3..))]
+    fd: Vec<RawFd>,
+
+    /// Read a serial device's output, such as a board's or router's console, as a source named
+    /// after the device: <DEVICE>[:<BAUD>[:<FRAMING>]], 115200 and 8N1 unless given (e.g.
+    /// /dev/ttyUSB0:9600:7E1); repeat for more
+    #[arg(long, value_name = "PORT", value_parser = serial::SerialPort::parse)]
+    serial: Vec<serial::SerialPort>,
+
+    /// Quit once stdin and every --fd input have closed and everything they sent is in, instead
+    /// of staying open to look through it, for oxtail at the end of a pipeline
+    #[arg(long)]
+    exit_on_eof: bool,
+
+    /// Show blank lines in files and stdin as entries, keeping logs made of blocks readable
+    #[arg(long)]
+    keep_empty_lines: bool,
+
+    /// Read a file's existing lines at most this fast (bytes per second, e.g. 16M), so a large file
+    /// loads in the background instead of flooding the UI
+    #[arg(long, value_name = "SIZE", value_parser = parse_size, default_value = "16M")]
+    load_rate: usize,
+
+    /// What to do with files holding binary data: skip them with a note, or show them with
+    /// unprintable bytes escaped (e.g. \x00)
+    #[arg(long, value_name = "HOW", value_enum, default_value_t = file_watcher::Binary::Skip)]
+    binary: file_watcher::Binary,
+
+    /// Carry on reading each file where the last session with --resume stopped, instead of at its
+    /// last --tail-lines lines
+    #[arg(long)]
+    resume: bool,
+
+    /// Check files on a timer instead of waiting for filesystem events, for network mounts and
+    /// container volumes: INTERVAL for every file, or PATH=INTERVAL for one file or directory
+    #[arg(long, value_name = "[PATH=]INTERVAL", value_parser = file_watcher::PollSetting::parse)]
+    poll: Vec<file_watcher::PollSetting>,
+
+    /// Character encoding of sources that aren't UTF-8 (e.g. utf-16le, latin1, shift_jis): ENCODING
+    /// for every source, or SOURCE=ENCODING for one. A byte order mark takes precedence
+    #[arg(long, value_name = "[SOURCE=]ENCODING", value_parser = encoding::EncodingSetting::parse)]
+    encoding: Vec<encoding::EncodingSetting>,
+
+    /// Keep at most this many entries in memory, moving older ones to a temporary file
+    /// and reading them back when scrolled to
+    #[arg(long, value_name = "N")]
+    spill_after: Option<usize>,
+
+    /// Add a source that makes up this many lines per second, for trying oxtail under load
+    #[arg(long, value_name = "LINES_PER_SEC")]
+    synthetic: Option<u32>,
+
+    /// Length of each synthetic line, in bytes
+    #[arg(long, value_name = "BYTES", default_value_t = synthetic::DEFAULT_LINE_SIZE)]
+    line_size: usize,
+
+    /// Time ingesting, filtering and drawing this many synthetic lines, print the results and exit
+    #[arg(long, value_name = "LINES", num_args = 0..=1, default_missing_value = "200000", hide = true)]
+    benchmark: Option<usize>,
+
+    /// Start the command again this long after it exits (1s if no delay is given); :restart
+    /// starts it again at any time
+    #[arg(long, value_name = "DELAY", value_parser = parse_duration, num_args = 0..=1, require_equals = true, default_missing_value = "1s")]
+    restart_on_exit: Option<Duration>,
+
+    /// Run the command again this long after each run ends, like watch, keeping every run's
+    /// output under a header entry (e.g. --every 5s -- curl -s localhost:8080/health)
+    #[arg(long, value_name = "INTERVAL", value_parser = parse_duration, conflicts_with = "restart_on_exit")]
+    every: Option<Duration>,
+
+    /// Restart the command whenever files matching this pattern change, for a simple dev loop
+    /// (e.g. --watch-exec 'src/**/*.rs' -- cargo test); ** matches any number of directories
+    #[arg(long, value_name = "PATTERN", value_parser = exec_watch::ChangePattern::parse)]
+    watch_exec: Vec<exec_watch::ChangePattern>,
+
+    /// Run the command on a pseudo-terminal, so it keeps its colors and writes a line at a time
+    /// as it would to a terminal. Its stderr is still read apart
+    #[arg(long)]
+    pty: bool,
+
+    /// Name the command's sources NAME:stdout and NAME:stderr, instead of after the program
+    /// (e.g. server:stdout for ./server)
+    #[arg(long, value_name = "NAME")]
+    name: Option<String>,
+
+    /// Quit as soon as the command exits, with its exit code, for wrapping a command in CI or
+    /// a script while still watching its output live
+    #[arg(long, conflicts_with_all = ["restart_on_exit", "every", "watch_exec"])]
+    propagate_exit: bool,
+
+    /// How long the command gets to exit after SIGTERM, on quitting or restarting it, before
+    /// it's killed
+    #[arg(long, value_name = "DURATION", value_parser = parse_duration, default_value = "3s")]
+    stop_grace: Duration,
+
+    /// On quitting, once the command has stopped and its last output is in, write the active
+    /// tab's entries to this file
+    #[arg(long, value_name = "PATH")]
+    write_on_exit: Option<PathBuf>,
+
+    /// Set a variable in the command's environment (e.g. RUST_LOG=debug); repeat for more
+    #[arg(long, value_name = "KEY=VALUE", value_parser = process_handler::EnvVar::parse)]
+    env: Vec<process_handler::EnvVar>,
+
+    /// Run the command in this directory
+    #[arg(long, value_name = "DIR")]
+    cwd: Option<PathBuf>,
+
+    /// Run the command line with sh -c, so pipes, redirections and variables work
+    /// (e.g. --shell -- 'make 2>&1 | grep -v noise')
+    #[arg(long)]
+    shell: bool,
+
     /// The command to run followed by its arguments (after --)
     #[arg(last = true)]
     cmd: Vec<String>,
@@ -56,11 +380,88 @@ use std::io::{self, BufRead};
 use std::fs::File;
 use chrono::Local;
 
+/// Parse a `--max-memory` or `--load-rate` size
+fn parse_size(value: &str) -> std::result::Result<usize, String> {
+    settings::parse_size(value)
+        .filter(|size| *size > 0)
+        .ok_or_else(|| format!("invalid size: {} (e.g. 512M, 2G)", value))
+}
+
+/// Parse a duration such as `--partial-timeout 500ms`
+fn parse_duration(value: &str) -> std::result::Result<Duration, String> {
+    settings::parse_duration(value).ok_or_else(|| format!("invalid duration: {} (e.g. 500ms, 2s)", value))
+}
+
+/// Parse a `--where` condition, as `:where` takes it
+fn parse_condition(value: &str) -> std::result::Result<String, String> {
+    fields::FieldCondition::parse(value).map(|_| value.to_string())
+}
+
+/// Parse a `--highlight` regex with an optional color, as `:highlight` takes it
+fn parse_highlight(value: &str) -> std::result::Result<String, String> {
+    settings::Highlight::parse(value).map(|_| value.to_string())
+}
+
+/// Parse a `--level` severity, as `:level` takes it
+fn parse_level(value: &str) -> std::result::Result<String, String> {
+    log_entry::Severity::parse(value)
+        .map(|_| value.to_string())
+        .ok_or_else(|| format!("unknown level: {} ({})", value, log_entry::SEVERITY_NAMES.join("/")))
+}
+
+/// Parse an `--alert`, `--filter-in` or `--filter-out` regex
+fn parse_pattern(value: &str) -> std::result::Result<String, String> {
+    pattern::Pattern::new(value).map(|_| value.to_string()).map_err(|err| err.to_string())
+}
+
+/// The commands in a `--cmds` file
+fn read_commands(path: &Path) -> Result<Vec<String>> {
+    let text = std::fs::read_to_string(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Ok(text.lines()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| line.strip_prefix(':').unwrap_or(line).to_string())
+        .collect())
+}
+
+/// Whether anything other than piped stdin was given to read from. Every source option
+/// belongs here
+fn has_explicit_sources(args: &Args, fds: &[(RawFd, File)]) -> bool {
+    !args.files.is_empty()
+        || !args.dir.is_empty()
+        || !args.cmd.is_empty()
+        || args.synthetic.is_some()
+        || !args.listen.is_empty()
+        || !args.kube.is_empty()
+        || !args.http_stream.is_empty()
+        || !args.ws.is_empty()
+        || !args.redis.is_empty()
+        || !args.serial.is_empty()
+        || !fds.is_empty()
+}
+
 fn main() -> Result<()> {
     let args = Args::parse();
 
+    if let Some(lines) = args.benchmark {
+        return benchmark::run(lines, args.line_size);
+    }
+
+    if args.init_config {
+        let path = args.config.or_else(config::Config::default_path)
+            .context("Nowhere to write the config: HOME isn't set (use --config)")?;
+        config::Config::init(&path)?;
+        println!("Wrote {}", path.display());
+        return Ok(());
+    }
+
+    // Descriptors given with --fd are taken before anything is opened that could reuse their numbers
+    let fds = args.fd.iter()
+        .map(|fd| Ok((*fd, stdin_reader::open_fd(*fd)?)))
+        .collect::<Result<Vec<_>>>()?;
+
     // Only show help if we have no inputs at all (no files, no command, and no stdin)
-    if args.files.is_empty() && args.cmd.is_empty() && atty::is(atty::Stream::Stdin) {
+    if !has_explicit_sources(&args, &fds) && atty::is(atty::Stream::Stdin) {
         Args::command().print_help().context("Failed to print help")?;
         println!();
         return Ok(());
@@ -70,7 +471,7 @@ fn main() -> Result<()> {
     // This is required for the TUI to work with stdin piping
     let has_tty_access = File::open("/dev/tty").is_ok();
     let has_stdin_pipe = !atty::is(atty::Stream::Stdin);
-    let stdin_only = has_stdin_pipe && args.files.is_empty() && args.cmd.is_empty();
+    let stdin_only = has_stdin_pipe && !has_explicit_sources(&args, &fds);
     
     // Use non-interactive mode when:
     // 1. We have piped stdin and no other inputs (files or commands)
@@ -87,11 +488,12 @@ fn main() -> Result<()> {
         
         while reader.read_line(&mut buffer)? > 0 {
             let content = buffer.trim_end().to_string();
-            if !content.is_empty() {
+            if args.keep_empty_lines || !content.is_empty() {
                 // Format the output similar to how the TUI would
                 let timestamp = Local::now();
-                println!("[{}] [stdin:{}] {}", 
+                println!("[{}] [{}:{}] {}", 
                     timestamp.format("%H:%M:%S%.3f"),
+                    args.stdin_name,
                     line_number,
                     content);
                 line_number += 1;
@@ -102,31 +504,168 @@ fn main() -> Result<()> {
         return Ok(());
     } else {
         // INTERACTIVE MODE: Full terminal UI with all sources
-        let (tx, rx) = mpsc::channel::<log_entry::LogEntry>();
+        // Read the config first so mistakes are reported before the TUI takes over
+        // Flags take precedence over the config file, including after :config reload
+        let overrides = config::Config {
+            theme: args.theme,
+            wrap: args.wrap.then_some(true),
+            fps: args.fps,
+            show: args.hide_meta.into_iter().map(|column| (column, false))
+                .chain(args.show_meta.into_iter().map(|column| (column, true)))
+                .collect(),
+            highlights: args.highlight,
+            filters: config::FilterConfig { hide: args.hide_source, conditions: args.conditions, alerts: args.alert,
+                filter_in: args.filter_in, filter_out: args.filter_out, level: args.level },
+            ..config::Config::default()
+        };
+        let config = config::Config::load(args.config.as_deref())?.with_overrides(&overrides);
+        let (themes, theme) = config.themes()?;
+        // Commands to run once the screen is up, as if typed
+        let mut commands = match &args.cmds {
+            Some(path) => read_commands(path)?,
+            None => Vec::new(),
+        };
+        commands.extend(args.commands.iter().map(|command| command.strip_prefix(':').unwrap_or(command).to_string()));
+        let keymap = config.keymap()?;
 
-        // Start file watchers if files are specified
-        if !args.files.is_empty() {
-            file_watcher::start_watching(args.files.clone(), tx.clone())
-                .context("Failed to start file watcher")?;
+        // Storage is set up before any source starts, so a spill file problem stops us early
+        let mut log_storage = log_storage::LogStorage::new();
+        // Limits given on the command line take precedence over the config file's
+        let retention = log_storage::Retention {
+            max_lines: args.max_lines,
+            max_lines_per_source: args.max_lines_per_source,
+            max_memory: args.max_memory,
+        };
+        log_storage.set_retention(config.retention(retention).map_err(anyhow::Error::msg)?);
+        log_storage.set_groups(groups::SourceGroups::new(args.group));
+        if let Some(max) = args.spill_after {
+            log_storage.spill_after(max).context("Failed to create spill file")?;
         }
 
+        let encodings = encoding::Encodings::new(args.encoding);
+        let (tx, rx) = batch::channel();
+        // Readers report when their sources start and stop
+        let status = source_status::SourceStatusRegistry::new();
+
+        // Paths with wildcards that don't name an existing file are patterns, as are directories
+        let mut files = Vec::new();
+        let mut patterns = Vec::new();
+        for path in &args.files {
+            match file_watcher::FilePattern::parse(path) {
+                Ok(Some(pattern)) if !path.exists() => patterns.push(pattern),
+                Err(err) if !path.exists() => return Err(err),
+                _ => files.push(path.clone()),
+            }
+        }
+        for dir in &args.dir {
+            patterns.push(file_watcher::FilePattern::directory(dir)?);
+        }
+
+        // Start the file watcher, even without files, so `:open` can add some later
+        let checkpoints = if args.resume {
+            Some(checkpoints::Checkpoints::default_path()
+                .context("Nowhere to keep checkpoints for --resume: HOME isn't set")?)
+        } else {
+            None
+        };
+        let options = file_watcher::WatchOptions { tail_lines: args.tail_lines, follow: args.follow, poll: args.poll,
+            partial_timeout: args.partial_timeout, encodings: encodings.clone(),
+            keep_empty_lines: args.keep_empty_lines, binary: args.binary, resolve_symlinks: args.resolve_symlinks,
+            load_rate: args.load_rate, checkpoints };
+        let file_watcher = file_watcher::start_watching(files, patterns, options, tx.clone(), status.clone())
+            .context("Failed to start file watcher")?;
+
+        // Commands added with :exec run with oxtail's own environment and directory
+        let exec_options = process_handler::ProcessOptions { encodings: encodings.clone(), restart_on_exit: None,
+            pty: false, env: Vec::new(), cwd: None, shell: true, name: None, every: None, watch_exec: Vec::new(),
+            stop_grace: args.stop_grace, propagate_exit: false };
+        let launcher = process_handler::Launcher::new(exec_options, tx.clone(), status.clone());
+
         // Spawn the specified process if a command was given
-        if !args.cmd.is_empty() {
+        let process = if !args.cmd.is_empty() {
             let cmd = &args.cmd[0];
             let cmd_args: Vec<&str> = args.cmd.iter().skip(1).map(|s| s.as_str()).collect();
-            process_handler::start_process(cmd, &cmd_args, tx.clone())
-                .context("Failed to start process")?;
+            let options = process_handler::ProcessOptions { encodings: encodings.clone(), restart_on_exit: args.restart_on_exit,
+                pty: args.pty, env: args.env, cwd: args.cwd, shell: args.shell,
+                name: args.name, every: args.every, watch_exec: args.watch_exec, stop_grace: args.stop_grace,
+                propagate_exit: args.propagate_exit };
+            Some(process_handler::start_process(cmd, &cmd_args, options, tx.clone(), status.clone())
+                .context("Failed to start process")?)
+        } else {
+            None
+        };
+
+        if let Some(rate) = args.synthetic {
+            synthetic::start_synthetic(rate, args.line_size, tx.clone(), status.clone());
         }
 
+        for target in &args.kube {
+            let stream = stdin_reader::StreamOptions { keep_empty_lines: args.keep_empty_lines, timestamps: false };
+            let options = kube::KubeOptions { namespace: args.namespace.clone(), tail_lines: args.tail_lines, stream };
+            kube::start_following(target, options, tx.clone(), status.clone())
+                .context("Failed to follow Kubernetes pods")?;
+        }
+        for url in &args.http_stream {
+            http_stream::start_streaming(url, args.bearer_token.clone(), tx.clone(), status.clone());
+        }
+        for url in &args.ws {
+            websocket::start_subscribing(url, args.bearer_token.clone(), tx.clone(), status.clone());
+        }
+        for url in &args.redis {
+            redis_reader::start_reading(url, args.tail_lines, tx.clone(), status.clone());
+        }
+        for address in &args.listen {
+            listener::start_listening(address, tx.clone(), status.clone())?;
+        }
+
+        let stream_options = stdin_reader::StreamOptions { keep_empty_lines: args.keep_empty_lines, timestamps: args.stdin_timestamps };
         // In interactive mode, we can safely enable stdin reading if stdin is not a terminal
         // but only if other sources are also present
         if !atty::is(atty::Stream::Stdin) {
-            stdin_reader::start_reading_stdin(tx.clone()).context("Failed to initialize input reader")?;
+            let encoding = encodings.get_aliased(&args.stdin_name, "stdin");
+            stdin_reader::start_reading_stdin(args.stdin_name.clone(), encoding, stream_options, tx.clone(), status.clone()).context("Failed to initialize input reader")?;
         }
+        for (fd, file) in fds {
+            stdin_reader::start_reading_fd(fd, file, encodings.get(&stdin_reader::fd_source(fd)), stream_options, tx.clone(), status.clone());
+        }
+        for port in &args.serial {
+            let options = stdin_reader::StreamOptions { timestamps: false, ..stream_options };
+            serial::start_reading(port, encodings.get(&port.source()), options, tx.clone(), status.clone())?;
+        }
+
+        // With --exit-on-eof, oxtail quits once stdin, if it's piped, and every --fd input have closed
+        let exit_on_eof = if args.exit_on_eof {
+            has_stdin_pipe.then(|| args.stdin_name.clone()).into_iter()
+                .chain(args.fd.iter().map(|fd| stdin_reader::fd_source(*fd)))
+                .collect()
+        } else {
+            Vec::new()
+        };
+
+        // The socket is removed once the UI is done
+        let (control_socket, control) = match &args.control {
+            Some(address) => {
+                let (socket, requests) = control::listen(address)?;
+                (Some(socket), Some(requests))
+            },
+            None => (None, None),
+        };
 
         // Run the neon-styled UI to display output
-        ui::run_ui(rx)
-            .context("UI error")?;
+        let inputs = ui::Inputs { files: file_watcher.clone(), process: process.clone(), launcher, write_on_exit: args.write_on_exit,
+            exit_on_eof, config, config_path: args.config, overrides, retention, paused: args.no_follow,
+            commands, control, history: xdg::state_dir().map(|dir| dir.join("history")) };
+        let result = ui::run_ui(rx, status, log_storage, inputs, themes, theme, keymap);
+        // Where each file was read up to is saved for --resume
+        if let Err(e) = file_watcher.finish() {
+            eprintln!("{:#}", e);
+        }
+        result.context("UI error")?;
+        drop(control_socket);
+        // With --propagate-exit, oxtail exits as the command did
+        if let Some(code) = process.and_then(|process| process.exit_code()) {
+            std::process::exit(code);
+        }
     }
 
     Ok(())
@@ -0,0 +1,103 @@
+use std::ops::Range;
+use aho_corasick::AhoCorasick;
+use memchr::memmem::Finder;
+use regex::Regex;
+use regex_syntax::hir::{Hir, HirKind};
+
+/// How a pattern is checked against a line
+#[derive(Debug, Clone)]
+enum Matcher {
+    /// Plain text, found with memchr
+    Literal(Finder<'static>),
+    /// A choice of plain texts (`ERROR|WARN`), found in one pass
+    Literals(AhoCorasick),
+    /// Anything else. Lines without `required`, which every match contains, are
+    /// turned away before the regex runs
+    Regex { regex: Regex, required: Option<Finder<'static>> },
+}
+
+/// A pattern typed by the user, with regex syntax. Plain text and choices of plain text
+/// skip the regex engine, which matters when checking every entry of a huge buffer
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    pattern: String,
+    matcher: Matcher,
+}
+
+impl Pattern {
+    pub fn new(pattern: &str) -> Result<Self, regex::Error> {
+        // Compiling the regex first reports mistakes the way users expect
+        let regex = Regex::new(pattern)?;
+        let hir = regex_syntax::parse(pattern).ok();
+        let matcher = match hir.as_ref().map(|hir| (hir, literal(hir))) {
+            Some((_, Some(text))) => Matcher::Literal(Finder::new(&text).into_owned()),
+            Some((hir, None)) => match literals(hir).and_then(|texts| AhoCorasick::new(texts).ok()) {
+                Some(searcher) => Matcher::Literals(searcher),
+                None => Matcher::Regex {
+                    regex,
+                    required: required_literal(hir).map(|text| Finder::new(&text).into_owned()),
+                },
+            },
+            None => Matcher::Regex { regex, required: None },
+        };
+        Ok(Self { pattern: pattern.to_string(), matcher })
+    }
+
+    pub fn is_match(&self, text: &str) -> bool {
+        match &self.matcher {
+            Matcher::Literal(finder) => finder.find(text.as_bytes()).is_some(),
+            Matcher::Literals(searcher) => searcher.is_match(text),
+            Matcher::Regex { regex, required } => {
+                required.as_ref().is_none_or(|finder| finder.find(text.as_bytes()).is_some())
+                    && regex.is_match(text)
+            },
+        }
+    }
+
+    /// Where the pattern matches in `text`, as byte ranges
+    pub fn find_ranges(&self, text: &str) -> Vec<Range<usize>> {
+        match &self.matcher {
+            Matcher::Literal(finder) => {
+                let len = finder.needle().len();
+                finder.find_iter(text.as_bytes()).map(|start| start..start + len).collect()
+            },
+            Matcher::Literals(searcher) => searcher.find_iter(text).map(|found| found.range()).collect(),
+            Matcher::Regex { regex, .. } => regex.find_iter(text).map(|found| found.range()).collect(),
+        }
+    }
+
+    /// The pattern as typed
+    pub fn as_str(&self) -> &str {
+        &self.pattern
+    }
+}
+
+/// The text a pattern matches, if it only matches that text
+fn literal(hir: &Hir) -> Option<Vec<u8>> {
+    match hir.kind() {
+        HirKind::Empty => Some(Vec::new()),
+        HirKind::Literal(literal) => Some(literal.0.to_vec()),
+        HirKind::Capture(capture) => literal(&capture.sub),
+        _ => None,
+    }
+}
+
+/// The texts of an alternation made only of plain text
+fn literals(hir: &Hir) -> Option<Vec<Vec<u8>>> {
+    match hir.kind() {
+        HirKind::Alternation(alternatives) => alternatives.iter().map(literal).collect(),
+        HirKind::Capture(capture) => literals(&capture.sub),
+        _ => None,
+    }
+}
+
+/// The longest text that every match of the pattern contains
+fn required_literal(hir: &Hir) -> Option<Vec<u8>> {
+    match hir.kind() {
+        HirKind::Literal(literal) => Some(literal.0.to_vec()),
+        HirKind::Capture(capture) => required_literal(&capture.sub),
+        HirKind::Repetition(repetition) if repetition.min > 0 => required_literal(&repetition.sub),
+        HirKind::Concat(parts) => parts.iter().filter_map(required_literal).max_by_key(|text| text.len()),
+        _ => None,
+    }
+}
@@ -1,48 +1,675 @@
-use anyhow::Result;
-use std::io::{BufRead, BufReader};
-use std::process::{Command, Stdio};
-use std::sync::mpsc::Sender;
-use std::thread;
-use crate::log_entry::LogEntry;
-
-pub fn start_process(cmd: &str, args: &[&str], tx: Sender<LogEntry>) -> Result<()> {
-    let mut child = Command::new(cmd)
-        .args(args)
-        .stdout(Stdio::piped())
-        .stderr(Stdio::piped())
-        .spawn()?;
-
-    let stdout = child.stdout.take().unwrap();
-    let stderr = child.stderr.take().unwrap();
-
-    // Capture stdout in a separate thread
-    let tx_stdout = tx.clone();
-    thread::spawn(move || {
-        let mut line_number = 1;
-        let reader = BufReader::new(stdout);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let mut entry = LogEntry::new("stdout", l);
-                entry.line_number = line_number;
-                let _ = tx_stdout.send(entry);
-                line_number += 1;
+use anyhow::{ensure, Result};
+use std::fs::File;
+use std::io::{self, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::os::fd::OwnedFd;
+use std::os::unix::process::{CommandExt, ExitStatusExt};
+use std::process::{Child, Command, ExitStatus, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender};
+use std::sync::{Arc, OnceLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+use crate::batch::EntryBatcher;
+use crate::encoding::{self, Encodings};
+use crate::exec_watch::{self, ChangePattern};
+use crate::line_reader::cr_lines;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin, Severity};
+use crate::pty::{self, Pty, PtyOutput};
+use crate::resource_usage::UsageSampler;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// Source of the entries oxtail adds itself, such as the command exiting
+const SOURCE: &str = "oxtail";
+
+/// How often to check whether the command has exited
+const EXIT_CHECK: Duration = Duration::from_millis(100);
+
+/// How long to wait for the last of a run's output once it has ended, in case something outside
+/// its group still holds the pipes open
+const OUTPUT_DRAIN: Duration = Duration::from_secs(1);
+
+/// How often the command's CPU and memory use are sampled
+const USAGE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Signals `:signal` takes by name, with or without the `SIG` prefix
+const SIGNALS: &[(&str, libc::c_int)] = &[
+    ("HUP", libc::SIGHUP),
+    ("INT", libc::SIGINT),
+    ("QUIT", libc::SIGQUIT),
+    ("KILL", libc::SIGKILL),
+    ("USR1", libc::SIGUSR1),
+    ("USR2", libc::SIGUSR2),
+    ("ALRM", libc::SIGALRM),
+    ("TERM", libc::SIGTERM),
+    ("CONT", libc::SIGCONT),
+    ("STOP", libc::SIGSTOP),
+    ("TSTP", libc::SIGTSTP),
+    ("WINCH", libc::SIGWINCH),
+];
+
+/// Parse a signal such as `TERM`, `SIGUSR1` or `15`
+pub fn parse_signal(value: &str) -> Option<i32> {
+    if let Ok(number) = value.parse::<i32>() {
+        return (1..=64).contains(&number).then_some(number);
+    }
+    let upper = value.to_ascii_uppercase();
+    let name = upper.strip_prefix("SIG").unwrap_or(&upper);
+    SIGNALS.iter().find(|(known, _)| *known == name).map(|(_, number)| *number)
+}
+
+/// A signal's name, such as `SIGTERM`, or its number if it has no name here
+fn signal_name(signal: i32) -> String {
+    SIGNALS.iter()
+        .find(|(_, number)| *number == signal)
+        .map(|(name, _)| format!("SIG{}", name))
+        .unwrap_or_else(|| format!("signal {}", signal))
+}
+
+enum ProcessMessage {
+    /// Stop the command if it's running and start it again
+    Restart,
+    /// Send a signal to the command and whatever it started
+    Signal(i32),
+    /// Write a line to the command's stdin
+    Input(String),
+    /// A file it's restarted for with `--watch-exec` changed
+    Changed(String),
+    /// Stop the command for good before oxtail exits, answering once it's gone
+    Finish(Sender<()>),
+    /// Stop the command for good while oxtail carries on, as `:close` does
+    Stop,
+}
+
+/// Handle on the command run after `--`
+#[derive(Clone)]
+pub struct ProcessHandle {
+    tx: Sender<ProcessMessage>,
+    stop_grace: Duration,
+    /// With `propagate_exit`, the code the command exited with once it has
+    exit_code: Arc<OnceLock<i32>>,
+}
+
+impl ProcessHandle {
+    /// Stop the command if it's running and start it again
+    pub fn restart(&self) {
+        let _ = self.tx.send(ProcessMessage::Restart);
+    }
+
+    /// Send a signal to the command, which says on the prompt line whether it was sent
+    pub fn signal(&self, signal: i32) {
+        let _ = self.tx.send(ProcessMessage::Signal(signal));
+    }
+
+    /// Restart the command because a file it's watched for changed, returning false once the
+    /// command is no longer looked after
+    pub fn changed(&self, path: String) -> bool {
+        self.tx.send(ProcessMessage::Changed(path)).is_ok()
+    }
+
+    /// Send a line typed in input mode to the command's stdin
+    pub fn send_input(&self, line: String) {
+        let _ = self.tx.send(ProcessMessage::Input(line));
+    }
+
+    /// With `propagate_exit`, the code the command exited with, once it has: its own, or 128
+    /// plus the signal that killed it, as a shell gives
+    pub fn exit_code(&self) -> Option<i32> {
+        self.exit_code.get().copied()
+    }
+
+    /// Stop the command for good, without waiting for it to go
+    pub fn stop(&self) {
+        let _ = self.tx.send(ProcessMessage::Stop);
+    }
+}
+
+/// Stop commands so they aren't left running after oxtail exits: SIGTERM, then SIGKILL once
+/// the grace period is up, all of them at once. `drain` is called while waiting, to take in
+/// their last output
+pub fn finish(processes: &[ProcessHandle], mut drain: impl FnMut()) {
+    // Those already gone have nothing to wait for, but what they wrote last may not have been
+    // taken in
+    let mut waiting: Vec<Receiver<()>> = processes.iter()
+        .filter_map(|process| {
+            let (done_tx, done_rx) = mpsc::channel();
+            process.tx.send(ProcessMessage::Finish(done_tx)).ok().map(|()| done_rx)
+        })
+        .collect();
+    let grace = processes.iter().map(|process| process.stop_grace).max().unwrap_or_default();
+    let deadline = Instant::now() + grace + Duration::from_secs(1);
+    while !waiting.is_empty() && Instant::now() < deadline {
+        drain();
+        waiting.retain(|done| matches!(done.recv_timeout(EXIT_CHECK / 10), Err(RecvTimeoutError::Timeout)));
+    }
+    drain();
+}
+
+/// How a run of the command came to an end
+enum Ended {
+    /// It exited by itself
+    Exited,
+    /// It was stopped to start it again
+    Restart,
+    /// It was stopped because oxtail is exiting, with whoever is waiting for that, if anyone
+    Finish(Option<Sender<()>>),
+    /// It was stopped for good while oxtail carries on
+    Stopped,
+}
+
+/// A variable set in the command's environment, given as `KEY=VALUE`
+#[derive(Debug, Clone)]
+pub struct EnvVar {
+    key: String,
+    value: String,
+}
+
+impl EnvVar {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let (key, value) = value.split_once('=')
+            .filter(|(key, _)| !key.is_empty())
+            .ok_or_else(|| format!("invalid variable: {} (e.g. RUST_LOG=debug)", value))?;
+        Ok(Self { key: key.to_string(), value: value.to_string() })
+    }
+}
+
+/// How the command is run
+#[derive(Debug, Clone)]
+pub struct ProcessOptions {
+    /// Encodings of stdout and stderr, if they aren't UTF-8
+    pub encodings: Encodings,
+    /// Start the command again this long after it exits
+    pub restart_on_exit: Option<Duration>,
+    /// Run the command on a pseudo-terminal, which its stdin and stdout are connected to
+    pub pty: bool,
+    /// Variables added to the environment the command inherits
+    pub env: Vec<EnvVar>,
+    /// Directory to run the command in, instead of oxtail's own
+    pub cwd: Option<PathBuf>,
+    /// Run the command line with `sh -c`, so pipes, redirections and variables work
+    pub shell: bool,
+    /// Name of the command's sources, as in `server:stdout`, instead of the program's name
+    pub name: Option<String>,
+    /// Run the command again this long after each run ends, like `watch`, with a header entry
+    /// before each run's output
+    pub every: Option<Duration>,
+    /// Restart the command when files matching these change, with a header entry before each
+    /// run's output
+    pub watch_exec: Vec<ChangePattern>,
+    /// How long the command gets to stop after SIGTERM before it's killed
+    pub stop_grace: Duration,
+    /// Once the command exits, leave it stopped and make its exit code known, for oxtail to
+    /// exit with
+    pub propagate_exit: bool,
+}
+
+/// The command and how to run it, kept to start it again
+struct Process {
+    cmd: String,
+    exit_code: Arc<OnceLock<i32>>,
+    /// Its sources are this name with `:stdout` or `:stderr`
+    name: String,
+    args: Vec<String>,
+    options: ProcessOptions,
+    tx: SyncSender<Vec<LogEntry>>,
+    status: SourceStatusRegistry,
+}
+
+/// One run of the command, with the threads reading its output
+struct Run {
+    child: Child,
+    /// Where lines typed in input mode go
+    stdin: Option<File>,
+    readers: Vec<JoinHandle<()>>,
+    started: Instant,
+}
+
+/// Run `cmd`, reading its stdout and stderr. With `restart_on_exit`, it's started again that
+/// long after it exits; either way `:restart` starts it again at once
+pub fn start_process(
+    cmd: &str,
+    args: &[&str],
+    options: ProcessOptions,
+    tx: SyncSender<Vec<LogEntry>>,
+    status: SourceStatusRegistry,
+) -> Result<ProcessHandle> {
+    if let Some(cwd) = &options.cwd {
+        ensure!(cwd.is_dir(), "{} is not a directory", cwd.display());
+    }
+    let name = options.name.clone().unwrap_or_else(|| program_name(cmd));
+    // The shell gets the whole command line as one, but the words given apart are kept apart
+    let (cmd, args) = if options.shell {
+        let line = std::iter::once(cmd).chain(args.iter().copied()).collect::<Vec<_>>().join(" ");
+        (line, Vec::new())
+    } else {
+        (cmd.to_string(), args.iter().map(|arg| arg.to_string()).collect())
+    };
+    let process = Process {
+        cmd,
+        exit_code: Arc::default(),
+        name,
+        args,
+        options,
+        tx,
+        status,
+    };
+    // A command that can't be started at all is an error up front
+    process.header(1);
+    let run = process.spawn()?;
+    let (tx, rx) = mpsc::channel();
+    let handle = ProcessHandle { tx, stop_grace: process.options.stop_grace, exit_code: process.exit_code.clone() };
+    if !process.options.watch_exec.is_empty() {
+        exec_watch::start(&process.options.watch_exec, handle.clone())?;
+    }
+    thread::spawn(move || process.supervise(run, rx));
+    Ok(handle)
+}
+
+/// Starts the commands added with `:exec` while oxtail runs, keeping them to stop by name
+pub struct Launcher {
+    /// How each is run; its name is set for each
+    options: ProcessOptions,
+    tx: SyncSender<Vec<LogEntry>>,
+    status: SourceStatusRegistry,
+    /// Each command's name, which its sources start with, and its handle
+    commands: Vec<(String, ProcessHandle)>,
+}
+
+impl Launcher {
+    pub fn new(options: ProcessOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) -> Self {
+        Self { options, tx, status, commands: Vec::new() }
+    }
+
+    /// Run a command line with `sh -c`, as the sources `<name>:stdout` and `<name>:stderr`,
+    /// named after its program unless `name` is given. Returns the name. A name can be used
+    /// again once the command that had it has exited
+    pub fn exec(&mut self, command_line: &str, name: Option<String>) -> Result<String> {
+        let name = name.unwrap_or_else(|| program_name(command_line));
+        if let Some(index) = self.commands.iter().position(|(running, _)| *running == name) {
+            let running = self.status.get(&format!("{}:stdout", name)) == Some(SourceState::Running);
+            ensure!(!running, "{} is already running (give another with --name)", name);
+            self.commands.remove(index).1.stop();
+        }
+        let options = ProcessOptions { name: Some(name.clone()), ..self.options.clone() };
+        let handle = start_process(command_line, &[], options, self.tx.clone(), self.status.clone())?;
+        self.commands.push((name.clone(), handle));
+        Ok(name)
+    }
+
+    /// Stop a command started with `exec`, given by its name or one of its sources, returning
+    /// its name if there was one
+    pub fn close(&mut self, source: &str) -> Option<String> {
+        let name = source.strip_suffix(":stdout").or_else(|| source.strip_suffix(":stderr")).unwrap_or(source);
+        let index = self.commands.iter().position(|(running, _)| running == name)?;
+        let (name, handle) = self.commands.remove(index);
+        handle.stop();
+        Some(name)
+    }
+
+    /// The commands started, to stop on quitting
+    pub fn handles(&self) -> impl Iterator<Item = &ProcessHandle> {
+        self.commands.iter().map(|(_, handle)| handle)
+    }
+}
+
+impl Process {
+    fn spawn(&self) -> Result<Run> {
+        let mut command = if self.options.shell {
+            let mut command = Command::new("sh");
+            command.arg("-c").arg(&self.cmd);
+            command
+        } else {
+            Command::new(&self.cmd)
+        };
+        command.envs(self.options.env.iter().map(|var| (&var.key, &var.value)));
+        if let Some(cwd) = &self.options.cwd {
+            command.current_dir(cwd);
+        }
+        // Its input comes from input mode, never from the terminal oxtail draws on, and its
+        // stderr is read apart even on a pseudo-terminal
+        command.args(&self.args).stderr(Stdio::piped());
+        let pty = if self.options.pty {
+            // As wide and high as oxtail's own terminal, for commands that lay out their output
+            let (columns, rows) = termion::terminal_size().unwrap_or((80, 24));
+            let pty = Pty::open(columns, rows)?;
+            command.stdin(pty.slave.try_clone()?).stdout(pty.slave.try_clone()?);
+            // SAFETY: make_controlling only makes async-signal-safe calls
+            unsafe {
+                command.pre_exec(pty::make_controlling);
+            }
+            Some(pty.master)
+        } else {
+            // A group of its own, so stopping it stops whatever it started too
+            command.stdin(Stdio::piped()).stdout(Stdio::piped()).process_group(0);
+            None
+        };
+        let mut child = command.spawn()?;
+        // Only the command may hold the terminal open, so its output ends when the command does
+        drop(command);
+        let started = Instant::now();
+        let origin = log_entry::origin([("command", FieldValue::Str(self.command_line())), ("pid", FieldValue::Int(child.id().into()))]);
+
+        let (stdin, stdout): (Option<File>, Box<dyn Read + Send>) = match pty {
+            Some(master) => (Some(master.try_clone()?), Box::new(PtyOutput(master))),
+            None => (child.stdin.take().map(|stdin| File::from(OwnedFd::from(stdin))), Box::new(child.stdout.take().unwrap())),
+        };
+        let stderr = child.stderr.take().unwrap();
+
+        // Capture stdout in a separate thread
+        let source = format!("{}:stdout", self.name);
+        let tx_stdout = self.tx.clone();
+        let status_stdout = self.status.clone();
+        let encoding = self.options.encodings.get_aliased(&source, "stdout");
+        let origin_stdout = origin.clone();
+        self.status.set(&source, SourceState::Running);
+        let stdout_reader = thread::spawn(move || {
+            read_stream(&source, origin_stdout, BufReader::new(encoding::decode(stdout, encoding)), tx_stdout);
+            status_stdout.set(&source, SourceState::Stopped);
+        });
+
+        // Capture stderr in a separate thread
+        let source = format!("{}:stderr", self.name);
+        let tx_stderr = self.tx.clone();
+        let status_stderr = self.status.clone();
+        let encoding = self.options.encodings.get_aliased(&source, "stderr");
+        self.status.set(&source, SourceState::Running);
+        let stderr_reader = thread::spawn(move || {
+            read_stream(&source, origin, BufReader::new(encoding::decode(stderr, encoding)), tx_stderr);
+            status_stderr.set(&source, SourceState::Stopped);
+        });
+
+        Ok(Run { child, stdin, readers: vec![stdout_reader, stderr_reader], started })
+    }
+
+    /// Wait for each run of the command to end, saying how it did, and start it again when
+    /// asked to or when it restarts on exit
+    fn supervise(self, run: Run, rx: Receiver<ProcessMessage>) {
+        let mut run = Some(run);
+        let mut restarts = 0;
+        loop {
+            if let Some(run) = run.take() {
+                match self.wait(run, &rx) {
+                    Ended::Restart => {},
+                    Ended::Finish(done) => {
+                        if let Some(done) = done {
+                            let _ = done.send(());
+                        }
+                        return;
+                    },
+                    Ended::Exited if self.options.propagate_exit => return,
+                    Ended::Stopped => return,
+                    // Dying by itself, it's started again after the delay unless asked to sooner
+                    Ended::Exited => {
+                        let restarting = self.options.every.is_none() && self.options.restart_on_exit.is_some();
+                        if restarting {
+                            self.set_state(SourceState::Reconnecting);
+                        }
+                        if !self.idle(self.options.every.or(self.options.restart_on_exit), &rx) {
+                            if restarting {
+                                self.set_state(SourceState::Stopped);
+                            }
+                            return;
+                        }
+                    },
+                }
+            } else if !self.idle(None, &rx) {
+                return;
+            }
+
+            restarts += 1;
+            self.header(restarts + 1);
+            match self.spawn() {
+                Ok(started) => {
+                    // Runs on an interval or on changes have their header instead
+                    if !self.headers() {
+                        self.note(format!("Restarted {} (restart {})", self.cmd, restarts), Severity::Info);
+                    }
+                    run = Some(started);
+                },
+                Err(e) => self.note(format!("Failed to restart {}: {}", self.cmd, e), Severity::Error),
             }
         }
-    });
-
-    // Capture stderr in a separate thread
-    thread::spawn(move || {
-        let mut line_number = 1;
-        let reader = BufReader::new(stderr);
-        for line in reader.lines() {
-            if let Ok(l) = line {
-                let mut entry = LogEntry::new("stderr", l);
-                entry.line_number = line_number;
-                let _ = tx.send(entry);
-                line_number += 1;
+    }
+
+    /// Wait while the command isn't running: for `delay` when it restarts on exit, or else until
+    /// asked to restart it. Returns whether to start it again
+    fn idle(&self, delay: Option<Duration>, rx: &Receiver<ProcessMessage>) -> bool {
+        let deadline = delay.map(|delay| Instant::now() + delay);
+        loop {
+            let message = match deadline {
+                Some(deadline) => match rx.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+                    Ok(message) => message,
+                    Err(RecvTimeoutError::Timeout) => return true,
+                    Err(RecvTimeoutError::Disconnected) => return false,
+                },
+                None => match rx.recv() {
+                    Ok(message) => message,
+                    Err(_) => return false,
+                },
+            };
+            match message {
+                ProcessMessage::Restart => return true,
+                ProcessMessage::Changed(path) => {
+                    self.status.announce(format!("{} changed", path));
+                    return true;
+                },
+                ProcessMessage::Signal(_) | ProcessMessage::Input(_) => self.status.announce(format!("Error: {} isn't running", self.cmd)),
+                ProcessMessage::Finish(done) => {
+                    let _ = done.send(());
+                    return false;
+                },
+                ProcessMessage::Stop => return false,
+            }
+        }
+    }
+
+    /// Wait for a run to end, passing on signals, or stop it when asked to, then say how it
+    /// exited after its last lines
+    fn wait(&self, mut run: Run, rx: &Receiver<ProcessMessage>) -> Ended {
+        // The command leads its own group, which takes in whatever it starts
+        let mut sampler = UsageSampler::new(run.child.id());
+        let mut next_sample = Instant::now();
+        let ended = loop {
+            if Instant::now() >= next_sample {
+                if let Some(usage) = sampler.sample() {
+                    self.status.set_usage(&self.name, Some(usage));
+                }
+                next_sample += USAGE_INTERVAL;
+            }
+            match rx.recv_timeout(EXIT_CHECK) {
+                Ok(ProcessMessage::Restart) => {
+                    stop(&mut run.child, self.options.stop_grace);
+                    break Ended::Restart;
+                },
+                Ok(ProcessMessage::Changed(path)) => {
+                    self.status.announce(format!("{} changed, restarting {}", path, self.cmd));
+                    stop(&mut run.child, self.options.stop_grace);
+                    break Ended::Restart;
+                },
+                Ok(ProcessMessage::Signal(signal)) => {
+                    if send_signal(&run.child, signal) {
+                        self.status.announce(format!("Sent {} to {}", signal_name(signal), self.cmd));
+                    } else {
+                        self.status.announce(format!("Error: Failed to send {} to {}", signal_name(signal), self.cmd));
+                    }
+                },
+                Ok(ProcessMessage::Input(line)) => {
+                    let written = run.stdin.as_mut()
+                        .is_some_and(|stdin| writeln!(stdin, "{}", line).and_then(|()| stdin.flush()).is_ok());
+                    if !written {
+                        // It closed its stdin, so later lines needn't try
+                        run.stdin = None;
+                        self.status.announce(format!("Error: {} isn't reading its input", self.cmd));
+                    }
+                },
+                Ok(ProcessMessage::Finish(done)) => {
+                    stop(&mut run.child, self.options.stop_grace);
+                    break Ended::Finish(Some(done));
+                },
+                Ok(ProcessMessage::Stop) => {
+                    stop(&mut run.child, self.options.stop_grace);
+                    break Ended::Stopped;
+                },
+                // Nobody is left to see the command, so it isn't left running either
+                Err(RecvTimeoutError::Disconnected) => {
+                    stop(&mut run.child, self.options.stop_grace);
+                    break Ended::Finish(None);
+                },
+                Err(RecvTimeoutError::Timeout) => {},
+            }
+            if !matches!(run.child.try_wait(), Ok(None)) {
+                break Ended::Exited;
             }
+        };
+        self.status.set_usage(&self.name, None);
+        if matches!(ended, Ended::Finish(None)) {
+            // Nobody is left to tell how it went
+            let _ = run.child.wait();
+            return ended;
         }
-    });
+        if matches!(ended, Ended::Exited) {
+            // Whatever it left running in the background goes with it, rather than holding its
+            // output open and outliving a restart
+            stop(&mut run.child, self.options.stop_grace);
+        }
+        finish_readers(run.readers, OUTPUT_DRAIN);
+        let elapsed = format_elapsed(run.started.elapsed());
+        let exit = run.child.wait();
+        if let (Ended::Exited, true, Ok(exit)) = (&ended, self.options.propagate_exit, &exit) {
+            let _ = self.exit_code.set(exit.code().or(exit.signal().map(|signal| 128 + signal)).unwrap_or(1));
+        }
+        match exit {
+            // Runs on an interval only say so when they fail
+            Ok(exit) if exit.success() && self.options.every.is_some() => {},
+            Ok(exit) if exit.success() => self.note(format!("{} exited with code 0 after {}", self.cmd, elapsed), Severity::Info),
+            Ok(_) if matches!(ended, Ended::Restart) => self.note(format!("{} was stopped to restart it after {}", self.cmd, elapsed), Severity::Info),
+            Ok(_) if matches!(ended, Ended::Finish(_)) => self.note(format!("{} was stopped on quitting after {}", self.cmd, elapsed), Severity::Info),
+            Ok(_) if matches!(ended, Ended::Stopped) => self.note(format!("{} was stopped after {}", self.cmd, elapsed), Severity::Info),
+            Ok(exit) => self.note(format!("{} {} after {}", self.cmd, describe_exit(exit), elapsed), Severity::Error),
+            Err(e) => self.note(format!("Failed to get the exit status of {}: {}", self.cmd, e), Severity::Error),
+        }
+        ended
+    }
+
+    /// The command with its arguments
+    fn command_line(&self) -> String {
+        std::iter::once(&self.cmd).chain(&self.args).cloned().collect::<Vec<_>>().join(" ")
+    }
+
+    /// Whether each run's output starts with a header, when runs come one after another
+    fn headers(&self) -> bool {
+        self.options.every.is_some() || !self.options.watch_exec.is_empty()
+    }
 
-    Ok(())
+    /// With `every` or `watch_exec`, add the entry that starts a run's output
+    fn header(&self, run: usize) {
+        if !self.headers() {
+            return;
+        }
+        let mut entry = LogEntry::new(SOURCE, format!("── Run {}: {} ──", run, self.command_line()));
+        entry.severity = Some(Severity::Info);
+        let _ = self.tx.send(vec![entry]);
+    }
+
+    /// Record the state of both the command's sources
+    fn set_state(&self, state: SourceState) {
+        self.status.set(&format!("{}:stdout", self.name), state);
+        self.status.set(&format!("{}:stderr", self.name), state);
+    }
+
+    /// Add an entry from oxtail itself and show it on the prompt line
+    fn note(&self, message: String, severity: Severity) {
+        self.status.announce(message.clone());
+        let mut entry = LogEntry::new(SOURCE, message);
+        entry.severity = Some(severity);
+        let _ = self.tx.send(vec![entry]);
+    }
+}
+
+/// Send a signal to the command and the group of processes it leads, returning whether it was sent
+fn send_signal(child: &Child, signal: i32) -> bool {
+    let group = -(child.id() as libc::pid_t);
+    // SAFETY: kill only sends a signal, to the group the command leads
+    unsafe { libc::kill(group, signal) == 0 }
+}
+
+/// Whether any process in the command's group is still there
+fn group_alive(child: &Child) -> bool {
+    let group = -(child.id() as libc::pid_t);
+    // SAFETY: signal 0 only checks that the group exists
+    unsafe { libc::kill(group, 0) == 0 || io::Error::last_os_error().raw_os_error() != Some(libc::ESRCH) }
+}
+
+/// Ask the command and everything it started to stop, killing whatever is left if they take
+/// too long
+fn stop(child: &mut Child, grace: Duration) {
+    send_signal(child, libc::SIGTERM);
+    let deadline = Instant::now() + grace;
+    while Instant::now() < deadline {
+        // The command is reaped first, as the group lasts as long as it's waiting to be
+        let _ = child.try_wait();
+        if !group_alive(child) {
+            return;
+        }
+        thread::sleep(EXIT_CHECK);
+    }
+    send_signal(child, libc::SIGKILL);
+}
+
+/// Wait up to `timeout` for the readers to reach the end of the output, leaving any still
+/// reading to finish on their own
+fn finish_readers(readers: Vec<JoinHandle<()>>, timeout: Duration) {
+    let deadline = Instant::now() + timeout;
+    while readers.iter().any(|reader| !reader.is_finished()) && Instant::now() < deadline {
+        thread::sleep(EXIT_CHECK / 10);
+    }
+    for reader in readers.into_iter().filter(|reader| reader.is_finished()) {
+        let _ = reader.join();
+    }
+}
+
+/// The name of the program a command line runs, such as `server` for `./server --port 80`
+fn program_name(cmd: &str) -> String {
+    let program = cmd.split_whitespace().next().unwrap_or(cmd);
+    Path::new(program).file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| program.to_string())
+}
+
+/// How a command that failed ended: an exit code, or the signal that killed it
+fn describe_exit(exit: ExitStatus) -> String {
+    match (exit.code(), exit.signal()) {
+        (Some(code), _) => format!("exited with code {}", code),
+        (None, Some(signal)) => format!("was killed by signal {}", signal),
+        (None, None) => "exited".to_string(),
+    }
+}
+
+/// A run time such as `42s`, `3m12s` or `1h05m`
+pub fn format_elapsed(elapsed: Duration) -> String {
+    let seconds = elapsed.as_secs();
+    if seconds < 60 {
+        format!("{}s", seconds)
+    } else if seconds < 3600 {
+        format!("{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// Send the lines of one of the child's output streams until it closes
+fn read_stream(source: &str, origin: Origin, reader: BufReader<impl Read>, tx: SyncSender<Vec<LogEntry>>) {
+    let mut batcher = EntryBatcher::new(tx).with_origin(Some(origin));
+    let mut lines = cr_lines(reader);
+    let mut line_number = 0;
+    while let Some(Ok(l)) = lines.next() {
+        line_number += 1;
+        let mut entry = LogEntry::new(source, l.text);
+        entry.line_number = line_number;
+        entry.redraw = l.redraw;
+        // Send what's collected before waiting on the child for more
+        if batcher.push(entry).is_err() || (!lines.is_buffered() && batcher.flush().is_err()) {
+            break;
+        }
+    }
 }
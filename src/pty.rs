@@ -0,0 +1,80 @@
+use std::ffi::CStr;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Read};
+use std::os::fd::{AsRawFd, FromRawFd};
+use std::os::unix::fs::OpenOptionsExt;
+
+/// A pseudo-terminal to run a command on, so it writes colors and a line at a time as it
+/// would to a real terminal
+pub struct Pty {
+    /// The end oxtail reads the command's output from and writes its input to
+    pub master: File,
+    /// The end the command has as its terminal
+    pub slave: File,
+}
+
+impl Pty {
+    /// Open a pseudo-terminal of the given size. Output comes through as it's written, without
+    /// `\r` added before each `\n`, and input isn't echoed back into it
+    pub fn open(columns: u16, rows: u16) -> io::Result<Self> {
+        // SAFETY: posix_openpt returns a new descriptor, owned by the File from here on
+        let fd = unsafe { libc::posix_openpt(libc::O_RDWR | libc::O_NOCTTY | libc::O_CLOEXEC) };
+        if fd < 0 {
+            return Err(io::Error::last_os_error());
+        }
+        let master = unsafe { File::from_raw_fd(fd) };
+
+        let mut name = [0 as libc::c_char; 128];
+        // SAFETY: these only act on the descriptor, and ptsname_r writes at most name.len() bytes
+        unsafe {
+            if libc::grantpt(fd) != 0 || libc::unlockpt(fd) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            let error = libc::ptsname_r(fd, name.as_mut_ptr(), name.len());
+            if error != 0 {
+                return Err(io::Error::from_raw_os_error(error));
+            }
+        }
+        // SAFETY: ptsname_r succeeded, so name holds a terminated path
+        let path = unsafe { CStr::from_ptr(name.as_ptr()) }.to_string_lossy().into_owned();
+        let slave = OpenOptions::new().read(true).write(true).custom_flags(libc::O_NOCTTY).open(path)?;
+
+        // SAFETY: termios and winsize are plain structs filled in or read by the calls
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(slave.as_raw_fd(), &mut termios) == 0 {
+                termios.c_oflag &= !libc::OPOST;
+                termios.c_lflag &= !(libc::ECHO | libc::ECHONL);
+                libc::tcsetattr(slave.as_raw_fd(), libc::TCSANOW, &termios);
+            }
+            let size = libc::winsize { ws_row: rows, ws_col: columns, ws_xpixel: 0, ws_ypixel: 0 };
+            libc::ioctl(slave.as_raw_fd(), libc::TIOCSWINSZ, &size);
+        }
+        Ok(Self { master, slave })
+    }
+}
+
+/// Run in the child before the command: start a session of its own, which also puts it in a
+/// group of its own, and make the pseudo-terminal on its stdin the session's terminal
+pub fn make_controlling() -> io::Result<()> {
+    // SAFETY: only async-signal-safe calls, on the child's own stdin
+    unsafe {
+        if libc::setsid() < 0 || libc::ioctl(0, libc::TIOCSCTTY, 0) < 0 {
+            return Err(io::Error::last_os_error());
+        }
+    }
+    Ok(())
+}
+
+/// The command's output read from the master end. Once the command and everything it started
+/// have closed the terminal, reading fails with EIO, which here is the end of the output
+pub struct PtyOutput(pub File);
+
+impl Read for PtyOutput {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self.0.read(buf) {
+            Err(err) if err.raw_os_error() == Some(libc::EIO) => Ok(0),
+            result => result,
+        }
+    }
+}
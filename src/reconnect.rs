@@ -0,0 +1,91 @@
+use std::sync::mpsc::{SendError, SyncSender};
+use std::thread;
+use std::time::Duration;
+use anyhow::Result;
+use crate::batch::EntryBatcher;
+use crate::log_entry::{LogEntry, Origin};
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// First wait before connecting again; it doubles with each failure in a row
+pub const RECONNECT_DELAY: Duration = Duration::from_secs(1);
+pub const MAX_RECONNECT_DELAY: Duration = Duration::from_secs(30);
+
+/// The entries couldn't be sent, because the UI is gone
+pub type Closed = SendError<Vec<LogEntry>>;
+
+/// A source read over a connection that can fail and be made again, such as a network stream.
+/// `start` runs it, so every such source goes through the same states and waits the same way
+/// between attempts
+pub trait Source: Send + 'static {
+    /// The name the source's state is kept under
+    fn name(&self) -> String;
+
+    /// Where its entries come from, such as its URL, for `:where`
+    fn origin(&self) -> Option<Origin> {
+        None
+    }
+
+    /// Connect, calling `session.connected()` once that's done, then read until the connection
+    /// ends, returning why it did. Failing to connect or read is an error; an error that's
+    /// `Closed` means the UI is gone, which ends the source for good
+    fn run(&mut self, session: &mut Session) -> Result<String>;
+
+    /// The connection was lost. For sources that keep the states of others, such as the
+    /// channels a pattern matched
+    fn disconnected(&mut self, _status: &SourceStatusRegistry) {}
+}
+
+/// What a source has while it's connected: where its entries go and how its state is kept
+pub struct Session {
+    name: String,
+    pub batcher: EntryBatcher,
+    pub status: SourceStatusRegistry,
+    /// How long to wait before connecting again if the connection ends now
+    delay: Duration,
+}
+
+impl Session {
+    /// The source is connected: it's running, and failing again starts from the shortest wait
+    pub fn connected(&mut self) {
+        self.status.set(&self.name, SourceState::Running);
+        self.delay = RECONNECT_DELAY;
+    }
+
+    /// Wait this long before connecting again, as a server may ask
+    pub fn retry_after(&mut self, delay: Duration) {
+        self.delay = delay;
+    }
+}
+
+/// Run a source in a thread of its own, connecting again whenever its connection ends or
+/// fails, waiting twice as long each time it does so in a row, up to `MAX_RECONNECT_DELAY`.
+/// Each loss is said on the prompt line; the source shows as connecting until it first is,
+/// and as reconnecting while it waits after that
+pub fn start(mut source: impl Source, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let name = source.name();
+    status.set(&name, SourceState::Connecting);
+    thread::spawn(move || {
+        let batcher = EntryBatcher::new(tx).with_origin(source.origin());
+        let mut session = Session { name: name.clone(), batcher, status, delay: RECONNECT_DELAY };
+        loop {
+            let ended = match source.run(&mut session) {
+                Ok(ended) => ended,
+                Err(err) if err.is::<Closed>() => return,
+                Err(err) => format!("{:#}", err),
+            };
+            // What was read before the connection ended comes before the note about it
+            if session.batcher.flush().is_err() {
+                return;
+            }
+            // One that never connected is still connecting
+            if session.status.get(&name) != Some(SourceState::Connecting) {
+                session.status.set(&name, SourceState::Reconnecting);
+            }
+            source.disconnected(&session.status);
+            let delay = session.delay;
+            session.status.announce(format!("{}: {}; connecting again in {:?}", name, ended, delay));
+            thread::sleep(delay);
+            session.delay = (delay * 2).min(MAX_RECONNECT_DELAY);
+        }
+    });
+}
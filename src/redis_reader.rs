@@ -0,0 +1,223 @@
+use std::sync::mpsc::SyncSender;
+use std::time::Duration;
+use anyhow::{bail, Result};
+use redis::{Connection, Value};
+use crate::batch::EntryBatcher;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin};
+use crate::reconnect::{self, Closed, Session};
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long a stream read waits for new entries before asking again
+const BLOCK: Duration = Duration::from_secs(5);
+
+/// Most stream entries taken in a read
+const READ_COUNT: usize = 1000;
+
+/// How long to wait for more pub/sub messages before sending the ones received, so a burst
+/// reaches the UI in a few batches
+const BATCH_WINDOW: Duration = Duration::from_millis(10);
+
+/// A Redis pub/sub channel or stream, as given with `--redis redis://host/channel`
+#[derive(Debug, Clone)]
+pub struct RedisUrl {
+    /// The server, as the redis crate takes it (`redis://:password@host:6379/0`)
+    server: String,
+    /// The channel, channel pattern or stream key
+    key: String,
+}
+
+impl RedisUrl {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let rest = value.strip_prefix("redis://")
+            .ok_or_else(|| format!("not a redis:// URL: {}", value))?;
+        let (server, key) = rest.rsplit_once('/')
+            .filter(|(server, key)| !server.is_empty() && !key.is_empty())
+            .ok_or_else(|| format!("no channel or stream in {} (e.g. redis://localhost/logs)", value))?;
+        let server = format!("redis://{}", server);
+        redis::Client::open(server.as_str()).map_err(|err| format!("invalid URL: {} ({})", value, err))?;
+        Ok(Self { server, key: key.to_string() })
+    }
+
+    fn is_pattern(&self) -> bool {
+        self.key.contains(['*', '?', '['])
+    }
+}
+
+/// Read a Redis stream, or subscribe to a pub/sub channel if the key isn't a stream, as a
+/// source named after the stream or channel. A pattern such as `logs.*` subscribes to every
+/// channel it matches, each shown as a source of its own. A stream shows its last `tail_lines`
+/// entries to begin with, and carries on from the last one read when the connection is made
+/// again after failing
+pub fn start_reading(url: &RedisUrl, tail_lines: usize, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let reader = RedisReader { url: url.clone(), tail_lines, last_id: None, channels: Vec::new() };
+    reconnect::start(reader, tx, status);
+}
+
+struct RedisReader {
+    url: RedisUrl,
+    tail_lines: usize,
+    /// The ID of the last stream entry read; None until the first read
+    last_id: Option<String>,
+    /// Channels a pattern has matched, which reconnect along with it
+    channels: Vec<String>,
+}
+
+impl reconnect::Source for RedisReader {
+    fn name(&self) -> String {
+        self.url.key.clone()
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        // Without the password, if there's one
+        let server = self.url.server.rsplit_once('@').map_or(&self.url.server["redis://".len()..], |(_, server)| server);
+        Some(log_entry::origin([("url", FieldValue::Str(format!("redis://{}/{}", server, self.url.key)))]))
+    }
+
+    /// Connect, then read the key as a stream or channel until the connection fails
+    fn run(&mut self, session: &mut Session) -> Result<String> {
+        let client = redis::Client::open(self.url.server.as_str())?;
+        let mut connection = client.get_connection_with_timeout(CONNECT_TIMEOUT)?;
+        let kind: String = redis::cmd("TYPE").arg(&self.url.key).query(&mut connection)?;
+        match kind.as_str() {
+            "stream" => read_stream(connection, &self.url, self.tail_lines, &mut self.last_id, session),
+            // Channels aren't keys, so a channel's name is one that's free
+            "none" => read_channel(connection, &self.url, &mut self.channels, session),
+            _ => bail!("{} is a {}, not a stream or channel", self.url.key, kind),
+        }
+    }
+
+    fn disconnected(&mut self, status: &SourceStatusRegistry) {
+        for channel in &self.channels {
+            status.set(channel, SourceState::Reconnecting);
+        }
+    }
+}
+
+/// Add the messages published to the channel, or those matching the pattern, a line to an entry
+fn read_channel(mut connection: Connection, url: &RedisUrl, channels: &mut Vec<String>, session: &mut Session) -> Result<String> {
+    let mut pubsub = connection.as_pubsub();
+    if url.is_pattern() {
+        pubsub.psubscribe(&url.key)?;
+    } else {
+        pubsub.subscribe(&url.key)?;
+    }
+    session.connected();
+    let Session { batcher, status, .. } = session;
+    let mut pending = false;
+    loop {
+        // Wait as long as it takes for the first message of a batch, then briefly for more
+        pubsub.set_read_timeout(pending.then_some(BATCH_WINDOW))?;
+        let message = match pubsub.get_message() {
+            Ok(message) => message,
+            Err(err) if err.is_timeout() => {
+                batcher.flush()?;
+                pending = false;
+                continue;
+            },
+            Err(err) => return Err(err.into()),
+        };
+        let channel = message.get_channel_name();
+        if url.is_pattern() && !channels.iter().any(|seen| seen == channel) {
+            channels.push(channel.to_string());
+        }
+        status.set(channel, SourceState::Running);
+        let text = String::from_utf8_lossy(message.get_payload_bytes());
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            batcher.push(LogEntry::new(channel, line))?;
+            pending = true;
+        }
+    }
+}
+
+/// Add the entries appended to the stream, each dated by its ID
+fn read_stream(mut connection: Connection, url: &RedisUrl, tail_lines: usize, last_id: &mut Option<String>, session: &mut Session) -> Result<String> {
+    // A read may wait for BLOCK before answering
+    connection.set_read_timeout(Some(BLOCK + CONNECT_TIMEOUT))?;
+    session.connected();
+    let batcher = &mut session.batcher;
+    if last_id.is_none() && tail_lines > 0 {
+        let reply: Value = redis::cmd("XREVRANGE").arg(&url.key).arg("+").arg("-").arg("COUNT").arg(tail_lines).query(&mut connection)?;
+        let mut entries = stream_entries(reply);
+        entries.reverse();
+        add_stream_entries(entries, url, last_id, batcher)?;
+    }
+    loop {
+        // `$` is whatever's added from now on
+        let from = last_id.as_deref().unwrap_or("$");
+        let reply: Value = redis::cmd("XREAD")
+            .arg("COUNT").arg(READ_COUNT)
+            .arg("BLOCK").arg(BLOCK.as_millis() as u64)
+            .arg("STREAMS").arg(&url.key).arg(from)
+            .query(&mut connection)?;
+        // Nil when nothing was added in time; otherwise the one stream asked for
+        let entries = match reply {
+            Value::Array(streams) => streams.into_iter()
+                .filter_map(|stream| match stream {
+                    Value::Array(mut stream) if stream.len() == 2 => stream.pop(),
+                    _ => None,
+                })
+                .flat_map(stream_entries)
+                .collect(),
+            _ => Vec::new(),
+        };
+        add_stream_entries(entries, url, last_id, batcher)?;
+    }
+}
+
+/// A stream entry's ID and its field-value pairs
+type StreamEntry = (String, Vec<(String, String)>);
+
+fn add_stream_entries(entries: Vec<StreamEntry>, url: &RedisUrl, last_id: &mut Option<String>, batcher: &mut EntryBatcher) -> Result<(), Closed> {
+    for (id, fields) in entries {
+        // An entry of a single field, such as `message`, is that field's text; one of several is
+        // a JSON object of them, so they work with `:where`
+        let content = match fields.as_slice() {
+            [(_, value)] => value.clone(),
+            _ => serde_json::Value::Object(fields.into_iter().map(|(field, value)| (field, value.into())).collect()).to_string(),
+        };
+        let mut entry = LogEntry::new(url.key.as_str(), content);
+        // IDs are the millisecond the entry was added and a sequence number
+        if let Some(millis) = id.split('-').next().and_then(|millis| millis.parse().ok()) {
+            entry.set_timestamp_millis(millis);
+        }
+        batcher.push(entry)?;
+        *last_id = Some(id);
+    }
+    batcher.flush()
+}
+
+/// The entries of an XRANGE-style reply: `[[id, [field, value, ...]], ...]`
+fn stream_entries(reply: Value) -> Vec<StreamEntry> {
+    let Value::Array(entries) = reply else {
+        return Vec::new();
+    };
+    entries.into_iter()
+        .filter_map(|entry| {
+            let Value::Array(entry) = entry else {
+                return None;
+            };
+            let mut entry = entry.into_iter();
+            let id = text(entry.next()?)?;
+            let Value::Array(fields) = entry.next()? else {
+                return None;
+            };
+            let mut fields = fields.into_iter().filter_map(text);
+            let mut pairs = Vec::new();
+            while let (Some(field), Some(value)) = (fields.next(), fields.next()) {
+                pairs.push((field, value));
+            }
+            Some((id, pairs))
+        })
+        .collect()
+}
+
+fn text(value: Value) -> Option<String> {
+    match value {
+        Value::BulkString(bytes) => Some(String::from_utf8_lossy(&bytes).into_owned()),
+        Value::SimpleString(text) => Some(text),
+        _ => None,
+    }
+}
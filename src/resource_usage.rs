@@ -0,0 +1,73 @@
+use std::fs;
+use std::time::Instant;
+
+/// CPU and memory use of the command and the processes it started
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ResourceUsage {
+    /// Percent of one CPU used since the last sample
+    pub cpu: f64,
+    /// Resident memory, in bytes
+    pub rss: u64,
+}
+
+/// Samples a process group's usage from /proc. Where there's no /proc, there are no samples
+pub struct UsageSampler {
+    group: u32,
+    /// When the last sample was taken, and the CPU ticks used by then
+    last: Option<(Instant, u64)>,
+    ticks_per_second: f64,
+    page_size: u64,
+}
+
+impl UsageSampler {
+    pub fn new(group: u32) -> Self {
+        // SAFETY: sysconf only reads configuration values
+        let (ticks_per_second, page_size) = unsafe { (libc::sysconf(libc::_SC_CLK_TCK), libc::sysconf(libc::_SC_PAGESIZE)) };
+        Self {
+            group,
+            last: None,
+            ticks_per_second: ticks_per_second.max(1) as f64,
+            page_size: page_size.max(1) as u64,
+        }
+    }
+
+    /// Usage since the last sample; None for the first one, which CPU use is measured from
+    pub fn sample(&mut self) -> Option<ResourceUsage> {
+        let (ticks, pages) = group_totals(self.group)?;
+        let now = Instant::now();
+        let (then, last_ticks) = self.last.replace((now, ticks))?;
+        let seconds = now.duration_since(then).as_secs_f64().max(0.001);
+        // Ticks of processes that exited since are gone from the total
+        let cpu = ticks.saturating_sub(last_ticks) as f64 / self.ticks_per_second / seconds * 100.0;
+        Some(ResourceUsage { cpu, rss: pages * self.page_size })
+    }
+}
+
+/// CPU ticks and resident pages of every process in a group, or None if none could be read
+fn group_totals(group: u32) -> Option<(u64, u64)> {
+    let mut totals = None;
+    for entry in fs::read_dir("/proc").ok()?.flatten() {
+        // Only the numbered entries are processes
+        if !entry.file_name().to_str().is_some_and(|name| name.bytes().all(|b| b.is_ascii_digit())) {
+            continue;
+        }
+        let Ok(stat) = fs::read_to_string(entry.path().join("stat")) else {
+            continue;
+        };
+        // The program name in parentheses may hold spaces, so fields are counted from after it,
+        // where the third (state) begins
+        let Some((_, rest)) = stat.rsplit_once(')') else {
+            continue;
+        };
+        let fields: Vec<&str> = rest.split_whitespace().collect();
+        let field = |number: usize| fields.get(number - 3).and_then(|field| field.parse::<u64>().ok());
+        // Fields 5, 14, 15 and 24 are the group, user and system ticks and resident pages
+        if field(5) != Some(group as u64) {
+            continue;
+        }
+        let (ticks, pages) = totals.get_or_insert((0, 0));
+        *ticks += field(14).unwrap_or(0) + field(15).unwrap_or(0);
+        *pages += field(24).unwrap_or(0);
+    }
+    totals
+}
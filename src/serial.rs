@@ -0,0 +1,144 @@
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::os::fd::AsRawFd;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
+use std::sync::mpsc::SyncSender;
+use anyhow::{Context, Result};
+use encoding_rs::Encoding;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry};
+use crate::source_status::SourceStatusRegistry;
+use crate::stdin_reader::{self, StreamOptions};
+
+/// Speeds a port can be set to, with the termios constant for each
+const BAUD_RATES: &[(u32, libc::speed_t)] = &[
+    (1200, libc::B1200), (2400, libc::B2400), (4800, libc::B4800), (9600, libc::B9600),
+    (19200, libc::B19200), (38400, libc::B38400), (57600, libc::B57600), (115200, libc::B115200),
+    (230400, libc::B230400), (460800, libc::B460800), (500000, libc::B500000), (576000, libc::B576000),
+    (921600, libc::B921600), (1000000, libc::B1000000), (1500000, libc::B1500000), (2000000, libc::B2000000),
+    (3000000, libc::B3000000),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Parity {
+    None,
+    Even,
+    Odd,
+}
+
+/// A serial device to read, as given with `--serial /dev/ttyUSB0:115200:8N1`
+#[derive(Debug, Clone)]
+pub struct SerialPort {
+    path: PathBuf,
+    baud: libc::speed_t,
+    data_bits: libc::tcflag_t,
+    parity: Parity,
+    two_stop_bits: bool,
+}
+
+impl SerialPort {
+    /// Parse `<device>[:<baud>[:<framing>]]`, where the framing is data bits, parity (N, E or O)
+    /// and stop bits, as in `8N1`, which it is unless given
+    pub fn parse(value: &str) -> Result<Self, String> {
+        let mut parts = value.split(':');
+        let path = PathBuf::from(parts.next().unwrap_or_default());
+        if path.as_os_str().is_empty() {
+            return Err(format!("no device in {} (e.g. /dev/ttyUSB0:115200)", value));
+        }
+        let baud = match parts.next() {
+            Some(baud) => {
+                let rate = baud.parse::<u32>().map_err(|_| format!("invalid baud rate: {}", baud))?;
+                BAUD_RATES.iter().find(|(known, _)| *known == rate).map(|(_, speed)| *speed)
+                    .ok_or_else(|| format!("unsupported baud rate: {} (e.g. 9600 or 115200)", baud))?
+            },
+            None => libc::B115200,
+        };
+        let framing = parts.next().unwrap_or("8N1");
+        let invalid = || format!("invalid framing: {} (data bits 5-8, parity N, E or O, stop bits 1 or 2, e.g. 8N1)", framing);
+        let &[bits, parity, stop] = framing.as_bytes() else {
+            return Err(invalid());
+        };
+        let data_bits = match bits {
+            b'5' => libc::CS5,
+            b'6' => libc::CS6,
+            b'7' => libc::CS7,
+            b'8' => libc::CS8,
+            _ => return Err(invalid()),
+        };
+        let parity = match parity.to_ascii_uppercase() {
+            b'N' => Parity::None,
+            b'E' => Parity::Even,
+            b'O' => Parity::Odd,
+            _ => return Err(invalid()),
+        };
+        let two_stop_bits = match stop {
+            b'1' => false,
+            b'2' => true,
+            _ => return Err(invalid()),
+        };
+        if parts.next().is_some() {
+            return Err(format!("invalid serial port: {} (e.g. /dev/ttyUSB0:115200:8N1)", value));
+        }
+        Ok(Self { path, baud, data_bits, parity, two_stop_bits })
+    }
+
+    /// The source the device's lines are shown under, its file name as for files (`ttyUSB0`)
+    pub fn source(&self) -> String {
+        self.path.file_name().map_or_else(|| self.path.display().to_string(), |name| name.to_string_lossy().into_owned())
+    }
+
+    /// Open the device and set it up as raw, so its bytes arrive as they were sent
+    fn open(&self) -> io::Result<File> {
+        // Opened without waiting for a modem's carrier, which a board's UART never raises
+        let device = OpenOptions::new().read(true).custom_flags(libc::O_NOCTTY | libc::O_NONBLOCK).open(&self.path)?;
+        let fd = device.as_raw_fd();
+        // SAFETY: termios is a plain struct, filled in by tcgetattr; the calls only act on the
+        // descriptor
+        unsafe {
+            let mut termios: libc::termios = std::mem::zeroed();
+            if libc::tcgetattr(fd, &mut termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            libc::cfmakeraw(&mut termios);
+            termios.c_cflag |= libc::CREAD | libc::CLOCAL;
+            termios.c_cflag &= !(libc::CSIZE | libc::PARENB | libc::PARODD | libc::CSTOPB | libc::CRTSCTS);
+            termios.c_cflag |= self.data_bits;
+            match self.parity {
+                Parity::None => {},
+                Parity::Even => termios.c_cflag |= libc::PARENB,
+                Parity::Odd => termios.c_cflag |= libc::PARENB | libc::PARODD,
+            }
+            if self.two_stop_bits {
+                termios.c_cflag |= libc::CSTOPB;
+            }
+            // Reads wait for at least a byte
+            termios.c_cc[libc::VMIN] = 1;
+            termios.c_cc[libc::VTIME] = 0;
+            if libc::cfsetispeed(&mut termios, self.baud) != 0
+                || libc::cfsetospeed(&mut termios, self.baud) != 0
+                || libc::tcsetattr(fd, libc::TCSANOW, &termios) != 0 {
+                return Err(io::Error::last_os_error());
+            }
+            // Whatever was received before oxtail started is left out, as it may be cut off
+            libc::tcflush(fd, libc::TCIFLUSH);
+            // Set up, reads can block as they do on any other stream
+            let flags = libc::fcntl(fd, libc::F_GETFL);
+            if flags < 0 || libc::fcntl(fd, libc::F_SETFL, flags & !libc::O_NONBLOCK) < 0 {
+                return Err(io::Error::last_os_error());
+            }
+        }
+        Ok(device)
+    }
+}
+
+/// Read a serial device's output as a source, a line to an entry, as stdin is read. The device
+/// is opened and set up here, so a missing device or one without permission to read it is
+/// reported before the UI starts
+pub fn start_reading(port: &SerialPort, encoding: Option<&'static Encoding>, options: StreamOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) -> Result<()> {
+    let device = port.open()
+        .with_context(|| format!("Failed to open {} (reading serial devices often takes the dialout group)", port.path.display()))?;
+    let origin = log_entry::origin([("device", FieldValue::Str(port.path.display().to_string()))]);
+    stdin_reader::start_reading(port.source(), device, Some(origin), encoding, options, tx, status);
+    Ok(())
+}
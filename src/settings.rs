@@ -1,11 +1,139 @@
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
+use std::time::Duration;
+use chrono::{DateTime, Local};
+use ratatui::style::Color;
+use crate::fields::FieldCondition;
+use crate::groups::SourceGroups;
+use crate::log_entry::Severity;
+use crate::pattern::Pattern;
 
 // Source configuration - uses string identifiers for flexibility
+#[derive(Clone)]
 pub struct SourceConfig {
     pub visible: bool,
 }
 
+/// What gets copied when yanking entries
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum YankFormat {
+    Raw,       // Original content including ANSI codes
+    Plain,     // Content with ANSI codes stripped
+    Formatted, // The line as displayed, with metadata columns
+}
+
+impl YankFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "raw" => Some(YankFormat::Raw),
+            "plain" => Some(YankFormat::Plain),
+            "formatted" => Some(YankFormat::Formatted),
+            _ => None,
+        }
+    }
+}
+
+/// A pattern from `:highlight`, with matches drawn in its color, or the theme's search color
+#[derive(Debug, Clone)]
+pub struct Highlight {
+    pub pattern: Pattern,
+    pub color: Option<Color>,
+    /// As typed, for listing it
+    spec: String,
+}
+
+impl Highlight {
+    /// Parse `<regex>` or `<regex>:<color>`. Only a color after the last `:` is taken for one,
+    /// so patterns can hold a `:` too
+    pub fn parse(spec: &str) -> Result<Self, String> {
+        let (pattern, color) = match spec.rsplit_once(':') {
+            Some((pattern, color)) if !pattern.is_empty() => match Color::from_str(color) {
+                Ok(color) => (pattern, Some(color)),
+                Err(_) => (spec, None),
+            },
+            _ => (spec, None),
+        };
+        let pattern = Pattern::new(pattern).map_err(|err| format!("Invalid pattern: {}", err))?;
+        Ok(Self { pattern, color, spec: spec.to_string() })
+    }
+}
+
+impl PartialEq for Highlight {
+    fn eq(&self, other: &Self) -> bool {
+        self.spec == other.spec
+    }
+}
+
+impl fmt::Display for Highlight {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.spec)
+    }
+}
+
+/// Parse a duration such as `10s`, `500ms`, `2m` or `1h`; a bare number is seconds
+pub fn parse_duration(value: &str) -> Option<Duration> {
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let seconds = match unit {
+        "ms" => number / 1000.0,
+        "" | "s" => number,
+        "m" => number * 60.0,
+        "h" => number * 3600.0,
+        _ => return None,
+    };
+    Duration::try_from_secs_f64(seconds).ok()
+}
+
+/// Parse a size such as `512M`, `2G` or `64k` into bytes; a bare number is bytes
+pub fn parse_size(value: &str) -> Option<usize> {
+    let split = value.find(|c: char| !c.is_ascii_digit() && c != '.').unwrap_or(value.len());
+    let (number, unit) = value.split_at(split);
+    let number: f64 = number.parse().ok()?;
+    let scale = match unit.to_ascii_lowercase().trim_end_matches('b') {
+        "" => 1.0,
+        "k" => 1024.0,
+        "m" => 1024.0 * 1024.0,
+        "g" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((number * scale) as usize)
+}
+
+/// Size in bytes with a binary unit, e.g. `980 B`, `12.3 MB` or `1.5 GB`
+pub fn format_size(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024.0 && unit + 1 < UNITS.len() {
+        size /= 1024.0;
+        unit += 1;
+    }
+    match unit {
+        0 => format!("{} B", bytes),
+        _ => format!("{:.1} {}", size, UNITS[unit]),
+    }
+}
+
+/// The settings that decide how an entry is drawn, compared to tell when drawn lines are stale
+#[derive(Debug, Clone, PartialEq)]
+pub struct DisplayOptions {
+    show_time: bool,
+    show_source_labels: bool,
+    show_line_numbers: bool,
+    show_file_type: bool,
+    show_level: bool,
+    show_raw: bool,
+    show_delta: bool,
+    wrap: bool,
+    wrap_prefix: String,
+    delta_threshold: Duration,
+    highlights: Vec<Highlight>,
+}
+
 // Global settings
+#[derive(Clone)]
 pub struct LogSettings {
     // Per-source configurations
     pub sources: HashMap<String, SourceConfig>,
@@ -15,32 +143,69 @@ pub struct LogSettings {
     pub show_source_labels: bool,
     pub show_line_numbers: bool,
     pub show_file_type: bool,
+    pub show_level: bool,
     pub show_raw: bool,  // When true, shows content with ANSI codes, otherwise shows plain content
+    pub show_delta: bool,  // Time since the previous visible entry, before the other columns
+    pub wrap: bool,  // Wrap long entries instead of truncating them
+    pub wrap_prefix: String,  // Shown at the start of each wrapped continuation row
+
+    // Deltas at least this long are highlighted (`:set delta_threshold`)
+    pub delta_threshold: Duration,
+
+    // Structured field conditions from `:where`, all of which must match
+    pub field_conditions: Vec<FieldCondition>,
+
+    // Patterns from `:filter_in` and `:filter_out`, which entries must and mustn't match
+    pub filter_in: Option<Pattern>,
+    pub filter_out: Option<Pattern>,
+
+    // Least severe level shown from `:level`; entries without a level are always shown
+    pub min_severity: Option<Severity>,
+
+    // What `y` copies to the clipboard
+    pub yank_format: YankFormat,
+
+    // Inclusive timestamp range set by `:narrow`
+    pub time_range: Option<(DateTime<Local>, DateTime<Local>)>,
+
+    // Patterns from `:alert`; the latest matching entry is shown in the headline bar
+    pub alert_patterns: Vec<Pattern>,
+
+    // Patterns from `:highlight`, drawn over each other in order
+    pub highlights: Vec<Highlight>,
+
+    // Go back to following after this long without input while paused (`:set autoresume`)
+    pub autoresume: Option<Duration>,
+
+    // Groups from `--group`; a source not shown or hidden on its own follows its group
+    pub groups: SourceGroups,
 }
 
 impl Default for LogSettings {
     fn default() -> Self {
-        let mut sources = HashMap::new();
-        sources.insert(
-            "stdout".to_string(), 
-            SourceConfig { 
-                visible: true 
-            }
-        );
-        sources.insert(
-            "stderr".to_string(), 
-            SourceConfig { 
-                visible: true 
-            }
-        );
-
         Self {
-            sources,
+            // Sources are visible until shown or hidden on their own
+            sources: HashMap::new(),
             show_time: true,
             show_source_labels: true,
             show_line_numbers: false,
             show_file_type: false,
+            show_level: true,
             show_raw: false,  // Default to plain text (no ANSI codes)
+            show_delta: false,
+            wrap: false,
+            wrap_prefix: "\u{21aa} ".to_string(),
+            delta_threshold: Duration::from_secs(1),
+            field_conditions: Vec::new(),
+            filter_in: None,
+            filter_out: None,
+            min_severity: None,
+            yank_format: YankFormat::Plain,
+            time_range: None,
+            alert_patterns: Vec::new(),
+            highlights: Vec::new(),
+            autoresume: None,
+            groups: SourceGroups::default(),
         }
     }
 }
@@ -62,14 +227,56 @@ impl LogSettings {
     }
     
     pub fn is_source_visible(&self, name: &str) -> bool {
-        let normalized_name = name.to_lowercase();
-        self.sources.get(&normalized_name)
-            .map_or(true, |s| s.visible)
+        let visible = |name: &str| self.sources.get(&name.to_lowercase()).map(|s| s.visible);
+        visible(name)
+            .or_else(|| self.groups.group_of(name).and_then(|(_, group)| visible(group)))
+            .unwrap_or(true)
+    }
+
+    /// Show or hide a group, including its sources that were shown or hidden on their own
+    pub fn set_group_visibility(&mut self, group: &str, visible: bool) {
+        let groups = self.groups.clone();
+        self.sources.retain(|name, _| groups.group_of(name).is_none_or(|(_, member_of)| !member_of.eq_ignore_ascii_case(group)));
+        self.get_source_config(group).visible = visible;
     }
     
+    /// Show or hide a metadata column by the name `show_meta` takes, returning whether
+    /// there's such a column
+    pub fn set_meta(&mut self, name: &str, show: bool) -> bool {
+        let column = match name {
+            "time" => &mut self.show_time,
+            "source" => &mut self.show_source_labels,
+            "lines" => &mut self.show_line_numbers,
+            "filetype" => &mut self.show_file_type,
+            "level" => &mut self.show_level,
+            "delta" => &mut self.show_delta,
+            "ansi" => &mut self.show_raw,
+            _ => return false,
+        };
+        *column = show;
+        true
+    }
+
     pub fn set_all_sources_visibility(&mut self, visible: bool) {
         for (_, source) in self.sources.iter_mut() {
             source.visible = visible;
         }
     }
+
+    /// Snapshot of the settings that change how entries are drawn
+    pub fn display_options(&self) -> DisplayOptions {
+        DisplayOptions {
+            show_time: self.show_time,
+            show_source_labels: self.show_source_labels,
+            show_line_numbers: self.show_line_numbers,
+            show_file_type: self.show_file_type,
+            show_level: self.show_level,
+            show_raw: self.show_raw,
+            show_delta: self.show_delta,
+            wrap: self.wrap,
+            wrap_prefix: self.wrap_prefix.clone(),
+            delta_threshold: self.delta_threshold,
+            highlights: self.highlights.clone(),
+        }
+    }
 }
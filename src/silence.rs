@@ -0,0 +1,69 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::log_entry::{LogEntry, Severity};
+use crate::process_handler::format_elapsed;
+
+/// Sources expected to keep producing output, as set with `:expect`, so a hung service is
+/// noticed: once one goes quiet for longer than allowed, a marker entry in it says so
+#[derive(Debug, Default)]
+pub struct SilenceWatch {
+    expected: HashMap<String, Expectation>,
+}
+
+#[derive(Debug)]
+struct Expectation {
+    /// The longest it may go without output
+    within: Duration,
+    /// Whether going quiet also raises an alert
+    alert: bool,
+    /// When it last produced output, or when it started being watched
+    last_seen: Instant,
+    /// Whether this silence was already reported
+    reported: bool,
+}
+
+impl SilenceWatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Expect output from a source at least every `within`, raising an alert too with `alert`,
+    /// or stop expecting it with None. The wait starts now
+    pub fn expect(&mut self, source: &str, within: Option<Duration>, alert: bool) {
+        match within {
+            Some(within) => {
+                self.expected.insert(source.to_string(), Expectation { within, alert, last_seen: Instant::now(), reported: false });
+            },
+            None => {
+                self.expected.remove(source);
+            },
+        }
+    }
+
+    /// Note that a source produced output
+    pub fn saw(&mut self, source: &str) {
+        if let Some(expectation) = self.expected.get_mut(source) {
+            expectation.last_seen = Instant::now();
+            expectation.reported = false;
+        }
+    }
+
+    /// Markers for the sources that have gone quiet for too long since the last call, each
+    /// with whether it raises an alert. A silence is reported once, however long it lasts
+    pub fn silent(&mut self) -> Vec<(LogEntry, bool)> {
+        self.expected.iter_mut()
+            .filter(|(_, expectation)| !expectation.reported && expectation.last_seen.elapsed() >= expectation.within)
+            .map(|(source, expectation)| {
+                expectation.reported = true;
+                let mut entry = LogEntry::new(source.as_str(), format!("[no output from {} for {}]", source, format_elapsed(expectation.within)));
+                entry.severity = Some(Severity::Warning);
+                (entry, expectation.alert)
+            })
+            .collect()
+    }
+
+    /// How long a source may go without output, if it's expected to produce some
+    pub fn within(&self, source: &str) -> Option<Duration> {
+        self.expected.get(source).map(|expectation| expectation.within)
+    }
+}
@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::resource_usage::ResourceUsage;
+
+/// Lifecycle state of an input source
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SourceState {
+    Running,
+    Stopped,
+    /// A file that doesn't exist yet, read once it's created
+    Waiting,
+    /// A network source that hasn't connected yet
+    Connecting,
+    /// A source whose connection was lost or whose command exited, waiting to start again
+    Reconnecting,
+}
+
+/// Shared registry that reader threads update as their sources start and stop
+#[derive(Debug, Clone, Default)]
+pub struct SourceStatusRegistry {
+    states: Arc<Mutex<HashMap<String, SourceState>>>,
+    /// Messages for the status line, such as a new file being picked up
+    notices: Arc<Mutex<Vec<String>>>,
+    /// Files whose existing lines are still being read, with the bytes read and to read
+    loading: Arc<Mutex<HashMap<String, (u64, u64)>>>,
+    /// CPU and memory use of running commands, by name
+    usage: Arc<Mutex<HashMap<String, ResourceUsage>>>,
+}
+
+impl SourceStatusRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record the state of a source
+    pub fn set(&self, source: &str, state: SourceState) {
+        if let Ok(mut states) = self.states.lock() {
+            states.insert(source.to_string(), state);
+        }
+    }
+
+    /// Get the last recorded state of a source, if it has reported one
+    pub fn get(&self, source: &str) -> Option<SourceState> {
+        self.states.lock().ok().and_then(|states| states.get(source).copied())
+    }
+
+    /// Sources waiting to start or to connect again, with their states, in name order
+    pub fn waiting(&self) -> Vec<(String, SourceState)> {
+        let Ok(states) = self.states.lock() else {
+            return Vec::new();
+        };
+        let mut waiting: Vec<(String, SourceState)> = states.iter()
+            .filter(|(_, state)| matches!(state, SourceState::Waiting | SourceState::Connecting | SourceState::Reconnecting))
+            .map(|(name, state)| (name.clone(), *state))
+            .collect();
+        waiting.sort_by(|a, b| a.0.cmp(&b.0));
+        waiting
+    }
+
+    /// Record how far a file's existing lines have been read, or `None` once they all have
+    pub fn set_loading(&self, source: &str, progress: Option<(u64, u64)>) {
+        if let Ok(mut loading) = self.loading.lock() {
+            match progress {
+                Some(progress) => loading.insert(source.to_string(), progress),
+                None => loading.remove(source),
+            };
+        }
+    }
+
+    /// What's still loading, by name or as a count of files, and how far along it is in percent
+    pub fn loading(&self) -> Option<(String, u64)> {
+        let loading = self.loading.lock().ok()?;
+        let (read, total) = loading.values().fold((0, 0), |(read, total), progress| (read + progress.0, total + progress.1));
+        let name = match loading.len() {
+            0 => return None,
+            1 => loading.keys().next()?.clone(),
+            files => format!("{} files", files),
+        };
+        Some((name, read * 100 / total.max(1)))
+    }
+
+    /// Record a command's latest resource use, or `None` once it isn't running
+    pub fn set_usage(&self, command: &str, usage: Option<ResourceUsage>) {
+        if let Ok(mut all) = self.usage.lock() {
+            match usage {
+                Some(usage) => all.insert(command.to_string(), usage),
+                None => all.remove(command),
+            };
+        }
+    }
+
+    /// Resource use of each running command, in name order
+    pub fn usage(&self) -> Vec<(String, ResourceUsage)> {
+        let Ok(all) = self.usage.lock() else {
+            return Vec::new();
+        };
+        let mut usage: Vec<(String, ResourceUsage)> = all.iter().map(|(name, usage)| (name.clone(), *usage)).collect();
+        usage.sort_by(|a, b| a.0.cmp(&b.0));
+        usage
+    }
+
+    /// Leave a message for the UI to show
+    pub fn announce(&self, message: impl Into<String>) {
+        if let Ok(mut notices) = self.notices.lock() {
+            notices.push(message.into());
+        }
+    }
+
+    /// Messages left since the last call, oldest first
+    pub fn take_notices(&self) -> Vec<String> {
+        self.notices.lock().map(|mut notices| std::mem::take(&mut *notices)).unwrap_or_default()
+    }
+}
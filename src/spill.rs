@@ -0,0 +1,68 @@
+use std::env;
+use std::fs::{self, File, OpenOptions};
+use std::io;
+use std::os::unix::fs::FileExt;
+use std::process;
+use std::sync::Arc;
+use crate::xdg;
+
+/// Where a spilled entry's record sits in the spill file
+#[derive(Debug, Clone, Copy)]
+pub struct SpillRef {
+    offset: u64,
+    len: u32,
+}
+
+/// Reads records back from a spill file; cheap to clone, so a worker thread can have one
+#[derive(Debug, Clone)]
+pub struct SpillReader {
+    file: Arc<File>,
+}
+
+impl SpillReader {
+    pub fn read(&self, at: SpillRef) -> io::Result<Vec<u8>> {
+        let mut bytes = vec![0; at.len as usize];
+        self.file.read_exact_at(&mut bytes, at.offset)?;
+        Ok(bytes)
+    }
+}
+
+/// An append-only temporary file holding entries moved out of memory. It's unlinked as
+/// soon as it's created, so the space is given back when oxtail exits, however it exits
+#[derive(Debug)]
+pub struct SpillFile {
+    reader: SpillReader,
+    /// Offset the next record is written at
+    end: u64,
+}
+
+impl SpillFile {
+    /// Create the file in the cache directory, or the temporary directory if that can't be made
+    pub fn create() -> io::Result<Self> {
+        let dir = xdg::cache_dir()
+            .filter(|dir| fs::create_dir_all(dir).is_ok())
+            .unwrap_or_else(env::temp_dir);
+        let path = dir.join(format!("oxtail-{}.spill", process::id()));
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .open(&path)?;
+        fs::remove_file(&path)?;
+        Ok(Self { reader: SpillReader { file: Arc::new(file) }, end: 0 })
+    }
+
+    /// Append a record, returning where to find it
+    pub fn write(&mut self, bytes: &[u8]) -> io::Result<SpillRef> {
+        let len = u32::try_from(bytes.len()).map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "entry too large to spill"))?;
+        self.reader.file.write_all_at(bytes, self.end)?;
+        let at = SpillRef { offset: self.end, len };
+        self.end += bytes.len() as u64;
+        Ok(at)
+    }
+
+    pub fn reader(&self) -> &SpillReader {
+        &self.reader
+    }
+}
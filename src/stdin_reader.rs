@@ -1,52 +1,180 @@
-use std::io::{self, BufRead};
-use std::sync::mpsc::Sender;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::os::fd::{AsRawFd, FromRawFd, RawFd};
+use std::sync::Arc;
+use std::sync::mpsc::SyncSender;
 use std::thread;
-use chrono::Local;
-use crate::log_entry::LogEntry;
-use anyhow::{Result, Context};
+use std::time::{Duration, Instant};
+use chrono::{DateTime, Local, NaiveDateTime, TimeZone};
+use encoding_rs::Encoding;
+use crate::batch::EntryBatcher;
+use crate::encoding::{self, Text};
+use crate::line_reader::cr_lines;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin, Severity};
+use crate::source_status::{SourceState, SourceStatusRegistry};
+use anyhow::{bail, Result};
 
-/// Starts reading from stdin in a separate thread
+/// Source of the entry added when an input closes, as for oxtail's notes about the command
+const SOURCE: &str = "oxtail";
+
+/// How long lines arriving in quick succession are collected before they're sent, so a burst
+/// written a line at a time reaches the UI in a few batches rather than thousands
+const BATCH_WINDOW: Duration = Duration::from_millis(10);
+
+/// How lines from stdin and `--fd` inputs become entries
+#[derive(Debug, Clone, Copy)]
+pub struct StreamOptions {
+    /// Keep blank lines as entries
+    pub keep_empty_lines: bool,
+    /// Date each line by the timestamp it starts with, if any, rather than by when it's read
+    pub timestamps: bool,
+}
+
+/// Starts reading from stdin in a separate thread, as the `name` source
 /// This is kept simple - just a thread that reads from stdin and sends log entries
 /// The UI will read keyboard events from /dev/tty instead to avoid conflicts
-pub fn start_reading_stdin(tx: Sender<LogEntry>) -> Result<()> {
+pub fn start_reading_stdin(name: String, encoding: Option<&'static Encoding>, options: StreamOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) -> Result<()> {
     // Skip if stdin is a terminal
     if atty::is(atty::Stream::Stdin) {
         return Ok(());
     }
 
-    // Spawn a thread to read from stdin
+    start_reading(name, io::stdin(), None, encoding, options, tx, status);
+    Ok(())
+}
+
+/// The source name of a file descriptor given with `--fd`
+pub fn fd_source(fd: RawFd) -> String {
+    format!("fd{}", fd)
+}
+
+/// Take over a file descriptor inherited from the shell (`--fd 3` with `3< <(journalctl -f)`).
+/// Called before oxtail opens anything, so a descriptor that wasn't passed in can't turn out
+/// to be one of oxtail's own
+pub fn open_fd(fd: RawFd) -> Result<File> {
+    // SAFETY: fcntl only sets the descriptor's flags. Closing it on exec keeps a command given
+    // after `--` from holding the pipe open
+    if unsafe { libc::fcntl(fd, libc::F_SETFD, libc::FD_CLOEXEC) } < 0 {
+        bail!("File descriptor {} isn't open (e.g. oxtail --fd {} {}< <(command))", fd, fd, fd);
+    }
+    // SAFETY: the descriptor is open, and nothing else in oxtail uses it
+    Ok(unsafe { File::from_raw_fd(fd) })
+}
+
+/// Read a file descriptor taken over with `open_fd` the way stdin is read, as the `fd3` source
+pub fn start_reading_fd(fd: RawFd, file: File, encoding: Option<&'static Encoding>, options: StreamOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    let origin = log_entry::origin([("fd", FieldValue::Int(fd.into()))]);
+    start_reading(fd_source(fd), file, Some(origin), encoding, options, tx, status);
+}
+
+/// Read `input` in a thread of its own, as the `source` source, its entries coming from `origin`.
+/// Input in another `encoding`, or starting with a byte order mark, is turned into UTF-8 as
+/// it's read; bytes that aren't valid text become U+FFFD rather than ending the source, and
+/// input that starts out as binary data is shown escaped. Once the input closes, an entry says how many lines it sent and the
+/// source is marked stopped, after everything it sent is on its way to the UI
+pub fn start_reading(source: String, input: impl Read + AsRawFd + Send + 'static, origin: Option<Origin>, encoding: Option<&'static Encoding>, options: StreamOptions, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    status.set(&source, SourceState::Running);
     thread::spawn(move || {
-        let stdin = io::stdin();
-        let mut line_number = 0;
-        
-        // Process each line from stdin
-        for line in stdin.lock().lines() {
-            match line {
-                Ok(content) if !content.is_empty() => {
-                    // Create a log entry for this line
-                    let entry = LogEntry {
-                        timestamp: Local::now(),
-                        source: "stdin".to_string(),
-                        content: content.clone(),
-                        content_plain: content,
-                        line_number,
-                        is_json: false,  // Let LogEntry handle JSON detection
-                    };
-                    line_number += 1;
-                    
-                    // Send to the main thread
-                    if tx.send(entry).is_err() {
-                        break; // Channel closed, stop reading
-                    }
+        let fd = input.as_raw_fd();
+        // A stream can't be read again, so binary data is always shown, never skipped as a file's is
+        let mut raw = BufReader::new(input);
+        let text = match raw.fill_buf() {
+            Ok(start) => {
+                let encoding = encoding::from_bom(start).or(encoding);
+                if encoding::is_binary(start, encoding) {
+                    status.announce(format!("{} looks like binary data; showing it escaped", source));
+                    Text::Escaped
+                } else {
+                    Text::Encoded(encoding)
                 }
+            },
+            // The error comes up again on reading below
+            Err(_) => Text::Encoded(encoding),
+        };
+        let mut lines = cr_lines(BufReader::new(text.reader(raw)));
+        let mut batcher = EntryBatcher::new(tx).with_origin(origin);
+        let name: Arc<str> = source.as_str().into();
+        let mut line_number = 0;
+        let mut error = None;
+        // When the first entry not sent yet was read
+        let mut batch_started = None;
+
+        // Process each line of input
+        while let Some(line) = lines.next() {
+            let line = match line {
+                Ok(line) => line,
                 Err(e) => {
-                    eprintln!("Error reading from stdin: {}", e);
+                    error = Some(e);
                     break;
                 }
-                _ => continue,
+            };
+            if options.keep_empty_lines || !line.text.is_empty() {
+                // Create a log entry for this line
+                let timestamp = options.timestamps.then(|| leading_timestamp(&line.text)).flatten();
+                let mut entry = LogEntry::new(name.clone(), line.text);
+                if let Some(timestamp) = timestamp {
+                    entry.set_timestamp_millis(timestamp);
+                }
+                entry.line_number = line_number;
+                entry.redraw = line.redraw;
+                line_number += 1;
+                if batcher.push(entry).is_err() {
+                    // Channel closed: oxtail is quitting
+                    return;
+                }
+            }
+
+            // Send to the main thread once the input goes quiet or the window is up, so
+            // entries are never held back long waiting for more input. A blank line that's
+            // skipped can be the last of a burst too
+            let started = *batch_started.get_or_insert_with(Instant::now);
+            let send = !lines.is_buffered() && match BATCH_WINDOW.checked_sub(started.elapsed()) {
+                Some(remaining) => !readable(fd, remaining),
+                None => true,
+            };
+            if send {
+                if batcher.flush().is_err() {
+                    return;
+                }
+                batch_started = None;
             }
         }
+
+        // The screen belongs to the UI, so how reading ended is said there
+        let (message, severity) = match error {
+            Some(e) => (format!("Error reading from {} after {} lines: {}", source, line_number, e), Severity::Error),
+            None => (format!("{} closed ({} lines)", source, line_number), Severity::Info),
+        };
+        status.announce(message.clone());
+        let mut entry = LogEntry::new(SOURCE, message);
+        entry.severity = Some(severity);
+        if batcher.push(entry).is_ok() && batcher.flush().is_ok() {
+            status.set(&source, SourceState::Stopped);
+        }
     });
+}
 
-    Ok(())
+/// Whether there's input to read from `fd`, or it has closed, waiting up to `timeout` for it
+fn readable(fd: RawFd, timeout: Duration) -> bool {
+    let mut poll = libc::pollfd { fd, events: libc::POLLIN, revents: 0 };
+    // SAFETY: poll only writes revents of the one pollfd it's given
+    unsafe { libc::poll(&mut poll, 1, timeout.as_millis().max(1) as libc::c_int) > 0 }
+}
+
+/// The time a line starts with, in milliseconds since the Unix epoch: an ISO 8601 date and
+/// time, with a `T` or a space between them, as `kubectl logs --timestamps` and many loggers
+/// write it (e.g. `2024-05-01T12:00:00.123456789Z`). Without a zone it's local time
+fn leading_timestamp(text: &str) -> Option<i64> {
+    let date = text.get(..10)?;
+    if !matches!(text.get(10..11)?, "T" | " ") {
+        return None;
+    }
+    let time = text[11..].split(char::is_whitespace).next()?;
+    let stamp = format!("{}T{}", date, time);
+    if let Ok(at) = DateTime::parse_from_rfc3339(&stamp) {
+        return Some(at.timestamp_millis());
+    }
+    let naive = NaiveDateTime::parse_from_str(&stamp, "%Y-%m-%dT%H:%M:%S%.f").ok()?;
+    Local.from_local_datetime(&naive).earliest().map(|at| at.timestamp_millis())
 }
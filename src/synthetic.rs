@@ -0,0 +1,95 @@
+use std::sync::mpsc::SyncSender;
+use std::thread;
+use std::time::{Duration, Instant};
+use crate::batch::EntryBatcher;
+use crate::log_entry::LogEntry;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+
+/// Name the synthetic source's entries are shown under
+pub const SOURCE: &str = "synthetic";
+
+/// Default length of a synthetic line, in bytes
+pub const DEFAULT_LINE_SIZE: usize = 120;
+
+/// How often the synthetic source catches up with its rate
+const TICK: Duration = Duration::from_millis(10);
+
+const PATHS: &[&str] = &["/api/items", "/api/users", "/api/orders", "/health", "/login", "/static/app.js"];
+const FILLER: &[&str] = &["lorem", "ipsum", "dolor", "sit", "amet", "consectetur", "adipiscing", "elit", "sed", "do"];
+
+/// Makes up logfmt lines of about the same length, with a level, status and duration
+/// to filter on, padded out with filler words. The same size always gives the same lines
+pub struct LineGenerator {
+    line_size: usize,
+    count: u64,
+    /// xorshift state
+    state: u64,
+}
+
+impl LineGenerator {
+    pub fn new(line_size: usize) -> Self {
+        Self { line_size, count: 0, state: 0x9e37_79b9_7f4a_7c15 }
+    }
+
+    fn random(&mut self, below: u64) -> u64 {
+        self.state ^= self.state << 13;
+        self.state ^= self.state >> 7;
+        self.state ^= self.state << 17;
+        self.state % below
+    }
+}
+
+impl Iterator for LineGenerator {
+    type Item = String;
+
+    fn next(&mut self) -> Option<String> {
+        self.count += 1;
+        // Mostly successful requests, with the odd slow one or failure
+        let roll = self.random(100);
+        let (level, status) = match roll {
+            0..=1 => ("error", 500 + self.random(4) as u16),
+            2..=9 => ("warn", 400 + self.random(30) as u16),
+            _ => ("info", 200),
+        };
+        let duration = if roll < 5 { 250 + self.random(2000) } else { self.random(250) };
+        let path = PATHS[self.random(PATHS.len() as u64) as usize];
+        let mut line = format!(
+            "level={} msg=\"GET {}/{}\" status={} duration_ms={} request_id={:08x}",
+            level, path, self.random(1000), status, duration, self.count,
+        );
+        while line.len() < self.line_size {
+            line.push(' ');
+            line.push_str(FILLER[self.random(FILLER.len() as u64) as usize]);
+        }
+        line.truncate(self.line_size.max(1));
+        Some(line)
+    }
+}
+
+/// Starts a source that makes up `rate` lines per second, for trying oxtail under load
+pub fn start_synthetic(rate: u32, line_size: usize, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    status.set(SOURCE, SourceState::Running);
+    thread::spawn(move || {
+        let mut lines = LineGenerator::new(line_size);
+        let mut batcher = EntryBatcher::new(tx);
+        let started = Instant::now();
+        let mut sent: u64 = 0;
+        'generate: loop {
+            // Catch up with the lines due by now, so the rate holds however long a tick takes
+            let due = (started.elapsed().as_secs_f64() * rate as f64) as u64;
+            while sent < due {
+                let mut entry = LogEntry::new(SOURCE, lines.next().unwrap_or_default());
+                entry.line_number = sent as i64;
+                sent += 1;
+                if batcher.push(entry).is_err() {
+                    break 'generate; // Channel closed
+                }
+            }
+            if batcher.flush().is_err() {
+                break;
+            }
+            thread::sleep(TICK);
+        }
+        status.set(SOURCE, SourceState::Stopped);
+    });
+}
@@ -0,0 +1,421 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use crate::log_entry::LogEntry;
+use crate::log_storage::{Filter, FilteredView, LogStorage, SearchJob, ViewEntries};
+use crate::settings::LogSettings;
+use crate::widgets::LogViewer;
+
+/// Tab operations requested from the command prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum TabCommand {
+    New(Option<String>),
+    Next,
+    Prev,
+    Select(usize),
+    Rename(String),
+    Close,
+    /// Show a tab (1-based) in a second pane beside the active one, or close the split
+    Split(Option<usize>),
+    /// One pane per visible source in the given arrangement
+    Layout(PaneLayout),
+    /// Toggle scrolling the split panes together by timestamp
+    Sync,
+}
+
+/// The latest entry that matched one of a tab's alert patterns
+#[derive(Debug, Clone)]
+pub struct AlertHit {
+    pub source: Arc<str>,
+    pub line_number: i64,
+    /// Milliseconds since the Unix epoch
+    pub timestamp: i64,
+    /// Headline text: time, source and content
+    pub text: String,
+}
+
+impl AlertHit {
+    fn new(entry: &LogEntry) -> Self {
+        Self {
+            source: entry.source.clone(),
+            line_number: entry.line_number,
+            timestamp: entry.timestamp_millis(),
+            text: format!("[{}] [{}] {}",
+                entry.timestamp().format("%H:%M:%S"),
+                entry.source.to_uppercase(),
+                entry.content_plain()),
+        }
+    }
+}
+
+/// A named view over the shared log storage with its own settings, filter and scroll state
+pub struct Tab {
+    pub name: String,
+    pub settings: LogSettings,
+    pub filter: Filter,
+    /// Entries passing the filter, refreshed as the storage changes
+    pub view: FilteredView,
+    pub viewer: LogViewer,
+    /// Entries per source that arrived while the tab wasn't following that source
+    unread: HashMap<String, usize>,
+    /// Latest entry matching the alert patterns, and the patterns it was found with
+    alert: Option<AlertHit>,
+    alert_patterns: Vec<String>,
+    /// Search for the latest match after the patterns changed, while it runs
+    alert_search: Option<SearchJob>,
+}
+
+impl Tab {
+    pub fn new(name: impl Into<String>, settings: LogSettings) -> Self {
+        let mut filter = Filter::new();
+        filter.update_from_settings(&settings);
+
+        Self {
+            name: name.into(),
+            settings,
+            filter,
+            view: FilteredView::new(),
+            viewer: LogViewer::new(),
+            unread: HashMap::new(),
+            alert: None,
+            alert_patterns: Vec::new(),
+            alert_search: None,
+        }
+    }
+
+    /// Re-sync the filter after the tab's settings changed
+    pub fn update_filter_from_settings(&mut self) {
+        self.filter.update_from_settings(&self.settings);
+        self.view.invalidate();
+    }
+
+    /// Catch the filtered view up with the storage
+    pub fn refresh_view(&mut self, log_storage: &LogStorage) {
+        log_storage.refresh_view(&mut self.view, &self.filter);
+    }
+
+    /// The entries shown in the tab, as of the last `refresh_view`
+    pub fn entries<'a>(&'a self, log_storage: &'a LogStorage) -> ViewEntries<'a> {
+        log_storage.view_entries(&self.view)
+    }
+
+    /// Whether the tab is showing the latest entries from a source as they arrive
+    fn is_following(&self, source: &str, active: bool) -> bool {
+        active && !self.viewer.is_paused() && self.settings.is_source_visible(source)
+    }
+
+    /// Whether an entry matches one of the tab's alert patterns
+    fn is_alert(&self, entry: &LogEntry) -> bool {
+        self.settings.alert_patterns.iter().any(|pattern| pattern.is_match(entry.content_plain()))
+    }
+
+    /// The latest alert, if any pattern has matched
+    pub fn alert(&self) -> Option<&AlertHit> {
+        self.alert.as_ref()
+    }
+
+    /// Show an entry in the alert bar whether or not it matches a pattern, as a source going
+    /// quiet does
+    pub fn raise_alert(&mut self, entry: &LogEntry) {
+        self.alert = Some(AlertHit::new(entry));
+    }
+
+    /// Search the whole log again if the alert patterns changed since the last search, and
+    /// take in what the search found once it's done
+    pub fn refresh_alert(&mut self, log_storage: &LogStorage) {
+        let patterns: Vec<String> = self.settings.alert_patterns.iter().map(|p| p.as_str().to_string()).collect();
+        if patterns != self.alert_patterns {
+            let alert_patterns = self.settings.alert_patterns.clone();
+            self.alert = None;
+            self.alert_search = Some(log_storage.search_latest(move |entry| {
+                alert_patterns.iter().any(|pattern| pattern.is_match(entry.content_plain()))
+            }));
+            self.alert_patterns = patterns;
+        }
+        if let Some(found) = self.alert_search.as_mut().and_then(SearchJob::poll) {
+            self.alert_search = None;
+            // An alert raised while searching is newer than anything the search found
+            if self.alert.is_none() {
+                self.alert = found.map(|entry| AlertHit::new(&entry));
+            }
+        }
+    }
+
+    /// Entries checked and total, while the alert patterns are searched for
+    pub fn alert_progress(&self) -> Option<(usize, usize)> {
+        self.alert_search.as_ref().map(SearchJob::progress)
+    }
+
+    /// Count an incoming entry as unread unless it shows up on screen right away
+    /// and remember it if it raises an alert. `entry` must already be in storage,
+    /// so its line number is final
+    pub fn note_new_entry(&mut self, entry: &LogEntry, active: bool) {
+        // Earlier lines only raise an alert if there's none, as any there is came later
+        if self.is_alert(entry) && (!entry.earlier || self.alert.is_none()) {
+            self.alert = Some(AlertHit::new(entry));
+        }
+
+        // Redraws update an entry that was already counted, and earlier lines aren't news
+        if !entry.redraw && !entry.earlier && !self.is_following(&entry.source, active) {
+            *self.unread.entry(entry.source.to_string()).or_default() += 1;
+        }
+    }
+
+    /// Clear the counts of sources the (active) tab is following
+    pub fn mark_seen(&mut self) {
+        let seen: Vec<String> = self.unread.keys()
+            .filter(|source| self.is_following(source, true))
+            .cloned()
+            .collect();
+        for source in seen {
+            self.unread.remove(&source);
+        }
+        self.viewer.set_unread(self.unread.values().sum());
+    }
+
+    /// Forget what the tab knew of entries cleared from storage, from one source or all
+    /// of them: their unread counts, the alert if it was one of them, and what was drawn
+    pub fn forget_entries(&mut self, source: Option<&str>) {
+        match source {
+            Some(source) => {
+                self.unread.remove(source);
+            },
+            None => self.unread.clear(),
+        }
+        if self.alert.as_ref().is_some_and(|alert| source.is_none_or(|source| *alert.source == *source)) {
+            // Searched for again on the next refresh
+            self.alert = None;
+            self.alert_patterns.clear();
+            self.alert_search = None;
+        }
+        self.viewer.forget_entries();
+        self.viewer.set_unread(self.unread.values().sum());
+    }
+
+    /// Number of unread entries from a source
+    pub fn unread(&self, source: &str) -> usize {
+        self.unread.get(source).copied().unwrap_or(0)
+    }
+}
+
+/// How the panes of a split are arranged
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaneLayout {
+    /// Side by side
+    Columns,
+    /// Stacked top to bottom
+    Rows,
+    /// As square a grid as the pane count allows
+    Grid,
+}
+
+impl PaneLayout {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "columns" => Some(PaneLayout::Columns),
+            "rows" => Some(PaneLayout::Rows),
+            "grid" => Some(PaneLayout::Grid),
+            _ => None,
+        }
+    }
+}
+
+/// The set of open tabs and which one is active
+pub struct Tabs {
+    tabs: Vec<Tab>,
+    active: usize,
+    /// Tabs shown in the other panes of a split, never including the active one
+    split: Vec<usize>,
+    layout: PaneLayout,
+    /// Whether the other panes follow the active pane's timestamp
+    sync: bool,
+    /// Settings every new tab starts with: the source groups and the config file's defaults
+    defaults: LogSettings,
+}
+
+impl Tabs {
+    pub fn new(defaults: LogSettings) -> Self {
+        Self {
+            tabs: vec![Tab::new("main", defaults.clone())],
+            defaults,
+            active: 0,
+            split: Vec::new(),
+            layout: PaneLayout::Columns,
+            sync: false,
+        }
+    }
+
+    /// Change the settings new tabs start with
+    pub fn set_defaults(&mut self, defaults: LogSettings) {
+        self.defaults = defaults;
+    }
+
+    pub fn active(&self) -> &Tab {
+        &self.tabs[self.active]
+    }
+
+    pub fn active_mut(&mut self) -> &mut Tab {
+        &mut self.tabs[self.active]
+    }
+
+    pub fn active_index(&self) -> usize {
+        self.active
+    }
+
+    pub fn len(&self) -> usize {
+        self.tabs.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.tabs.is_empty()
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = &mut Tab> {
+        self.tabs.iter_mut()
+    }
+
+    pub fn get(&self, index: usize) -> &Tab {
+        &self.tabs[index]
+    }
+
+    pub fn get_mut(&mut self, index: usize) -> &mut Tab {
+        &mut self.tabs[index]
+    }
+
+    /// The tabs in the other panes of a split
+    pub fn split_indices(&self) -> &[usize] {
+        &self.split
+    }
+
+    pub fn layout(&self) -> PaneLayout {
+        self.layout
+    }
+
+    pub fn is_synced(&self) -> bool {
+        self.sync && !self.split.is_empty()
+    }
+
+    /// Indices of the tabs on screen, in tab order
+    pub fn panes(&self) -> Vec<usize> {
+        let mut panes: Vec<usize> = std::iter::once(self.active).chain(self.split.iter().copied()).collect();
+        panes.sort();
+        panes
+    }
+
+    /// Make a tab active. Switching to a tab in another pane moves focus to that pane
+    fn focus(&mut self, index: usize) {
+        if let Some(pane) = self.split.iter_mut().find(|pane| **pane == index) {
+            *pane = self.active;
+        }
+        self.active = index;
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.tabs.iter().map(|t| t.name.as_str()).collect()
+    }
+
+    pub fn next(&mut self) {
+        self.focus((self.active + 1) % self.tabs.len());
+    }
+
+    pub fn prev(&mut self) {
+        self.focus((self.active + self.tabs.len() - 1) % self.tabs.len());
+    }
+
+    /// Show every source the active tab can see in a pane of its own, reusing
+    /// tabs named after a source and opening new ones for the rest
+    fn layout_sources(&mut self, layout: PaneLayout, log_storage: &LogStorage) -> Result<(), String> {
+        let names: Vec<String> = log_storage.sources()
+            .iter()
+            .map(|source| source.name().to_string())
+            .filter(|name| self.active().settings.is_source_visible(name))
+            .collect();
+        if names.is_empty() {
+            return Err("No visible sources to lay out".to_string());
+        }
+
+        let mut panes = Vec::new();
+        for name in &names {
+            let index = match self.tabs.iter().position(|tab| tab.name == *name) {
+                Some(index) => index,
+                None => {
+                    let mut tab = Tab::new(name.clone(), self.defaults.clone());
+                    tab.settings.set_all_sources_visibility(false);
+                    for other in log_storage.sources() {
+                        tab.settings.get_source_config(other.name()).visible = other.name() == name;
+                    }
+                    tab.update_filter_from_settings();
+                    self.tabs.push(tab);
+                    self.tabs.len() - 1
+                },
+            };
+            panes.push(index);
+        }
+
+        self.active = panes.remove(0);
+        self.split = panes;
+        self.layout = layout;
+        Ok(())
+    }
+
+    /// Apply a tab command, returning an error message if it can't be done
+    pub fn apply(&mut self, command: TabCommand, log_storage: &LogStorage) -> Result<(), String> {
+        match command {
+            TabCommand::New(name) => {
+                let name = name.unwrap_or_else(|| format!("tab{}", self.tabs.len() + 1));
+                self.tabs.push(Tab::new(name, self.defaults.clone()));
+                self.focus(self.tabs.len() - 1);
+            },
+            TabCommand::Next => self.next(),
+            TabCommand::Prev => self.prev(),
+            TabCommand::Select(index) => {
+                // Tab numbers are 1-based for humans
+                if index == 0 || index > self.tabs.len() {
+                    return Err(format!("No tab {}", index));
+                }
+                self.focus(index - 1);
+            },
+            TabCommand::Rename(name) => {
+                self.active_mut().name = name;
+            },
+            TabCommand::Close => {
+                if self.tabs.len() == 1 {
+                    return Err("Cannot close the last tab".to_string());
+                }
+                let closed = self.active;
+                self.tabs.remove(closed);
+                for pane in &mut self.split {
+                    if *pane > closed {
+                        *pane -= 1;
+                    }
+                }
+                // Closing a split pane moves focus to the next pane
+                self.active = if self.split.is_empty() {
+                    closed.min(self.tabs.len() - 1)
+                } else {
+                    self.split.remove(0)
+                };
+            },
+            TabCommand::Split(None) => {
+                self.split.clear();
+            },
+            TabCommand::Split(Some(index)) => {
+                if index == 0 || index > self.tabs.len() {
+                    return Err(format!("No tab {}", index));
+                }
+                if index - 1 == self.active {
+                    return Err(format!("Tab {} is already shown", index));
+                }
+                self.split = vec![index - 1];
+                self.layout = PaneLayout::Columns;
+            },
+            TabCommand::Layout(layout) => self.layout_sources(layout, log_storage)?,
+            TabCommand::Sync => {
+                if self.split.is_empty() {
+                    return Err("Nothing to sync; split first with :split <n> or :layout".to_string());
+                }
+                self.sync = !self.sync;
+            },
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,73 @@
+use std::collections::VecDeque;
+use std::io::{self, Write};
+use std::time::{Duration, Instant};
+
+/// How often the title is rewritten
+const UPDATE_INTERVAL: Duration = Duration::from_secs(2);
+
+/// Window over which the line rate is measured
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+/// Keeps the terminal window title showing the sources and how busy they are,
+/// e.g. `oxtail: 3 sources (2 running) — 1.2k lines/min`
+#[derive(Debug, Default)]
+pub struct TerminalTitle {
+    /// Entries counted at each update within the rate window
+    samples: VecDeque<(Instant, usize)>,
+    /// Entries received since the last update
+    pending: usize,
+    last_update: Option<Instant>,
+}
+
+impl TerminalTitle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Count entries towards the line rate
+    pub fn record(&mut self, entries: usize) {
+        self.pending += entries;
+    }
+
+    /// Rewrite the title if it's due
+    pub fn update(&mut self, out: &mut impl Write, sources: usize, running: usize) -> io::Result<()> {
+        let now = Instant::now();
+        if self.last_update.is_some_and(|last| now.duration_since(last) < UPDATE_INTERVAL) {
+            return Ok(());
+        }
+        self.last_update = Some(now);
+
+        self.samples.push_back((now, std::mem::take(&mut self.pending)));
+        while self.samples.front().is_some_and(|(time, _)| now.duration_since(*time) > RATE_WINDOW) {
+            self.samples.pop_front();
+        }
+        let per_minute: usize = self.samples.iter().map(|(_, count)| count).sum();
+
+        let title = format!("oxtail: {} source{} ({} running) \u{2014} {} lines/min",
+            sources,
+            if sources == 1 { "" } else { "s" },
+            running,
+            human_count(per_minute));
+        write!(out, "\x1b]0;{}\x07", title)?;
+        out.flush()
+    }
+}
+
+/// Save the current title on the terminal's title stack
+pub fn save(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[22;0t")
+}
+
+/// Restore the title saved by `save`
+pub fn restore(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[23;0t")
+}
+
+/// Compact count such as `950`, `1.2k` or `3.4M`
+pub fn human_count(count: usize) -> String {
+    match count {
+        0..=999 => count.to_string(),
+        1_000..=999_999 => format!("{:.1}k", count as f64 / 1_000.0),
+        _ => format!("{:.1}M", count as f64 / 1_000_000.0),
+    }
+}
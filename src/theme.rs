@@ -0,0 +1,333 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use ratatui::style::Color;
+
+/// Names of the themes that ship with oxtail
+pub const BUILTIN_THEMES: &[&str] = &["neon", "solarized-dark", "light"];
+
+/// Colors used across the UI
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    /// Block titles, headings, the active tab and popup borders
+    pub title: Color,
+    pub border: Color,
+    /// Default log and help text
+    pub text: Color,
+    /// Hints, inactive tabs and hidden sources
+    pub muted: Color,
+    /// Stopped sources and the scrollbar track
+    pub dim: Color,
+    /// Key names, visible sources and the completion highlight
+    pub accent: Color,
+    /// Text drawn on top of the accent color
+    pub accent_text: Color,
+    pub stdout: Color,
+    pub stderr: Color,
+    /// Entries with an error (or worse) severity
+    pub error: Color,
+    /// Background of selected entries
+    pub selection: Color,
+    /// Status messages in the prompt line
+    pub status: Color,
+    /// Reverse search in the prompt line
+    pub search: Color,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::neon()
+    }
+}
+
+impl Theme {
+    /// The original magenta-on-black look
+    pub fn neon() -> Self {
+        Self {
+            name: "neon".to_string(),
+            title: Color::Magenta,
+            border: Color::Reset,
+            text: Color::White,
+            muted: Color::Gray,
+            dim: Color::DarkGray,
+            accent: Color::Cyan,
+            accent_text: Color::Black,
+            stdout: Color::Yellow,
+            stderr: Color::Red,
+            error: Color::Red,
+            selection: Color::DarkGray,
+            status: Color::Green,
+            search: Color::Blue,
+        }
+    }
+
+    /// Ethan Schoonover's Solarized palette for dark backgrounds
+    pub fn solarized_dark() -> Self {
+        Self {
+            name: "solarized-dark".to_string(),
+            title: Color::Rgb(0x26, 0x8b, 0xd2),      // blue
+            border: Color::Rgb(0x58, 0x6e, 0x75),     // base01
+            text: Color::Rgb(0x83, 0x94, 0x96),       // base0
+            muted: Color::Rgb(0x65, 0x7b, 0x83),      // base00
+            dim: Color::Rgb(0x58, 0x6e, 0x75),        // base01
+            accent: Color::Rgb(0x2a, 0xa1, 0x98),     // cyan
+            accent_text: Color::Rgb(0x00, 0x2b, 0x36), // base03
+            stdout: Color::Rgb(0xb5, 0x89, 0x00),     // yellow
+            stderr: Color::Rgb(0xcb, 0x4b, 0x16),     // orange
+            error: Color::Rgb(0xdc, 0x32, 0x2f),      // red
+            selection: Color::Rgb(0x07, 0x36, 0x42),  // base02
+            status: Color::Rgb(0x85, 0x99, 0x00),     // green
+            search: Color::Rgb(0x6c, 0x71, 0xc4),     // violet
+        }
+    }
+
+    /// Dark text for terminals with a light background
+    pub fn light() -> Self {
+        Self {
+            name: "light".to_string(),
+            title: Color::Magenta,
+            border: Color::DarkGray,
+            text: Color::Black,
+            muted: Color::DarkGray,
+            dim: Color::Gray,
+            accent: Color::Blue,
+            accent_text: Color::White,
+            stdout: Color::Rgb(0x8a, 0x5a, 0x00),
+            stderr: Color::Red,
+            error: Color::Red,
+            selection: Color::Rgb(0xd0, 0xd0, 0xd0),
+            status: Color::Green,
+            search: Color::Blue,
+        }
+    }
+
+    /// Look up a built-in theme by name
+    pub fn builtin(name: &str) -> Option<Self> {
+        match name {
+            "neon" => Some(Self::neon()),
+            "solarized-dark" => Some(Self::solarized_dark()),
+            "light" => Some(Self::light()),
+            _ => None,
+        }
+    }
+
+    /// Build a user theme from a config table
+    /// The optional `base` key names a built-in theme to start from (neon by default),
+    /// every other key overrides one color, e.g. `stderr = "#ff5555"`
+    pub fn from_table(name: &str, table: &HashMap<String, String>) -> Result<Self, String> {
+        let mut theme = match table.get("base") {
+            Some(base) => Self::builtin(base)
+                .ok_or_else(|| format!("theme {}: unknown base theme '{}'", name, base))?,
+            None => Self::neon(),
+        };
+        theme.name = name.to_string();
+
+        for (key, value) in table {
+            if key == "base" {
+                continue;
+            }
+            let color = Color::from_str(value)
+                .map_err(|_| format!("theme {}: invalid color '{}' for {}", name, value, key))?;
+            let slot = match key.as_str() {
+                "title" => &mut theme.title,
+                "border" => &mut theme.border,
+                "text" => &mut theme.text,
+                "muted" => &mut theme.muted,
+                "dim" => &mut theme.dim,
+                "accent" => &mut theme.accent,
+                "accent_text" => &mut theme.accent_text,
+                "stdout" => &mut theme.stdout,
+                "stderr" => &mut theme.stderr,
+                "error" => &mut theme.error,
+                "selection" => &mut theme.selection,
+                "status" => &mut theme.status,
+                "search" => &mut theme.search,
+                _ => return Err(format!("theme {}: unknown color key '{}'", name, key)),
+            };
+            *slot = color;
+        }
+        Ok(theme)
+    }
+
+    /// Copy of the theme using only colors the terminal can display
+    pub fn adapted(&self, depth: ColorDepth) -> Self {
+        Self {
+            name: self.name.clone(),
+            title: depth.adapt(self.title),
+            border: depth.adapt(self.border),
+            text: depth.adapt(self.text),
+            muted: depth.adapt(self.muted),
+            dim: depth.adapt(self.dim),
+            accent: depth.adapt(self.accent),
+            accent_text: depth.adapt(self.accent_text),
+            stdout: depth.adapt(self.stdout),
+            stderr: depth.adapt(self.stderr),
+            error: depth.adapt(self.error),
+            selection: depth.adapt(self.selection),
+            status: depth.adapt(self.status),
+            search: depth.adapt(self.search),
+        }
+    }
+    /// Color of the entries in the `index`th `--group`, taking turns with a few theme colors
+    pub fn group(&self, index: usize) -> Color {
+        [self.accent, self.status, self.search, self.title][index % 4]
+    }
+
+    /// Copy of the theme with quieter borders, for split panes without focus
+    pub fn unfocused(&self) -> Self {
+        Self { border: self.dim, title: self.muted, ..self.clone() }
+    }
+}
+
+/// Built-in themes plus any defined in the config file
+#[derive(Debug, Clone)]
+pub struct Themes {
+    user: Vec<Theme>,
+    /// Colors are degraded to what the terminal supports when a theme is picked
+    depth: ColorDepth,
+}
+
+impl Themes {
+    /// Parse the user themes from the config's `[themes.<name>]` tables
+    pub fn from_config(tables: &HashMap<String, HashMap<String, String>>, depth: ColorDepth) -> Result<Self, String> {
+        let mut user = tables.iter()
+            .map(|(name, table)| Theme::from_table(name, table))
+            .collect::<Result<Vec<_>, _>>()?;
+        user.sort_by(|a, b| a.name.cmp(&b.name));
+        Ok(Self { user, depth })
+    }
+
+    /// Find a theme by name, preferring user themes so built-ins can be overridden
+    pub fn get(&self, name: &str) -> Option<Theme> {
+        self.user.iter()
+            .find(|theme| theme.name == name)
+            .cloned()
+            .or_else(|| Theme::builtin(name))
+            .map(|theme| theme.adapted(self.depth))
+    }
+
+    /// Names of all available themes
+    pub fn names(&self) -> Vec<String> {
+        let mut names: Vec<String> = BUILTIN_THEMES.iter().map(|s| s.to_string()).collect();
+        for theme in &self.user {
+            if !names.contains(&theme.name) {
+                names.push(theme.name.clone());
+            }
+        }
+        names
+    }
+}
+
+/// How many colors the terminal can display
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ColorDepth {
+    Basic16,
+    Indexed256,
+    TrueColor,
+}
+
+/// RGB values of the 16 basic colors, as xterm draws them
+const BASIC_COLORS: [(Color, (u8, u8, u8)); 16] = [
+    (Color::Black, (0, 0, 0)),
+    (Color::Red, (205, 0, 0)),
+    (Color::Green, (0, 205, 0)),
+    (Color::Yellow, (205, 205, 0)),
+    (Color::Blue, (0, 0, 238)),
+    (Color::Magenta, (205, 0, 205)),
+    (Color::Cyan, (0, 205, 205)),
+    (Color::Gray, (229, 229, 229)),
+    (Color::DarkGray, (127, 127, 127)),
+    (Color::LightRed, (255, 0, 0)),
+    (Color::LightGreen, (0, 255, 0)),
+    (Color::LightYellow, (255, 255, 0)),
+    (Color::LightBlue, (92, 92, 255)),
+    (Color::LightMagenta, (255, 0, 255)),
+    (Color::LightCyan, (0, 255, 255)),
+    (Color::White, (255, 255, 255)),
+];
+
+/// Channel levels of the 6x6x6 color cube in the 256-color palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+impl ColorDepth {
+    /// Guess the color depth from COLORTERM and TERM
+    pub fn detect() -> Self {
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default().to_lowercase();
+        let term = std::env::var("TERM").unwrap_or_default().to_lowercase();
+        if colorterm == "truecolor" || colorterm == "24bit" || term.contains("direct") {
+            ColorDepth::TrueColor
+        } else if term.contains("256color") {
+            ColorDepth::Indexed256
+        } else {
+            ColorDepth::Basic16
+        }
+    }
+
+    /// Parse a depth from the config (`truecolor`, `256` or `16`)
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "truecolor" | "24bit" => Some(ColorDepth::TrueColor),
+            "256" => Some(ColorDepth::Indexed256),
+            "16" => Some(ColorDepth::Basic16),
+            _ => None,
+        }
+    }
+
+    /// Convert a color to the closest one this depth can display
+    pub fn adapt(self, color: Color) -> Color {
+        match (self, color) {
+            (ColorDepth::TrueColor, _) => color,
+            (ColorDepth::Indexed256, Color::Rgb(r, g, b)) => Color::Indexed(nearest_indexed((r, g, b))),
+            (ColorDepth::Basic16, Color::Rgb(r, g, b)) => nearest_basic((r, g, b)),
+            (ColorDepth::Basic16, Color::Indexed(index)) => nearest_basic(indexed_rgb(index)),
+            _ => color,
+        }
+    }
+}
+
+/// RGB value of an entry in the 256-color palette
+fn indexed_rgb(index: u8) -> (u8, u8, u8) {
+    match index {
+        0..=15 => BASIC_COLORS[index as usize].1,
+        16..=231 => {
+            let i = index - 16;
+            (CUBE_LEVELS[(i / 36) as usize], CUBE_LEVELS[(i / 6 % 6) as usize], CUBE_LEVELS[(i % 6) as usize])
+        },
+        _ => {
+            let level = 8 + (index - 232) * 10;
+            (level, level, level)
+        },
+    }
+}
+
+fn distance(a: (u8, u8, u8), b: (u8, u8, u8)) -> u32 {
+    let d = |x: u8, y: u8| (x as i32 - y as i32).pow(2) as u32;
+    d(a.0, b.0) + d(a.1, b.1) + d(a.2, b.2)
+}
+
+/// Closest entry in the color cube or grayscale ramp of the 256-color palette
+fn nearest_indexed(rgb: (u8, u8, u8)) -> u8 {
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0) as u8
+    };
+    let cube = 16 + 36 * level(rgb.0) + 6 * level(rgb.1) + level(rgb.2);
+
+    let average = (rgb.0 as u32 + rgb.1 as u32 + rgb.2 as u32) / 3;
+    let gray = 232 + (average.saturating_sub(3) / 10).min(23) as u8;
+
+    if distance(indexed_rgb(gray), rgb) < distance(indexed_rgb(cube), rgb) {
+        gray
+    } else {
+        cube
+    }
+}
+
+/// Closest of the 16 basic colors
+fn nearest_basic(rgb: (u8, u8, u8)) -> Color {
+    BASIC_COLORS.iter()
+        .min_by_key(|(_, basic)| distance(*basic, rgb))
+        .map(|(color, _)| *color)
+        .unwrap_or(Color::Reset)
+}
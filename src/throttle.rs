@@ -0,0 +1,130 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use crate::log_entry::{LogEntry, Severity};
+
+const WINDOW: Duration = Duration::from_secs(1);
+
+/// Caps on how many entries a second each source may add, as set with `:throttle`, so a
+/// runaway producer can't swamp the UI or fill memory. What goes over a cap is dropped, and
+/// a marker entry in that source says how many were, once its second is up
+#[derive(Default)]
+pub struct Throttle {
+    limits: HashMap<String, Limit>,
+    /// Markers for entries dropped under caps that were changed or lifted since
+    pending: Vec<LogEntry>,
+}
+
+#[derive(Debug)]
+struct Limit {
+    per_second: usize,
+    /// When the current second started
+    started: Instant,
+    /// Entries added this second
+    taken: usize,
+    /// Entries dropped since the last marker
+    dropped: usize,
+    /// The timestamp and sequence number of the last entry dropped, which its marker takes so
+    /// it sorts where the entries it stands for would have
+    last_dropped: (i64, u64),
+    /// Entries dropped since the cap was set
+    total_dropped: usize,
+}
+
+impl Throttle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cap a source at `per_second` entries, or lift its cap with None. Entries dropped under
+    /// the cap before still get their marker
+    pub fn set(&mut self, source: &str, per_second: Option<usize>) {
+        self.pending.extend(self.limits.get_mut(source).and_then(|limit| limit.marker(source)));
+        match per_second {
+            Some(per_second) => {
+                let limit = self.limits.entry(source.to_string()).or_insert_with(|| Limit {
+                    per_second,
+                    started: Instant::now(),
+                    taken: 0,
+                    dropped: 0,
+                    last_dropped: (0, 0),
+                    total_dropped: 0,
+                });
+                limit.per_second = per_second;
+            },
+            None => {
+                self.limits.remove(source);
+            },
+        }
+    }
+
+    /// The entries of a batch that are under their sources' caps, with a marker ahead of the
+    /// first entry of a source after some of its entries were dropped
+    pub fn filter(&mut self, batch: Vec<LogEntry>) -> Vec<LogEntry> {
+        if self.limits.is_empty() {
+            return batch;
+        }
+        let now = Instant::now();
+        let mut admitted = Vec::with_capacity(batch.len());
+        for mut entry in batch {
+            let Some(limit) = self.limits.get_mut(&*entry.source) else {
+                admitted.push(entry);
+                continue;
+            };
+            if now.duration_since(limit.started) >= WINDOW {
+                limit.started = now;
+                limit.taken = 0;
+                if let Some(marker) = limit.marker(&entry.source) {
+                    admitted.push(marker);
+                    // Redrawing the line before would overwrite the marker
+                    entry.redraw = false;
+                }
+            }
+            if limit.taken < limit.per_second {
+                limit.taken += 1;
+                admitted.push(entry);
+            } else {
+                limit.dropped += 1;
+                limit.last_dropped = (entry.timestamp_millis(), entry.sequence());
+                limit.total_dropped += 1;
+            }
+        }
+        admitted
+    }
+
+    /// Markers for sources whose entries were dropped in a second that's now over, when
+    /// nothing has come from them since, or whose cap was changed
+    pub fn markers(&mut self) -> Vec<LogEntry> {
+        let mut markers = std::mem::take(&mut self.pending);
+        markers.extend(self.limits.iter_mut()
+            .filter(|(_, limit)| limit.started.elapsed() >= WINDOW)
+            .filter_map(|(source, limit)| limit.marker(source)));
+        markers
+    }
+
+    /// The cap on a source and how many of its entries it has dropped, if it has one
+    pub fn limit(&self, source: &str) -> Option<(usize, usize)> {
+        self.limits.get(source).map(|limit| (limit.per_second, limit.total_dropped))
+    }
+}
+
+impl Limit {
+    /// An entry saying how many entries were dropped since the last one, if any were
+    fn marker(&mut self, source: &str) -> Option<LogEntry> {
+        if self.dropped == 0 {
+            return None;
+        }
+        let dropped = std::mem::take(&mut self.dropped);
+        let lines = if dropped == 1 { "line" } else { "lines" };
+        let mut entry = LogEntry::new(source, format!("[dropped {} {} over the {}/s throttle]", dropped, lines, self.per_second));
+        entry.severity = Some(Severity::Warning);
+        entry.set_timestamp_millis(self.last_dropped.0);
+        entry.set_sequence(self.last_dropped.1);
+        Some(entry)
+    }
+}
+
+/// A rate as given to `:throttle`: a number of lines a second, such as `1000/s`
+pub fn parse_rate(value: &str) -> Option<usize> {
+    let count = value.strip_suffix("/s").unwrap_or(value);
+    count.parse().ok().filter(|count| *count > 0)
+}
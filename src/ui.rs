@@ -1,28 +1,42 @@
-use std::io::{self, Write};
+use std::collections::VecDeque;
+use std::io::{self, Stdout, Write};
+use std::path::{Path, PathBuf};
 use std::sync::mpsc::Receiver;
 use std::time::Duration;
 use std::thread;
 use anyhow::Result;
+use chrono::{Local, TimeZone};
 use crossbeam_channel::{unbounded, Receiver as CrossbeamReceiver};
 use termion::{
     input::TermRead,
-    raw::IntoRawMode,
-    event::{Event, Key, MouseEvent, MouseButton},
+    raw::{IntoRawMode, RawTerminal},
+    event::{Event, MouseEvent, MouseButton},
     cursor,
-    clear,
-    screen::ToAlternateScreen,
-    screen::ToMainScreen,
 };
 use ratatui::{
     backend::TermionBackend,
-    layout::{Constraint, Direction, Layout},
+    layout::{Constraint, Direction, Layout, Rect},
     Terminal,
 };
-use crate::log_entry::LogEntry;
-use crate::log_storage::LogStorage;
-use crate::settings::LogSettings;
-use crate::commands::{self, CommandResult};
-use crate::widgets::{CommandPrompt, CommandInputResult, LogViewer, LogViewerExt};
+use crate::batch;
+use crate::file_watcher::FileWatcher;
+use crate::process_handler::{self, format_elapsed, Launcher, ProcessHandle};
+use crate::log_entry::{LogEntry, MARK_SOURCE};
+use crate::log_storage::{LogStorage, Retention, ViewEntries};
+use crate::tabs::{PaneLayout, Tab, Tabs};
+use crate::settings::{format_size, LogSettings};
+use crate::silence::SilenceWatch;
+use crate::source_status::{SourceState, SourceStatusRegistry};
+use crate::terminal_title::{self, human_count, TerminalTitle};
+use crate::keymap::{Action, KeyMap};
+use crate::clipboard::{self, ClipboardMethod};
+use crate::config::Config;
+use crate::commands::{self, AliasCommand, CommandResult, LineJump, Report, SelectionCommand, StorageCommand};
+use crate::control::{ControlAction, ControlRequest};
+use crate::export;
+use crate::theme::{Theme, Themes};
+use crate::throttle::Throttle;
+use crate::widgets::{AlertBar, CommandPrompt, CommandPromptExt, CommandInputResult, HelpOverlay, HelpOverlayExt, OutputPane, OutputPaneExt, LogViewerExt, delta_column_width, SourceRow, SourceSidebar, SourceSidebarExt, TabBar, TextSelection};
 
 // Helper function to spawn an input handling thread
 fn spawn_input_handler() -> CrossbeamReceiver<Event> {
@@ -32,12 +46,10 @@ fn spawn_input_handler() -> CrossbeamReceiver<Event> {
         let tty = termion::get_tty().expect("Failed to get TTY");
         let events = tty.events();
         
-        for event in events {
-            if let Ok(evt) = event {
-                if tx.send(evt).is_err() {
-                    // Channel closed, receiver dropped, exit thread
-                    break;
-                }
+        for evt in events.flatten() {
+            if tx.send(evt).is_err() {
+                // Channel closed, receiver dropped, exit thread
+                break;
             }
         }
     });
@@ -45,17 +57,362 @@ fn spawn_input_handler() -> CrossbeamReceiver<Event> {
     rx
 }
 
-pub fn run_ui(rx: Receiver<LogEntry>) -> Result<()> {
-    // Set up terminal I/O - direct approach without stacking wrappers
-    let mut stdout = io::stdout().into_raw_mode()?;
-    
-    // Setup terminal features by writing escape sequences directly
-    // Use raw escape sequences for mouse capture since termion v2.0 might not export them directly
-    write!(stdout, "{}{}[?1000h[?1002h[?1015h[?1006h",
+/// Build the sidebar rows from storage, the active tab's visibility settings and source status.
+/// Sources still waiting for their file or connection come last
+fn source_rows(log_storage: &LogStorage, tabs: &Tabs, status: &SourceStatusRegistry) -> Vec<SourceRow> {
+    let tab = tabs.active();
+    let mut rows: Vec<SourceRow> = log_storage.sources()
+        .into_iter()
+        .map(|source| SourceRow {
+            name: source.name().to_string(),
+            visible: tab.settings.is_source_visible(source.name()),
+            entries: source.len(),
+            unread: tab.unread(source.name()),
+            state: status.get(source.name()),
+        })
+        .collect();
+    for (name, state) in status.waiting() {
+        if log_storage.get_source(&name).is_none() {
+            rows.push(SourceRow {
+                visible: tab.settings.is_source_visible(&name),
+                entries: 0,
+                unread: 0,
+                state: Some(state),
+                name,
+            });
+        }
+    }
+    rows
+}
+
+/// Entries per page in the active tab's view, which is fewer than the rows when entries wrap
+fn page_size(log_storage: &LogStorage, tab: &Tab, log_area: Rect) -> usize {
+    if !tab.settings.wrap {
+        return log_area.height as usize;
+    }
+    tab.viewer.page_entries(&tab.entries(log_storage), &tab.settings, log_area)
+}
+
+/// Split `area` into `count` panes arranged by `layout`
+fn pane_areas(area: Rect, count: usize, layout: PaneLayout) -> Vec<Rect> {
+    let split = |area: Rect, direction: Direction, count: usize| -> Vec<Rect> {
+        Layout::default()
+            .direction(direction)
+            .constraints(vec![Constraint::Ratio(1, count as u32); count])
+            .split(area)
+            .to_vec()
+    };
+    match layout {
+        PaneLayout::Columns => split(area, Direction::Horizontal, count),
+        PaneLayout::Rows => split(area, Direction::Vertical, count),
+        PaneLayout::Grid => {
+            let columns = (1..=count).find(|c| c * c >= count).unwrap_or(1);
+            let rows = count.div_ceil(columns);
+            // The last row spreads whatever panes are left across the full width
+            split(area, Direction::Vertical, rows)
+                .into_iter()
+                .enumerate()
+                .flat_map(|(row, row_area)| {
+                    split(row_area, Direction::Horizontal, columns.min(count - row * columns))
+                })
+                .collect()
+        },
+    }
+}
+
+/// Title and lines of a report for the output pane
+fn report_lines(report: Report, log_storage: &LogStorage, tabs: &Tabs, status: &SourceStatusRegistry, throttle: &Throttle, silence: &SilenceWatch) -> (String, Vec<String>) {
+    let tab = tabs.active();
+    match report {
+        Report::Sources => {
+            let mut lines: Vec<String> = log_storage.sources()
+                .iter()
+                .map(|source| {
+                    let state = match status.get(source.name()) {
+                        Some(SourceState::Running) => "running",
+                        Some(SourceState::Stopped) => "stopped",
+                        Some(SourceState::Waiting) => "waiting",
+                        Some(SourceState::Connecting) => "connecting",
+                        Some(SourceState::Reconnecting) => "reconnecting",
+                        None => "-",
+                    };
+                    let visibility = if tab.settings.is_source_visible(source.name()) { "shown" } else { "hidden" };
+                    let last = source.last_timestamp_millis()
+                        .and_then(|millis| Local.timestamp_millis_opt(millis).single())
+                        .map_or_else(|| "-".to_string(), |last| last.format("%H:%M:%S").to_string());
+                    let mut line = format!("{:<24} {:<12} {:>9} entries  {:<6}  {:>6}/min  last {:<8}  {} unread",
+                        source.name(), state, source.len(), visibility, human_count(source.per_minute()), last,
+                        tab.unread(source.name()));
+                    if source.evicted() > 0 {
+                        line.push_str(&format!("  {} dropped", source.evicted()));
+                    }
+                    if let Some((_, group)) = tab.settings.groups.group_of(source.name()) {
+                        line.push_str(&format!("  group {}", group));
+                    }
+                    if let Some((per_second, dropped)) = throttle.limit(source.name()) {
+                        line.push_str(&format!("  throttled {}/s, {} dropped", per_second, dropped));
+                    }
+                    if let Some(within) = silence.within(source.name()) {
+                        line.push_str(&format!("  expected every {}", format_elapsed(within)));
+                    }
+                    line
+                })
+                .collect();
+            for (name, state) in status.waiting() {
+                if log_storage.get_source(&name).is_none() {
+                    let (state, note) = match state {
+                        SourceState::Connecting => ("connecting", "(not connected yet)"),
+                        SourceState::Reconnecting => ("reconnecting", "(connection lost)"),
+                        _ => ("waiting", "(file not created yet)"),
+                    };
+                    lines.push(format!("{:<24} {:<12} {:>9} entries  {}", name, state, 0, note));
+                }
+            }
+            ("Sources".to_string(), lines)
+        },
+        Report::Filters => {
+            let settings = &tab.settings;
+            let hidden: Vec<String> = log_storage.sources()
+                .iter()
+                .map(|source| source.name().to_string())
+                .filter(|name| !settings.is_source_visible(name))
+                .collect();
+            let hidden_groups: Vec<String> = settings.groups.names()
+                .filter(|group| settings.sources.get(&group.to_lowercase()).is_some_and(|config| !config.visible))
+                .map(|group| group.to_string())
+                .collect();
+            let conditions: Vec<String> = settings.field_conditions.iter().map(|c| c.to_string()).collect();
+            let alerts: Vec<&str> = settings.alert_patterns.iter().map(|p| p.as_str()).collect();
+            let or_none = |values: Vec<String>| if values.is_empty() { "none".to_string() } else { values.join(", ") };
+
+            let mut lines = vec![
+                format!("Hidden sources: {}", or_none(hidden)),
+                format!("Where: {}", or_none(conditions)),
+                format!("Filter in: {}", settings.filter_in.as_ref().map_or("none", |pattern| pattern.as_str())),
+                format!("Filter out: {}", settings.filter_out.as_ref().map_or("none", |pattern| pattern.as_str())),
+                format!("Level: {}", settings.min_severity.map_or("all".to_string(), |severity| format!("{} and above", severity.label()))),
+            ];
+            if !settings.groups.is_empty() {
+                lines.insert(1, format!("Hidden groups: {}", or_none(hidden_groups)));
+            }
+            lines.push(match settings.time_range {
+                Some((from, to)) => format!("Narrowed to: {} - {} (about {} entries before other filters)",
+                    from.format("%H:%M:%S%.3f"),
+                    to.format("%H:%M:%S%.3f"),
+                    log_storage.count_between(from, to)),
+                None => "Narrowed to: none".to_string(),
+            });
+            lines.push(format!("Alerts: {}", or_none(alerts.iter().map(|a| a.to_string()).collect())));
+            lines.push(format!("Highlights: {}", or_none(settings.highlights.iter().map(|h| h.to_string()).collect())));
+            let spilled = match log_storage.spilled() {
+                0 => String::new(),
+                spilled => format!(" ({} spilled to disk)", spilled),
+            };
+            lines.push(format!("Showing {} of {} entries{}",
+                tab.entries(log_storage).len(),
+                log_storage.total_entries(),
+                spilled));
+            (format!("Filters - {}", tab.name), lines)
+        },
+    }
+}
+
+/// Names of the sources in the order `Report::Sources` lists them: those with entries, then
+/// those still waiting to start
+fn listed_sources(log_storage: &LogStorage, status: &SourceStatusRegistry) -> Vec<String> {
+    log_storage.sources().iter()
+        .map(|source| source.name().to_string())
+        .chain(status.waiting().into_iter()
+            .map(|(name, _)| name)
+            .filter(|name| log_storage.get_source(name).is_none()))
+        .collect()
+}
+
+/// Read back any selected entries that were spilled to disk, so `selected_entries` has them
+fn page_in_selection(log_storage: &mut LogStorage, tab: &Tab) {
+    let total = tab.entries(log_storage).len();
+    if let Some((first, last)) = tab.viewer.selected_range(total) {
+        log_storage.page_in(&tab.view, first..last + 1);
+    }
+}
+
+/// Entries covered by the tab's visual selection or line cursor
+fn selected_entries<'a>(log_storage: &'a LogStorage, tab: &'a Tab) -> Vec<&'a LogEntry> {
+    let entries = tab.entries(log_storage);
+    match tab.viewer.selected_range(entries.len()) {
+        Some((first, last)) => entries.range(first..last + 1).collect(),
+        None => Vec::new(),
+    }
+}
+
+/// Write every entry in the active tab, once its view has caught up with all of them,
+/// returning how many there were
+fn write_all(path: &Path, log_storage: &mut LogStorage, tabs: &mut Tabs) -> Result<usize> {
+    let tab = tabs.active_mut();
+    tab.refresh_view(log_storage);
+    while tab.view.progress().is_some() {
+        tab.refresh_view(log_storage);
+    }
+    let total = tab.entries(log_storage).len();
+    log_storage.page_in(&tab.view, 0..total);
+    let entries: Vec<&LogEntry> = tab.entries(log_storage).range(0..total).collect();
+    export::write_entries(&path.to_string_lossy(), &entries, &tab.settings)?;
+    Ok(total)
+}
+
+/// Run a selection command against the active tab, returning a status message
+fn run_selection_command(command: SelectionCommand, log_storage: &LogStorage, tab: &mut Tab) -> String {
+    let entries = selected_entries(log_storage, tab);
+    if entries.is_empty() {
+        return "Error: No selection (press v or V)".to_string();
+    }
+
+    match command {
+        SelectionCommand::Write(path) => match export::write_entries(&path, &entries, &tab.settings) {
+            Ok(()) => format!("Wrote {} entries to {}", entries.len(), path),
+            Err(err) => format!("Error: {:#}", err),
+        },
+        SelectionCommand::Pipe(cmd) => match export::pipe_entries(&cmd, &entries, &tab.settings) {
+            Ok(output) if output.is_empty() => format!("Piped {} entries to {}", entries.len(), cmd),
+            Ok(output) => format!("Piped {} entries: {}", entries.len(), output),
+            Err(err) => format!("Error: {:#}", err),
+        },
+        SelectionCommand::Narrow => {
+            let first = entries.iter().map(|e| e.timestamp()).min();
+            let last = entries.iter().map(|e| e.timestamp()).max();
+            if let (Some(first), Some(last)) = (first, last) {
+                tab.settings.time_range = Some((first, last));
+                tab.update_filter_from_settings();
+                tab.viewer.clear_cursor();
+                tab.viewer.jump_to_end();
+            }
+            format!("Showing {} to {}", first.map(|t| t.format("%H:%M:%S%.3f").to_string()).unwrap_or_default(),
+                last.map(|t| t.format("%H:%M:%S%.3f").to_string()).unwrap_or_default())
+        },
+    }
+}
+
+/// Copy text to the clipboard and describe the outcome for the status line
+fn copy_with_status(text: &str, terminal: &mut impl Write) -> String {
+    let chars = text.chars().count();
+    match clipboard::copy(text, terminal) {
+        Ok(ClipboardMethod::Native(tool)) => format!("Copied {} characters ({})", chars, tool),
+        Ok(ClipboardMethod::Osc52) => format!("Copied {} characters (OSC52)", chars),
+        Err(err) => format!("Error: copy failed: {}", err),
+    }
+}
+
+/// Switch to the alternate screen with mouse capture and a hidden cursor
+fn enter_tui(out: &mut impl Write) -> io::Result<()> {
+    // The window title is ours until leave_tui puts the old one back
+    terminal_title::save(out)?;
+    // Mouse capture: button events, drag events, and the SGR/urxvt extended encodings
+    write!(out, "{}{}\x1b[?1000h\x1b[?1002h\x1b[?1015h\x1b[?1006h",
         termion::screen::ToAlternateScreen,
         cursor::Hide
     )?;
-    stdout.flush()?;
+    out.flush()
+}
+
+/// Undo `enter_tui`, returning to the main screen
+fn leave_tui(out: &mut impl Write) -> io::Result<()> {
+    write!(out, "\x1b[?1006l\x1b[?1015l\x1b[?1002l\x1b[?1000l{}{}",
+        termion::screen::ToMainScreen,
+        cursor::Show
+    )?;
+    terminal_title::restore(out)?;
+    out.flush()
+}
+
+/// Hand the terminal back to the shell and stop the process until it is resumed
+fn suspend(terminal: &mut Terminal<TermionBackend<Stdout>>, raw_mode: &RawTerminal<Stdout>) -> Result<()> {
+    leave_tui(terminal.backend_mut())?;
+    raw_mode.suspend_raw_mode()?;
+
+    // Raw mode disables the terminal's own Ctrl-Z handling, so stop ourselves.
+    // Execution continues here once the shell sends SIGCONT (e.g. `fg`)
+    unsafe {
+        libc::raise(libc::SIGTSTP);
+    }
+
+    raw_mode.activate_raw_mode()?;
+    enter_tui(terminal.backend_mut())?;
+    // The screen contents are gone, so force a full redraw
+    terminal.clear()?;
+    Ok(())
+}
+
+/// Frames drawn per second until `:set fps` changes it
+const DEFAULT_FPS: u32 = 30;
+
+/// Most aliases one command line may expand to, counting those within aliases, before it's
+/// taken to be a loop
+const MAX_ALIAS_EXPANSIONS: usize = 100;
+
+/// Longest an unchanged screen goes without being redrawn
+const IDLE_REDRAW: Duration = Duration::from_secs(1);
+
+/// Set the throttles and expected output of the sources the config file has settings for
+fn apply_source_limits(config: &Config, throttle: &mut Throttle, silence: &mut SilenceWatch) -> Result<(), String> {
+    for limits in config.source_limits()? {
+        if limits.throttle.is_some() {
+            throttle.set(&limits.source, limits.throttle);
+        }
+        if limits.expect.is_some() {
+            silence.expect(&limits.source, limits.expect, limits.alert);
+        }
+    }
+    Ok(())
+}
+
+/// Time between frames at a frame rate
+fn frame_time(fps: u32) -> Duration {
+    Duration::from_secs(1) / fps.max(1)
+}
+
+/// The sources that can be acted on from the UI, and what's done with them on quitting
+pub struct Inputs {
+    pub files: FileWatcher,
+    /// The command given after `--`, if any
+    pub process: Option<ProcessHandle>,
+    /// Runs the commands added with `:exec`
+    pub launcher: Launcher,
+    /// Where to write the active tab's entries once the command has stopped
+    pub write_on_exit: Option<PathBuf>,
+    /// With --exit-on-eof, the piped inputs to quit after, once they've all closed and
+    /// everything they sent is in
+    pub exit_on_eof: Vec<String>,
+    /// The config file's settings, and the file given with --config, for `:config reload`
+    pub config: Config,
+    pub config_path: Option<PathBuf>,
+    /// Settings given as flags, which take precedence over the file's
+    pub overrides: Config,
+    /// The limits on entries given on the command line, which the config file's don't override
+    pub retention: Retention,
+    /// With --no-follow, the first tab starts paused
+    pub paused: bool,
+    /// Commands from --cmds and -c, to run once the screen is up
+    pub commands: Vec<String>,
+    /// Requests from other programs through the --control socket
+    pub control: Option<Receiver<ControlRequest>>,
+    /// Where the command history is kept between sessions, if anywhere
+    pub history: Option<PathBuf>,
+}
+
+pub fn run_ui(
+    rx: Receiver<Vec<LogEntry>>,
+    status: SourceStatusRegistry,
+    mut log_storage: LogStorage,
+    mut inputs: Inputs,
+    mut themes: Themes,
+    mut theme: Theme,
+    mut keymap: KeyMap,
+) -> Result<()> {
+
+    // Set up terminal I/O - raw mode is held separately so it can be suspended on Ctrl-Z
+    let raw_mode = io::stdout().into_raw_mode()?;
+    let mut stdout = io::stdout();
+    enter_tui(&mut stdout)?;
     
     // Prepare backend and terminal
     let backend = TermionBackend::new(stdout);
@@ -64,70 +421,251 @@ pub fn run_ui(rx: Receiver<LogEntry>) -> Result<()> {
     // Create a non-blocking event handler
     let events = spawn_input_handler();
     
-    // Log storage - manages all log entries and filtering
-    let mut log_storage = LogStorage::new();
-    let mut previous_filtered_count = 0;
     let mut command_prompt = CommandPrompt::new();
-    let mut log_viewer = LogViewer::new();
-    let mut settings = LogSettings::default();
-    
-    // Initialize log storage filter from settings
-    log_storage.update_filter_from_settings(&settings);
+    if let Some(path) = &inputs.history {
+        command_prompt.load_history(path);
+    }
+    // Each tab has its own settings, filter and scroll state over the shared storage, starting
+    // from the config file's
+    let mut defaults = LogSettings { groups: log_storage.groups().clone(), ..LogSettings::default() };
+    inputs.config.apply(&mut defaults).map_err(anyhow::Error::msg)?;
+    let mut tabs = Tabs::new(defaults);
+    tabs.active_mut().viewer.set_paused(inputs.paused);
+    let mut source_sidebar = SourceSidebar::new();
+    let mut help_overlay = HelpOverlay::new();
+    let mut output_pane = OutputPane::new();
+    // The report in the output pane, redone as it's drawn so it stays current
+    let mut live_report: Option<Report> = None;
+    // Unfocused split panes get quieter borders
+    let mut unfocused_theme = theme.unfocused();
+    // Area of the output pane from the last draw
+    let mut output_area = Rect::default();
+    let mut terminal_title = TerminalTitle::new();
+
+    // Mouse selection over the log area (mouse capture disables the terminal's own)
+    let mut selection: Option<TextSelection> = None;
+    let mut selecting = false;
+    let mut selected_text = String::new();
+    // Whether the scrollbar thumb is being dragged
+    let mut dragging_scrollbar = false;
+    // Inner (borderless) area of the log viewer from the last draw
+    let mut log_area = Rect::default();
     
-    // Track time for UI refresh
+    // Last key or mouse event, for `:set autoresume`
+    let mut last_input = std::time::Instant::now();
+
+    // Frames are drawn when something changed, at most `:set fps` times a second, however
+    // fast entries come in; an idle screen is still redrawn now and then for source states
     let mut last_refresh = std::time::Instant::now();
-    let refresh_rate = std::time::Duration::from_millis(100); // 10fps refresh rate
+    let mut frame_interval = frame_time(inputs.config.fps.unwrap_or(DEFAULT_FPS));
+    // Caps on the entries a second from sources set with `:throttle`
+    let mut throttle = Throttle::new();
+    // Sources expected to produce output every so often, set with `:expect`
+    let mut silence = SilenceWatch::new();
+    apply_source_limits(&inputs.config, &mut throttle, &mut silence).map_err(anyhow::Error::msg)?;
+    // Names for commands, from the config file and `:alias`
+    let mut aliases = inputs.config.aliases().map_err(anyhow::Error::msg)?;
+    // Commands waiting to run, each with the line to add to the history once it has
+    // Those given on the command line run first, without going in the history
+    // Dividers added with `:mark` or `m` without text of their own are numbered
+    let mut marks = 0;
+    let mut queued: VecDeque<(String, Option<String>)> = inputs.commands.drain(..).map(|command| (command, None)).collect();
+    let mut dirty = true;
 
     // Main application loop
     let result: Result<()> = (|| {
         loop {
+            // Piped inputs are marked stopped after their last entries are sent, so once
+            // they're taken in below there's nothing more to wait for
+            let inputs_done = !inputs.exit_on_eof.is_empty()
+                && inputs.exit_on_eof.iter().all(|source| status.get(source) == Some(SourceState::Stopped));
+
             // Process log entries
             let mut had_new_entries = false;
-            while let Ok(entry) = rx.try_recv() {
-                log_storage.add_entry(entry);
+            log_storage.release_pages();
+            let panes = tabs.panes();
+            // Take at most a channel's worth of batches, so a flood of input still leaves
+            // time to draw and handle keys; readers wait while the channel is full. Throttled
+            // sources' markers for what they dropped come first
+            let markers = throttle.markers();
+            let batches = rx.try_iter().take(batch::MAX_PENDING_BATCHES).map(|batch| throttle.filter(batch));
+            for batch in std::iter::once(markers).chain(batches).filter(|batch| !batch.is_empty()) {
+                terminal_title.record(batch.len());
+                for entry in batch {
+                    silence.saw(&entry.source);
+                    // Lines loaded with :load_more go before the source's first entry
+                    let entry = if entry.earlier {
+                        match log_storage.add_earlier(entry) {
+                            Some(entry) => entry,
+                            None => continue,
+                        }
+                    } else {
+                        log_storage.add_entry(entry)
+                    };
+                    for (index, tab) in tabs.iter_mut().enumerate() {
+                        tab.note_new_entry(entry, panes.contains(&index));
+                    }
+                }
                 had_new_entries = true;
             }
-            
+            for (marker, alert) in silence.silent() {
+                let entry = log_storage.add_entry(marker);
+                for (index, tab) in tabs.iter_mut().enumerate() {
+                    tab.note_new_entry(entry, panes.contains(&index));
+                    if alert {
+                        tab.raise_alert(entry);
+                    }
+                }
+                had_new_entries = true;
+            }
+            if let Some(message) = log_storage.take_warning() {
+                command_prompt.set_status(Some(message));
+            }
+            // Readers announce things like a new file turning up in a watched directory
+            let notices = status.take_notices();
+            if !notices.is_empty() {
+                command_prompt.set_status(Some(notices.join("; ")));
+                dirty = true;
+            }
+
             // Scope for handling log storage operations
             {
-                let filtered_logs = log_storage.get_filtered_entries();
-                let current_count = filtered_logs.len();
-                let new_entries_count = current_count.saturating_sub(previous_filtered_count);
-                
-                // Update previous count early since we have the current count
-                if had_new_entries {
-                    previous_filtered_count = current_count;
+                // Views catch up with the entries that arrived since the last frame.
+                // Background tabs catch up the next time they are shown
+                for index in tabs.panes() {
+                    tabs.get_mut(index).refresh_view(&log_storage);
                 }
 
-                let has_visible_entries = if had_new_entries && !log_viewer.is_paused() && new_entries_count > 0 {
-                    log_storage.has_new_visible_entries()
-                } else {
-                    false
-                };
+                // Keep the view on the same entries through new arrivals and filter changes
+                let tab = tabs.active_mut();
+                tab.viewer.follow_anchors(&log_storage.view_entries(&tab.view));
+                tab.mark_seen();
+                let dropped = log_storage.evicted();
+                for tab in tabs.iter_mut() {
+                    tab.viewer.set_dropped(dropped);
+                }
+                tabs.active_mut().refresh_alert(&log_storage);
 
-                // Now that we're done with filtered_logs, we can perform mutable operations
-                if had_new_entries {
-                    log_viewer.adjust_for_new_entries(new_entries_count);
+                // Other panes keep their own view, or track the active pane's timestamp when synced
+                let following = !tabs.active().viewer.is_paused();
+                let timestamp = tabs.active().viewer.bottom_timestamp();
+                let synced = tabs.is_synced();
+                for index in tabs.split_indices().to_vec() {
+                    let split = tabs.get_mut(index);
+                    let entries = log_storage.view_entries(&split.view);
+                    if synced && following {
+                        split.viewer.jump_to_end();
+                    } else if let (true, Some(timestamp)) = (synced, timestamp) {
+                        split.viewer.scroll_to_timestamp(&entries, timestamp);
+                    }
+                    split.viewer.follow_anchors(&entries);
+                    split.mark_seen();
                 }
+                // Entries that were spilled to disk are read back for the windows about to be drawn
+                let height = terminal.size()?.height as usize;
+                for index in tabs.panes() {
+                    let pane = tabs.get(index);
+                    let window = pane.viewer.window(pane.entries(&log_storage).len(), height);
+                    log_storage.page_in(&pane.view, window);
+                }
+                let filtered_logs = tabs.active().entries(&log_storage);
+                let split_logs: Vec<(usize, ViewEntries)> = tabs.split_indices()
+                    .iter()
+                    .map(|&index| (index, tabs.get(index).entries(&log_storage)))
+                    .collect();
+                let tab = tabs.active();
 
-                // Check if it's time to refresh the UI (either due to new entries or timer)
+                // Check if it's time to refresh the UI (something changed, or it's been idle a while)
                 let now = std::time::Instant::now();
-                if had_new_entries || now.duration_since(last_refresh) >= refresh_rate {
+                let since_refresh = now.duration_since(last_refresh);
+                dirty |= had_new_entries;
+                if (dirty && since_refresh >= frame_interval) || since_refresh >= IDLE_REDRAW {
+                    // An open report keeps up with what it reports on
+                    if let Some(report) = live_report.filter(|_| output_pane.is_open()) {
+                        output_pane.set_lines(report_lines(report, &log_storage, &tabs, &status, &throttle, &silence).1);
+                    }
                     // Draw UI
                     terminal.draw(|f| {
+                        // The tab bar only appears once there's more than one tab
+                        let show_tab_bar = tabs.len() > 1;
+                        // The alert headline appears once an `:alert` pattern has matched
+                        let alert = tab.alert();
+                        let header_rows = show_tab_bar as u16 + alert.is_some() as u16;
                         let chunks = Layout::default()
                             .direction(Direction::Vertical)
-                            .constraints([
-                                Constraint::Min(1),
-                                Constraint::Length(1),
-                            ])
+                            .constraints([Constraint::Length(header_rows), Constraint::Min(1), Constraint::Length(1)])
                             .split(f.size());
+                        let (mut viewer_area, prompt_area) = (chunks[1], chunks[2]);
+                        let mut header_row = Rect { height: 1, ..chunks[0] };
+                        if let Some(alert) = alert {
+                            f.render_widget(AlertBar::new(&alert.text, &theme), header_row);
+                            header_row.y += 1;
+                        }
+                        if show_tab_bar {
+                            f.render_widget(TabBar::new(tabs.names(), tabs.active_index(), &theme), header_row);
+                        }
+
+                        // Command output takes the bottom of the log area until dismissed
+                        if output_pane.is_open() {
+                            let rows = Layout::default()
+                                .direction(Direction::Vertical)
+                                .constraints([Constraint::Min(1), Constraint::Length(output_pane.height(viewer_area.height))])
+                                .split(viewer_area);
+                            viewer_area = rows[0];
+                            output_area = rows[1];
+                            f.render_output_pane(&output_pane, output_area, &theme);
+                        }
+
+                        // The source sidebar takes a fixed column on the left
+                        if source_sidebar.is_open() {
+                            let columns = Layout::default()
+                                .direction(Direction::Horizontal)
+                                .constraints([Constraint::Length(32), Constraint::Min(1)])
+                                .split(viewer_area);
+                            let rows = source_rows(&log_storage, &tabs, &status);
+                            f.render_source_sidebar(&source_sidebar, columns[0], &rows, &theme);
+                            viewer_area = columns[1];
+                        }
         
-                        f.render_log_viewer(log_viewer.clone(), chunks[0], &filtered_logs, &settings);
-                        f.render_widget(command_prompt.clone(), chunks[1]);
+                        // Split panes are laid out in tab order, titled with their tab
+                        let panes = tabs.panes();
+                        let pane_areas = pane_areas(viewer_area, panes.len(), tabs.layout());
+                        for (&index, &pane_area) in panes.iter().zip(pane_areas.iter()) {
+                            let pane = tabs.get(index);
+                            let title = (panes.len() > 1).then(|| format!("{}:{}", index + 1, pane.name));
+                            if index == tabs.active_index() {
+                                f.render_log_viewer(&pane.viewer, title.as_deref(), pane_area, &filtered_logs, &pane.settings, &theme);
+                                viewer_area = pane_area;
+                            } else if let Some((_, logs)) = split_logs.iter().find(|(i, _)| *i == index) {
+                                f.render_log_viewer(&pane.viewer, title.as_deref(), pane_area, logs, &pane.settings, &unfocused_theme);
+                            }
+                        }
+
+                        // Highlight the mouse selection and remember its text for copying
+                        log_area = Rect {
+                            x: viewer_area.x + 1,
+                            y: viewer_area.y + 1,
+                            width: viewer_area.width.saturating_sub(2),
+                            height: viewer_area.height.saturating_sub(2),
+                        };
+                        if let Some(selection) = &selection {
+                            selected_text = selection.extract(f.buffer_mut());
+                            selection.highlight(f.buffer_mut());
+                        }
+                        f.render_command_prompt(&command_prompt, prompt_area, &theme);
+                        if let Some(popup) = command_prompt.completion_popup(&theme) {
+                            let popup_area = popup.area(prompt_area);
+                            f.render_widget(popup, popup_area);
+                        }
+
+                        // The help overlay draws over everything else
+                        if help_overlay.is_open() {
+                            f.render_help_overlay(&help_overlay, f.size(), &keymap, &theme);
+                        }
                     })?;
                     
                     last_refresh = now;
+                    dirty = false;
                 }
             }
             
@@ -135,79 +673,601 @@ pub fn run_ui(rx: Receiver<LogEntry>) -> Result<()> {
                 log_storage.clear_new_entries_flags();
             }
 
-            let visible_count = (terminal.size()?.height as usize).saturating_sub(3);
+            // Rows in the focused pane, which may be one of several
+            let visible_count = log_area.height as usize;
             
+            dirty |= command_prompt.expire_status();
+            let progress = tabs.active().view.progress();
+            // A background filter fills the view in as it goes
+            dirty |= progress.is_some();
+            // Memory use is always shown, against the cap if there is one
+            let memory = match log_storage.retention().max_memory {
+                Some(max) => format!("{} / {}", format_size(log_storage.memory()), format_size(max)),
+                None => format_size(log_storage.memory()),
+            };
+            // So does a large file whose lines are still being read
+            let loading = status.loading();
+            dirty |= loading.is_some();
+            let mut activity = Vec::new();
+            if let Some((checked, total)) = progress {
+                activity.push(format!("Filtering {}%", checked * 100 / total.max(1)));
+            }
+            let alert_progress = tabs.active().alert_progress();
+            dirty |= alert_progress.is_some();
+            if let Some((checked, total)) = alert_progress {
+                activity.push(format!("searching for alerts {}%", checked * 100 / total.max(1)));
+            }
+            if let Some((name, percent)) = loading {
+                activity.push(format!("loading {} {}%", name, percent));
+            }
+            // And the command's own CPU and memory use, to set against bursts of output
+            for (name, usage) in status.usage() {
+                activity.push(format!("{} cpu {:.0}% rss {}", name, usage.cpu, format_size(usage.rss as usize)));
+            }
+            activity.push(memory);
+            command_prompt.set_activity(Some(activity.join("  ")));
+
+            let sources = log_storage.sources();
+            let running = sources.iter()
+                .filter(|source| status.get(source.name()) == Some(SourceState::Running))
+                .count();
+            terminal_title.update(terminal.backend_mut(), sources.len(), running)?;
+
+            // Go back to following once the user has left a paused view alone long enough
+            if let Some(autoresume) = tabs.active().settings.autoresume {
+                let viewer = &mut tabs.active_mut().viewer;
+                if viewer.is_paused() && !command_prompt.is_active() && last_input.elapsed() >= autoresume {
+                    viewer.jump_to_end();
+                    dirty = true;
+                }
+            }
+
+            // With --propagate-exit, oxtail is done once the command is
+            if inputs.process.as_ref().is_some_and(|process| process.exit_code().is_some()) {
+                return Ok(());
+            }
+            // With --exit-on-eof, oxtail is done once its piped inputs are
+            if inputs_done {
+                return Ok(());
+            }
+
+            // A request through the --control socket waits for the prompt, so it doesn't run over
+            // a line being typed, and for the commands before it, so its errors are its own
+            let mut control_reply = None;
+            if let Some(control) = inputs.control.as_ref().filter(|_| queued.is_empty() && !command_prompt.is_active()) {
+                if let Ok(request) = control.try_recv() {
+                    match request.action {
+                        ControlAction::Command(command) => {
+                            command_prompt.set_status(None);
+                            queued.push_back((command, None));
+                            control_reply = Some(request.reply);
+                        },
+                        ControlAction::Export(path) => {
+                            let result = write_all(&path, &mut log_storage, &mut tabs)
+                                .map(|count| Some(format!("Wrote {} entries to {}", count, path.display())))
+                                .map_err(|err| format!("{:#}", err));
+                            let _ = request.reply.send(result);
+                        },
+                    }
+                    dirty = true;
+                }
+            }
+
+            // Run the commands typed last time round, and those aliases stand for, in order
+            let mut expansions = 0;
+            while let Some((cmd, line)) = queued.pop_front() {
+                if let Some(commands) = aliases.expand(&cmd) {
+                    expansions += 1;
+                    if expansions > MAX_ALIAS_EXPANSIONS {
+                        command_prompt.set_status(Some(format!("Error: Aliases refer to each other in a loop: {}", cmd)));
+                        queued.clear();
+                        break;
+                    }
+                    // The line typed goes in the history once the alias's last command has run
+                    let last = commands.len() - 1;
+                    for (index, command) in commands.into_iter().enumerate().rev() {
+                        queued.push_front((command, if index == last { line.clone() } else { None }));
+                    }
+                    continue;
+                }
+
+                match commands::execute_command(&cmd, &mut tabs.active_mut().settings) {
+                    CommandResult::Success(_) => {
+                        tabs.active_mut().update_filter_from_settings();
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Tab(tab_command) => {
+                        match tabs.apply(tab_command, &log_storage) {
+                            Ok(()) => {
+                                command_prompt.finish(line);
+                            },
+                            Err(err) => {
+                                command_prompt.set_status(Some(format!("Error: {}", err)));
+                            },
+                        }
+                    },
+                    CommandResult::Selection(selection_command) => {
+                        page_in_selection(&mut log_storage, tabs.active());
+                        let message = run_selection_command(selection_command, &log_storage, tabs.active_mut());
+                        command_prompt.finish(line);
+                        command_prompt.set_status(Some(message));
+                    },
+                    CommandResult::Storage(storage_command) => {
+                        let mut retention = log_storage.retention();
+                        match storage_command {
+                            StorageCommand::Lines(max) => retention.max_lines = max,
+                            StorageCommand::LinesPerSource(max) => retention.max_lines_per_source = max,
+                            StorageCommand::Memory(max) => retention.max_memory = max,
+                        }
+                        log_storage.set_retention(retention);
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Report(report) => {
+                        let (title, lines) = report_lines(report, &log_storage, &tabs, &status, &throttle, &silence);
+                        // Sources can be shown and hidden from their list
+                        if report == Report::Sources {
+                            output_pane.open_list(title, lines);
+                        } else {
+                            output_pane.open(title, lines);
+                        }
+                        live_report = Some(report);
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::LoadMore(source) => {
+                        inputs.files.load_more(source);
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Open(path) => {
+                        // The watcher says on the prompt line how it went
+                        inputs.files.open(PathBuf::from(path));
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Close { source, drop } => {
+                        // A command's entries are those of both its sources
+                        let closed = if inputs.files.close(&source) {
+                            Some((source.clone(), vec![source.clone()]))
+                        } else {
+                            inputs.launcher.close(&source)
+                                .map(|name| (name.clone(), vec![format!("{}:stdout", name), format!("{}:stderr", name)]))
+                        };
+                        match closed {
+                            Some((closed, sources)) => {
+                                let message = if drop {
+                                    let dropped: usize = sources.iter().map(|source| log_storage.clear_source(source, false)).sum();
+                                    // As :clear does, so nothing points at the dropped entries
+                                    for tab in tabs.iter_mut() {
+                                        for source in &sources {
+                                            tab.forget_entries(Some(source));
+                                        }
+                                    }
+                                    format!("Closed {} and dropped {} entries", closed, dropped)
+                                } else {
+                                    format!("Closed {}", closed)
+                                };
+                                command_prompt.finish(line);
+                                command_prompt.set_status(Some(message));
+                            },
+                            None => command_prompt.set_status(Some(format!("Error: Not an open file or a command started with exec: {}", source))),
+                        }
+                    },
+                    CommandResult::Clear { source, renumber } => {
+                        if let Some(name) = source.as_deref().filter(|name| log_storage.get_source(name).is_none()) {
+                            command_prompt.set_status(Some(format!("Error: Unknown source: {}", name)));
+                        } else {
+                            let cleared = match source.as_deref() {
+                                Some(name) => log_storage.clear_source(name, renumber),
+                                None => log_storage.clear(renumber),
+                            };
+                            for tab in tabs.iter_mut() {
+                                tab.forget_entries(source.as_deref());
+                            }
+                            command_prompt.finish(line);
+                            command_prompt.set_status(Some(format!("Cleared {} entries", cleared)));
+                        }
+                    },
+                    CommandResult::Mark(text) => {
+                        let text = text.unwrap_or_else(|| {
+                            marks += 1;
+                            format!("mark {}", marks)
+                        });
+                        let entry = log_storage.add_entry(LogEntry::new(MARK_SOURCE, text));
+                        let panes = tabs.panes();
+                        for (index, tab) in tabs.iter_mut().enumerate() {
+                            tab.note_new_entry(entry, panes.contains(&index));
+                        }
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Exec { command, name } => match inputs.launcher.exec(&command, name) {
+                        Ok(name) => {
+                            command_prompt.finish(line);
+                            command_prompt.set_status(Some(format!("Started {}", name)));
+                        },
+                        Err(err) => command_prompt.set_status(Some(format!("Error: Failed to run {}: {:#}", command, err))),
+                    },
+                    CommandResult::Restart => match &inputs.process {
+                        Some(process) => {
+                            // The process says on the prompt line when it's restarted
+                            process.restart();
+                            command_prompt.finish(line);
+                        },
+                        None => command_prompt.set_status(Some("Error: No command to restart".to_string())),
+                    },
+                    CommandResult::Signal(signal) => match &inputs.process {
+                        Some(process) => {
+                            // The process says on the prompt line whether it was sent
+                            process.signal(signal);
+                            command_prompt.finish(line);
+                        },
+                        None => command_prompt.set_status(Some("Error: No command to signal".to_string())),
+                    },
+                    CommandResult::Throttle { source, per_second } => {
+                        throttle.set(&source, per_second);
+                        let message = match per_second {
+                            Some(per_second) => format!("Throttling {} to {} lines a second", source, per_second),
+                            None => format!("Stopped throttling {}", source),
+                        };
+                        command_prompt.finish(line);
+                        command_prompt.set_status(Some(message));
+                    },
+                    CommandResult::Expect { source, within, alert } => {
+                        silence.expect(&source, within, alert);
+                        let message = match within {
+                            Some(within) => format!("Expecting output from {} at least every {}", source, format_elapsed(within)),
+                            None => format!("Stopped expecting output from {}", source),
+                        };
+                        command_prompt.finish(line);
+                        command_prompt.set_status(Some(message));
+                    },
+                    CommandResult::ReloadConfig => {
+                        let path = inputs.config_path.clone().or_else(Config::default_path);
+                        let reloaded = Config::load(inputs.config_path.as_deref())
+                            .map(|config| config.with_overrides(&inputs.overrides))
+                            .and_then(|config| Ok((config.themes()?, config.keymap()?, config)));
+                        match reloaded {
+                            Ok(((new_themes, new_theme), new_keymap, config)) => {
+                                // Checked when it was loaded
+                                let _ = apply_source_limits(&config, &mut throttle, &mut silence);
+                                if let Ok(retention) = config.retention(inputs.retention) {
+                                    log_storage.set_retention(retention);
+                                }
+                                for tab in tabs.iter_mut() {
+                                    let _ = config.apply(&mut tab.settings);
+                                    tab.update_filter_from_settings();
+                                }
+                                let mut defaults = LogSettings { groups: log_storage.groups().clone(), ..LogSettings::default() };
+                                let _ = config.apply(&mut defaults);
+                                tabs.set_defaults(defaults);
+                                if let Some(fps) = config.fps {
+                                    frame_interval = frame_time(fps);
+                                }
+                                // The theme only changes if the file names one
+                                if config.theme.is_some() {
+                                    unfocused_theme = new_theme.unfocused();
+                                    theme = new_theme;
+                                }
+                                // Aliases from the file replace those of the same name
+                                if let Ok(reloaded) = config.aliases() {
+                                    aliases.extend(reloaded);
+                                }
+                                themes = new_themes;
+                                keymap = new_keymap;
+                                inputs.config = config;
+                                command_prompt.finish(line);
+                                command_prompt.set_status(Some(match path {
+                                    Some(path) => format!("Reloaded {}", path.display()),
+                                    None => "Reloaded the config".to_string(),
+                                }));
+                            },
+                            Err(err) => command_prompt.set_status(Some(format!("Error: {:#}", err))),
+                        }
+                    },
+                    CommandResult::Alias(alias_command) => match alias_command {
+                        AliasCommand::List => {
+                            let lines = aliases.lines();
+                            if lines.is_empty() {
+                                command_prompt.set_status(Some("No aliases".to_string()));
+                            } else {
+                                output_pane.open("Aliases".to_string(), lines);
+                                live_report = None;
+                                command_prompt.finish(line);
+                            }
+                        },
+                        AliasCommand::Define { name, commands } => match aliases.set(&name, commands) {
+                            Ok(()) => command_prompt.finish(line),
+                            Err(err) => command_prompt.set_status(Some(format!("Error: {}", err))),
+                        },
+                        AliasCommand::Remove(name) => {
+                            if aliases.remove(&name) {
+                                command_prompt.finish(line);
+                            } else {
+                                command_prompt.set_status(Some(format!("Error: No alias {}", name)));
+                            }
+                        },
+                    },
+                    CommandResult::Jump(jump) => {
+                        let total_lines = tabs.active().entries(&log_storage).len();
+                        let viewer = &mut tabs.active_mut().viewer;
+                        match jump {
+                            LineJump::To(line) => viewer.jump_to_line(line, total_lines),
+                            LineJump::Down(lines) => viewer.scroll_down(lines),
+                            LineJump::Up(lines) => viewer.scroll_up(lines, total_lines),
+                        };
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Help(spec) => {
+                        match spec {
+                            Some(spec) => {
+                                let mut lines = vec![spec.usage_line()];
+                                if !spec.aliases.is_empty() {
+                                    lines.push(format!("Also: {}", spec.aliases.iter().map(|alias| format!(":{}", alias)).collect::<Vec<_>>().join(", ")));
+                                }
+                                lines.push(String::new());
+                                lines.push(spec.description.to_string());
+                                output_pane.open(format!("Help: {}", spec.name), lines);
+                                live_report = None;
+                            },
+                            None => help_overlay.open(),
+                        }
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Fps(fps) => {
+                        frame_interval = frame_time(fps);
+                        command_prompt.finish(line);
+                    },
+                    CommandResult::Theme(name) => match themes.get(&name) {
+                        Some(selected) => {
+                            unfocused_theme = selected.unfocused();
+                            theme = selected;
+                            command_prompt.finish(line);
+                        },
+                        None => {
+                            command_prompt.set_status(Some(format!(
+                                "Error: Unknown theme: {} ({})", name, themes.names().join("/")
+                            )));
+                        },
+                    },
+                    CommandResult::Error(err) => {
+                        command_prompt.set_status(Some(format!("Error: {}", err)));
+                    },
+                    CommandResult::Quit => {
+                        // A program that asked oxtail to quit isn't left waiting for a reply
+                        if let Some(reply) = control_reply.take() {
+                            let _ = reply.send(Ok(None));
+                        }
+                        return Ok(());
+                    },
+                }
+                // An alias stops at the first of its commands that fails
+                if command_prompt.has_error() {
+                    queued.clear();
+                }
+            }
+            // The program that sent the request is told what the prompt line was
+            if let Some(reply) = control_reply {
+                let status = command_prompt.status().map(|status| status.to_string());
+                let _ = reply.send(match status {
+                    Some(error) if command_prompt.has_error() => Err(error.trim_start_matches("Error: ").to_string()),
+                    status => Ok(status),
+                });
+            }
+
             // Non-blocking event check
             if let Ok(event) = events.try_recv() {
+                last_input = std::time::Instant::now();
+                dirty = true;
                 match event {
                     // Handle keyboard events
                     Event::Key(key) => {
-                        if command_prompt.is_active() {
+                        if help_overlay.is_open() {
+                            match keymap.action_for(key) {
+                                Some(Action::Escape | Action::Quit | Action::Help) => help_overlay.close(),
+                                Some(Action::ScrollUp) => help_overlay.scroll_up(1),
+                                Some(Action::ScrollDown) => help_overlay.scroll_down(1),
+                                Some(Action::PageUp) => help_overlay.scroll_up(visible_count),
+                                Some(Action::PageDown) => help_overlay.scroll_down(visible_count),
+                                _ => {},
+                            }
+                        } else if command_prompt.is_active() {
+                            // Completion offers whatever sources exist right now, and the groups
+                            command_prompt.set_source_names(
+                                log_storage.sources().iter().map(|s| s.name().to_string())
+                                    .chain(log_storage.groups().names().map(|name| name.to_string()))
+                                    .collect()
+                            );
                             let (consumed, result) = command_prompt.handle_key_event(key);
                             if consumed {
                                 match result {
                                     CommandInputResult::Command(cmd) => {
-                                        match commands::execute_command(&cmd, &mut settings) {
-                                            CommandResult::Success(_) => {
-                                                log_storage.update_filter_from_settings(&settings);
-                                                command_prompt.add_to_history(cmd);
-                                                command_prompt.deactivate();
-                                            },
-                                            CommandResult::Error(err) => {
-                                                command_prompt.set_status(Some(format!("Error: {}", err)));
-                                            },
-                                            CommandResult::Quit => {
-                                                return Ok(());
-                                            },
-                                        }
+                                        queued.push_back((cmd.clone(), Some(cmd)));
                                     },
                                     CommandInputResult::Cancelled => {
                                         command_prompt.deactivate();
                                     },
                                     CommandInputResult::Pending => {},
-                                    CommandInputResult::LineJump(line) => {
-                                        let total_lines = log_storage.get_filtered_entries().len();
-                                        log_viewer.jump_to_line(line, total_lines);
-                                        command_prompt.deactivate();
-                                    }
+                                    CommandInputResult::Input(line) => {
+                                        if let Some(process) = &inputs.process {
+                                            process.send_input(line);
+                                        }
+                                    },
                                 }
                             }
-                        } else {
-                            match key {
-                                Key::Char('q') => return Ok(()),
-                                Key::Char(':') => {
+                        } else if let Some(action) = keymap.action_for(key).filter(|action| {
+                            output_pane.is_open()
+                                && (matches!(action, Action::ScrollUp | Action::ScrollDown | Action::PageUp | Action::PageDown
+                                    | Action::Escape | Action::Quit)
+                                    || (*action == Action::ToggleSource && output_pane.selected().is_some()))
+                        }) {
+                            // Open command output takes over scrolling until it's dismissed
+                            let page = output_area.height.saturating_sub(2) as usize;
+                            match action {
+                                Action::ScrollUp => output_pane.scroll_up(1),
+                                Action::ScrollDown => output_pane.scroll_down(1),
+                                Action::PageUp => output_pane.scroll_up(page),
+                                Action::PageDown => output_pane.scroll_down(page),
+                                // Show or hide the source highlighted in the `:sources` list
+                                Action::ToggleSource => {
+                                    let listed = listed_sources(&log_storage, &status);
+                                    if let Some(name) = output_pane.selected().and_then(|index| listed.get(index)) {
+                                        let tab = tabs.active_mut();
+                                        let visible = !tab.settings.is_source_visible(name);
+                                        tab.settings.get_source_config(name).visible = visible;
+                                        tab.update_filter_from_settings();
+                                    }
+                                },
+                                _ => output_pane.close(),
+                            }
+                        } else if let Some(action) = keymap.action_for(key).filter(|action| {
+                            source_sidebar.is_open()
+                                && matches!(action, Action::ScrollUp | Action::ScrollDown | Action::ToggleSource | Action::Escape)
+                        }) {
+                            // The open sidebar takes over line navigation
+                            let rows = source_rows(&log_storage, &tabs, &status);
+                            match action {
+                                Action::ScrollUp => source_sidebar.select_prev(),
+                                Action::ScrollDown => source_sidebar.select_next(rows.len()),
+                                Action::ToggleSource => {
+                                    if let Some(row) = rows.get(source_sidebar.selected()) {
+                                        let tab = tabs.active_mut();
+                                        let config = tab.settings.get_source_config(&row.name);
+                                        config.visible = !config.visible;
+                                        tab.update_filter_from_settings();
+                                    }
+                                },
+                                _ => source_sidebar.toggle(),
+                            }
+                        } else if let Some(action) = keymap.action_for(key) {
+                            match action {
+                                Action::Quit => return Ok(()),
+                                Action::Suspend => {
+                                    suspend(&mut terminal, &raw_mode)?;
+                                },
+                                Action::CommandMode => {
                                     command_prompt.activate();
                                 },
-                                Key::Char('r') => {
+                                Action::Help => {
+                                    help_overlay.open();
+                                },
+                                Action::ToggleSidebar => {
+                                    source_sidebar.toggle();
+                                },
+                                Action::CopySelection => {
+                                    if selection.is_none() || selected_text.is_empty() {
+                                        command_prompt.set_status(Some("Nothing selected".to_string()));
+                                    } else {
+                                        let message = copy_with_status(&selected_text, terminal.backend_mut());
+                                        command_prompt.set_status(Some(message));
+                                        selection = None;
+                                    }
+                                },
+                                Action::ToggleCursor => {
+                                    tabs.active_mut().viewer.toggle_cursor();
+                                },
+                                Action::VisualMode => {
+                                    tabs.active_mut().viewer.toggle_visual();
+                                },
+                                Action::Yank => {
+                                    page_in_selection(&mut log_storage, tabs.active());
+                                    let tab = tabs.active();
+                                    let entries = selected_entries(&log_storage, tab);
+                                    let message = if entries.is_empty() {
+                                        "No line cursor (press v or V)".to_string()
+                                    } else {
+                                        let text = export::entries_text(&entries, &tab.settings);
+                                        copy_with_status(&text, terminal.backend_mut())
+                                    };
+                                    command_prompt.set_status(Some(message));
+                                },
+                                Action::Escape => {
+                                    tabs.active_mut().viewer.clear_cursor();
+                                    selection = None;
+                                },
+                                Action::InputMode => match &inputs.process {
+                                    Some(_) => command_prompt.activate_input(),
+                                    None => command_prompt.set_status(Some("No command to send input to".to_string())),
+                                },
+                                // Runs as `:mark` would, with the commands
+                                Action::Mark => queued.push_back(("mark".to_string(), None)),
+                                Action::JumpToAlert => {
+                                    let tab = tabs.active();
+                                    let message = match tab.alert() {
+                                        None => Some("No alert yet (add a pattern with :alert)".to_string()),
+                                        Some(alert) => {
+                                            let filtered = tab.entries(&log_storage);
+                                            let index = log_storage.entry_id(&alert.source, alert.line_number)
+                                                .and_then(|id| filtered.find(id, alert.timestamp));
+                                            match index {
+                                                Some(index) => {
+                                                    let total_lines = filtered.len();
+                                                    tabs.active_mut().viewer.jump_to_entry(index, total_lines);
+                                                    None
+                                                },
+                                                None => Some("The alert entry is hidden by this tab's filters".to_string()),
+                                            }
+                                        },
+                                    };
+                                    if message.is_some() {
+                                        command_prompt.set_status(message);
+                                    }
+                                },
+                                // Only meaningful while the sidebar is open
+                                Action::ToggleSource => {},
+                                Action::ToggleRaw => {
+                                    let settings = &mut tabs.active_mut().settings;
                                     settings.show_raw = !settings.show_raw;
                                 },
-                                Key::Char('p') => {
-                                    log_viewer.set_paused(!log_viewer.is_paused());
+                                Action::TogglePause => {
+                                    let viewer = &mut tabs.active_mut().viewer;
+                                    viewer.set_paused(!viewer.is_paused());
                                 },
-                                // Vim-style navigation
-                                Key::Char('j') | Key::Down => {
-                                    log_viewer.scroll_down(1);
+                                // Stop following where the view is, or jump back to the end
+                                Action::Follow => {
+                                    let viewer = &mut tabs.active_mut().viewer;
+                                    if viewer.is_paused() {
+                                        viewer.jump_to_end();
+                                    } else {
+                                        viewer.set_paused(true);
+                                    }
+                                },
+                                // Tab switching
+                                Action::NextTab => {
+                                    tabs.next();
+                                },
+                                Action::PrevTab => {
+                                    tabs.prev();
+                                },
+                                // In cursor mode, line movement moves the cursor
+                                Action::ScrollDown if tabs.active().viewer.has_cursor() => {
+                                    tabs.active_mut().viewer.cursor_down(1);
                                 },
-                                Key::Char('k') | Key::Up => {
-                                    let total_lines = log_storage.get_filtered_entries().len();
-                                    log_viewer.scroll_up(1, total_lines);
+                                Action::ScrollUp if tabs.active().viewer.has_cursor() => {
+                                    let total_lines = tabs.active().entries(&log_storage).len();
+                                    let page_size = page_size(&log_storage, tabs.active(), log_area);
+                                    tabs.active_mut().viewer.cursor_up(1, page_size, total_lines);
+                                },
+                                Action::ScrollDown => {
+                                    tabs.active_mut().viewer.scroll_down(1);
+                                },
+                                Action::ScrollUp => {
+                                    let total_lines = tabs.active().entries(&log_storage).len();
+                                    tabs.active_mut().viewer.scroll_up(1, total_lines);
                                 },
                                 // Beginning/end navigation
-                                Key::Char('g') | Key::Char('<') => {
-                                    let total_lines = log_storage.get_filtered_entries().len();
-                                    log_viewer.jump_to_start(total_lines);
+                                Action::JumpStart => {
+                                    let total_lines = tabs.active().entries(&log_storage).len();
+                                    tabs.active_mut().viewer.jump_to_start(total_lines);
                                 },
-                                Key::Char('G') | Key::Char('>') => {
-                                    log_viewer.jump_to_end();
+                                Action::JumpEnd => {
+                                    tabs.active_mut().viewer.jump_to_end();
                                 },
-                                Key::PageUp => {
-                                    let total_lines = log_storage.get_filtered_entries().len();
-                                    log_viewer.page_up(visible_count, total_lines);
+                                Action::PageUp => {
+                                    let total_lines = tabs.active().entries(&log_storage).len();
+                                    let page_size = page_size(&log_storage, tabs.active(), log_area);
+                                    tabs.active_mut().viewer.page_up(page_size, total_lines);
                                 },
-                                Key::PageDown => {
-                                    log_viewer.page_down(visible_count);
+                                Action::PageDown => {
+                                    let page_size = page_size(&log_storage, tabs.active(), log_area);
+                                    tabs.active_mut().viewer.page_down(page_size);
                                 },
-                                _ => {},
                             }
                         }
                     },
@@ -215,22 +1275,94 @@ pub fn run_ui(rx: Receiver<LogEntry>) -> Result<()> {
                     Event::Mouse(mouse_event) => {
                         match mouse_event {
                             MouseEvent::Press(MouseButton::WheelUp, _, _) => {
-                                let total_lines = log_storage.get_filtered_entries().len();
-                                log_viewer.scroll_up(3, total_lines);
+                                let total_lines = tabs.active().entries(&log_storage).len();
+                                tabs.active_mut().viewer.scroll_up(3, total_lines);
                             },
                             MouseEvent::Press(MouseButton::WheelDown, _, _) => {
-                                log_viewer.scroll_down(3);
+                                tabs.active_mut().viewer.scroll_down(3);
                             },
-                            MouseEvent::Press(MouseButton::Left, _x, y) => {
+                            MouseEvent::Press(MouseButton::Left, x, y) => {
                                 // Handle click events
                                 // Check if click is in command prompt area
                                 let term_height = terminal.size()?.height;
                                 if y == term_height {
-                                    // Clicked on command prompt
-                                    if !command_prompt.is_active() {
+                                    // Clicked on command prompt: open it, or put the cursor where it was clicked
+                                    if command_prompt.is_active() {
+                                        command_prompt.click(x.saturating_sub(1));
+                                    } else {
                                         command_prompt.activate();
                                     }
-                                    // Potentially adjust cursor position based on x
+                                } else {
+                                    // Clicking away from a prompt with nothing typed in it closes it
+                                    if command_prompt.is_empty() {
+                                        command_prompt.deactivate();
+                                    }
+                                    // Termion reports 1-based coordinates
+                                    let (col, row) = (x.saturating_sub(1), y.saturating_sub(1));
+                                    let in_log_area = col >= log_area.x && col < log_area.x + log_area.width
+                                        && row >= log_area.y && row < log_area.y + log_area.height;
+                                    // The scrollbar sits on the right border of the log area
+                                    let on_scrollbar = col == log_area.x + log_area.width
+                                        && row >= log_area.y && row < log_area.y + log_area.height;
+
+                                    // Clicking a [SOURCE] label toggles that source
+                                    let tab = tabs.active();
+                                    let clicked_source = if in_log_area && tab.settings.show_source_labels {
+                                        let filtered = tab.entries(&log_storage);
+                                        tab.viewer
+                                            .entry_index_at_row((row - log_area.y) as usize, &filtered, &tab.settings, log_area)
+                                            .and_then(|index| filtered.get(index))
+                                            .filter(|entry| entry.source_label_columns(&tab.settings)
+                                                .zip(((col - log_area.x) as usize).checked_sub(delta_column_width(&tab.settings)))
+                                                .is_some_and(|(columns, col)| columns.contains(&col)))
+                                            .map(|entry| entry.source.clone())
+                                    } else {
+                                        None
+                                    };
+
+                                    if on_scrollbar {
+                                        let total_lines = tabs.active().entries(&log_storage).len();
+                                        let fraction = (row - log_area.y) as f64 / log_area.height.saturating_sub(1).max(1) as f64;
+                                        tabs.active_mut().viewer.scroll_to_fraction(fraction, total_lines, log_area.height as usize);
+                                        dragging_scrollbar = true;
+                                    } else if let Some(source) = clicked_source {
+                                        let tab = tabs.active_mut();
+                                        let config = tab.settings.get_source_config(&source);
+                                        config.visible = !config.visible;
+                                        let state = if config.visible { "Showing" } else { "Hid" };
+                                        tab.update_filter_from_settings();
+                                        command_prompt.flash_status(format!("{} source {}", state, source));
+                                        selection = None;
+                                    } else if in_log_area {
+                                        selection = Some(TextSelection::start(col, row, log_area));
+                                        selecting = true;
+                                    } else {
+                                        selection = None;
+                                    }
+                                }
+                            },
+                            MouseEvent::Hold(_, y) if dragging_scrollbar => {
+                                let row = y.saturating_sub(1).clamp(log_area.y, log_area.y + log_area.height.saturating_sub(1));
+                                let total_lines = tabs.active().entries(&log_storage).len();
+                                let fraction = (row - log_area.y) as f64 / log_area.height.saturating_sub(1).max(1) as f64;
+                                tabs.active_mut().viewer.scroll_to_fraction(fraction, total_lines, log_area.height as usize);
+                            },
+                            MouseEvent::Release(_, _) if dragging_scrollbar => {
+                                dragging_scrollbar = false;
+                            },
+                            MouseEvent::Hold(x, y) if selecting => {
+                                if let Some(selection) = &mut selection {
+                                    selection.extend_to(x.saturating_sub(1), y.saturating_sub(1));
+                                }
+                            },
+                            MouseEvent::Release(x, y) if selecting => {
+                                selecting = false;
+                                if let Some(current) = &mut selection {
+                                    current.extend_to(x.saturating_sub(1), y.saturating_sub(1));
+                                    // A plain click clears rather than selecting one cell
+                                    if current.is_empty() {
+                                        selection = None;
+                                    }
                                 }
                             },
                             _ => {},
@@ -246,14 +1378,31 @@ pub fn run_ui(rx: Receiver<LogEntry>) -> Result<()> {
         }
     })();
 
+    // The commands are stopped and their last output taken in before the screen goes
+    let processes: Vec<ProcessHandle> = inputs.process.iter().chain(inputs.launcher.handles()).cloned().collect();
+    if !processes.is_empty() {
+        let height = terminal.size().map(|size| size.height).unwrap_or(1);
+        let what = if processes.len() == 1 { "the command" } else { "the commands" };
+        write!(terminal.backend_mut(), "{}{}Stopping {}...", cursor::Goto(1, height), termion::clear::CurrentLine, what)?;
+        terminal.backend_mut().flush()?;
+        process_handler::finish(&processes, || {
+            for entry in rx.try_iter().flatten() {
+                log_storage.add_entry(entry);
+            }
+        });
+    }
+    let result = result.and_then(|()| match &inputs.write_on_exit {
+        Some(path) => write_all(path, &mut log_storage, &mut tabs).map(|_| ()),
+        None => Ok(()),
+    });
+    // Losing the history isn't worth failing over
+    if let Some(path) = &inputs.history {
+        let _ = command_prompt.save_history(path);
+    }
+
     // Reset terminal state when exiting
-    write!(
-        terminal.backend_mut(),
-        "{}{}{}",
-        termion::screen::ToMainScreen,
-        cursor::Show,
-        termion::clear::All
-    )?;
+    leave_tui(terminal.backend_mut())?;
+    write!(terminal.backend_mut(), "{}", termion::clear::All)?;
     
     // Return any error that occurred
     result
@@ -0,0 +1,119 @@
+use std::io;
+use std::net::TcpStream;
+use std::sync::mpsc::SyncSender;
+use std::time::{Duration, Instant};
+use anyhow::Result;
+use tungstenite::client::IntoClientRequest;
+use tungstenite::http::HeaderValue;
+use tungstenite::stream::MaybeTlsStream;
+use tungstenite::{Message, WebSocket};
+use crate::batch::EntryBatcher;
+use crate::http_stream;
+use crate::fields::FieldValue;
+use crate::log_entry::{self, LogEntry, Origin};
+use crate::reconnect::{self, Closed, Session};
+use crate::source_status::SourceStatusRegistry;
+
+/// How long a quiet connection goes before it's pinged. One that doesn't answer within
+/// another such interval is taken to be gone and connected again
+const KEEPALIVE: Duration = Duration::from_secs(20);
+
+/// A WebSocket to subscribe to, as given with `--ws wss://host/logs`
+#[derive(Debug, Clone)]
+pub struct WsUrl(String);
+
+impl WsUrl {
+    pub fn parse(value: &str) -> Result<Self, String> {
+        if !(value.starts_with("ws://") || value.starts_with("wss://")) {
+            return Err(format!("not a ws:// or wss:// URL: {}", value));
+        }
+        Ok(Self(value.to_string()))
+    }
+
+    pub fn source(&self) -> String {
+        http_stream::source_name(&self.0)
+    }
+}
+
+/// Read the log lines or JSON log events a WebSocket sends, each line of a message as an
+/// entry. A connection that closes, fails or stops answering pings is made again.
+/// `bearer_token` is sent as `Authorization: Bearer <token>`
+pub fn start_subscribing(url: &WsUrl, bearer_token: Option<String>, tx: SyncSender<Vec<LogEntry>>, status: SourceStatusRegistry) {
+    reconnect::start(WsSource { url: url.clone(), bearer_token }, tx, status);
+}
+
+struct WsSource {
+    url: WsUrl,
+    bearer_token: Option<String>,
+}
+
+impl reconnect::Source for WsSource {
+    fn name(&self) -> String {
+        self.url.source()
+    }
+
+    fn origin(&self) -> Option<Origin> {
+        Some(log_entry::origin([("url", FieldValue::Str(self.url.0.clone()))]))
+    }
+
+    fn run(&mut self, session: &mut Session) -> Result<String> {
+        let mut socket = connect(&self.url, self.bearer_token.as_deref())?;
+        session.connected();
+        Ok(read_messages(&mut socket, &self.name(), &mut session.batcher)?)
+    }
+}
+
+fn connect(url: &WsUrl, bearer_token: Option<&str>) -> Result<WebSocket<MaybeTlsStream<TcpStream>>> {
+    let mut request = url.0.as_str().into_client_request()?;
+    if let Some(token) = bearer_token {
+        request.headers_mut().insert("Authorization", HeaderValue::from_str(&format!("Bearer {}", token))?);
+    }
+    let (socket, _) = tungstenite::connect(request)?;
+    // Reads wake up now and then, so a quiet connection can be pinged
+    let stream = match socket.get_ref() {
+        MaybeTlsStream::Plain(stream) => Some(stream),
+        MaybeTlsStream::Rustls(stream) => Some(stream.get_ref()),
+        _ => None,
+    };
+    if let Some(stream) = stream {
+        stream.set_read_timeout(Some(KEEPALIVE))?;
+    }
+    Ok(socket)
+}
+
+/// Add the messages from a socket as entries until it closes, returning why it did
+fn read_messages(socket: &mut WebSocket<MaybeTlsStream<TcpStream>>, source: &str, batcher: &mut EntryBatcher) -> Result<String, Closed> {
+    let mut last_heard = Instant::now();
+    loop {
+        let text = match socket.read() {
+            Ok(Message::Text(text)) => text,
+            Ok(Message::Binary(data)) => String::from_utf8_lossy(&data).into_owned(),
+            // Pings are answered by the next read
+            Ok(Message::Ping(_) | Message::Pong(_) | Message::Frame(_)) => {
+                last_heard = Instant::now();
+                continue;
+            },
+            Ok(Message::Close(frame)) => {
+                return Ok(match frame {
+                    Some(frame) if !frame.reason.is_empty() => format!("closed by the server ({})", frame.reason),
+                    _ => "closed by the server".to_string(),
+                });
+            },
+            Err(tungstenite::Error::Io(err)) if matches!(err.kind(), io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut) => {
+                if last_heard.elapsed() >= KEEPALIVE * 2 {
+                    return Ok("no answer to a ping".to_string());
+                }
+                if let Err(err) = socket.send(Message::Ping(Vec::new())) {
+                    return Ok(err.to_string());
+                }
+                continue;
+            },
+            Err(err) => return Ok(err.to_string()),
+        };
+        last_heard = Instant::now();
+        for line in text.lines().filter(|line| !line.trim().is_empty()) {
+            batcher.push(LogEntry::new(source, line))?;
+        }
+        batcher.flush()?;
+    }
+}
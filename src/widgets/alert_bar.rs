@@ -0,0 +1,33 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Span,
+    widgets::Widget,
+};
+use crate::theme::Theme;
+
+/// A one-line headline showing the latest entry that matched an alert rule
+#[derive(Debug, Clone)]
+pub struct AlertBar<'a> {
+    text: &'a str,
+    theme: &'a Theme,
+}
+
+impl<'a> AlertBar<'a> {
+    pub fn new(text: &'a str, theme: &'a Theme) -> Self {
+        Self { text, theme }
+    }
+}
+
+impl Widget for AlertBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let style = Style::default()
+            .fg(self.theme.accent_text)
+            .bg(self.theme.error)
+            .add_modifier(Modifier::BOLD);
+        buf.set_style(area, style);
+        let span = Span::styled(format!(" ! {}", self.text), style);
+        buf.set_span(area.x, area.y, &span, area.width);
+    }
+}
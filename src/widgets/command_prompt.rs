@@ -1,11 +1,18 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::{Duration, Instant};
 use termion::event::Key;
 use ratatui::{
     buffer::Buffer,
     layout::Rect,
-    style::{Color, Style},
-    text::Span,
-    widgets::Widget,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph, Widget},
 };
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+use crate::commands;
+use crate::theme::Theme;
 
 /// Result returned after command input is complete
 #[derive(Debug, Clone)]
@@ -16,10 +23,13 @@ pub enum CommandInputResult {
     Cancelled,
     /// Still in input mode, no command to process yet
     Pending,
-    /// Jump to specific line number
-    LineJump(usize),
+    /// A line typed in input mode, to send to the command's stdin
+    Input(String),
 }
 
+/// Most commands kept in the history file
+const HISTORY_LIMIT: usize = 1000;
+
 /// Manages command history for the command prompt
 #[derive(Debug, Clone, Default)]
 pub struct CommandHistory {
@@ -44,6 +54,23 @@ impl CommandHistory {
         self.position = None;
     }
     
+    /// Read the commands saved by an earlier session, one a line, oldest first
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let text = fs::read_to_string(path)?;
+        let commands = text.lines().filter(|line| !line.trim().is_empty()).map(String::from).collect();
+        Ok(Self { commands, position: None })
+    }
+
+    /// Save the most recent commands for the next session, creating the directory if needed
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let start = self.commands.len().saturating_sub(HISTORY_LIMIT);
+        let text: String = self.commands[start..].iter().map(|command| format!("{}\n", command)).collect();
+        fs::write(path, text)
+    }
+
     /// Navigate up in command history
     pub fn up(&mut self) -> Option<String> {
         if self.commands.is_empty() {
@@ -100,6 +127,17 @@ impl CommandHistory {
     }
 }
 
+/// Tab-completion state while cycling through candidates
+#[derive(Debug, Clone)]
+struct Completion {
+    /// Candidates for the word being completed
+    candidates: Vec<String>,
+    /// Index of the candidate currently inserted, if cycling has started
+    index: Option<usize>,
+    /// Byte offset where the completed word starts
+    word_start: usize,
+}
+
 /// A command prompt widget for ratatui
 #[derive(Debug, Clone)]
 pub struct CommandPrompt {
@@ -117,6 +155,18 @@ pub struct CommandPrompt {
     search_query: String,
     /// Whether the prompt is active
     active: bool,
+    /// Active tab-completion, cleared by any other key
+    completion: Option<Completion>,
+    /// Known source names offered as completions
+    source_names: Vec<String>,
+    /// When a flashed status message should disappear
+    status_expires: Option<Instant>,
+    /// Background work in progress, shown at the right end of the line
+    activity: Option<String>,
+    /// Whether lines typed go to the command's stdin rather than being run as commands
+    input_mode: bool,
+    /// Lines sent in input mode, kept apart from commands
+    input_history: CommandHistory,
 }
 
 impl Default for CommandPrompt {
@@ -136,22 +186,38 @@ impl CommandPrompt {
             search_mode: false,
             search_query: String::new(),
             active: false,
+            completion: None,
+            source_names: Vec::new(),
+            status_expires: None,
+            activity: None,
+            input_mode: false,
+            input_history: CommandHistory::new(),
         }
     }
     
     /// Activate the command prompt
     pub fn activate(&mut self) {
         self.active = true;
+        self.completion = None;
         self.buffer.clear();
         self.cursor_position = 0;
         self.status = None;
         self.search_mode = false;
         self.search_query.clear();
+        self.input_mode = false;
+    }
+
+    /// Activate the prompt in input mode, where each line typed is sent to the command
+    pub fn activate_input(&mut self) {
+        self.activate();
+        self.input_mode = true;
     }
     
     /// Deactivate the command prompt
     pub fn deactivate(&mut self) {
         self.active = false;
+        self.input_mode = false;
+        self.completion = None;
         self.buffer.clear();
         self.cursor_position = 0;
         self.status = None;
@@ -163,16 +229,196 @@ impl CommandPrompt {
     pub fn is_active(&self) -> bool {
         self.active
     }
+
+    /// Whether the prompt is open with nothing typed in it
+    pub fn is_empty(&self) -> bool {
+        self.active && self.buffer.is_empty()
+    }
+
+    /// What's drawn before the text typed: the `:` of commands, or apart from it in input mode
+    fn prefix(&self) -> &'static str {
+        if self.input_mode { "stdin> " } else { ":" }
+    }
+
+    /// Column of the text cursor on the prompt line, while a line is being edited
+    pub fn cursor_column(&self) -> Option<u16> {
+        if !self.active || self.search_mode {
+            return None;
+        }
+        Some((self.prefix().width() + self.buffer[..self.cursor_position].width()) as u16)
+    }
+
+    /// Where the character before the cursor starts; the cursor stays on character boundaries
+    fn previous_boundary(&self) -> usize {
+        self.buffer[..self.cursor_position].char_indices().next_back().map_or(0, |(index, _)| index)
+    }
+
+    /// Where the character after the cursor ends
+    fn next_boundary(&self) -> usize {
+        self.buffer[self.cursor_position..].chars().next()
+            .map_or(self.cursor_position, |c| self.cursor_position + c.len_utf8())
+    }
+
+    /// Move the cursor to the character drawn at a column of the prompt line, or to the end
+    /// of the line when the column is past it
+    pub fn click(&mut self, column: u16) {
+        if !self.active || self.search_mode {
+            return;
+        }
+        self.completion = None;
+        let mut remaining = (column as usize).saturating_sub(self.prefix().width());
+        self.cursor_position = self.buffer.len();
+        for (index, c) in self.buffer.char_indices() {
+            let width = c.width().unwrap_or(0);
+            if remaining < width.max(1) {
+                self.cursor_position = index;
+                break;
+            }
+            remaining -= width;
+        }
+    }
     
     /// Set a status message
     pub fn set_status(&mut self, status: Option<String>) {
         self.status = status;
+        self.status_expires = None;
+    }
+
+    /// The status message shown, if any
+    pub fn status(&self) -> Option<&str> {
+        self.status.as_deref()
+    }
+
+    /// Show a status message briefly
+    pub fn flash_status(&mut self, message: String) {
+        self.status = Some(message);
+        self.status_expires = Some(Instant::now() + Duration::from_secs(3));
+    }
+
+    /// Set or clear the background activity shown at the right end of the line
+    pub fn set_activity(&mut self, activity: Option<String>) {
+        self.activity = activity;
+    }
+
+    /// Clear a flashed status message once it has been shown long enough
+    /// Returns whether a status was cleared
+    pub fn expire_status(&mut self) -> bool {
+        if self.status_expires.is_some_and(|expires| Instant::now() >= expires) {
+            self.status = None;
+            self.status_expires = None;
+            return true;
+        }
+        false
     }
     
+    /// Start with the commands from an earlier session; a missing or unreadable file leaves
+    /// the history empty
+    pub fn load_history(&mut self, path: &Path) {
+        if let Ok(history) = CommandHistory::load(path) {
+            self.history = history;
+        }
+    }
+
+    /// Save the commands run for the next session
+    pub fn save_history(&self, path: &Path) -> io::Result<()> {
+        self.history.save(path)
+    }
+
     /// Add a command to history
     pub fn add_to_history(&mut self, command: String) {
         self.history.add(command);
     }
+
+    /// A command ran: close the prompt, adding the line typed for it to the history if it's
+    /// the last one that line stands for
+    pub fn finish(&mut self, line: Option<String>) {
+        if let Some(line) = line {
+            self.add_to_history(line);
+        }
+        self.deactivate();
+    }
+
+    /// Whether the status says a command failed
+    pub fn has_error(&self) -> bool {
+        self.status.as_deref().is_some_and(|status| status.starts_with("Error:"))
+    }
+
+    /// History of the lines being typed: input lines in input mode, commands otherwise
+    fn history(&mut self) -> &mut CommandHistory {
+        if self.input_mode {
+            &mut self.input_history
+        } else {
+            &mut self.history
+        }
+    }
+
+    /// Most recent line in the history being typed that contains the search query
+    fn search_history(&mut self) -> Option<String> {
+        if self.input_mode {
+            self.input_history.search(&self.search_query)
+        } else {
+            self.history.search(&self.search_query)
+        }
+    }
+
+    /// Update the source names offered by tab completion
+    pub fn set_source_names(&mut self, names: Vec<String>) {
+        self.source_names = names;
+    }
+
+    /// Complete the word before the cursor, cycling through candidates on repeated presses
+    fn complete(&mut self) {
+        // Repeated Tab cycles through the existing candidates
+        if let Some(completion) = &mut self.completion {
+            if completion.candidates.len() > 1 {
+                let next = completion.index.map_or(0, |i| (i + 1) % completion.candidates.len());
+                completion.index = Some(next);
+                let replacement = completion.candidates[next].clone();
+                let word_start = completion.word_start;
+                self.buffer.replace_range(word_start..self.cursor_position, &replacement);
+                self.cursor_position = word_start + replacement.len();
+            }
+            return;
+        }
+
+        let before_cursor = &self.buffer[..self.cursor_position];
+        let word_start = before_cursor.rfind(' ').map_or(0, |i| i + 1);
+        let prefix = &before_cursor[word_start..];
+        let previous: Vec<&str> = before_cursor[..word_start].split_whitespace().collect();
+        let candidates = commands::completions(&previous, prefix, &self.source_names);
+
+        match candidates.len() {
+            0 => {},
+            1 => {
+                // Unique match: complete it and move on to the next word
+                let completed = format!("{} ", candidates[0]);
+                self.buffer.replace_range(word_start..self.cursor_position, &completed);
+                self.cursor_position = word_start + completed.len();
+            },
+            _ => {
+                // Several matches: extend to the longest common prefix and show the popup
+                let common = longest_common_prefix(&candidates);
+                if common.len() > prefix.len() {
+                    self.buffer.replace_range(word_start..self.cursor_position, &common);
+                    self.cursor_position = word_start + common.len();
+                }
+                self.completion = Some(Completion {
+                    candidates,
+                    index: None,
+                    word_start,
+                });
+            },
+        }
+    }
+
+    /// Popup listing the current completion candidates, if there are several
+    pub fn completion_popup<'a>(&'a self, theme: &'a Theme) -> Option<CompletionPopup<'a>> {
+        self.completion.as_ref().map(|completion| CompletionPopup {
+            candidates: &completion.candidates,
+            selected: completion.index,
+            theme,
+        })
+    }
     
     /// Handle keyboard input, returning whether the input was consumed
     /// and any completed command
@@ -188,52 +434,54 @@ impl CommandPrompt {
                     // Exit search mode but stay in command mode
                     self.search_mode = false;
                     self.search_query.clear();
-                    return (true, CommandInputResult::Pending);
                 },
                 Key::Char('\n') => {
                     // Accept the search result and exit search mode
                     self.search_mode = false;
                     self.search_query.clear();
                     self.cursor_position = self.buffer.len();
-                    return (true, CommandInputResult::Pending);
                 },
                 Key::Char(c) => {
                     // Add character to search query and search
                     self.search_query.push(c);
-                    if let Some(result) = self.history.search(&self.search_query) {
+                    if let Some(result) = self.search_history() {
                         self.buffer = result;
                         self.cursor_position = self.buffer.len();
                     }
-                    return (true, CommandInputResult::Pending);
                 },
-                Key::Backspace => {
+                Key::Backspace if !self.search_query.is_empty() => {
                     // Remove character from search query and search again
-                    if !self.search_query.is_empty() {
-                        self.search_query.pop();
-                        if let Some(result) = self.history.search(&self.search_query) {
-                            self.buffer = result;
-                            self.cursor_position = self.buffer.len();
-                        }
+                    self.search_query.pop();
+                    if let Some(result) = self.search_history() {
+                        self.buffer = result;
+                        self.cursor_position = self.buffer.len();
                     }
-                    return (true, CommandInputResult::Pending);
                 },
-                _ => return (true, CommandInputResult::Pending),
+                _ => {},
             }
+            (true, CommandInputResult::Pending)
         } else {
+            // Any key other than Tab ends a completion cycle
+            if key != Key::Char('\t') {
+                self.completion = None;
+            }
+
             // Regular command mode
             match key {
                 Key::Esc => {
                     return (true, CommandInputResult::Cancelled);
                 },
+                Key::Char('\n') if self.input_mode => {
+                    // Input mode stays open for the next line
+                    let line = std::mem::take(&mut self.buffer);
+                    self.cursor_position = 0;
+                    self.input_history.add(line.clone());
+                    return (true, CommandInputResult::Input(line));
+                },
                 Key::Char('\n') => {
-                    // Check if the buffer contains only numbers
-                    if let Ok(line_number) = self.buffer.parse::<usize>() {
-                        return (true, CommandInputResult::LineJump(line_number));
-                    } else {
-                        // Return the command for execution
-                        let cmd = self.buffer.clone();
-                        return (true, CommandInputResult::Command(cmd));
-                    }
+                    // Return the command for execution
+                    let cmd = self.buffer.clone();
+                    return (true, CommandInputResult::Command(cmd));
                 },
                 Key::Ctrl('a') => {
                     // Ctrl+A: Move to beginning of line
@@ -243,36 +491,32 @@ impl CommandPrompt {
                     // Ctrl+E: Move to end of line
                     self.cursor_position = self.buffer.len();
                 },
-                Key::Ctrl('k') => {
+                Key::Ctrl('k') if self.cursor_position < self.buffer.len() => {
                     // Ctrl+K: Kill to end of line
-                    if self.cursor_position < self.buffer.len() {
-                        self.buffer.truncate(self.cursor_position);
-                    }
+                    self.buffer.truncate(self.cursor_position);
                 },
-                Key::Ctrl('u') => {
+                Key::Ctrl('u') if self.cursor_position > 0 => {
                     // Ctrl+U: Kill to beginning of line
-                    if self.cursor_position > 0 {
-                        self.buffer = self.buffer[self.cursor_position..].to_string();
-                        self.cursor_position = 0;
-                    }
+                    self.buffer = self.buffer[self.cursor_position..].to_string();
+                    self.cursor_position = 0;
                 },
                 Key::Ctrl('w') => {
                     // Ctrl+W: Delete word backward
-                    let mut new_pos = self.cursor_position;
-                    // Skip spaces
-                    while new_pos > 0 && self.buffer.chars().nth(new_pos - 1).unwrap_or(' ').is_whitespace() {
-                        new_pos -= 1;
-                    }
-                    // Skip non-spaces
-                    while new_pos > 0 && !self.buffer.chars().nth(new_pos - 1).unwrap_or(' ').is_whitespace() {
-                        new_pos -= 1;
-                    }
-                    
+                    // Skip spaces, then non-spaces
+                    let new_pos = self.buffer[..self.cursor_position]
+                        .trim_end()
+                        .trim_end_matches(|c: char| !c.is_whitespace())
+                        .len();
+
                     if new_pos < self.cursor_position {
                         self.buffer.replace_range(new_pos..self.cursor_position, "");
                         self.cursor_position = new_pos;
                     }
                 },
+                // Commands are completed, but input is the command's business
+                Key::Char('\t') if !self.input_mode => {
+                    self.complete();
+                },
                 Key::Ctrl('r') => {
                     // Ctrl+R: Reverse search
                     self.search_mode = true;
@@ -287,17 +531,15 @@ impl CommandPrompt {
                     } else {
                         self.buffer.insert(self.cursor_position, c);
                     }
-                    self.cursor_position += 1;
+                    self.cursor_position += c.len_utf8();
                 },
                 Key::Backspace => {
                     self.status = None; // Clear status when editing
                     
                     // Handle backspace at cursor position
                     if self.cursor_position > 0 {
-                        self.cursor_position -= 1;
-                        if self.cursor_position < self.buffer.len() {
-                            self.buffer.remove(self.cursor_position);
-                        }
+                        self.cursor_position = self.previous_boundary();
+                        self.buffer.remove(self.cursor_position);
                     }
                 },
                 Key::Delete => {
@@ -307,17 +549,13 @@ impl CommandPrompt {
                         self.buffer.remove(self.cursor_position);
                     }
                 },
-                Key::Left => {
+                Key::Left if self.cursor_position > 0 => {
                     // Move cursor left
-                    if self.cursor_position > 0 {
-                        self.cursor_position -= 1;
-                    }
+                    self.cursor_position = self.previous_boundary();
                 },
-                Key::Right => {
+                Key::Right if self.cursor_position < self.buffer.len() => {
                     // Move cursor right
-                    if self.cursor_position < self.buffer.len() {
-                        self.cursor_position += 1;
-                    }
+                    self.cursor_position = self.next_boundary();
                 },
                 Key::Home => {
                     // Move to beginning of line
@@ -329,14 +567,14 @@ impl CommandPrompt {
                 },
                 Key::Up => {
                     // Navigate command history backward
-                    if let Some(cmd) = self.history.up() {
+                    if let Some(cmd) = self.history().up() {
                         self.buffer = cmd;
                         self.cursor_position = self.buffer.len();
                     }
                 },
                 Key::Down => {
                     // Navigate command history forward
-                    if let Some(cmd) = self.history.down() {
+                    if let Some(cmd) = self.history().down() {
                         self.buffer = cmd;
                         self.cursor_position = self.buffer.len();
                     } else {
@@ -346,40 +584,137 @@ impl CommandPrompt {
                 },
                 _ => {},
             }
-            return (true, CommandInputResult::Pending);
+            (true, CommandInputResult::Pending)
         }
     }
 }
 
-impl Widget for CommandPrompt {
-    fn render(self, area: Rect, buf: &mut Buffer) {
+/// Extension trait to enable rendering the CommandPrompt with a theme
+pub trait CommandPromptExt {
+    fn render_command_prompt(&mut self, widget: &CommandPrompt, area: Rect, theme: &Theme);
+}
+
+impl CommandPromptExt for ratatui::Frame<'_> {
+    fn render_command_prompt(&mut self, widget: &CommandPrompt, area: Rect, theme: &Theme) {
+        widget.render_line(theme, area, self.buffer_mut());
+        if let Some(column) = widget.cursor_column() {
+            self.set_cursor(area.x + column.min(area.width.saturating_sub(1)), area.y);
+        }
+    }
+}
+
+impl CommandPrompt {
+    /// Draw the prompt, status or hint line, with any background activity on the right
+    fn render_line(&self, theme: &Theme, area: Rect, buf: &mut Buffer) {
+        if let Some(activity) = &self.activity {
+            let width = (activity.chars().count() as u16).min(area.width);
+            let span = Span::styled(activity.as_str(), Style::default().fg(theme.accent));
+            buf.set_span(area.x + area.width - width, area.y, &span, width);
+        }
+        let area = match &self.activity {
+            Some(activity) => Rect { width: area.width.saturating_sub(activity.chars().count() as u16 + 1), ..area },
+            None => area,
+        };
+
         if !self.active && self.status.is_none() {
             // In normal mode, just show a helpful message
             let normal_text = "Press ':' to enter command mode";
-            let span = Span::styled(normal_text, Style::default().fg(Color::Gray));
+            let span = Span::styled(normal_text, Style::default().fg(theme.muted));
             buf.set_span(area.x, area.y, &span, area.width);
             return;
         }
         
+        let prefix = self.prefix();
         let display_text = if self.search_mode {
             format!("(reverse-i-search)`{}': {}", self.search_query, self.buffer)
         } else if let Some(ref msg) = self.status {
-            format!(":{} | {}", self.buffer, msg)
+            format!("{}{} | {}", prefix, self.buffer, msg)
         } else {
             // Format with cursor position indicator
-            format!(":{}", self.buffer)
+            format!("{}{}", prefix, self.buffer)
             // We'll handle cursor rendering separately with set_cursor
         };
         
         let style = if self.search_mode {
-            Style::default().fg(Color::Blue)
+            Style::default().fg(theme.search)
         } else if self.status.is_some() {
-            Style::default().fg(Color::Green)
+            Style::default().fg(theme.status)
         } else {
-            Style::default().fg(Color::White)
+            Style::default().fg(theme.text)
         };
         
         let span = Span::styled(display_text, style);
         buf.set_span(area.x, area.y, &span, area.width);
     }
 }
+
+/// Longest prefix shared by all candidates
+fn longest_common_prefix(candidates: &[String]) -> String {
+    let first = &candidates[0];
+    let mut len = first.len();
+    for candidate in &candidates[1..] {
+        // Compared a character at a time, so the prefix ends on a character boundary
+        len = first.char_indices()
+            .zip(candidate.chars())
+            .find(|((index, a), b)| *index >= len || a != b)
+            .map_or(len.min(candidate.len()), |((index, _), _)| index);
+    }
+    first[..len].to_string()
+}
+
+/// A small popup listing completion candidates above the prompt
+#[derive(Debug, Clone)]
+pub struct CompletionPopup<'a> {
+    candidates: &'a [String],
+    selected: Option<usize>,
+    theme: &'a Theme,
+}
+
+impl CompletionPopup<'_> {
+    /// Maximum number of candidates shown at once
+    const MAX_ROWS: usize = 8;
+
+    /// Area for the popup, anchored just above the prompt
+    pub fn area(&self, prompt_area: Rect) -> Rect {
+        let rows = self.candidates.len().min(Self::MAX_ROWS) as u16;
+        let width = self.candidates.iter().map(|c| c.len()).max().unwrap_or(0) as u16 + 4;
+        let height = (rows + 2).min(prompt_area.y);
+        Rect {
+            x: prompt_area.x + 1,
+            y: prompt_area.y.saturating_sub(height),
+            width: width.min(prompt_area.width.saturating_sub(1)),
+            height,
+        }
+    }
+}
+
+impl Widget for CompletionPopup<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        // Keep the selected candidate in view
+        let visible = area.height.saturating_sub(2) as usize;
+        let selected = self.selected.unwrap_or(0);
+        let first = selected.saturating_sub(visible.saturating_sub(1));
+
+        let lines: Vec<Line> = self.candidates
+            .iter()
+            .enumerate()
+            .skip(first)
+            .take(visible)
+            .map(|(i, candidate)| {
+                let style = if Some(i) == self.selected {
+                    Style::default().fg(self.theme.accent_text).bg(self.theme.accent)
+                } else {
+                    Style::default().fg(self.theme.text)
+                };
+                Line::from(Span::styled(candidate.as_str(), style))
+            })
+            .collect();
+
+        Clear.render(area, buf);
+        Paragraph::new(lines)
+            .block(Block::default().borders(Borders::ALL).border_style(
+                Style::default().fg(self.theme.title).add_modifier(Modifier::BOLD),
+            ))
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,127 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use crate::commands::COMMANDS;
+use crate::keymap::{key_label, KeyMap};
+use crate::theme::Theme;
+
+/// A scrollable overlay listing key bindings and commands
+#[derive(Debug, Clone, Default)]
+pub struct HelpOverlay {
+    /// Whether the overlay is shown
+    open: bool,
+    /// Number of lines scrolled from the top
+    scroll: usize,
+}
+
+impl HelpOverlay {
+    /// Create a new (closed) help overlay
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the overlay is currently shown
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Show the overlay from the top
+    pub fn open(&mut self) {
+        self.open = true;
+        self.scroll = 0;
+    }
+
+    /// Hide the overlay
+    pub fn close(&mut self) {
+        self.open = false;
+    }
+
+    /// Scroll up by the specified amount
+    pub fn scroll_up(&mut self, amount: usize) {
+        self.scroll = self.scroll.saturating_sub(amount);
+    }
+
+    /// Scroll down by the specified amount
+    pub fn scroll_down(&mut self, amount: usize) {
+        self.scroll += amount;
+    }
+
+    /// Build the help text from the live binding and command tables
+    fn help_lines(keymap: &KeyMap, theme: &Theme) -> Vec<Line<'static>> {
+        let heading = Style::default().fg(theme.title).add_modifier(Modifier::BOLD);
+        let key_style = Style::default().fg(theme.accent);
+        let text_style = Style::default().fg(theme.text);
+
+        let mut lines = vec![Line::from(Span::styled("Keys", heading))];
+        for (action, keys) in keymap.describe() {
+            let keys = keys.iter().map(key_label).collect::<Vec<_>>().join(", ");
+            lines.push(Line::from(vec![
+                Span::styled(format!("  {:<20}", keys), key_style),
+                Span::styled(action.description(), text_style),
+            ]));
+        }
+
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled("Commands", heading)));
+        for spec in COMMANDS {
+            let mut usage = spec.usage_line();
+            if !spec.aliases.is_empty() {
+                usage.push_str(&format!(" (alias: {})", spec.aliases.join(", ")));
+            }
+            lines.push(Line::from(Span::styled(format!("  {}", usage), key_style)));
+            lines.push(Line::from(Span::styled(format!("      {}", spec.description), text_style)));
+        }
+        lines.push(Line::from(Span::styled("  :<n>, :+<n>, :-<n>", key_style)));
+        lines.push(Line::from(Span::styled("      Jump to line n of the view, or move n lines down or up", text_style)));
+        lines
+    }
+
+    /// Handle rendering the overlay
+    fn render_help(&self, keymap: &KeyMap, theme: &Theme, area: Rect) -> Paragraph<'static> {
+        let lines = Self::help_lines(keymap, theme);
+        let visible = area.height.saturating_sub(2) as usize;
+        let max_scroll = lines.len().saturating_sub(visible);
+        let scroll = self.scroll.min(max_scroll);
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title(Span::styled(
+                "Help - Esc/q to close",
+                Style::default().fg(theme.title).add_modifier(Modifier::BOLD),
+            ));
+
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0))
+    }
+}
+
+/// Centered rectangle covering the given percentage of `area`
+fn centered_rect(percent_x: u16, percent_y: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    let height = area.height * percent_y / 100;
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + (area.height - height) / 2,
+        width,
+        height,
+    }
+}
+
+/// Extension trait to enable rendering the HelpOverlay over the whole frame
+pub trait HelpOverlayExt {
+    fn render_help_overlay(&mut self, widget: &HelpOverlay, area: Rect, keymap: &KeyMap, theme: &Theme);
+}
+
+impl HelpOverlayExt for ratatui::Frame<'_> {
+    fn render_help_overlay(&mut self, widget: &HelpOverlay, area: Rect, keymap: &KeyMap, theme: &Theme) {
+        let area = centered_rect(80, 80, area);
+        let paragraph = widget.render_help(keymap, theme, area);
+        self.render_widget(Clear, area);
+        self.render_widget(paragraph, area);
+    }
+}
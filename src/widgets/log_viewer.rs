@@ -1,15 +1,136 @@
 use ratatui::{
-    backend::Backend,
     buffer::Buffer,
     layout::Rect,
     style::{Color, Modifier, Style},
-    text::{Line, Span},
-    widgets::{Block, Borders, Paragraph, Widget, Wrap},
+    text::Span,
+    widgets::{Block, Borders, Scrollbar, ScrollbarOrientation, ScrollbarState, Widget},
 };
-use crate::log_entry::LogEntry;
-use crate::settings::LogSettings;
-use ansi_parser::{Output, AnsiParser};
-use unicode_width::UnicodeWidthChar;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::ops::Range;
+use chrono::{DateTime, Local};
+use crate::log_entry::{local_time, LogEntry, Severity};
+use crate::log_storage::{EntryId, ViewEntries};
+use crate::settings::{DisplayOptions, Highlight, LogSettings};
+use crate::theme::Theme;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// A remembered entry, identified by its source and per-source line number
+/// The timestamp locates its neighbours when the entry itself is filtered out
+#[derive(Debug, Clone, PartialEq)]
+struct EntryAnchor {
+    id: EntryId,
+    /// Milliseconds since the Unix epoch
+    timestamp: i64,
+    /// Bottom-relative position of the entry when it was recorded
+    offset: usize,
+}
+
+impl EntryAnchor {
+    /// Record the entry `offset` lines up from the bottom, if there is one
+    fn at(filtered_logs: &ViewEntries, offset: usize) -> Option<Self> {
+        let index = filtered_logs.len().checked_sub(offset + 1)?;
+        Some(Self {
+            id: filtered_logs.id(index)?,
+            timestamp: filtered_logs.timestamp_millis(index)?,
+            offset,
+        })
+    }
+
+    /// Current bottom-relative position of the entry (or the next one after it),
+    /// shifted by however far `current` has moved since the anchor was recorded
+    fn resolve(&self, filtered_logs: &ViewEntries, current: usize) -> usize {
+        // Entries are sorted by timestamp, so if it's gone the next one is the first after it
+        let index = filtered_logs.find(self.id, self.timestamp)
+            .unwrap_or_else(|| filtered_logs.partition_point(|t| t <= self.timestamp));
+        let offset = filtered_logs.len().saturating_sub(index + 1);
+        (offset + current).saturating_sub(self.offset)
+    }
+}
+
+/// Most entries whose drawn rows are kept before the cache starts over
+const MAX_CACHED_LINES: usize = 4096;
+
+/// Everything besides the entry itself that drawn rows depend on
+#[derive(Debug, Clone, PartialEq)]
+struct LineFormat {
+    options: DisplayOptions,
+    theme: Theme,
+    width: usize,
+}
+
+/// Drawn rows of one entry, with the per-entry state they were drawn with
+#[derive(Debug, Clone)]
+struct CachedLine {
+    selected: bool,
+    delta: Option<chrono::Duration>,
+    /// The only frame the rows are good for, when the entry is redrawn in place or
+    /// wasn't in memory, so they can't be kept
+    frame: Option<u64>,
+    rows: Vec<Vec<Span<'static>>>,
+}
+
+/// Rows of recently drawn entries, so redrawing or scrolling over the same entries skips
+/// formatting, styling and truncating them again. Frames are drawn straight from here.
+/// Dropped whenever the format changes
+#[derive(Debug, Clone, Default)]
+struct LineCache {
+    format: Option<LineFormat>,
+    lines: HashMap<EntryId, CachedLine>,
+    /// Frames drawn so far
+    frame: u64,
+}
+
+impl LineCache {
+    /// Start a frame, starting over if the rows would now be drawn differently
+    fn start_frame(&mut self, options: DisplayOptions, theme: &Theme, width: usize) {
+        let unchanged = self.format.as_ref()
+            .is_some_and(|format| format.options == options && format.theme == *theme && format.width == width);
+        if !unchanged {
+            self.lines.clear();
+            self.format = Some(LineFormat { options, theme: theme.clone(), width });
+        }
+        self.frame += 1;
+    }
+
+    /// Rows for an entry, drawn with `draw` unless they're cached
+    fn rows<'a>(
+        &mut self,
+        filtered_logs: &ViewEntries,
+        index: usize,
+        selected: bool,
+        show_delta: bool,
+        draw: impl FnOnce() -> Vec<Vec<Span<'a>>>,
+    ) -> &[Vec<Span<'static>>] {
+        let Some(id) = filtered_logs.id(index) else {
+            return &[];
+        };
+        let delta = index.checked_sub(1)
+            .and_then(|previous| filtered_logs.timestamp_millis(previous))
+            .filter(|_| show_delta)
+            .zip(filtered_logs.timestamp_millis(index))
+            .map(|(previous, timestamp)| chrono::Duration::milliseconds(timestamp - previous));
+        // A redrawn entry keeps its id but not its content
+        let keep = filtered_logs.get(index).is_some_and(|entry| !entry.redraw);
+        let frame = self.frame;
+        let fresh = self.lines.get(&id).is_some_and(|line| {
+            line.selected == selected && line.delta == delta && match line.frame {
+                Some(drawn) => drawn == frame,
+                None => keep,
+            }
+        });
+        if !fresh {
+            if self.lines.len() >= MAX_CACHED_LINES {
+                self.lines.clear();
+            }
+            let rows = draw().into_iter()
+                .map(|row| row.into_iter().map(|span| Span::styled(span.content.into_owned(), span.style)).collect())
+                .collect();
+            self.lines.insert(id, CachedLine { selected, delta, frame: (!keep).then_some(frame), rows });
+        }
+        &self.lines[&id].rows
+    }
+}
 
 /// A widget for displaying log entries
 #[derive(Debug, Clone)]
@@ -20,6 +141,20 @@ pub struct LogViewer {
     is_paused: bool,
     /// Widget title
     title: String,
+    /// Line cursor as a number of entries up from the bottom, when cursor mode is on
+    cursor: Option<usize>,
+    /// Other end of a visual line selection, counted the same way as the cursor
+    visual_anchor: Option<usize>,
+    /// Entries that arrived from sources this view wasn't following
+    unread: usize,
+    /// Entries the retention policy has dropped from storage
+    dropped: usize,
+    /// Entries under the bottom line, the cursor and the selection end as of the last frame
+    bottom_entry: Option<EntryAnchor>,
+    cursor_entry: Option<EntryAnchor>,
+    visual_entry: Option<EntryAnchor>,
+    /// Rows drawn in recent frames, filled in while drawing, which only borrows the viewer
+    lines: RefCell<LineCache>,
 }
 
 impl Default for LogViewer {
@@ -35,6 +170,14 @@ impl LogViewer {
             scroll_offset: 0, // offset is the number of lines up from the bottom
             is_paused: false, // if true it should now scroll
             title: "Oxtail - Neon Terminal UI".to_string(),
+            cursor: None,
+            visual_anchor: None,
+            unread: 0,
+            dropped: 0,
+            bottom_entry: None,
+            cursor_entry: None,
+            visual_entry: None,
+            lines: RefCell::default(),
         }
     }
     
@@ -48,6 +191,28 @@ impl LogViewer {
     pub fn is_paused(&self) -> bool {
         self.is_paused
     }
+
+    /// Set the number of dropped entries shown in the title
+    pub fn set_dropped(&mut self, dropped: usize) -> &mut Self {
+        self.dropped = dropped;
+        self
+    }
+
+    /// Forget the entries drawn and followed so far, after storage was cleared and their
+    /// ids may be given to new entries
+    pub fn forget_entries(&mut self) -> &mut Self {
+        self.lines.get_mut().lines.clear();
+        self.bottom_entry = None;
+        self.cursor_entry = None;
+        self.visual_entry = None;
+        self
+    }
+
+    /// Set the number of unread entries shown in the title
+    pub fn set_unread(&mut self, unread: usize) -> &mut Self {
+        self.unread = unread;
+        self
+    }
     
     /// Set the title of the log viewer
     #[allow(dead_code)]
@@ -119,6 +284,16 @@ impl LogViewer {
         self
     }
 
+    /// Scroll to an entry and put the cursor on it
+    pub fn jump_to_entry(&mut self, index: usize, total_lines: usize) -> &mut Self {
+        let offset = total_lines.saturating_sub(index + 1);
+        self.scroll_offset = offset;
+        self.cursor = Some(offset);
+        self.visual_anchor = None;
+        self.set_paused(true);
+        self
+    }
+
     /// Jump to the start of the log
     pub fn jump_to_start(&mut self, total_lines: usize) -> &mut Self {
         self.scroll_offset = total_lines.saturating_sub(1);
@@ -133,141 +308,411 @@ impl LogViewer {
         self
     }
     
-    /// Adjust scroll position for new entries
-    pub fn adjust_for_new_entries(&mut self, new_entries_count: usize) -> &mut Self {
-        // When paused, we should maintain the exact position in the log
-        // by adjusting the scroll offset by the number of new entries
-        if self.is_paused {
-            self.scroll_offset += new_entries_count;
-        }
-        // } else if self.scroll_offset > new_entries_count {
-        //     // When not paused but scrolled up, maintain some position but allow gradual scrolling
-        //     // self.scroll_offset -= new_entries_count;
-        // } else {
-        //     // When not paused, we want to stay at the bottom
-        //     self.scroll_offset = 0;
-        // }
+    /// Whether the line cursor is shown
+    pub fn has_cursor(&self) -> bool {
+        self.cursor.is_some()
+    }
+
+    /// Turn the line cursor on (at the bottom visible line) or off
+    pub fn toggle_cursor(&mut self) -> &mut Self {
+        if self.cursor.is_some() {
+            self.cursor = None;
+        } else {
+            self.cursor = Some(self.scroll_offset);
+            self.set_paused(true);
+        }
         self
     }
 
-    /// Truncate ANSI strings to fit within a specified width
-    /// This is non-trivial because of ANSI escape codes
-    /// it also doesn't always clear at the end
-    fn truncate_ansi(&self, input: &str, max_width: usize) -> String {
-        let mut result = String::new();
-        let mut current_width = 0;
-    
-        // Parse the input string into ANSI pieces.
-        for piece in input.ansi_parse() {
-            match piece {
-                Output::TextBlock(text) => {
-                    let mut remaining = text;
-                    while !remaining.is_empty() && current_width < max_width {
-                        // Get the next character.
-                        let ch = remaining.chars().next().unwrap();
-                        let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
-                        
-                        // Check if adding this character would exceed max_width.
-                        if current_width + ch_width > max_width {
-                            break;
-                        }
-                        result.push(ch);
-                        current_width += ch_width;
-                        remaining = &remaining[ch.len_utf8()..];
-                    }
-                }
-                // For escape sequences, convert them to string properly
-                Output::Escape(seq) => {
-                    result.push_str(&format!("\x1b{}", seq));
-                }
+    /// Turn the line cursor (and any visual selection) off
+    pub fn clear_cursor(&mut self) -> &mut Self {
+        self.cursor = None;
+        self.visual_anchor = None;
+        self
+    }
+
+    /// Whether a visual line selection is active
+    pub fn is_visual(&self) -> bool {
+        self.visual_anchor.is_some()
+    }
+
+    /// Start a visual line selection at the cursor, or end the current one
+    pub fn toggle_visual(&mut self) -> &mut Self {
+        if self.visual_anchor.is_some() {
+            self.visual_anchor = None;
+        } else {
+            if self.cursor.is_none() {
+                self.toggle_cursor();
             }
-            if current_width >= max_width {
-                break;
+            self.visual_anchor = self.cursor;
+        }
+        self
+    }
+
+    /// Inclusive range of selected entry indices: the visual selection, or just the cursor line
+    pub fn selected_range(&self, total_lines: usize) -> Option<(usize, usize)> {
+        let cursor = self.cursor_index(total_lines)?;
+        let anchor = self.visual_anchor
+            .and_then(|offset| total_lines.checked_sub(offset + 1))
+            .unwrap_or(cursor);
+        Some((cursor.min(anchor), cursor.max(anchor)))
+    }
+
+    /// Index of the cursor entry in the filtered entries, if the cursor is on
+    pub fn cursor_index(&self, total_lines: usize) -> Option<usize> {
+        self.cursor.and_then(|offset| total_lines.checked_sub(offset + 1))
+    }
+
+    /// Move the cursor up, scrolling to keep it within a page of `page_size` lines
+    pub fn cursor_up(&mut self, amount: usize, page_size: usize, total_lines: usize) -> &mut Self {
+        if let Some(cursor) = self.cursor {
+            let cursor = (cursor + amount).min(total_lines.saturating_sub(1));
+            self.cursor = Some(cursor);
+            if cursor >= self.scroll_offset + page_size.max(1) {
+                self.scroll_offset = cursor + 1 - page_size.max(1);
+            }
+            self.set_paused(true);
+        }
+        self
+    }
+
+    /// Move the cursor down, scrolling to keep it visible
+    pub fn cursor_down(&mut self, amount: usize) -> &mut Self {
+        if let Some(cursor) = self.cursor {
+            let cursor = cursor.saturating_sub(amount);
+            self.cursor = Some(cursor);
+            if cursor < self.scroll_offset {
+                self.scroll_offset = cursor;
             }
         }
+        self
+    }
     
-        // Pad with spaces if the visible width is less than max_width.
-        // if current_width < max_width {
-        //     result.push_str(&"X".repeat(max_width - current_width));
-        // }
+    /// Keep the view, cursor and selection on the same entries as the last frame
+    /// Entries are followed by identity, so this holds when new entries arrive,
+    /// land out of order, or the filter changes how many entries are visible
+    pub fn follow_anchors(&mut self, filtered_logs: &ViewEntries) -> &mut Self {
+        // While following, the view stays at the bottom
+        if self.is_paused {
+            if let Some(anchor) = &self.bottom_entry {
+                self.scroll_offset = anchor.resolve(filtered_logs, self.scroll_offset);
+            }
+        }
+        if let (Some(anchor), Some(cursor)) = (&self.cursor_entry, self.cursor) {
+            self.cursor = Some(anchor.resolve(filtered_logs, cursor));
+        }
+        if let (Some(anchor), Some(visual)) = (&self.visual_entry, self.visual_anchor) {
+            self.visual_anchor = Some(anchor.resolve(filtered_logs, visual));
+        }
 
-        // Strip any trailing whitespace
-        result.trim_end().to_string()
+        self.bottom_entry = EntryAnchor::at(filtered_logs, self.scroll_offset);
+        self.cursor_entry = self.cursor.and_then(|offset| EntryAnchor::at(filtered_logs, offset));
+        self.visual_entry = self.visual_anchor.and_then(|offset| EntryAnchor::at(filtered_logs, offset));
+        self
     }
 
-    /// Handle rendering the log entries to the screen
-    fn render_logs<'a>(
-        &self,
-        filtered_logs: &[&'a LogEntry],
-        settings: &LogSettings,
-        area: Rect,
-    ) -> Paragraph<'a> {
-        // Calculate visible lines
-        let total_filtered_lines = filtered_logs.len();
-        let log_area_height = area.height.saturating_sub(2) as usize; // Subtract 2 for the borders
-        let log_area_width = area.width.saturating_sub(2) as usize; // Subtract 2 for the borders
+    /// Timestamp of the entry at the bottom of the view, as of the last `follow_anchors`
+    pub fn bottom_timestamp(&self) -> Option<DateTime<Local>> {
+        self.bottom_entry.as_ref().map(|anchor| local_time(anchor.timestamp))
+    }
+
+    /// Scroll so the bottom of the view is the last entry at or before `timestamp`
+    pub fn scroll_to_timestamp(&mut self, filtered_logs: &ViewEntries, timestamp: DateTime<Local>) -> &mut Self {
+        let after = filtered_logs.partition_point(|t| t <= timestamp.timestamp_millis());
+        // Nothing that early: show the start of the log
+        self.scroll_offset = filtered_logs.len().saturating_sub(after.max(1));
+        self.set_paused(true);
+        self
+    }
 
+    /// Range of entry indices shown in a log area of `height` rows
+    fn visible_range(&self, total_lines: usize, height: usize) -> (usize, usize) {
         // Calculate valid scroll range
-        let max_scroll = total_filtered_lines.saturating_sub(log_area_height);
+        let max_scroll = total_lines.saturating_sub(height);
         let effective_scroll = self.scroll_offset.min(max_scroll);
-        
-        // Calculate the range of logs to display
-        let start = if total_filtered_lines > log_area_height + effective_scroll {
-            total_filtered_lines - log_area_height - effective_scroll
+
+        let start = if total_lines > height + effective_scroll {
+            total_lines - height - effective_scroll
         } else {
             0
         };
-        let end = total_filtered_lines.saturating_sub(effective_scroll);
-        
-        // Format the visible lines based on settings
-        let display_lines: Vec<Line> = filtered_logs[start..end]
-            .iter()
-            .map(|entry| {
-                let formatted = entry.format(settings, None);
-                let style = match entry.source.as_str() {
-                    "stderr" => Style::default().fg(Color::Red),
-                    "stdout" => Style::default().fg(Color::Yellow),
-                    _ => Style::default().fg(Color::White),
-                };
-                // pad the formatted string to fit the log area width
-                let formatted = if settings.show_raw {
-                    // raw mode -- need to figure out some better way to pad this
-                    let plain_len = entry.content_plain.len();
-                    let padding = log_area_width.saturating_sub(plain_len);
-                    let truncated = self.truncate_ansi(&formatted, log_area_width);
-                    
-                    // create extra spaces based on the padding
-                    let extra_spaces = " ".repeat(padding);
-                    format!("{truncated}{extra_spaces}")
-                } else {
-                    // if not raw mode, we should be okay
-                    let padding = log_area_width.saturating_sub(formatted.len());
-                    let extra_spaces = " ".repeat(padding);
-                    format!("{:<width$}{}", formatted, extra_spaces, width = log_area_width)
-                };
-                Line::from(Span::styled(formatted, style))
-            })
+        let end = total_lines.saturating_sub(effective_scroll);
+        (start, end)
+    }
+
+    /// Range of entry indices that drawing a log area of `height` rows may read, with or
+    /// without wrapping, including the entry above the top one for its delta
+    pub fn window(&self, total_lines: usize, height: usize) -> Range<usize> {
+        let end = total_lines - self.scroll_offset.min(total_lines.saturating_sub(1));
+        end.saturating_sub(height + 1)..(end + height).min(total_lines)
+    }
+
+    /// Scroll so the top of the view sits at `fraction` (0.0 = start, 1.0 = end) of the log
+    pub fn scroll_to_fraction(&mut self, fraction: f64, total_lines: usize, height: usize) -> &mut Self {
+        let max_scroll = total_lines.saturating_sub(height);
+        let fraction = fraction.clamp(0.0, 1.0);
+        self.scroll_offset = ((1.0 - fraction) * max_scroll as f64).round() as usize;
+        self.set_paused(self.scroll_offset > 0);
+        self
+    }
+
+    /// Scrollbar state for the current view, or None when everything fits
+    fn scrollbar_state(&self, total_lines: usize, height: usize) -> Option<ScrollbarState> {
+        let max_start = total_lines.checked_sub(height).filter(|m| *m > 0)?;
+        let (start, _) = self.visible_range(total_lines, height);
+        // The scrollbar measures position against content length, so scale the
+        // top line so that the last page puts the thumb at the bottom of the track
+        let position = (start as f64 / max_start as f64 * total_lines as f64).round() as usize;
+        Some(ScrollbarState::new(total_lines)
+            .viewport_content_length(height)
+            .position(position))
+    }
+
+    /// Index of the entry displayed on a row of the log area (0-based, inside the borders)
+    pub fn entry_index_at_row(&self, row: usize, filtered_logs: &ViewEntries, settings: &LogSettings, area: Rect) -> Option<usize> {
+        let width = area.width as usize;
+        self.layout_rows(filtered_logs.len(), settings.wrap, area.height as usize, |index| {
+            self.row_count(filtered_logs, index, settings, width)
+        })
+            .get(row)
+            .map(|(index, _)| *index)
+    }
+
+    /// Number of entries that fit in the log area, for paging and keeping the cursor in view
+    pub fn page_entries(&self, filtered_logs: &ViewEntries, settings: &LogSettings, area: Rect) -> usize {
+        if !settings.wrap {
+            return area.height as usize;
+        }
+        let width = area.width as usize;
+        let mut rows = self.layout_rows(filtered_logs.len(), true, area.height as usize, |index| {
+            self.row_count(filtered_logs, index, settings, width)
+        });
+        rows.dedup_by_key(|(index, _)| *index);
+        // The top entry may only be partly visible
+        rows.len().saturating_sub(1).max(1)
+    }
+
+    /// Spans for one entry: delta column, metadata prefix and content
+    /// Also returns the entry's base style, used to pad its rows
+    fn entry_spans<'a>(
+        &self,
+        filtered_logs: &ViewEntries<'a>,
+        index: usize,
+        settings: &LogSettings,
+        theme: &Theme,
+        selected_range: Option<(usize, usize)>,
+    ) -> (Vec<Span<'a>>, Style) {
+        // A spilled entry outside the paged-in window draws as a blank row
+        let Some(entry) = filtered_logs.get(index) else {
+            return (Vec::new(), Style::default().fg(theme.text));
+        };
+        let group = settings.groups.group_of(&entry.source).map(|(index, _)| index);
+        let style = match (entry.severity, group, &*entry.source) {
+            // Decoded severities take priority over the group and source colors
+            (Some(severity), _, _) if severity <= Severity::Error => Style::default().fg(theme.error),
+            (_, Some(group), _) => Style::default().fg(theme.group(group)),
+            // The command's streams, named like `server:stderr`
+            (_, _, source) if source.ends_with(":stderr") => Style::default().fg(theme.stderr),
+            (_, _, source) if source.ends_with(":stdout") => Style::default().fg(theme.stdout),
+            _ => Style::default().fg(theme.text),
+        };
+        let selected = selected_range
+            .is_some_and(|(first, last)| (first..=last).contains(&index));
+        let style = if selected {
+            style.bg(theme.selection)
+        } else {
+            style
+        };
+
+        // The entry's own ANSI styling is layered over the source color
+        let mut spans = Vec::new();
+        if settings.show_delta {
+            // Measured from the previous visible entry, even when it's scrolled off the top
+            let delta = index.checked_sub(1)
+                .and_then(|previous| filtered_logs.timestamp_millis(previous))
+                .map(|previous| chrono::Duration::milliseconds(entry.timestamp_millis() - previous));
+            let large = delta
+                .and_then(|delta| delta.to_std().ok())
+                .is_some_and(|delta| delta >= settings.delta_threshold);
+            let delta_style = if large {
+                style.fg(theme.accent).add_modifier(Modifier::BOLD)
+            } else {
+                style.fg(theme.muted)
+            };
+            let text = delta.map(format_delta).unwrap_or_default();
+            spans.push(Span::styled(format!("{:>width$} ", text, width = DELTA_WIDTH), delta_style));
+        }
+        spans.push(Span::styled(entry.prefix(settings), style));
+        spans.extend(highlight_segments(entry.styled_content(settings), &settings.highlights, theme)
+            .into_iter()
+            .map(|(ansi_style, text)| Span::styled(text, style.patch(ansi_style))));
+        if selected {
+            // Keep the selection visible over any background set by the entry
+            for span in &mut spans {
+                span.style = span.style.bg(theme.selection);
+            }
+        }
+        (spans, style)
+    }
+
+    /// Rows for one entry: a single truncated row, or several when wrapping
+    fn entry_rows<'a>(
+        &self,
+        filtered_logs: &ViewEntries<'a>,
+        index: usize,
+        settings: &LogSettings,
+        theme: &Theme,
+        width: usize,
+        selected_range: Option<(usize, usize)>,
+    ) -> Vec<Vec<Span<'a>>> {
+        if let Some(entry) = filtered_logs.get(index).filter(|entry| entry.is_mark()) {
+            return vec![mark_row(entry, index, theme, width, selected_range)];
+        }
+        let (spans, style) = self.entry_spans(filtered_logs, index, settings, theme, selected_range);
+        if !settings.wrap {
+            return vec![fit_spans(spans, width, style)];
+        }
+        let prefix = Span::styled(settings.wrap_prefix.clone(), style.fg(theme.dim));
+        wrap_spans(spans, width, prefix)
+            .into_iter()
+            .map(|row| fit_spans(row, width, style))
+            .collect()
+    }
+
+    /// Display rows for a log area, as the index of the entry each belongs to and which of
+    /// its rows it is, given how many rows an entry takes
+    /// Without wrapping each entry takes one (truncated) row. With wrapping, entries are
+    /// filled in upwards from the bottom entry, so the top one may only partly show.
+    fn layout_rows(
+        &self,
+        total_lines: usize,
+        wrap: bool,
+        height: usize,
+        mut row_count: impl FnMut(usize) -> usize,
+    ) -> Vec<(usize, usize)> {
+        if !wrap {
+            let (start, end) = self.visible_range(total_lines, height);
+            return (start..end).map(|index| (index, 0)).collect();
+        }
+
+        // Fewer entries fit than rows, so the scroll offset is only limited by the entry count
+        let end = total_lines - self.scroll_offset.min(total_lines.saturating_sub(1));
+        let mut counts = std::collections::VecDeque::new();
+        let mut filled = 0;
+        let mut first = end;
+        while filled < height && first > 0 {
+            first -= 1;
+            let count = row_count(first);
+            counts.push_front(count);
+            filled += count;
+        }
+        // Near the start of the log there may be room left for entries below
+        let mut next = end;
+        while filled < height && next < total_lines {
+            let count = row_count(next);
+            counts.push_back(count);
+            filled += count;
+            next += 1;
+        }
+
+        let mut rows: Vec<(usize, usize)> = (first..next)
+            .zip(counts)
+            .flat_map(|(index, count)| (0..count).map(move |row| (index, row)))
             .collect();
-        
+        if rows.len() > height {
+            if next > end {
+                rows.truncate(height);
+            } else {
+                rows.drain(..rows.len() - height);
+            }
+        }
+        rows
+    }
+
+    /// Rows an entry takes, drawn without the cache, for questions about the layout
+    /// asked between frames
+    fn row_count(&self, filtered_logs: &ViewEntries, index: usize, settings: &LogSettings, width: usize) -> usize {
+        if !settings.wrap {
+            return 1;
+        }
+        self.entry_rows(filtered_logs, index, settings, &Theme::default(), width, None).len()
+    }
+
+    /// Cached rows of an entry, drawing them if need be
+    fn cached_rows<'c>(
+        &self,
+        cache: &'c mut LineCache,
+        filtered_logs: &ViewEntries,
+        index: usize,
+        settings: &LogSettings,
+        theme: &Theme,
+        width: usize,
+    ) -> &'c [Vec<Span<'static>>] {
+        let selected_range = self.selected_range(filtered_logs.len());
+        let selected = selected_range.is_some_and(|(first, last)| (first..=last).contains(&index));
+        cache.rows(filtered_logs, index, selected, settings.show_delta, || {
+            self.entry_rows(filtered_logs, index, settings, theme, width, selected_range)
+        })
+    }
+
+    /// Handle rendering the log entries to the screen
+    /// Rows are written from the cache straight into the buffer, so a frame only draws
+    /// (and copies) entries that weren't on screen recently
+    fn render_logs(
+        &self,
+        filtered_logs: &ViewEntries,
+        settings: &LogSettings,
+        theme: &Theme,
+        area: Rect,
+        title: Option<&str>,
+        buf: &mut Buffer,
+    ) {
         // Get the title with pause indicator
-        let title = if self.is_paused {
-            format!("{} offset: {} - [PAUSED]", self.title, self.scroll_offset)
+        let title = title.unwrap_or(&self.title);
+        let title = if self.is_visual() {
+            format!("{} offset: {} - [VISUAL]", title, self.scroll_offset)
+        } else if self.is_paused {
+            format!("{} offset: {} - [PAUSED]", title, self.scroll_offset)
+        } else {
+            format!("{} offset: {}", title, self.scroll_offset)
+        };
+        let title = if self.unread > 0 {
+            format!("{} - {} unread", title, self.unread)
         } else {
-            format!("{} offset: {}", self.title, self.scroll_offset)
+            title
+        };
+        // Older entries are gone, so the start of the log isn't the start of the input
+        let title = if self.dropped > 0 {
+            format!("{} - {} oldest dropped", title, self.dropped)
+        } else {
+            title
         };
         
         // Create the block with title
         let log_block = Block::default()
             .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
             .title(Span::styled(
                 title,
-                Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                Style::default().fg(theme.title).add_modifier(Modifier::BOLD),
             ));
         
-        // Create and return the paragraph widget
-        Paragraph::new(display_lines)
-            .block(log_block)
-            // .wrap(Wrap { trim: true })
+        let log_area = log_block.inner(area);
+        log_block.render(area, buf);
+
+        let width = log_area.width as usize;
+        let mut cache = self.lines.borrow_mut();
+        cache.start_frame(settings.display_options(), theme, width);
+        let rows = self.layout_rows(filtered_logs.len(), settings.wrap, log_area.height as usize, |index| {
+            self.cached_rows(&mut cache, filtered_logs, index, settings, theme, width).len()
+        });
+        for ((index, row), y) in rows.into_iter().zip(log_area.top()..log_area.bottom()) {
+            let spans = self.cached_rows(&mut cache, filtered_logs, index, settings, theme, width);
+            let mut x = log_area.x;
+            for span in spans.get(row).into_iter().flatten() {
+                x = buf.set_span(x, y, span, log_area.right().saturating_sub(x)).0;
+            }
+        }
     }
 }
 
@@ -284,7 +729,7 @@ impl Widget for LogViewer {
                     } else { 
                         self.title 
                     },
-                    Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+                    Style::default().fg(Theme::default().title).add_modifier(Modifier::BOLD),
                 )
             )
             .borders(Borders::ALL);
@@ -293,26 +738,188 @@ impl Widget for LogViewer {
     }
 }
 
-/// Extension trait to enable rendering LogViewer with log entries
+/// Width of the delta column, not counting the space after it
+const DELTA_WIDTH: usize = 9;
+
+/// Columns taken by the delta column before an entry's prefix
+pub fn delta_column_width(settings: &LogSettings) -> usize {
+    if settings.show_delta { DELTA_WIDTH + 1 } else { 0 }
+}
+
+/// Short elapsed time such as `+0.532s`, `+12.3s`, `+4m05s` or `+2h10m`
+fn format_delta(delta: chrono::Duration) -> String {
+    let millis = delta.num_milliseconds().max(0);
+    let seconds = millis / 1000;
+    if seconds < 10 {
+        format!("+{}.{:03}s", seconds, millis % 1000)
+    } else if seconds < 60 {
+        format!("+{}.{}s", seconds, millis % 1000 / 100)
+    } else if seconds < 3600 {
+        format!("+{}m{:02}s", seconds / 60, seconds % 60)
+    } else {
+        format!("+{}h{:02}m", seconds / 3600, seconds % 3600 / 60)
+    }
+}
+
+/// A `:mark` divider: a rule across the log with the time and text of the mark in it, on one
+/// row even when wrapping
+fn mark_row<'a>(
+    entry: &LogEntry,
+    index: usize,
+    theme: &Theme,
+    width: usize,
+    selected_range: Option<(usize, usize)>,
+) -> Vec<Span<'a>> {
+    let mut style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+    if selected_range.is_some_and(|(first, last)| (first..=last).contains(&index)) {
+        style = style.bg(theme.selection);
+    }
+    let label = format!("── {} {} ", entry.timestamp().format("%H:%M:%S"), entry.content_plain());
+    let rule = "─".repeat(width.saturating_sub(label.width()));
+    fit_spans(vec![Span::styled(label + &rule, style)], width, style)
+}
+
+/// Content segments split where highlights match, with the matches drawn reversed in the
+/// highlight's color. Later highlights are drawn over earlier ones
+fn highlight_segments<'a>(segments: Vec<(Style, &'a str)>, highlights: &[Highlight], theme: &Theme) -> Vec<(Style, &'a str)> {
+    if highlights.is_empty() {
+        return segments;
+    }
+    // Matched against the whole content, so a match can span the entry's own styling
+    let text: String = segments.iter().map(|(_, text)| *text).collect();
+    let mut colors: Vec<Option<Color>> = vec![None; text.len()];
+    for highlight in highlights {
+        for range in highlight.pattern.find_ranges(&text) {
+            colors[range].fill(Some(highlight.color.unwrap_or(theme.search)));
+        }
+    }
+    if colors.iter().all(Option::is_none) {
+        return segments;
+    }
+
+    let mut split = Vec::new();
+    let mut offset = 0;
+    for (style, segment) in segments {
+        let colors = &colors[offset..offset + segment.len()];
+        let mut start = 0;
+        for end in 1..=segment.len() {
+            if end == segment.len() || colors[end] != colors[start] {
+                let style = match colors[start] {
+                    Some(color) => style.fg(color).add_modifier(Modifier::REVERSED),
+                    None => style,
+                };
+                split.push((style, &segment[start..end]));
+                start = end;
+            }
+        }
+        offset += segment.len();
+    }
+    split
+}
+
+/// Break spans into rows of `width` columns, starting each continuation row with `prefix`
+fn wrap_spans<'a>(spans: Vec<Span<'a>>, width: usize, prefix: Span<'a>) -> Vec<Vec<Span<'a>>> {
+    let prefix_width = prefix.width();
+    // A prefix that leaves no room for text is dropped
+    let prefix = (prefix_width < width).then_some(prefix);
+    let continuation_width = if prefix.is_some() { width - prefix_width } else { width.max(1) };
+
+    let mut rows = vec![Vec::new()];
+    let mut limit = width.max(1);
+    let mut used = 0;
+    for span in spans {
+        let mut piece_start = 0;
+        for (index, ch) in span.content.char_indices() {
+            let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+            if used + ch_width > limit {
+                if index > piece_start {
+                    rows.last_mut().unwrap().push(Span::styled(span.content[piece_start..index].to_string(), span.style));
+                }
+                piece_start = index;
+                rows.push(prefix.iter().cloned().collect());
+                limit = continuation_width;
+                used = 0;
+            }
+            used += ch_width;
+        }
+        if piece_start < span.content.len() {
+            rows.last_mut().unwrap().push(Span::styled(span.content[piece_start..].to_string(), span.style));
+        }
+    }
+    rows
+}
+
+/// Truncate spans to `width` columns, padding with `pad_style` so the whole row is styled
+fn fit_spans<'a>(spans: Vec<Span<'a>>, width: usize, pad_style: Style) -> Vec<Span<'a>> {
+    let mut fitted = Vec::with_capacity(spans.len() + 1);
+    let mut used = 0;
+    for span in spans {
+        if used >= width {
+            break;
+        }
+        let mut cut = span.content.len();
+        let mut span_width = 0;
+        for (index, ch) in span.content.char_indices() {
+            let ch_width = UnicodeWidthChar::width(ch).unwrap_or(0);
+            if used + span_width + ch_width > width {
+                cut = index;
+                break;
+            }
+            span_width += ch_width;
+        }
+        used += span_width;
+        if cut < span.content.len() {
+            fitted.push(Span::styled(span.content[..cut].to_string(), span.style));
+            break;
+        }
+        fitted.push(span);
+    }
+    if used < width {
+        fitted.push(Span::styled(" ".repeat(width - used), pad_style));
+    }
+    fitted
+}
+
+/// Extension trait to enable rendering LogViewer with log entries. `title` replaces the
+/// viewer's own title, e.g. to name the tab in a split pane
 pub trait LogViewerExt {
     fn render_log_viewer(
         &mut self,
-        widget: LogViewer,
+        widget: &LogViewer,
+        title: Option<&str>,
         area: Rect,
-        filtered_logs: &[&LogEntry],
+        filtered_logs: &ViewEntries,
         settings: &LogSettings,
+        theme: &Theme,
     );
 }
 
 impl LogViewerExt for ratatui::Frame<'_> {
     fn render_log_viewer(
         &mut self,
-        widget: LogViewer,
+        widget: &LogViewer,
+        title: Option<&str>,
         area: Rect,
-        filtered_logs: &[&LogEntry],
+        filtered_logs: &ViewEntries,
         settings: &LogSettings,
+        theme: &Theme,
     ) {
-        let paragraph = widget.render_logs(filtered_logs, settings, area);
-        self.render_widget(paragraph, area);
+        widget.render_logs(filtered_logs, settings, theme, area, title, self.buffer_mut());
+
+        // Draw the scrollbar over the right border, between the corners
+        let track = Rect {
+            x: area.x,
+            y: area.y + 1,
+            width: area.width,
+            height: area.height.saturating_sub(2),
+        };
+        if let Some(mut state) = widget.scrollbar_state(filtered_logs.len(), track.height as usize) {
+            let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
+                .begin_symbol(None)
+                .end_symbol(None)
+                .thumb_style(Style::default().fg(theme.title))
+                .track_style(Style::default().fg(theme.dim));
+            self.render_stateful_widget(scrollbar, track, &mut state);
+        }
     }
 }
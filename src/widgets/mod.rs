@@ -1,5 +1,17 @@
+mod alert_bar;
 mod command_prompt;
+mod help_overlay;
 mod log_viewer;
+mod output_pane;
+mod selection;
+mod source_sidebar;
+mod tab_bar;
 
+pub use alert_bar::*;
 pub use command_prompt::*;
+pub use help_overlay::*;
 pub use log_viewer::*;
+pub use output_pane::*;
+pub use selection::*;
+pub use source_sidebar::*;
+pub use tab_bar::*;
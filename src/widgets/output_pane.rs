@@ -0,0 +1,136 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Clear, Paragraph},
+};
+use crate::theme::Theme;
+
+/// A temporary, scrollable pane for multi-line command output
+#[derive(Debug, Clone, Default)]
+pub struct OutputPane {
+    /// Title and lines of the output being shown, if any
+    output: Option<(String, Vec<String>)>,
+    /// Number of lines scrolled from the top
+    scroll: usize,
+    /// The highlighted line, when the lines are a list to pick from
+    selected: Option<usize>,
+}
+
+impl OutputPane {
+    /// Create a new (closed) output pane
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the pane is currently shown
+    pub fn is_open(&self) -> bool {
+        self.output.is_some()
+    }
+
+    /// Show command output from the top
+    pub fn open(&mut self, title: impl Into<String>, lines: Vec<String>) {
+        self.output = Some((title.into(), lines));
+        self.scroll = 0;
+        self.selected = None;
+    }
+
+    /// Show a list with its first line highlighted; moving up and down moves the highlight
+    pub fn open_list(&mut self, title: impl Into<String>, lines: Vec<String>) {
+        self.open(title, lines);
+        self.selected = Some(0);
+    }
+
+    /// Replace the lines shown, keeping the scroll position and highlight
+    pub fn set_lines(&mut self, lines: Vec<String>) {
+        if let Some((_, shown)) = &mut self.output {
+            *shown = lines;
+        }
+    }
+
+    /// Index of the highlighted line of a list
+    pub fn selected(&self) -> Option<usize> {
+        let count = self.output.as_ref().map_or(0, |(_, lines)| lines.len());
+        self.selected.map(|selected| selected.min(count.saturating_sub(1)))
+    }
+
+    /// Hide the pane
+    pub fn close(&mut self) {
+        self.output = None;
+    }
+
+    /// Scroll up by the specified amount, or move a list's highlight up
+    pub fn scroll_up(&mut self, amount: usize) {
+        match self.selected() {
+            Some(selected) => self.selected = Some(selected.saturating_sub(amount)),
+            None => self.scroll = self.scroll.saturating_sub(amount),
+        }
+    }
+
+    /// Scroll down by the specified amount, or move a list's highlight down
+    pub fn scroll_down(&mut self, amount: usize) {
+        match self.selected() {
+            Some(selected) => {
+                let count = self.output.as_ref().map_or(0, |(_, lines)| lines.len());
+                self.selected = Some((selected + amount).min(count.saturating_sub(1)));
+            },
+            None => self.scroll += amount,
+        }
+    }
+
+    /// Rows the pane wants, borders included, capped at half of `available`
+    pub fn height(&self, available: u16) -> u16 {
+        let lines = self.output.as_ref().map_or(0, |(_, lines)| lines.len()) as u16;
+        (lines + 2).min(available / 2).max(3)
+    }
+
+    /// Handle rendering the pane
+    fn render_output(&self, theme: &Theme, area: Rect) -> Paragraph<'_> {
+        let (title, lines) = match &self.output {
+            Some((title, lines)) => (title.as_str(), lines.as_slice()),
+            None => ("", &[][..]),
+        };
+        let visible = area.height.saturating_sub(2) as usize;
+        let max_scroll = lines.len().saturating_sub(visible);
+        let selected = self.selected();
+        // A list scrolls to keep its highlight in view
+        let scroll = match selected {
+            Some(selected) => selected.saturating_sub(visible.saturating_sub(1)),
+            None => self.scroll,
+        }.min(max_scroll);
+
+        let text_style = Style::default().fg(theme.text);
+        let lines: Vec<Line> = lines.iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let style = if selected == Some(i) { text_style.add_modifier(Modifier::REVERSED) } else { text_style };
+                Line::from(Span::styled(line.as_str(), style))
+            })
+            .collect();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.accent))
+            .title(Span::styled(
+                format!("{} - Esc to close", title),
+                Style::default().fg(theme.title).add_modifier(Modifier::BOLD),
+            ));
+
+        Paragraph::new(lines)
+            .block(block)
+            .scroll((scroll as u16, 0))
+    }
+}
+
+/// Extension trait to enable rendering the OutputPane
+pub trait OutputPaneExt {
+    fn render_output_pane(&mut self, widget: &OutputPane, area: Rect, theme: &Theme);
+}
+
+impl OutputPaneExt for ratatui::Frame<'_> {
+    fn render_output_pane(&mut self, widget: &OutputPane, area: Rect, theme: &Theme) {
+        let paragraph = widget.render_output(theme, area);
+        self.render_widget(Clear, area);
+        self.render_widget(paragraph, area);
+    }
+}
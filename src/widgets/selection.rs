@@ -0,0 +1,93 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::Modifier,
+};
+
+/// A click-drag text selection over the rendered log area, in screen cells
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSelection {
+    /// Where the drag started (column, row)
+    anchor: (u16, u16),
+    /// Where the drag currently is (column, row)
+    head: (u16, u16),
+    /// The area the selection is confined to
+    bounds: Rect,
+}
+
+impl TextSelection {
+    /// Start a selection at a point inside `bounds`
+    pub fn start(x: u16, y: u16, bounds: Rect) -> Self {
+        let point = clamp_point(x, y, bounds);
+        Self {
+            anchor: point,
+            head: point,
+            bounds,
+        }
+    }
+
+    /// Extend the selection to a new point, clamped to the bounds
+    pub fn extend_to(&mut self, x: u16, y: u16) {
+        self.head = clamp_point(x, y, self.bounds);
+    }
+
+    /// True if the mouse never moved from where it was pressed
+    pub fn is_empty(&self) -> bool {
+        self.anchor == self.head
+    }
+
+    /// Start and end points in reading order
+    fn ordered(&self) -> ((u16, u16), (u16, u16)) {
+        let (a, h) = (self.anchor, self.head);
+        if (a.1, a.0) <= (h.1, h.0) { (a, h) } else { (h, a) }
+    }
+
+    /// Column range selected on a given row, like a terminal's stream selection
+    fn columns_for_row(&self, row: u16) -> Option<(u16, u16)> {
+        let (start, end) = self.ordered();
+        if row < start.1 || row > end.1 {
+            return None;
+        }
+        let left = self.bounds.x;
+        let right = self.bounds.x + self.bounds.width.saturating_sub(1);
+        let first = if row == start.1 { start.0 } else { left };
+        let last = if row == end.1 { end.0 } else { right };
+        Some((first, last))
+    }
+
+    /// Highlight the selected cells in a rendered buffer
+    pub fn highlight(&self, buf: &mut Buffer) {
+        let (start, end) = self.ordered();
+        for row in start.1..=end.1 {
+            if let Some((first, last)) = self.columns_for_row(row) {
+                for col in first..=last {
+                    let cell = buf.get_mut(col, row);
+                    cell.modifier.insert(Modifier::REVERSED);
+                }
+            }
+        }
+    }
+
+    /// Read the selected text back out of a rendered buffer
+    /// Trailing padding on each row is dropped and rows are joined with newlines
+    pub fn extract(&self, buf: &Buffer) -> String {
+        let (start, end) = self.ordered();
+        let mut lines = Vec::new();
+        for row in start.1..=end.1 {
+            if let Some((first, last)) = self.columns_for_row(row) {
+                let line: String = (first..=last)
+                    .map(|col| buf.get(col, row).symbol.as_str())
+                    .collect();
+                lines.push(line.trim_end().to_string());
+            }
+        }
+        lines.join("\n")
+    }
+}
+
+/// Clamp a point into the selectable area
+fn clamp_point(x: u16, y: u16, bounds: Rect) -> (u16, u16) {
+    let max_x = bounds.x + bounds.width.saturating_sub(1);
+    let max_y = bounds.y + bounds.height.saturating_sub(1);
+    (x.clamp(bounds.x, max_x), y.clamp(bounds.y, max_y))
+}
@@ -0,0 +1,133 @@
+use ratatui::{
+    layout::Rect,
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{Block, Borders, Paragraph},
+};
+use crate::source_status::SourceState;
+use crate::theme::Theme;
+
+/// One row of the source sidebar
+#[derive(Debug, Clone)]
+pub struct SourceRow {
+    pub name: String,
+    pub visible: bool,
+    pub entries: usize,
+    /// Entries that arrived while the source wasn't being followed
+    pub unread: usize,
+    pub state: Option<SourceState>,
+}
+
+/// A toggleable panel listing all sources with their visibility and status
+#[derive(Debug, Clone, Default)]
+pub struct SourceSidebar {
+    /// Whether the sidebar is shown
+    open: bool,
+    /// Index of the highlighted row
+    selected: usize,
+}
+
+impl SourceSidebar {
+    /// Create a new (closed) sidebar
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether the sidebar is currently shown
+    pub fn is_open(&self) -> bool {
+        self.open
+    }
+
+    /// Show or hide the sidebar
+    pub fn toggle(&mut self) {
+        self.open = !self.open;
+    }
+
+    /// Index of the highlighted row
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Move the highlight up one row
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+
+    /// Move the highlight down one row, bounded by the number of rows
+    pub fn select_next(&mut self, row_count: usize) {
+        if self.selected + 1 < row_count {
+            self.selected += 1;
+        }
+    }
+
+    /// Build the sidebar paragraph for the given rows
+    fn render_rows<'a>(&self, rows: &[SourceRow], theme: &Theme, area: Rect) -> Paragraph<'a> {
+        let width = area.width.saturating_sub(2) as usize;
+        let selected = self.selected.min(rows.len().saturating_sub(1));
+
+        let lines: Vec<Line> = rows
+            .iter()
+            .enumerate()
+            .map(|(i, row)| {
+                let marker = if row.visible { "[x]" } else { "[ ]" };
+                let state = match row.state {
+                    Some(SourceState::Running) => "●",
+                    Some(SourceState::Stopped) => "○",
+                    Some(SourceState::Waiting) => "…",
+                    Some(SourceState::Connecting) => "◌",
+                    Some(SourceState::Reconnecting) => "↻",
+                    None => " ",
+                };
+                let count = row.entries.to_string();
+                let badge = if row.unread > 0 {
+                    format!(" +{}", row.unread)
+                } else {
+                    String::new()
+                };
+
+                // Truncate the name so the counts stay right-aligned
+                let name_width = width.saturating_sub(marker.len() + count.len() + badge.len() + 4);
+                let name: String = row.name.chars().take(name_width).collect();
+                let text = format!("{} {} {:<name_width$}", marker, state, name);
+
+                let mut style = match row.state {
+                    Some(SourceState::Stopped | SourceState::Waiting | SourceState::Connecting | SourceState::Reconnecting) => Style::default().fg(theme.dim),
+                    _ if row.visible => Style::default().fg(theme.accent),
+                    _ => Style::default().fg(theme.muted),
+                };
+                let mut badge_style = Style::default().fg(theme.accent).add_modifier(Modifier::BOLD);
+                if i == selected {
+                    style = style.add_modifier(Modifier::REVERSED);
+                    badge_style = badge_style.add_modifier(Modifier::REVERSED);
+                }
+                Line::from(vec![
+                    Span::styled(text, style),
+                    Span::styled(badge, badge_style),
+                    Span::styled(format!(" {}", count), style),
+                ])
+            })
+            .collect();
+
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(theme.border))
+            .title(Span::styled(
+                "Sources",
+                Style::default().fg(theme.title).add_modifier(Modifier::BOLD),
+            ));
+
+        Paragraph::new(lines).block(block)
+    }
+}
+
+/// Extension trait to enable rendering the SourceSidebar with source rows
+pub trait SourceSidebarExt {
+    fn render_source_sidebar(&mut self, widget: &SourceSidebar, area: Rect, rows: &[SourceRow], theme: &Theme);
+}
+
+impl SourceSidebarExt for ratatui::Frame<'_> {
+    fn render_source_sidebar(&mut self, widget: &SourceSidebar, area: Rect, rows: &[SourceRow], theme: &Theme) {
+        let paragraph = widget.render_rows(rows, theme, area);
+        self.render_widget(paragraph, area);
+    }
+}
@@ -0,0 +1,41 @@
+use ratatui::{
+    buffer::Buffer,
+    layout::Rect,
+    style::{Modifier, Style},
+    text::Line,
+    widgets::{Tabs, Widget},
+};
+use crate::theme::Theme;
+
+/// A one-line bar listing the open tabs with the active one highlighted
+#[derive(Debug, Clone)]
+pub struct TabBar<'a> {
+    names: Vec<&'a str>,
+    active: usize,
+    theme: &'a Theme,
+}
+
+impl<'a> TabBar<'a> {
+    /// Create a tab bar from tab names and the active index
+    pub fn new(names: Vec<&'a str>, active: usize, theme: &'a Theme) -> Self {
+        Self { names, active, theme }
+    }
+}
+
+impl Widget for TabBar<'_> {
+    fn render(self, area: Rect, buf: &mut Buffer) {
+        let titles: Vec<Line> = self.names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| Line::from(format!("{}:{}", i + 1, name)))
+            .collect();
+
+        Tabs::new(titles)
+            .select(self.active)
+            .style(Style::default().fg(self.theme.muted))
+            .highlight_style(
+                Style::default().fg(self.theme.title).add_modifier(Modifier::BOLD | Modifier::REVERSED),
+            )
+            .render(area, buf);
+    }
+}
@@ -0,0 +1,29 @@
+use std::env;
+use std::path::PathBuf;
+
+/// oxtail's directory under an XDG base directory: the one the variable names if it's an
+/// absolute path, as the spec asks, or else the default under the home directory
+fn base_dir(variable: &str, default: &[&str]) -> Option<PathBuf> {
+    let base = env::var_os(variable)
+        .map(PathBuf::from)
+        .filter(|path| path.is_absolute())
+        .or_else(|| env::var_os("HOME").map(|home| default.iter().fold(PathBuf::from(home), |path, part| path.join(part))))?;
+    Some(base.join("oxtail"))
+}
+
+/// Where the config file is: `$XDG_CONFIG_HOME/oxtail`, `~/.config/oxtail` by default
+pub fn config_dir() -> Option<PathBuf> {
+    base_dir("XDG_CONFIG_HOME", &[".config"])
+}
+
+/// Where what's kept between sessions goes, such as the command history and `--resume`
+/// checkpoints: `$XDG_STATE_HOME/oxtail`, `~/.local/state/oxtail` by default
+pub fn state_dir() -> Option<PathBuf> {
+    base_dir("XDG_STATE_HOME", &[".local", "state"])
+}
+
+/// Where files that can be thrown away go, such as spill files: `$XDG_CACHE_HOME/oxtail`,
+/// `~/.cache/oxtail` by default
+pub fn cache_dir() -> Option<PathBuf> {
+    base_dir("XDG_CACHE_HOME", &[".cache"])
+}